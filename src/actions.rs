@@ -0,0 +1,310 @@
+// actions.rs
+
+use raylib::prelude::*;
+
+// Gameplay actions and the keys, buttons and sticks behind them. Movement, the sword and the
+// hotkeys ask for an action rather than a key, so an input preset only changes the bindings here:
+//   Default       WASD moves, arrows walk and turn, Shift sprints; left stick moves, right looks
+//   Left-Handed   IJKL moves and U/O turn, for the mouse in the left hand; arrows as in Default,
+//                 Right Shift sprints and the hotkeys sit around the right hand (see below)
+//   Southpaw      On a controller the right stick moves and the left stick looks, and the stick
+//                 clicks swap with them; the keyboard is as in Default
+// The mouse always turns and its left button always swings. Menus keep their own keys.
+// On top of the preset, the Controls screen can rebind an action's first key or button to any
+// key or button in KEY_NAMES or BUTTON_NAMES; those rebinds are kept in settings.txt as
+// `bind_<action> = <key>` and `bind_pad_<action> = <button>` lines, and picking another preset
+// drops them. Two actions (or an action and one of the fixed hotkeys in RESERVED) can end up on
+// the same key; the Controls screen flags that rather than refusing it.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputPreset {
+    Default,
+    LeftHanded,
+    Southpaw,
+}
+
+pub const INPUT_PRESETS: [InputPreset; 3] = [InputPreset::Default, InputPreset::LeftHanded, InputPreset::Southpaw];
+
+impl InputPreset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputPreset::Default => "Default",
+            InputPreset::LeftHanded => "Left-Handed",
+            InputPreset::Southpaw => "Southpaw",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Forward,
+    Back,
+    StrafeLeft,
+    StrafeRight,
+    TurnLeft,
+    TurnRight,
+    Sprint,
+    Attack,
+    Potion,
+    Lantern,
+    Ping,
+    Command, // Send the companion after an enemy
+    Switch,  // Pick the next deployable
+    Deploy,  // Set it down, or enter a hub portal
+}
+
+// Every action, in the order the Controls screen lists them
+pub const ACTIONS: [Action; 14] = [
+    Action::Forward, Action::Back, Action::StrafeLeft, Action::StrafeRight, Action::TurnLeft, Action::TurnRight, Action::Sprint,
+    Action::Attack, Action::Potion, Action::Lantern, Action::Ping, Action::Command, Action::Switch, Action::Deploy,
+];
+
+impl Action {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Forward => "Move Forward",
+            Action::Back => "Move Back",
+            Action::StrafeLeft => "Strafe Left",
+            Action::StrafeRight => "Strafe Right",
+            Action::TurnLeft => "Turn Left",
+            Action::TurnRight => "Turn Right",
+            Action::Sprint => "Sprint",
+            Action::Attack => "Attack",
+            Action::Potion => "Drink Potion",
+            Action::Lantern => "Lantern",
+            Action::Ping => "Ping",
+            Action::Command => "Command Wisp",
+            Action::Switch => "Next Deployable",
+            Action::Deploy => "Deploy / Enter",
+        }
+    }
+
+    // Name in settings.txt
+    pub fn id(&self) -> &'static str {
+        match self {
+            Action::Forward => "forward",
+            Action::Back => "back",
+            Action::StrafeLeft => "strafe_left",
+            Action::StrafeRight => "strafe_right",
+            Action::TurnLeft => "turn_left",
+            Action::TurnRight => "turn_right",
+            Action::Sprint => "sprint",
+            Action::Attack => "attack",
+            Action::Potion => "potion",
+            Action::Lantern => "lantern",
+            Action::Ping => "ping",
+            Action::Command => "command",
+            Action::Switch => "switch",
+            Action::Deploy => "deploy",
+        }
+    }
+}
+
+// A key or controller button behind an action
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binding {
+    Key(KeyboardKey),
+    Button(GamepadButton),
+}
+
+// Keys an action can be rebound to, with the names shown and saved for them. Escape, Tab and
+// the function keys stay with the menus, the map and the debug tools.
+pub const KEY_NAMES: [(KeyboardKey, &str); 76] = {
+    use KeyboardKey::*;
+    [
+        (KEY_A, "A"), (KEY_B, "B"), (KEY_C, "C"), (KEY_D, "D"), (KEY_E, "E"), (KEY_F, "F"), (KEY_G, "G"), (KEY_H, "H"), (KEY_I, "I"),
+        (KEY_J, "J"), (KEY_K, "K"), (KEY_L, "L"), (KEY_M, "M"), (KEY_N, "N"), (KEY_O, "O"), (KEY_P, "P"), (KEY_Q, "Q"), (KEY_R, "R"),
+        (KEY_S, "S"), (KEY_T, "T"), (KEY_U, "U"), (KEY_V, "V"), (KEY_W, "W"), (KEY_X, "X"), (KEY_Y, "Y"), (KEY_Z, "Z"),
+        (KEY_ZERO, "0"), (KEY_ONE, "1"), (KEY_TWO, "2"), (KEY_THREE, "3"), (KEY_FOUR, "4"), (KEY_FIVE, "5"), (KEY_SIX, "6"),
+        (KEY_SEVEN, "7"), (KEY_EIGHT, "8"), (KEY_NINE, "9"),
+        (KEY_SPACE, "Space"), (KEY_ENTER, "Enter"), (KEY_BACKSPACE, "Backspace"),
+        (KEY_UP, "Up"), (KEY_DOWN, "Down"), (KEY_LEFT, "Left"), (KEY_RIGHT, "Right"),
+        (KEY_LEFT_SHIFT, "Left Shift"), (KEY_RIGHT_SHIFT, "Right Shift"), (KEY_LEFT_CONTROL, "Left Ctrl"),
+        (KEY_RIGHT_CONTROL, "Right Ctrl"), (KEY_LEFT_ALT, "Left Alt"), (KEY_RIGHT_ALT, "Right Alt"),
+        (KEY_COMMA, "Comma"), (KEY_PERIOD, "Period"), (KEY_SLASH, "Slash"), (KEY_SEMICOLON, "Semicolon"),
+        (KEY_APOSTROPHE, "Apostrophe"), (KEY_MINUS, "Minus"), (KEY_EQUAL, "Equals"), (KEY_LEFT_BRACKET, "Left Bracket"),
+        (KEY_RIGHT_BRACKET, "Right Bracket"), (KEY_BACKSLASH, "Backslash"), (KEY_GRAVE, "Grave"),
+        (KEY_INSERT, "Insert"), (KEY_DELETE, "Delete"), (KEY_HOME, "Home"), (KEY_END, "End"), (KEY_PAGE_UP, "Page Up"),
+        (KEY_PAGE_DOWN, "Page Down"),
+        (KEY_KP_0, "Num 0"), (KEY_KP_1, "Num 1"), (KEY_KP_2, "Num 2"), (KEY_KP_3, "Num 3"), (KEY_KP_4, "Num 4"),
+        (KEY_KP_5, "Num 5"), (KEY_KP_6, "Num 6"), (KEY_KP_7, "Num 7"), (KEY_KP_8, "Num 8"), (KEY_KP_9, "Num 9"),
+    ]
+};
+
+// Controller buttons, named as on a PlayStation pad. Options pauses and Create opens the map.
+pub const BUTTON_NAMES: [(GamepadButton, &str); 14] = {
+    use GamepadButton::*;
+    [
+        (GAMEPAD_BUTTON_RIGHT_FACE_DOWN, "Cross"), (GAMEPAD_BUTTON_RIGHT_FACE_RIGHT, "Circle"),
+        (GAMEPAD_BUTTON_RIGHT_FACE_LEFT, "Square"), (GAMEPAD_BUTTON_RIGHT_FACE_UP, "Triangle"),
+        (GAMEPAD_BUTTON_LEFT_FACE_UP, "D-Pad Up"), (GAMEPAD_BUTTON_LEFT_FACE_DOWN, "D-Pad Down"),
+        (GAMEPAD_BUTTON_LEFT_FACE_LEFT, "D-Pad Left"), (GAMEPAD_BUTTON_LEFT_FACE_RIGHT, "D-Pad Right"),
+        (GAMEPAD_BUTTON_LEFT_TRIGGER_1, "L1"), (GAMEPAD_BUTTON_RIGHT_TRIGGER_1, "R1"),
+        (GAMEPAD_BUTTON_LEFT_TRIGGER_2, "L2"), (GAMEPAD_BUTTON_RIGHT_TRIGGER_2, "R2"),
+        (GAMEPAD_BUTTON_LEFT_THUMB, "L3"), (GAMEPAD_BUTTON_RIGHT_THUMB, "R3"),
+    ]
+};
+
+// Hotkeys the game reads directly while playing, outside any preset
+const RESERVED: [(KeyboardKey, &str); 7] = [
+    (KeyboardKey::KEY_M, "Minimap"),
+    (KeyboardKey::KEY_P, "Performance Mode"),
+    (KeyboardKey::KEY_N, "Music"),
+    (KeyboardKey::KEY_MINUS, "Music Volume"),
+    (KeyboardKey::KEY_EQUAL, "Music Volume"),
+    (KeyboardKey::KEY_LEFT_BRACKET, "SFX Volume"),
+    (KeyboardKey::KEY_RIGHT_BRACKET, "SFX Volume"),
+];
+
+impl Binding {
+    pub fn name(&self) -> &'static str {
+        let name = match self {
+            Binding::Key(key) => KEY_NAMES.iter().find(|(bound, _)| bound == key).map(|(_, name)| *name),
+            Binding::Button(button) => BUTTON_NAMES.iter().find(|(bound, _)| bound == button).map(|(_, name)| *name),
+        };
+        name.unwrap_or("?")
+    }
+
+    pub fn key_named(name: &str) -> Option<Binding> {
+        KEY_NAMES.iter().find(|(_, key_name)| *key_name == name).map(|&(key, _)| Binding::Key(key))
+    }
+
+    pub fn button_named(name: &str) -> Option<Binding> {
+        BUTTON_NAMES.iter().find(|(_, button_name)| *button_name == name).map(|&(button, _)| Binding::Button(button))
+    }
+
+    fn same_device(&self, other: &Binding) -> bool {
+        matches!((self, other), (Binding::Key(_), Binding::Key(_)) | (Binding::Button(_), Binding::Button(_)))
+    }
+}
+
+pub struct ActionMap {
+    pub preset: InputPreset,
+    keys: Vec<(Action, KeyboardKey)>,
+    buttons: Vec<(Action, GamepadButton)>,
+    move_stick: (GamepadAxis, GamepadAxis), // X and Y
+    look_stick: GamepadAxis,                // X only, the view does not pitch
+    rebinds: Vec<(Action, Binding)>,        // Changes made on the Controls screen, at most one per action and device
+}
+
+impl ActionMap {
+    pub fn new(preset: InputPreset) -> Self {
+        use Action::*;
+        use GamepadButton::*;
+        use KeyboardKey::*;
+
+        let keys = match preset {
+            InputPreset::LeftHanded => vec![
+                (Forward, KEY_I), (Forward, KEY_UP), (Back, KEY_K), (Back, KEY_DOWN),
+                (StrafeLeft, KEY_J), (StrafeRight, KEY_L),
+                (TurnLeft, KEY_U), (TurnLeft, KEY_LEFT), (TurnRight, KEY_O), (TurnRight, KEY_RIGHT),
+                (Sprint, KEY_RIGHT_SHIFT), (Attack, KEY_SPACE), (Attack, KEY_ENTER),
+                (Potion, KEY_H), (Lantern, KEY_APOSTROPHE), (Ping, KEY_SLASH),
+                (Command, KEY_PERIOD), (Switch, KEY_COMMA), (Deploy, KEY_SEMICOLON),
+            ],
+            InputPreset::Default | InputPreset::Southpaw => vec![
+                (Forward, KEY_W), (Forward, KEY_UP), (Back, KEY_S), (Back, KEY_DOWN),
+                (StrafeLeft, KEY_A), (StrafeRight, KEY_D),
+                (TurnLeft, KEY_LEFT), (TurnRight, KEY_RIGHT),
+                (Sprint, KEY_LEFT_SHIFT), (Attack, KEY_SPACE), (Attack, KEY_E),
+                (Potion, KEY_H), (Lantern, KEY_L), (Ping, KEY_G),
+                (Command, KEY_C), (Switch, KEY_X), (Deploy, KEY_F),
+            ],
+        };
+
+        // The moving stick's click sprints and the looking stick's pings
+        let (sprint, ping) = match preset {
+            InputPreset::Southpaw => (GAMEPAD_BUTTON_RIGHT_THUMB, GAMEPAD_BUTTON_LEFT_THUMB),
+            InputPreset::Default | InputPreset::LeftHanded => (GAMEPAD_BUTTON_LEFT_THUMB, GAMEPAD_BUTTON_RIGHT_THUMB),
+        };
+        let buttons = vec![
+            (Forward, GAMEPAD_BUTTON_LEFT_FACE_UP), (Back, GAMEPAD_BUTTON_LEFT_FACE_DOWN),
+            (StrafeLeft, GAMEPAD_BUTTON_LEFT_FACE_LEFT), (StrafeRight, GAMEPAD_BUTTON_LEFT_FACE_RIGHT),
+            (TurnLeft, GAMEPAD_BUTTON_LEFT_TRIGGER_1), (TurnRight, GAMEPAD_BUTTON_RIGHT_TRIGGER_1),
+            (Sprint, sprint), (Attack, GAMEPAD_BUTTON_RIGHT_TRIGGER_2), (Attack, GAMEPAD_BUTTON_RIGHT_FACE_LEFT),
+            (Potion, GAMEPAD_BUTTON_RIGHT_FACE_UP), (Ping, ping), (Command, GAMEPAD_BUTTON_LEFT_TRIGGER_2),
+            (Switch, GAMEPAD_BUTTON_RIGHT_FACE_RIGHT), (Deploy, GAMEPAD_BUTTON_RIGHT_FACE_DOWN),
+        ];
+
+        let (move_stick, look_stick) = match preset {
+            InputPreset::Southpaw => ((GamepadAxis::GAMEPAD_AXIS_RIGHT_X, GamepadAxis::GAMEPAD_AXIS_RIGHT_Y), GamepadAxis::GAMEPAD_AXIS_LEFT_X),
+            InputPreset::Default | InputPreset::LeftHanded => ((GamepadAxis::GAMEPAD_AXIS_LEFT_X, GamepadAxis::GAMEPAD_AXIS_LEFT_Y), GamepadAxis::GAMEPAD_AXIS_RIGHT_X),
+        };
+
+        ActionMap { preset, keys, buttons, move_stick, look_stick, rebinds: Vec::new() }
+    }
+
+    // Keys then buttons behind an action, the first of each being the one a rebind replaces
+    pub fn bindings(&self, action: Action) -> Vec<Binding> {
+        let keys = self.keys.iter().filter(|(bound, _)| *bound == action).map(|&(_, key)| Binding::Key(key));
+        let buttons = self.buttons.iter().filter(|(bound, _)| *bound == action).map(|&(_, button)| Binding::Button(button));
+        keys.chain(buttons).collect()
+    }
+
+    // Puts `binding` in place of the action's first key or button, or adds it if it had none
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        match binding {
+            Binding::Key(key) => match self.keys.iter_mut().find(|(bound, _)| *bound == action) {
+                Some(slot) => slot.1 = key,
+                None => self.keys.push((action, key)),
+            },
+            Binding::Button(button) => match self.buttons.iter_mut().find(|(bound, _)| *bound == action) {
+                Some(slot) => slot.1 = button,
+                None => self.buttons.push((action, button)),
+            },
+        }
+        self.rebinds.retain(|(bound, old)| !(*bound == action && old.same_device(&binding)));
+        self.rebinds.push((action, binding));
+    }
+
+    pub fn rebinds(&self) -> &[(Action, Binding)] {
+        &self.rebinds
+    }
+
+    // What else `binding` sets off while playing: other actions, then any fixed hotkey
+    pub fn conflicts(&self, action: Action, binding: Binding) -> Vec<&'static str> {
+        let mut others: Vec<&'static str> = ACTIONS.iter()
+            .filter(|&&other| other != action && self.bindings(other).contains(&binding))
+            .map(|other| other.label())
+            .collect();
+        if let Binding::Key(key) = binding {
+            others.extend(RESERVED.iter().filter(|(reserved, _)| *reserved == key).map(|(_, name)| *name));
+        }
+        others
+    }
+
+    // Held this frame on the keyboard or the controller
+    pub fn down(&self, rl: &RaylibHandle, action: Action) -> bool {
+        let gamepad = rl.is_gamepad_available(0);
+        self.keys.iter().any(|&(bound, key)| bound == action && rl.is_key_down(key)) ||
+            (gamepad && self.buttons.iter().any(|&(bound, button)| bound == action && rl.is_gamepad_button_down(0, button)))
+    }
+
+    // Went down this frame
+    pub fn pressed(&self, rl: &RaylibHandle, action: Action) -> bool {
+        let gamepad = rl.is_gamepad_available(0);
+        self.keys.iter().any(|&(bound, key)| bound == action && rl.is_key_pressed(key)) ||
+            (gamepad && self.buttons.iter().any(|&(bound, button)| bound == action && rl.is_gamepad_button_pressed(0, button)))
+    }
+
+    // Went down this frame on the controller alone, for what only helps controller play
+    pub fn pressed_on_gamepad(&self, rl: &RaylibHandle, action: Action) -> bool {
+        rl.is_gamepad_available(0) && self.buttons.iter().any(|&(bound, button)| bound == action && rl.is_gamepad_button_pressed(0, button))
+    }
+
+    // The moving stick, right and down positive, zero without a controller
+    pub fn move_stick(&self, rl: &RaylibHandle) -> (f32, f32) {
+        if !rl.is_gamepad_available(0) {
+            return (0.0, 0.0);
+        }
+        (rl.get_gamepad_axis_movement(0, self.move_stick.0), rl.get_gamepad_axis_movement(0, self.move_stick.1))
+    }
+
+    // The looking stick's sideways tilt, right positive
+    pub fn look_stick(&self, rl: &RaylibHandle) -> f32 {
+        if !rl.is_gamepad_available(0) {
+            return 0.0;
+        }
+        rl.get_gamepad_axis_movement(0, self.look_stick)
+    }
+}
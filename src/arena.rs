@@ -0,0 +1,63 @@
+// arena.rs
+
+use crate::enemy::Enemy;
+use crate::maze::{Cell, Maze};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArenaState {
+    Waiting, // Doors open, trigger not stepped on yet
+    Locked,  // Doors shut until the arena enemies are dead
+    Cleared, // Doors open for good
+}
+
+// Boss or ambush room: stepping on a trigger cell shuts the map's doors
+// until every enemy flagged for the arena is dead
+pub struct Arena {
+    doors: Vec<(usize, usize)>,
+    pub state: ArenaState,
+}
+
+impl Arena {
+    // None for maps without arena triggers
+    pub fn from_maze(maze: &Maze) -> Option<Arena> {
+        if !maze.cells().any(|(_, _, cell)| cell == Cell::ArenaTrigger) {
+            return None;
+        }
+
+        let doors = maze
+            .cells()
+            .filter(|&(_, _, cell)| matches!(cell, Cell::Door { .. }))
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        Some(Arena { doors, state: ArenaState::Waiting })
+    }
+
+    // Enemies flagged for the arena that are still standing
+    pub fn remaining(enemies: &[Enemy]) -> usize {
+        enemies.iter().filter(|enemy| enemy.arena && !enemy.is_dead).count()
+    }
+
+    // Already beaten in an earlier sitting: doors open and nothing left to fight
+    pub fn clear(&mut self, maze: &mut Maze) {
+        for &(x, y) in self.doors.iter() {
+            maze.set(x, y, Cell::Door { open: true });
+        }
+        self.state = ArenaState::Cleared;
+    }
+
+    // Call every frame with the cell under the player, returns the new state when it changes
+    pub fn update(&mut self, maze: &mut Maze, floor: Cell, enemies: &[Enemy]) -> Option<ArenaState> {
+        let next = match self.state {
+            ArenaState::Waiting if floor == Cell::ArenaTrigger => ArenaState::Locked,
+            ArenaState::Locked if Arena::remaining(enemies) == 0 => ArenaState::Cleared,
+            _ => return None,
+        };
+
+        let open = next == ArenaState::Cleared;
+        for &(x, y) in self.doors.iter() {
+            maze.set(x, y, Cell::Door { open });
+        }
+        self.state = next;
+        Some(next)
+    }
+}
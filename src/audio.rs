@@ -1,42 +1,269 @@
 use raylib::prelude::*;
 
-pub struct AudioManager {
-    music_volume: f32,
-    sfx_volume: f32,
-    is_music_enabled: bool,
-    is_sfx_enabled: bool,
+use crate::chatter::{Chatter, ChatterLine, ChatterTrigger};
+use crate::maze::Cell;
+use crate::reverb::ReverbZone;
+
+const DUCK_LEVEL: f32 = 0.35;  // Music volume multiplier while ducked
+const DUCK_ATTACK: f32 = 6.0;  // Per second, how fast music drops when ducked
+const DUCK_RELEASE: f32 = 1.2; // Per second, how fast it comes back afterwards
+const MUSIC_FADE_IN: f32 = 0.8; // Per second, music starting with a map fades in from silence
+const FOOTSTEP_VOLUME: f32 = 0.5; // Walking sound at half of SFX volume
+const MAX_ECHOES: usize = 12;     // Echo taps alive at once, extra ones are skipped
+const SWORD_VOLUME: f32 = 0.8;    // Sword swing at 80% SFX volume
+const HIT_VOLUME: f32 = 0.9;      // Enemy hit at 90% SFX volume
+const GATE_PITCH: f32 = 0.5;
+const CRITICAL_PITCH: f32 = 1.5; // Critical hits are a sharper, louder splat
+
+// Mixer groups, each with its own volume under the master volume
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MixGroup {
+    Music,
+    Sfx,
+    Ui,
+    Ambience,
+}
+
+// What the per-frame sound update follows: the player's movement, footing and surroundings
+pub struct Listener {
+    pub moving: bool,
+    pub floor: Cell,
+    pub reverb: ReverbZone,
 }
 
-impl Default for AudioManager {
-    fn default() -> Self {
-        Self::new()
+// A delayed, quieter copy of a sound effect. Aliases share the sample data of
+// their source, so several taps of one sound can overlap.
+struct Echo {
+    alias: raylib::ffi::Sound,
+    delay: f32, // Seconds until it plays
+    started: bool,
+}
+
+impl Drop for Echo {
+    fn drop(&mut self) {
+        unsafe { raylib::ffi::UnloadSoundAlias(self.alias) };
     }
 }
 
-impl AudioManager {
-    pub fn new() -> Self {
-        AudioManager {
-            music_volume: 0.5,
-            sfx_volume: 0.7,
+// Owns the music tracks, the looping footsteps and the radio chatter, everything that needs
+// touching every frame. One-shot effects stay with their owners and only come here to be played.
+pub struct AudioManager<'aud> {
+    master_volume: f32,
+    group_volumes: [f32; 4], // Indexed by MixGroup
+    duck_timer: f32,         // Seconds music stays ducked
+    duck_gain: f32,          // Current music multiplier, eases between DUCK_LEVEL and 1.0
+    fade_gain: f32,          // Music fade-in after a track starts
+    is_music_enabled: bool,
+    is_sfx_enabled: bool,
+    music_tracks: Vec<Option<Music<'aud>>>, // One per map, indexed like the map list
+    current_track: Option<usize>,           // Track of the map being played
+    music_paused: bool,                     // Paused by the game, as opposed to switched off
+    walking_sound: Option<Sound<'aud>>,
+    chatter: Chatter<'aud>, // Voice lines and their subtitles, see chatter.rs
+    reverb: ReverbZone, // Zone the listener is in, applied to effects as they start
+    echoes: Vec<Echo>,
+}
+
+impl<'aud> AudioManager<'aud> {
+    pub fn new(music_tracks: Vec<Option<Music<'aud>>>, walking_sound: Option<Sound<'aud>>, chatter: Vec<ChatterLine<'aud>>) -> Self {
+        let mut audio_manager = AudioManager {
+            master_volume: 1.0,
+            group_volumes: [0.5, 0.7, 0.7, 0.6],
+            duck_timer: 0.0,
+            duck_gain: 1.0,
+            fade_gain: 1.0,
             is_music_enabled: true,
             is_sfx_enabled: true,
+            music_tracks,
+            current_track: None,
+            music_paused: false,
+            walking_sound,
+            chatter: Chatter::new(chatter),
+            reverb: ReverbZone::Dry,
+            echoes: Vec::new(),
+        };
+        audio_manager.apply_footstep_volume();
+        audio_manager
+    }
+
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, 1.0);
+        self.apply_music_volume();
+        self.apply_footstep_volume();
+    }
+
+    pub fn get_master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    pub fn set_group_volume(&mut self, group: MixGroup, volume: f32) {
+        self.group_volumes[group as usize] = volume.clamp(0.0, 1.0);
+    }
+
+    pub fn get_group_volume(&self, group: MixGroup) -> f32 {
+        self.group_volumes[group as usize]
+    }
+
+    // What a group actually plays at: its slider times the master volume, music also ducked and faded
+    pub fn output_volume(&self, group: MixGroup) -> f32 {
+        let volume = self.master_volume * self.get_group_volume(group);
+        if group == MixGroup::Music {
+            volume * self.duck_gain * self.fade_gain
+        } else {
+            volume
         }
     }
 
     pub fn set_music_volume(&mut self, volume: f32) {
-        self.music_volume = volume.clamp(0.0, 1.0);
+        self.set_group_volume(MixGroup::Music, volume);
+        self.apply_music_volume();
     }
 
     pub fn get_music_volume(&self) -> f32 {
-        self.music_volume
+        self.get_group_volume(MixGroup::Music)
     }
 
     pub fn set_sfx_volume(&mut self, volume: f32) {
-        self.sfx_volume = volume.clamp(0.0, 1.0);
+        self.set_group_volume(MixGroup::Sfx, volume);
+        self.apply_footstep_volume();
     }
 
     pub fn get_sfx_volume(&self) -> f32 {
-        self.sfx_volume
+        self.get_group_volume(MixGroup::Sfx)
+    }
+
+    // Pull the music down for a while so an important sound cuts through
+    pub fn duck(&mut self, seconds: f32) {
+        self.duck_timer = self.duck_timer.max(seconds);
+    }
+
+    // Every per-frame sound job: feed and loop the music stream, ease fades and
+    // ducking, keep the footstep loop in step with the player and play due echoes
+    pub fn update(&mut self, delta_time: f32, listener: &Listener) {
+        let previous_gain = self.duck_gain * self.fade_gain;
+        self.duck_timer = (self.duck_timer - delta_time).max(0.0);
+        self.duck_gain = if self.duck_timer > 0.0 {
+            (self.duck_gain - DUCK_ATTACK * delta_time).max(DUCK_LEVEL)
+        } else {
+            (self.duck_gain + DUCK_RELEASE * delta_time).min(1.0)
+        };
+        self.fade_gain = (self.fade_gain + MUSIC_FADE_IN * delta_time).min(1.0);
+
+        let should_play = self.is_music_enabled && !self.music_paused;
+        if let Some(music) = self.music() {
+            music.update_stream();
+
+            // Loop by hand: restart the track if it ran out while it should be playing
+            if should_play && !music.is_stream_playing() {
+                music.play_stream();
+            }
+            if self.duck_gain * self.fade_gain != previous_gain {
+                music.set_volume(self.output_volume(MixGroup::Music));
+            }
+        }
+
+        // The radio waits out pauses with the music
+        if !self.music_paused {
+            let volume = self.output_volume(MixGroup::Sfx);
+            if let Some(seconds) = self.chatter.update(delta_time, self.is_sfx_enabled, volume) {
+                self.duck(seconds);
+            }
+        }
+
+        // Footsteps loop while the player moves, pitched by the floor underfoot
+        let sfx_enabled = self.is_sfx_enabled;
+        if let Some(sound) = self.walking_sound.as_mut() {
+            if listener.moving && sfx_enabled {
+                sound.set_pitch(footstep_pitch(listener.floor));
+                if !sound.is_playing() {
+                    sound.play();
+                }
+            } else if sound.is_playing() {
+                sound.stop();
+            }
+        }
+
+        self.reverb = listener.reverb;
+        for echo in self.echoes.iter_mut().filter(|echo| !echo.started) {
+            echo.delay -= delta_time;
+            if echo.delay <= 0.0 {
+                unsafe { raylib::ffi::PlaySound(echo.alias) };
+                echo.started = true;
+            }
+        }
+        self.echoes.retain(|echo| !echo.started || unsafe { raylib::ffi::IsSoundPlaying(echo.alias) });
+    }
+
+    fn music(&self) -> Option<&Music<'aud>> {
+        self.current_track.and_then(|track| self.music_tracks.get(track)).and_then(|music| music.as_ref())
+    }
+
+    fn apply_music_volume(&self) {
+        if let Some(music) = self.music() {
+            music.set_volume(self.output_volume(MixGroup::Music));
+        }
+    }
+
+    fn apply_footstep_volume(&mut self) {
+        let volume = self.output_volume(MixGroup::Sfx) * FOOTSTEP_VOLUME;
+        if let Some(sound) = self.walking_sound.as_mut() {
+            sound.set_volume(volume);
+        }
+    }
+
+    // A map's track from the top, fading in
+    pub fn play_music(&mut self, track: usize) {
+        self.stop_music();
+        self.current_track = Some(track);
+        self.music_paused = false;
+        self.fade_gain = 0.0;
+        if let Some(music) = self.music() {
+            music.set_volume(self.output_volume(MixGroup::Music));
+            if self.is_music_enabled {
+                music.play_stream();
+            }
+        }
+    }
+
+    pub fn stop_music(&mut self) {
+        if let Some(music) = self.music() {
+            music.stop_stream();
+        }
+        self.current_track = None;
+        self.chatter.clear();
+        if let Some(sound) = self.walking_sound.as_ref() {
+            sound.stop();
+        }
+    }
+
+    // Held while a pause menu is open
+    pub fn pause_music(&mut self) {
+        self.music_paused = true;
+        if let Some(music) = self.music() {
+            music.pause_stream();
+        }
+        self.chatter.pause();
+        if let Some(sound) = self.walking_sound.as_ref() {
+            sound.stop();
+        }
+    }
+
+    pub fn resume_music(&mut self) {
+        self.music_paused = false;
+        if let Some(music) = self.music() && self.is_music_enabled {
+            music.resume_stream();
+        }
+        self.chatter.resume();
+    }
+
+    pub fn current_track(&self) -> Option<usize> {
+        self.current_track
+    }
+
+    // Seconds into the current track, None while it is silent
+    pub fn music_time(&self) -> Option<f32> {
+        let audible = self.is_music_enabled && !self.music_paused;
+        self.music().filter(|_| audible).map(|music| music.get_time_played())
     }
 
     pub fn is_music_enabled(&self) -> bool {
@@ -45,10 +272,17 @@ impl AudioManager {
 
     pub fn set_music_enabled(&mut self, enabled: bool) {
         self.is_music_enabled = enabled;
+        if let Some(music) = self.music() {
+            if !enabled {
+                music.pause_stream();
+            } else if !self.music_paused {
+                music.resume_stream();
+            }
+        }
     }
 
     pub fn toggle_music(&mut self) {
-        self.is_music_enabled = !self.is_music_enabled;
+        self.set_music_enabled(!self.is_music_enabled);
     }
 
     pub fn is_sfx_enabled(&self) -> bool {
@@ -63,51 +297,104 @@ impl AudioManager {
         self.is_sfx_enabled = !self.is_sfx_enabled;
     }
 
-    pub fn play_footstep(&self, sound: &Sound) {
-        if self.is_sfx_enabled {
-            // Direct sound playback using Sound's methods
-            sound.play();
+    pub fn set_sound_volume(&self, sound: &mut Sound, volume_multiplier: f32) {
+        sound.set_volume(self.output_volume(MixGroup::Sfx) * volume_multiplier);
+    }
+
+    // A sound effect plus the echo taps of the zone the listener is in. Aliases start
+    // at full volume and normal pitch, so the sound's own settings are passed along.
+    fn play_sfx(&mut self, sound: &Sound, volume_multiplier: f32, pitch: f32) {
+        if !self.is_sfx_enabled {
+            return;
+        }
+        sound.play();
+
+        let params = self.reverb.params();
+        let mut tap_volume = self.output_volume(MixGroup::Sfx) * volume_multiplier;
+        for tap in 1..=params.taps {
+            if self.echoes.len() >= MAX_ECHOES {
+                break;
+            }
+            tap_volume *= params.decay;
+            let alias = unsafe { raylib::ffi::LoadSoundAlias(**sound) };
+            unsafe {
+                raylib::ffi::SetSoundVolume(alias, tap_volume);
+                raylib::ffi::SetSoundPitch(alias, pitch * params.pitch);
+            }
+            self.echoes.push(Echo { alias, delay: params.delay * tap as f32, started: false });
         }
     }
 
-    pub fn set_sound_volume(&self, sound: &mut Sound, volume_multiplier: f32) {
-        sound.set_volume(self.sfx_volume * volume_multiplier);
+    // Combat sound effects
+    pub fn play_sword_swing(&mut self, sound: &Sound) {
+        self.play_sfx(sound, SWORD_VOLUME, 1.0);
+    }
+
+    pub fn play_enemy_hit(&mut self, sound: &Sound) {
+        self.play_sfx(sound, HIT_VOLUME, 1.0);
+    }
+
+    pub fn play_enemy_death(&mut self, sound: &Sound) {
+        self.play_sfx(sound, 1.0, 1.0);
     }
 
-    pub fn setup_walking_sound(&self, walking_sound: &mut Option<Sound>) {
-        if let Some(sound) = walking_sound {
-            self.set_sound_volume(sound, 0.5); // Set walking sound volume to half of SFX volume
+    pub fn play_critical_hit(&mut self, sound: &Sound) {
+        self.play_sfx(sound, 1.0, CRITICAL_PITCH);
+    }
+
+    // A voice line for `trigger`, with the music ducked under it
+    pub fn play_chatter(&mut self, trigger: ChatterTrigger) {
+        let volume = self.output_volume(MixGroup::Sfx);
+        if let Some(seconds) = self.chatter.trigger(trigger, self.is_sfx_enabled, volume) {
+            self.duck(seconds);
         }
     }
 
-    // Combat sound effects
-    pub fn play_sword_swing(&self, sound: &Sound) {
+    pub fn chatter_subtitle(&self) -> Option<&str> {
+        self.chatter.subtitle()
+    }
+
+    // Arena doors slamming shut or grinding open, loud enough to duck the music
+    pub fn play_gate(&mut self, sound: &Sound) {
         if self.is_sfx_enabled {
-            sound.play();
+            self.play_sfx(sound, 1.0, GATE_PITCH);
+            self.duck(1.5);
         }
     }
 
-    pub fn play_enemy_hit(&self, sound: &Sound) {
-        if self.is_sfx_enabled {
-            sound.play();
+    // No dedicated door sample yet: the death thud pitched down reads as a heavy gate
+    pub fn setup_gate_sound(&self, gate_sound: &mut Option<Sound>) {
+        if let Some(sound) = gate_sound {
+            self.set_sound_volume(sound, 1.0);
+            sound.set_pitch(GATE_PITCH);
         }
     }
 
-    pub fn play_enemy_death(&self, sound: &Sound) {
-        if self.is_sfx_enabled {
-            sound.play();
+    pub fn setup_critical_sound(&self, critical_sound: &mut Option<Sound>) {
+        if let Some(sound) = critical_sound {
+            self.set_sound_volume(sound, 1.0);
+            sound.set_pitch(CRITICAL_PITCH);
         }
     }
 
     pub fn setup_combat_sounds(&self, sword_sound: &mut Option<Sound>, hit_sound: &mut Option<Sound>, death_sound: &mut Option<Sound>) {
         if let Some(sound) = sword_sound {
-            self.set_sound_volume(sound, 0.8); // Sword swing at 80% SFX volume
+            self.set_sound_volume(sound, SWORD_VOLUME);
         }
         if let Some(sound) = hit_sound {
-            self.set_sound_volume(sound, 0.9); // Enemy hit at 90% SFX volume
+            self.set_sound_volume(sound, HIT_VOLUME);
         }
         if let Some(sound) = death_sound {
             self.set_sound_volume(sound, 1.0); // Enemy death at full SFX volume
         }
     }
 }
+
+// Pitch the footstep loop down on water and swamp so the surface can be heard
+fn footstep_pitch(floor: Cell) -> f32 {
+    match floor {
+        Cell::Water => 0.75,
+        Cell::Swamp => 0.6,
+        _ => 1.0,
+    }
+}
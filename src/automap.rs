@@ -0,0 +1,206 @@
+// automap.rs
+
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+use crate::debugview::draw_enemy_ai;
+use crate::heatmap::draw_heatmap;
+use crate::minimap_cell_color;
+use crate::sim::Vec2;
+use crate::world::World;
+
+// Full-screen map opened with Tab while playing, where the player can pin their own notes to
+// cells: danger, loot and come-back-later icons. A cursor moves over the cells with the arrow
+// keys, the D-pad or the mouse; confirming opens a radial menu around it, and each of its four
+// options sits on one direction so a single press picks it. Markers stay on the map, show on
+// the minimap and go into saves, up to MAX_MARKERS per map.
+
+pub const MAX_MARKERS: usize = 16;
+const MAP_MARGIN: i32 = 60;
+const RADIAL_RADIUS: f32 = 70.0; // Pixels from the cursor to each option
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarkerKind {
+    Danger,
+    Loot,
+    ComeBack,
+}
+
+impl MarkerKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarkerKind::Danger => "Danger",
+            MarkerKind::Loot => "Loot",
+            MarkerKind::ComeBack => "Come back",
+        }
+    }
+
+    // Drawn in the middle of the marker, and the key it is saved under
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            MarkerKind::Danger => "!",
+            MarkerKind::Loot => "$",
+            MarkerKind::ComeBack => "?",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            MarkerKind::Danger => Color::new(235, 70, 60, 255),
+            MarkerKind::Loot => Color::new(250, 200, 60, 255),
+            MarkerKind::ComeBack => Color::new(90, 200, 255, 255),
+        }
+    }
+
+    fn from_symbol(symbol: &str) -> Option<MarkerKind> {
+        [MarkerKind::Danger, MarkerKind::Loot, MarkerKind::ComeBack].into_iter().find(|kind| kind.symbol() == symbol)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Marker {
+    pub cell: (usize, usize),
+    pub kind: MarkerKind,
+}
+
+// "x:y:symbol" entries joined with commas, for the save file
+pub fn encode_markers(markers: &[Marker]) -> String {
+    let entries: Vec<String> = markers.iter().map(|marker| format!("{}:{}:{}", marker.cell.0, marker.cell.1, marker.kind.symbol())).collect();
+    entries.join(",")
+}
+
+pub fn decode_markers(value: &str) -> Vec<Marker> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.splitn(3, ':');
+            let x = parts.next()?.parse().ok()?;
+            let y = parts.next()?.parse().ok()?;
+            let kind = MarkerKind::from_symbol(parts.next()?)?;
+            Some(Marker { cell: (x, y), kind })
+        })
+        .take(MAX_MARKERS)
+        .collect()
+}
+
+// A cell holds one marker: a new one replaces it, and a full map takes no more
+pub fn place_marker(markers: &mut Vec<Marker>, cell: (usize, usize), kind: MarkerKind) -> bool {
+    if let Some(marker) = markers.iter_mut().find(|marker| marker.cell == cell) {
+        marker.kind = kind;
+        return true;
+    }
+    if markers.len() >= MAX_MARKERS {
+        return false;
+    }
+    markers.push(Marker { cell, kind });
+    true
+}
+
+// Radial menu options, clockwise from the top to match up, right, down and left
+pub const RADIAL_OPTIONS: [Option<MarkerKind>; 4] = [Some(MarkerKind::Danger), Some(MarkerKind::Loot), Some(MarkerKind::ComeBack), None];
+
+// The option a direction points at, for the mouse and the stick
+pub fn radial_option(direction: Vec2) -> usize {
+    let angle = direction.x.atan2(-direction.y); // 0 straight up, growing clockwise
+    ((angle + 2.0 * PI + PI / 4.0) / (PI / 2.0)) as usize % RADIAL_OPTIONS.len()
+}
+
+// Where the map sits on screen: its top-left corner and the pixels per cell
+pub fn map_layout(world: &World, screen_width: i32, screen_height: i32) -> Option<(i32, i32, i32)> {
+    let maze = world.maze()?;
+    let cell_size = ((screen_width - MAP_MARGIN * 2) / maze.width() as i32).min((screen_height - MAP_MARGIN * 2) / maze.height() as i32).max(1);
+    let origin_x = (screen_width - cell_size * maze.width() as i32) / 2;
+    let origin_y = (screen_height - cell_size * maze.height() as i32) / 2;
+    Some((origin_x, origin_y, cell_size))
+}
+
+fn draw_marker(d: &mut RaylibDrawHandle, center: Vector2, radius: f32, kind: MarkerKind) {
+    d.draw_circle_v(center, radius, kind.color());
+    d.draw_circle_lines(center.x as i32, center.y as i32, radius, Color::BLACK);
+    let size = (radius * 1.4) as i32;
+    d.draw_text(kind.symbol(), center.x as i32 - size / 4, center.y as i32 - size / 2, size, Color::BLACK);
+}
+
+// What the map screen shows on top of the map
+pub struct MapView<'a> {
+    pub cursor: (usize, usize),
+    pub radial: Option<usize>, // Highlighted option while the radial menu is open
+    pub message: Option<&'a str>,
+    pub heatmap: bool,         // Tint the cells by how often they were visited
+    pub review: bool,          // The end screens' look back over the route, where markers can't be changed
+    pub ai_overlay: bool,
+}
+
+// The map with every marker, the player, the cursor and, while picking, the radial menu around it
+pub fn draw_automap(d: &mut RaylibDrawHandle, world: &World, view: &MapView, screen_width: i32, screen_height: i32) {
+    let MapView { cursor, radial, message, heatmap, review, ai_overlay } = *view;
+    let (Some(maze), Some((origin_x, origin_y, cell_size))) = (world.maze(), map_layout(world, screen_width, screen_height)) else {
+        return;
+    };
+    let cell_center = |(x, y): (usize, usize)| {
+        Vector2::new((origin_x + x as i32 * cell_size) as f32 + cell_size as f32 / 2.0, (origin_y + y as i32 * cell_size) as f32 + cell_size as f32 / 2.0)
+    };
+
+    d.draw_rectangle(0, 0, screen_width, screen_height, Color::new(0, 0, 0, 210));
+    for (x, y, cell) in maze.cells() {
+        d.draw_rectangle(origin_x + x as i32 * cell_size, origin_y + y as i32 * cell_size, cell_size, cell_size, minimap_cell_color(cell));
+    }
+    if heatmap {
+        draw_heatmap(d, &world.visited, origin_x, origin_y, cell_size, (origin_x + 280, origin_y - 24));
+    }
+
+    let radius = (cell_size as f32 * 0.4).max(4.0);
+    for marker in world.markers.iter() {
+        draw_marker(d, cell_center(marker.cell), radius, marker.kind);
+    }
+
+    let scale = cell_size as f32 / world.block_size as f32;
+    if ai_overlay {
+        draw_enemy_ai(d, world, |pos| Vector2::new(origin_x as f32 + pos.x * scale, origin_y as f32 + pos.y * scale), cell_size);
+    }
+    let player = Vector2::new(origin_x as f32 + world.player.pos.x * scale, origin_y as f32 + world.player.pos.y * scale);
+    d.draw_circle_v(player, (cell_size as f32 * 0.3).max(3.0), Color::RED);
+    let facing = Vector2::new(player.x + world.player.a.cos() * cell_size as f32 * 0.6, player.y + world.player.a.sin() * cell_size as f32 * 0.6);
+    d.draw_line_ex(player, facing, 2.0, Color::YELLOW);
+
+    if !review {
+        let cursor_x = origin_x + cursor.0 as i32 * cell_size;
+        let cursor_y = origin_y + cursor.1 as i32 * cell_size;
+        d.draw_rectangle_lines_ex(Rectangle::new(cursor_x as f32 - 1.0, cursor_y as f32 - 1.0, cell_size as f32 + 2.0, cell_size as f32 + 2.0), 2.0, Color::WHITE);
+    }
+
+    if let Some(selected) = radial {
+        let center = cell_center(cursor);
+        d.draw_circle_v(center, RADIAL_RADIUS + 28.0, Color::new(0, 0, 0, 170));
+        for (index, option) in RADIAL_OPTIONS.iter().enumerate() {
+            let angle = index as f32 * PI / 2.0;
+            let pos = Vector2::new(center.x + angle.sin() * RADIAL_RADIUS, center.y - angle.cos() * RADIAL_RADIUS);
+            let option_radius = if index == selected { 20.0 } else { 15.0 };
+            match option {
+                Some(kind) => draw_marker(d, pos, option_radius, *kind),
+                None => {
+                    d.draw_circle_v(pos, option_radius, Color::DARKGRAY);
+                    d.draw_text("x", pos.x as i32 - 5, pos.y as i32 - 11, 22, Color::WHITE);
+                }
+            }
+            if index == selected {
+                let label = option.map_or("Remove", |kind| kind.name());
+                d.draw_text(label, center.x as i32 - 20 * label.len() as i32 / 4, center.y as i32 - 10, 20, Color::WHITE);
+            }
+        }
+    }
+
+    let title = format!("MAP | Markers {}/{}", world.markers.len(), MAX_MARKERS);
+    d.draw_text(&title, origin_x, origin_y - 34, 24, Color::WHITE);
+    let help = if review {
+        "H or Square: Heatmap | M, Esc or Circle: Close"
+    } else if radial.is_some() {
+        "Arrows, mouse or stick: Pick | Enter: Place | Esc: Back"
+    } else {
+        "Arrows, mouse or D-pad: Move | Enter, click or Cross: Mark | H or Square: Heatmap | Tab or Esc: Close"
+    };
+    d.draw_text(help, origin_x, screen_height - MAP_MARGIN + 16, 16, Color::LIGHTGRAY);
+    if let Some(message) = message {
+        d.draw_text(message, origin_x, screen_height - MAP_MARGIN + 36, 18, Color::ORANGE);
+    }
+}
@@ -3,13 +3,35 @@
 use raylib::color::Color;
 
 use crate::framebuffer::Framebuffer;
-use crate::maze::Maze;
+use crate::maze::{Cell, Maze, is_partial_wall, is_see_through_wall, is_walkable, partial_wall_offset};
 use crate::player::Player;
 
+// Side of the wall cell that the ray hit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WallFace {
+  North,
+  South,
+  East,
+  West,
+}
+
+impl WallFace {
+  // Faces on vertical grid lines, which rays reach by crossing them east or west
+  pub fn is_east_west(&self) -> bool {
+    matches!(self, WallFace::East | WallFace::West)
+  }
+}
+
+// Anything outside the map renders as a stone corner block
+const OUT_OF_BOUNDS: Cell = Cell::Wall { texture: '+' };
+
 pub struct Intersect {
   pub distance: f32,
-  pub impact: char,
+  pub impact: Cell,
   pub tx: usize,
+  pub cell_x: usize,
+  pub cell_y: usize,
+  pub face: WallFace,
 }
 
 pub fn cast_ray(
@@ -20,66 +42,164 @@ pub fn cast_ray(
   block_size: usize,
   draw_line: bool,
 ) -> Intersect {
-  let mut d = 0.0;
+  let mut hits = Vec::with_capacity(1);
+  trace_ray(draw_line.then_some(framebuffer), maze, player, a, block_size, false, &mut hits);
+  hits.pop().unwrap_or(Intersect { distance: 0.0, impact: OUT_OF_BOUNDS, tx: 0, cell_x: 0, cell_y: 0, face: WallFace::North })
+}
 
-  framebuffer.set_current_color(Color::WHITESMOKE);
+// Collect every see-through surface along the ray (nearest first) followed by the opaque wall that stops it
+pub fn cast_ray_layers(
+  maze: &Maze,
+  player: &Player,
+  a: f32,
+  block_size: usize,
+  hits: &mut Vec<Intersect>,
+) {
+  hits.clear();
+  trace_ray(None, maze, player, a, block_size, true, hits);
+}
+
+// Walks the ray cell to cell through the grid (DDA): each step crosses to whichever of the next
+// vertical or horizontal grid line is nearer, so every cell the ray passes is looked at exactly
+// once and wall hits land exactly on the face that was crossed, which is where the texture
+// coordinate comes from. Thin and diagonal walls are found where the ray crosses their segment
+// between entering and leaving their cell.
+fn trace_ray(
+  mut line: Option<&mut Framebuffer>, // Where to draw the ray's path, for the top-down view
+  maze: &Maze,
+  player: &Player,
+  a: f32,
+  block_size: usize,
+  see_through: bool,
+  hits: &mut Vec<Intersect>,
+) {
+  let block = block_size as f32;
+  let (dir_x, dir_y) = (a.cos(), a.sin());
+  let origin_x = player.pos.x;
+  let origin_y = player.pos.y;
+
+  // Faces the ray can see depend only on its direction
+  let ns_face = if dir_y > 0.0 { WallFace::North } else { WallFace::South };
+  let ew_face = if dir_x > 0.0 { WallFace::West } else { WallFace::East };
+
+  let mut i = (origin_x / block).floor() as i32;
+  let mut j = (origin_y / block).floor() as i32;
+  let step_i = if dir_x > 0.0 { 1 } else { -1 };
+  let step_j = if dir_y > 0.0 { 1 } else { -1 };
+
+  // Ray distance between two vertical (and two horizontal) grid lines, and to the first of each
+  let delta_x = if dir_x == 0.0 { f32::INFINITY } else { (block / dir_x).abs() };
+  let delta_y = if dir_y == 0.0 { f32::INFINITY } else { (block / dir_y).abs() };
+  let first_x = if dir_x > 0.0 { (i + 1) as f32 * block - origin_x } else { origin_x - i as f32 * block };
+  let first_y = if dir_y > 0.0 { (j + 1) as f32 * block - origin_y } else { origin_y - j as f32 * block };
+  let mut side_x = if dir_x == 0.0 { f32::INFINITY } else { first_x / dir_x.abs() };
+  let mut side_y = if dir_y == 0.0 { f32::INFINITY } else { first_y / dir_y.abs() };
+
+  let mut entered = 0.0;
+  let mut crossed_x = None; // Whether the ray came into this cell over a vertical grid line, None in the player's cell
+
+  if let Some(framebuffer) = line.as_deref_mut() {
+    framebuffer.set_current_color(Color::WHITESMOKE);
+  }
 
   loop {
-    let cos = d * a.cos();
-    let sin = d * a.sin();
-    let ray_x = player.pos.x + cos;
-    let ray_y = player.pos.y + sin;
-
-    // Check for negative coordinates before casting to usize
-    if ray_x < 0.0 || ray_y < 0.0 {
-      return Intersect{
-        distance: d,
-        impact: '+', // Return wall character for out of bounds
-        tx: 0
+    let exit = side_x.min(side_y);
+    let cell_x = i.max(0) as usize;
+    let cell_y = j.max(0) as usize;
+    let cell_origin_x = i as f32 * block;
+    let cell_origin_y = j as f32 * block;
+    let point = |d: f32| (origin_x + d * dir_x, origin_y + d * dir_y);
+
+    // Anything past the edge of the map stops the ray
+    let Some(cell) = maze.get_i32(i, j) else {
+      hits.push(Intersect {
+        distance: entered,
+        impact: OUT_OF_BOUNDS,
+        tx: 0,
+        cell_x,
+        cell_y,
+        face: if crossed_x == Some(true) { ew_face } else { ns_face },
+      });
+      return;
+    };
+
+    if is_partial_wall(cell) {
+      // Thin and diagonal walls: the offset from the segment changes linearly along the ray,
+      // so it crosses zero between entering and leaving the cell if the sign flips
+      let (enter_x, enter_y) = point(entered);
+      let (exit_x, exit_y) = point(exit);
+      let before = partial_wall_offset(cell, enter_x - cell_origin_x, enter_y - cell_origin_y, block);
+      let after = partial_wall_offset(cell, exit_x - cell_origin_x, exit_y - cell_origin_y, block);
+
+      if before == 0.0 || before.signum() != after.signum() {
+        let distance = if before == 0.0 { entered } else { entered + (exit - entered) * before / (before - after) };
+        let (hit_x, hit_y) = point(distance);
+
+        // Texture runs along the segment: x for horizontal and diagonal walls, y for vertical ones
+        let vertical = cell == Cell::Fence { vertical: true };
+        let along = if vertical { hit_y - cell_origin_y } else { hit_x - cell_origin_x };
+
+        hits.push(Intersect {
+          distance,
+          impact: cell,
+          tx: texture_x(along, block),
+          cell_x,
+          cell_y,
+          face: if vertical { ew_face } else { ns_face },
+        });
+
+        if !(see_through && is_see_through_wall(cell)) {
+          return;
+        }
+      }
+    } else if !is_walkable(cell) {
+      // The face crossed to get in: the texture runs along y on east and west faces, x on the others
+      let (hit_x, hit_y) = point(entered);
+      let (face, along) = match crossed_x {
+        Some(true) => (ew_face, hit_y - cell_origin_y),
+        Some(false) => (ns_face, hit_x - cell_origin_x),
+        None => (ns_face, 0.0),
       };
-    }
 
-    let x = ray_x as usize;
-    let y = ray_y as usize;
-
-    let i = x / block_size;
-    let j = y / block_size;
-
-    // Add bounds checking to prevent crash
-    if j >= maze.len() || i >= maze[0].len() {
-      return Intersect{
-        distance: d,
-        impact: '+', // Return wall character for out of bounds
-        tx: 0
-      };
+      hits.push(Intersect {
+        distance: entered,
+        impact: cell,
+        tx: texture_x(along, block),
+        cell_x,
+        cell_y,
+        face,
+      });
+
+      // Windows only record their front face, then the ray keeps going to find the wall behind
+      if !(see_through && is_see_through_wall(cell)) {
+        return;
+      }
     }
 
-    if maze[j][i] != ' ' && maze[j][i] != 'p' {
-      let hitx = x - i*block_size;
-      let hity = y - j*block_size;
-      let mut maxhit = hity;
-
-      if 1 < hitx && hitx < block_size - 1 {
-        maxhit = hitx
-      } 
-
-      // Fix texture coordinate calculation with proper floating point math
-      let tx = ((maxhit as f32 * 127.0) / block_size as f32) as usize;
-
-      return Intersect{
-        distance: d,
-        impact: maze[j][i],
-        tx: tx
-      };
+    if let Some(framebuffer) = line.as_deref_mut() {
+      let mut d = entered;
+      while d < exit {
+        let (x, y) = point(d);
+        framebuffer.set_pixel(x as u32, y as u32);
+        d += 1.0;
+      }
     }
 
-    if draw_line {
-      framebuffer.set_pixel(x as u32, y as u32);
+    // On to the next cell, over whichever grid line comes first
+    entered = exit;
+    if side_x < side_y {
+      side_x += delta_x;
+      i += step_i;
+      crossed_x = Some(true);
+    } else {
+      side_y += delta_y;
+      j += step_j;
+      crossed_x = Some(false);
     }
-
-    d += 1.0;
   }
 }
 
-
-
+// Texture column for a hit `along` world units into a wall face
+fn texture_x(along: f32, block: f32) -> usize {
+  ((along * 127.0) / block).clamp(0.0, 127.0) as usize
+}
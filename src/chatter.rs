@@ -0,0 +1,243 @@
+// chatter.rs
+
+use raylib::prelude::*;
+use std::fs;
+
+use crate::mods;
+
+// Radio chatter: short voice lines with subtitles, set off by moments in play. chatter.def lists
+// them in [trigger] sections, and a trigger with several sections takes turns through them.
+// Only one line plays at a time. A line of higher priority cuts off the one playing, anything
+// else waits its turn in a single slot, where it gives way to a more important line and goes
+// stale after QUEUE_TIMEOUT. Each trigger then stays quiet for REPEAT_GAP so it can't nag.
+// Lines without a clip show just their subtitle, for `seconds`.
+
+pub const CHATTER_PATH: &str = "chatter.def";
+const DEFAULT_SECONDS: f32 = 3.0; // Subtitle time of a line without a clip or `seconds`
+const CLIP_TAIL: f32 = 0.4;       // Seconds a clip's subtitle stays up after it ends
+const TRIGGERS: usize = 6;
+const QUEUE_TIMEOUT: f32 = 5.0;   // Seconds a waiting line stays worth saying
+const REPEAT_GAP: f32 = 20.0;     // Seconds before the same trigger speaks again
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChatterTrigger {
+    MapStart,
+    ZoneEntered,
+    LowHealth,
+    BossStart,
+    ArenaCleared,
+    Downed,
+}
+
+impl ChatterTrigger {
+    fn from_name(name: &str) -> Option<ChatterTrigger> {
+        match name {
+            "map_start" => Some(ChatterTrigger::MapStart),
+            "zone" => Some(ChatterTrigger::ZoneEntered),
+            "low_health" => Some(ChatterTrigger::LowHealth),
+            "boss_start" => Some(ChatterTrigger::BossStart),
+            "arena_cleared" => Some(ChatterTrigger::ArenaCleared),
+            "downed" => Some(ChatterTrigger::Downed),
+            _ => None,
+        }
+    }
+}
+
+// One section of chatter.def, before its clip is loaded
+pub struct ChatterDef {
+    pub trigger: ChatterTrigger,
+    pub clip: Option<String>, // Sound file, resolved through the mods
+    pub subtitle: String,
+    pub seconds: Option<f32>,
+    pub priority: u32,
+}
+
+pub fn load_chatter_defs(path: &str) -> Vec<ChatterDef> {
+    let mut defs: Vec<ChatterDef> = Vec::new();
+    let contents = match fs::read_to_string(mods::resolve(path)) {
+        Ok(contents) => contents,
+        Err(_) => {
+            log!("No radio chatter found at {}", path);
+            return defs;
+        }
+    };
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            match ChatterTrigger::from_name(name.trim()) {
+                Some(trigger) => defs.push(ChatterDef { trigger, clip: None, subtitle: String::new(), seconds: None, priority: 1 }),
+                None => elog!("Warning: Ignoring chatter section with an unknown trigger in {}: {}", path, line),
+            }
+            continue;
+        }
+        let Some(def) = defs.last_mut() else {
+            elog!("Warning: Ignoring chatter line outside a section in {}: {}", path, line);
+            continue;
+        };
+        let parsed = line.split_once('=').and_then(|(name, value)| {
+            let value = value.trim();
+            match name.trim() {
+                "clip" => def.clip = Some(mods::resolve(value)),
+                "subtitle" => def.subtitle = value.to_string(),
+                "seconds" => def.seconds = Some(value.parse().ok().filter(|&seconds: &f32| seconds > 0.0)?),
+                "priority" => def.priority = value.parse().ok()?,
+                _ => return None,
+            }
+            Some(())
+        });
+        if parsed.is_none() {
+            elog!("Warning: Ignoring invalid chatter line in {}: {}", path, line);
+        }
+    }
+
+    defs.retain(|def| {
+        let usable = !def.subtitle.is_empty() || def.clip.is_some();
+        if !usable {
+            elog!("Warning: Ignoring a {:?} chatter line with neither clip nor subtitle", def.trigger);
+        }
+        usable
+    });
+    log!("Loaded {} chatter lines from {}", defs.len(), path);
+    defs
+}
+
+pub struct ChatterLine<'aud> {
+    pub def: ChatterDef,
+    pub sound: Option<Sound<'aud>>,
+}
+
+impl ChatterLine<'_> {
+    // How long the line holds the radio: `seconds` when given, otherwise the clip's length
+    fn duration(&self) -> f32 {
+        match (self.def.seconds, self.sound.as_ref()) {
+            (Some(seconds), _) => seconds,
+            (None, Some(sound)) if sound.stream.sampleRate > 0 => sound.frameCount as f32 / sound.stream.sampleRate as f32 + CLIP_TAIL,
+            (None, _) => DEFAULT_SECONDS,
+        }
+    }
+}
+
+struct Playing {
+    line: usize,
+    left: f32, // Seconds
+}
+
+struct Waiting {
+    line: usize,
+    age: f32,
+}
+
+pub struct Chatter<'aud> {
+    lines: Vec<ChatterLine<'aud>>,
+    next: [usize; TRIGGERS], // Per trigger, which of its lines comes up next
+    quiet: [f32; TRIGGERS],  // Per trigger, seconds until it can speak again
+    playing: Option<Playing>,
+    waiting: Option<Waiting>,
+}
+
+impl<'aud> Chatter<'aud> {
+    pub fn new(lines: Vec<ChatterLine<'aud>>) -> Self {
+        Chatter { lines, next: [0; TRIGGERS], quiet: [0.0; TRIGGERS], playing: None, waiting: None }
+    }
+
+    // The line `trigger` would say now, if it has any and isn't resting
+    fn pick(&mut self, trigger: ChatterTrigger) -> Option<usize> {
+        let slot = trigger as usize;
+        if self.quiet[slot] > 0.0 {
+            return None;
+        }
+        let candidates: Vec<usize> = (0..self.lines.len()).filter(|&i| self.lines[i].def.trigger == trigger).collect();
+        let line = *candidates.get(self.next[slot] % candidates.len().max(1))?;
+        self.next[slot] += 1;
+        self.quiet[slot] = REPEAT_GAP;
+        Some(line)
+    }
+
+    // Something happened worth a word. Returns the line's length in seconds if it starts now.
+    pub fn trigger(&mut self, trigger: ChatterTrigger, sound_on: bool, volume: f32) -> Option<f32> {
+        let line = self.pick(trigger)?;
+        let priority = self.lines[line].def.priority;
+        match self.playing.as_ref() {
+            Some(playing) if self.lines[playing.line].def.priority >= priority => {
+                let outranks = self.waiting.as_ref().is_none_or(|waiting| self.lines[waiting.line].def.priority < priority);
+                if outranks {
+                    self.waiting = Some(Waiting { line, age: 0.0 });
+                }
+                None
+            }
+            _ => Some(self.start(line, sound_on, volume)),
+        }
+    }
+
+    fn start(&mut self, line: usize, sound_on: bool, volume: f32) -> f32 {
+        self.stop();
+        let duration = self.lines[line].duration();
+        if let Some(sound) = self.lines[line].sound.as_mut() {
+            sound.set_volume(volume);
+            if sound_on {
+                sound.play();
+            }
+        }
+        log!("Chatter: {}", self.lines[line].def.subtitle);
+        self.playing = Some(Playing { line, left: duration });
+        duration
+    }
+
+    // Time passes for the line on air and the one waiting. Returns the length of a waiting
+    // line that starts now.
+    pub fn update(&mut self, delta_time: f32, sound_on: bool, volume: f32) -> Option<f32> {
+        for quiet in self.quiet.iter_mut() {
+            *quiet = (*quiet - delta_time).max(0.0);
+        }
+        if let Some(waiting) = self.waiting.as_mut() {
+            waiting.age += delta_time;
+            if waiting.age > QUEUE_TIMEOUT {
+                self.waiting = None;
+            }
+        }
+        if let Some(playing) = self.playing.as_mut() {
+            playing.left -= delta_time;
+            if playing.left > 0.0 {
+                return None;
+            }
+            self.playing = None;
+        }
+        let waiting = self.waiting.take()?;
+        Some(self.start(waiting.line, sound_on, volume))
+    }
+
+    // Quiet the radio, used when the map is left
+    pub fn stop(&mut self) {
+        if let Some(playing) = self.playing.take() && let Some(sound) = self.lines[playing.line].sound.as_ref() {
+            sound.stop();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.stop();
+        self.waiting = None;
+        self.quiet = [0.0; TRIGGERS];
+    }
+
+    pub fn pause(&self) {
+        if let Some(sound) = self.playing.as_ref().and_then(|playing| self.lines[playing.line].sound.as_ref()) {
+            sound.pause();
+        }
+    }
+
+    pub fn resume(&self) {
+        if let Some(sound) = self.playing.as_ref().and_then(|playing| self.lines[playing.line].sound.as_ref()) {
+            sound.resume();
+        }
+    }
+
+    // What is being said right now, for the subtitle
+    pub fn subtitle(&self) -> Option<&str> {
+        let playing = self.playing.as_ref()?;
+        Some(self.lines[playing.line].def.subtitle.as_str()).filter(|text| !text.is_empty())
+    }
+}
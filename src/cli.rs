@@ -0,0 +1,98 @@
+// cli.rs
+
+use crate::stress::{DEFAULT_STRESS_ENEMIES, StressMap};
+use crate::{AVAILABLE_MAPS, DAILY_MAP_INDEX, RUN_MAP_INDEX};
+
+pub const USAGE: &str = "Usage: proyecto-joseauyon [options]
+  --map <file|daily|run>  Select a map, e.g. --map maze2.txt
+  --skip-menu             Start playing the selected map straight away
+  --seed <n>              Seed runs and enemy behaviour instead of using the clock
+  --windowed              Stay in a window instead of going fullscreen
+  --width <px>            Window width
+  --height <px>           Window height
+  --mute                  Start with music and sound effects off
+  --stress <w>x<h>        Play a generated benchmark maze of that many cells and log frame timings
+  --stress-enemies <n>    Enemies on the benchmark maze (default 200)
+  --timescale <x>         Run the game clock at x times normal speed, e.g. 0.5, for testing
+  --convert <in> <out>    Convert a map between formats and exit
+  --report <map>          Print a balance report for a map and exit, add --ngplus for its New Game Plus spawns
+  --help                  Show this help";
+
+// Launch configuration, so testers and speedrunners can skip the menus
+#[derive(Clone, Debug, Default)]
+pub struct LaunchOptions {
+    pub map: Option<usize>, // Map index, same order as the start screen
+    pub skip_menu: bool,
+    pub seed: Option<u64>,
+    pub windowed: bool,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub mute: bool,
+    pub stress: Option<StressMap>, // Benchmark maze, starts straight into it
+    pub timescale: Option<f32>,    // Base game clock rate, see timescale.rs
+    pub help: bool,
+}
+
+impl LaunchOptions {
+    pub fn parse(args: &[String]) -> Result<LaunchOptions, String> {
+        let mut options = LaunchOptions::default();
+        let mut args = args.iter();
+        let mut stress_enemies = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--map" => options.map = Some(map_index(value(&mut args, arg)?)?),
+                "--skip-menu" => options.skip_menu = true,
+                "--seed" => options.seed = Some(number(&mut args, arg)?),
+                "--windowed" => options.windowed = true,
+                "--width" => options.width = Some(number(&mut args, arg)?),
+                "--height" => options.height = Some(number(&mut args, arg)?),
+                "--mute" => options.mute = true,
+                "--stress" => {
+                    let (width, height) = StressMap::parse_size(value(&mut args, arg)?)?;
+                    options.stress = Some(StressMap { width, height, enemies: DEFAULT_STRESS_ENEMIES });
+                }
+                "--stress-enemies" => stress_enemies = Some(number(&mut args, arg)?),
+                "--timescale" => {
+                    let scale: f32 = number(&mut args, arg)?;
+                    if !(scale > 0.0 && scale <= 10.0) {
+                        return Err(format!("--timescale expects a rate above 0 and at most 10, got {}", scale));
+                    }
+                    options.timescale = Some(scale);
+                }
+                "--help" | "-h" => options.help = true,
+                _ => return Err(format!("Unknown option {}", arg)),
+            }
+        }
+
+        if let Some(enemies) = stress_enemies {
+            match options.stress.as_mut() {
+                Some(stress) => stress.enemies = enemies,
+                None => return Err("--stress-enemies needs --stress".to_string()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn value<'a>(args: &mut impl Iterator<Item = &'a String>, option: &str) -> Result<&'a str, String> {
+    args.next().map(String::as_str).ok_or_else(|| format!("{} needs a value", option))
+}
+
+fn number<'a, T: std::str::FromStr>(args: &mut impl Iterator<Item = &'a String>, option: &str) -> Result<T, String> {
+    let text = value(args, option)?;
+    text.parse().map_err(|_| format!("{} expects a number, got {}", option, text))
+}
+
+// Map files by name, plus the generated modes
+fn map_index(name: &str) -> Result<usize, String> {
+    match name {
+        "daily" => return Ok(DAILY_MAP_INDEX),
+        "run" => return Ok(RUN_MAP_INDEX),
+        _ => {}
+    }
+    AVAILABLE_MAPS.iter().position(|map| map.filename == name).ok_or_else(|| {
+        let names: Vec<&str> = AVAILABLE_MAPS.iter().map(|map| map.filename).collect();
+        format!("Unknown map {}, expected one of {}, daily or run", name, names.join(", "))
+    })
+}
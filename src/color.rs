@@ -0,0 +1,72 @@
+// color.rs
+
+use raylib::prelude::Color;
+
+// Small color math shared by fog, lighting, tints and the post-process effects.
+// Blends work on RGB and keep the alpha of the first color.
+
+#[inline]
+pub fn lerp_channel(a: u8, b: u8, t: f32) -> u8 {
+    (a as f32 + (b as f32 - a as f32) * t).clamp(0.0, 255.0) as u8
+}
+
+// Straight blend from a (t = 0.0) to b (t = 1.0)
+#[inline]
+pub fn lerp(a: Color, b: Color, t: f32) -> Color {
+    Color::new(lerp_channel(a.r, b.r, t), lerp_channel(a.g, b.g, t), lerp_channel(a.b, b.b, t), a.a)
+}
+
+// Brightness scale, 1.0 leaves the color unchanged
+#[inline]
+pub fn scale(color: Color, factor: f32) -> Color {
+    Color::new(
+        (color.r as f32 * factor).clamp(0.0, 255.0) as u8,
+        (color.g as f32 * factor).clamp(0.0, 255.0) as u8,
+        (color.b as f32 * factor).clamp(0.0, 255.0) as u8,
+        color.a,
+    )
+}
+
+// Darkens: white leaves the color unchanged, black gives black
+#[inline]
+pub fn multiply(a: Color, b: Color) -> Color {
+    Color::new(
+        (a.r as u16 * b.r as u16 / 255) as u8,
+        (a.g as u16 * b.g as u16 / 255) as u8,
+        (a.b as u16 * b.b as u16 / 255) as u8,
+        a.a,
+    )
+}
+
+// Lightens: black leaves the color unchanged, white gives white
+#[inline]
+pub fn screen(a: Color, b: Color) -> Color {
+    let channel = |x: u8, y: u8| (255 - (255 - x as u16) * (255 - y as u16) / 255) as u8;
+    Color::new(channel(a.r, b.r), channel(a.g, b.g), channel(a.b, b.b), a.a)
+}
+
+// Blend in (approximately) linear light, so mixes with bright colors don't turn muddy.
+// Uses gamma 2.0 so the conversion is a square and a square root.
+#[inline]
+pub fn mix_gamma_channel(a: u8, b: u8, t: f32) -> u8 {
+    let la = (a as f32 / 255.0).powi(2);
+    let lb = (b as f32 / 255.0).powi(2);
+    ((la + (lb - la) * t).sqrt() * 255.0).clamp(0.0, 255.0) as u8
+}
+
+#[inline]
+pub fn mix_gamma(a: Color, b: Color, t: f32) -> Color {
+    Color::new(mix_gamma_channel(a.r, b.r, t), mix_gamma_channel(a.g, b.g, t), mix_gamma_channel(a.b, b.b, t), a.a)
+}
+
+// Same color, fully opaque
+#[inline]
+pub fn opaque(color: Color) -> Color {
+    Color::new(color.r, color.g, color.b, 255)
+}
+
+// Same color with another alpha, 0.0 (invisible) to 1.0 (solid)
+#[inline]
+pub fn with_alpha(color: Color, alpha: f32) -> Color {
+    Color::new(color.r, color.g, color.b, (alpha.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
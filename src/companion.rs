@@ -0,0 +1,149 @@
+// companion.rs
+
+use raylib::prelude::*;
+
+use crate::enemy::Enemy;
+use crate::has_line_of_sight;
+use crate::maze::Maze;
+use crate::pathfind::path;
+use crate::pickups::Pickup;
+
+// Optional wisp that floats after the player. It follows along the pathfinder's route so it
+// comes around corners instead of through walls, pulses and marks coins it finds lying
+// nearby with a ping, and on command flies at an enemy and keeps it busy for a few seconds.
+// It is switched on in Settings and drawn with the same sprite code as the enemies.
+
+const FOLLOW_DISTANCE: f32 = 1.2;   // Cells; it hangs back this far from the player
+const TELEPORT_DISTANCE: f32 = 10.0; // Cells; further than this, or with no route, it jumps back to the player
+const SPEED: f32 = 4.0;             // Cells per second
+const REPATH_INTERVAL: f32 = 0.5;   // Seconds between route searches
+const MAX_PATH_STEPS: usize = 40;
+const SENSE_RANGE: f32 = 4.0;       // Cells; coins this close get marked
+const SENSE_INTERVAL: f32 = 1.0;
+const PULSE_TIME: f32 = 1.0;        // Seconds it glows after finding something
+const DISTRACT_RANGE: f32 = 6.0;    // Cells from the player an enemy can be sent after
+const DISTRACT_TIME: f32 = 5.0;     // Seconds the enemy stays busy
+const DISTRACT_COOLDOWN: f32 = 12.0;
+
+pub struct Companion {
+    pub pos: Vector2,
+    route: Vec<(usize, usize)>, // Cells left to fly through
+    repath_timer: f32,
+    sense_timer: f32,
+    marked: Vec<Vector2>,       // Coins it has pinged already
+    pub pulse: f32,             // 1.0 right after it finds something, fading to 0.0
+    pub cooldown: f32,          // Seconds until it can distract again
+    pub age: f32,               // Drives the flicker animation
+}
+
+impl Companion {
+    pub fn new(pos: Vector2) -> Self {
+        Companion {
+            pos,
+            route: Vec::new(),
+            repath_timer: 0.0,
+            sense_timer: SENSE_INTERVAL,
+            marked: Vec::new(),
+            pulse: 0.0,
+            cooldown: 0.0,
+            age: 0.0,
+        }
+    }
+
+    // Returns a coin it has just spotted, for the caller to ping
+    pub fn update(&mut self, delta_time: f32, player_pos: Vector2, enemies: &[Enemy], pickups: &[Pickup], maze: &Maze,
+                  block_size: usize) -> Option<Vector2> {
+        let block = block_size as f32;
+        self.age += delta_time;
+        self.pulse = (self.pulse - delta_time / PULSE_TIME).max(0.0);
+        self.cooldown = (self.cooldown - delta_time).max(0.0);
+
+        // Hover by the enemy it is distracting, otherwise by the player
+        let busy_with = enemies.iter().find(|enemy| !enemy.is_dead && enemy.distracted > 0.0).map(|enemy| enemy.pos);
+        let target = busy_with.unwrap_or(player_pos);
+        let stop_distance = if busy_with.is_some() { 0.5 } else { FOLLOW_DISTANCE };
+        let target_distance = distance(self.pos, target) / block;
+
+        self.repath_timer -= delta_time;
+        if self.repath_timer <= 0.0 {
+            self.repath_timer = REPATH_INTERVAL;
+            let cell = |pos: Vector2| ((pos.x / block) as usize, (pos.y / block) as usize);
+            match path(maze, cell(self.pos), cell(target), MAX_PATH_STEPS) {
+                Some(route) => self.route = route,
+                None if busy_with.is_none() => self.pos = player_pos, // Lost the player, catch up
+                None => self.route.clear(),
+            }
+        }
+        if busy_with.is_none() && target_distance > TELEPORT_DISTANCE {
+            self.pos = player_pos;
+            self.route.clear();
+        }
+
+        // Straight at the target once it is in the open, along the route otherwise
+        if target_distance > stop_distance {
+            let waypoint = if has_line_of_sight(self.pos, target, maze, block_size) {
+                target
+            } else {
+                match self.route.first() {
+                    Some(&(x, y)) => Vector2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block),
+                    None => target,
+                }
+            };
+            let gap = distance(self.pos, waypoint);
+            let step = SPEED * block * delta_time;
+            if gap <= step {
+                self.pos = waypoint;
+                if !self.route.is_empty() && waypoint != target {
+                    self.route.remove(0);
+                }
+            } else if gap > 0.0 {
+                self.pos.x += (waypoint.x - self.pos.x) / gap * step;
+                self.pos.y += (waypoint.y - self.pos.y) / gap * step;
+            }
+        }
+
+        // Now and then look around for coins nobody has pointed out yet
+        self.sense_timer -= delta_time;
+        if self.sense_timer <= 0.0 {
+            self.sense_timer = SENSE_INTERVAL;
+            self.marked.retain(|pos| pickups.iter().any(|pickup| pickup.pos == *pos));
+            let found = pickups
+                .iter()
+                .filter(|pickup| !self.marked.contains(&pickup.pos))
+                .filter(|pickup| distance(self.pos, pickup.pos) <= SENSE_RANGE * block)
+                .min_by(|a, b| distance(self.pos, a.pos).total_cmp(&distance(self.pos, b.pos)));
+            if let Some(pickup) = found {
+                self.marked.push(pickup.pos);
+                self.pulse = 1.0;
+                return Some(pickup.pos);
+            }
+        }
+        None
+    }
+
+    // Send it after the closest enemy the player can see, returns false if there is none or it is resting
+    pub fn distract(&mut self, player_pos: Vector2, enemies: &mut [Enemy], maze: &Maze, block_size: usize) -> bool {
+        if self.cooldown > 0.0 || enemies.iter().any(|enemy| enemy.distracted > 0.0) {
+            return false;
+        }
+        let range = DISTRACT_RANGE * block_size as f32;
+        let closest = enemies
+            .iter_mut()
+            .filter(|enemy| !enemy.is_dead && distance(enemy.pos, player_pos) <= range)
+            .filter(|enemy| has_line_of_sight(player_pos, enemy.pos, maze, block_size))
+            .min_by(|a, b| distance(a.pos, player_pos).total_cmp(&distance(b.pos, player_pos)));
+        let Some(enemy) = closest else {
+            return false;
+        };
+
+        enemy.distracted = DISTRACT_TIME;
+        enemy.awareness = 1.0;
+        self.cooldown = DISTRACT_COOLDOWN;
+        self.repath_timer = 0.0;
+        true
+    }
+}
+
+fn distance(a: Vector2, b: Vector2) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
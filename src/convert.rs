@@ -0,0 +1,534 @@
+// convert.rs
+
+use raylib::prelude::*;
+use std::fs;
+use std::path::Path;
+
+use crate::maze::{Cell, MAZE_SECTION, Maze, is_partial_wall, parse_map_meta};
+use crate::minimap_cell_color;
+
+// Map authoring tool, run as `--convert <input> <output>` without opening a window.
+// Formats come from the file extensions:
+//   .txt          plain ASCII maze, one character per cell
+//   .map          ASCII maze below a "key = value" metadata header
+//   .json / .tmj  Tiled JSON map with one tile layer, tile ids are the map characters
+//   .png          top-down render of the map (output only)
+
+const TILE_SIZE: i32 = 32;       // Pixels per cell in renders and in the Tiled tileset
+const TILESET_COLUMNS: i32 = 16; // The tileset covers the 128 ASCII map characters
+const TILESET_COUNT: i32 = 128;
+const TILED_FLIP_MASK: u64 = 0x1FFF_FFFF; // Tiled keeps flip flags in the top bits of a gid
+
+// A map plus the metadata the extended and Tiled formats carry (name, author, ...)
+pub struct MapFile {
+    pub meta: Vec<(String, String)>,
+    pub maze: Maze,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MapFormat {
+    Ascii,
+    Extended,
+    Tiled,
+    Png,
+}
+
+impl MapFormat {
+    fn from_path(path: &str) -> Option<MapFormat> {
+        let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "txt" => Some(MapFormat::Ascii),
+            "map" => Some(MapFormat::Extended),
+            "json" | "tmj" => Some(MapFormat::Tiled),
+            "png" => Some(MapFormat::Png),
+            _ => None,
+        }
+    }
+}
+
+// Entry point for `--convert`, returns the process exit code
+pub fn run(args: &[String]) -> i32 {
+    let [input, output] = args else {
+        eprintln!("Usage: --convert <input.txt|.map|.json> <output.txt|.map|.json|.png>");
+        return 2;
+    };
+    let (Some(from), Some(to)) = (MapFormat::from_path(input), MapFormat::from_path(output)) else {
+        eprintln!("Error: Unknown map format, use .txt, .map, .json/.tmj or .png");
+        return 2;
+    };
+    if from == MapFormat::Png {
+        eprintln!("Error: PNG renders can't be converted back into maps");
+        return 2;
+    }
+
+    let contents = match fs::read_to_string(input) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("Error: Could not read {}: {:?}", input, e);
+            return 1;
+        }
+    };
+    let map = match from {
+        MapFormat::Ascii => MapFile { meta: Vec::new(), maze: parse_ascii(&contents) },
+        MapFormat::Extended => parse_extended(&contents),
+        _ => match parse_tiled(&contents) {
+            Ok(map) => map,
+            Err(message) => {
+                eprintln!("Error: {} is not a usable Tiled map: {}", input, message);
+                return 1;
+            }
+        },
+    };
+
+    let saved = match to {
+        MapFormat::Ascii => write_file(output, &to_ascii(&map.maze)),
+        MapFormat::Extended => write_file(output, &to_extended(&map)),
+        MapFormat::Tiled => {
+            // The tileset image sits next to the map so Tiled can open it straight away
+            let tileset_path = Path::new(output).with_extension("tiles.png");
+            let tileset_name = tileset_path.file_name().and_then(|name| name.to_str()).unwrap_or("tiles.png");
+            write_file(output, &to_tiled(&map, tileset_name))
+                && export(&render_tileset(), &tileset_path.to_string_lossy())
+        }
+        MapFormat::Png => export(&render_map(&map.maze), output),
+    };
+    if !saved {
+        return 1;
+    }
+
+    println!("Converted {} ({}x{}) to {}", input, map.maze.width(), map.maze.height(), output);
+    0
+}
+
+fn write_file(path: &str, contents: &str) -> bool {
+    match fs::write(path, contents) {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!("Error: Could not write {}: {:?}", path, e);
+            false
+        }
+    }
+}
+
+fn export(image: &Image, path: &str) -> bool {
+    if !image.export_image(path) {
+        eprintln!("Error: Could not write {}", path);
+        return false;
+    }
+    true
+}
+
+fn parse_ascii(contents: &str) -> Maze {
+    Maze::from_rows(contents.lines().map(|line| line.chars().map(Cell::from_char).collect()).collect())
+}
+
+fn to_ascii(maze: &Maze) -> String {
+    let mut text = String::new();
+    for row in maze.rows() {
+        let line: String = row.iter().map(|cell| cell.to_char()).collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+// "key = value" lines and comments up to the [maze] line, the grid after it.
+// Width and height are written for readers' benefit and always recomputed from the grid.
+fn parse_extended(contents: &str) -> MapFile {
+    let lines: Vec<&str> = contents.lines().collect();
+    let Some(section) = lines.iter().position(|line| line.trim() == MAZE_SECTION) else {
+        eprintln!("Warning: No {} line, reading the whole file as the grid", MAZE_SECTION);
+        return MapFile { meta: Vec::new(), maze: parse_ascii(contents) };
+    };
+
+    let mut meta = parse_map_meta(&lines[..section]);
+    meta.retain(|(key, _)| key != "width" && key != "height");
+    let maze = Maze::from_rows(lines[section + 1..].iter().map(|line| line.chars().map(Cell::from_char).collect()).collect());
+    MapFile { meta, maze }
+}
+
+fn to_extended(map: &MapFile) -> String {
+    let mut text = String::from("# Raycaster Dungeon map\n");
+    for (key, value) in map.meta.iter() {
+        text.push_str(&format!("{} = {}\n", key, value));
+    }
+    text.push_str(&format!("width = {}\nheight = {}\n", map.maze.width(), map.maze.height()));
+    text.push_str(MAZE_SECTION);
+    text.push('\n');
+    text.push_str(&to_ascii(&map.maze));
+    text
+}
+
+// One tile layer whose gids are firstgid + map character; empty floor is left blank
+fn to_tiled(map: &MapFile, tileset_image: &str) -> String {
+    let maze = &map.maze;
+    let data: Vec<String> = maze
+        .cells()
+        .map(|(_, _, cell)| match cell {
+            Cell::Empty => 0,
+            _ => 1 + tile_id(cell),
+        })
+        .map(|gid| gid.to_string())
+        .collect();
+    let properties: Vec<String> = map
+        .meta
+        .iter()
+        .map(|(key, value)| format!("{{\"name\":{},\"type\":\"string\",\"value\":{}}}", json_string(key), json_string(value)))
+        .collect();
+
+    format!(
+        concat!(
+            "{{\"type\":\"map\",\"version\":\"1.10\",\"orientation\":\"orthogonal\",\"renderorder\":\"right-down\",",
+            "\"width\":{w},\"height\":{h},\"tilewidth\":{ts},\"tileheight\":{ts},\"infinite\":false,",
+            "\"nextlayerid\":2,\"nextobjectid\":1,\n\"properties\":[{props}],\n",
+            "\"layers\":[{{\"id\":1,\"name\":\"maze\",\"type\":\"tilelayer\",\"x\":0,\"y\":0,",
+            "\"width\":{w},\"height\":{h},\"opacity\":1,\"visible\":true,\n\"data\":[{data}]}}],\n",
+            "\"tilesets\":[{{\"firstgid\":1,\"name\":\"map characters\",\"tilewidth\":{ts},\"tileheight\":{ts},",
+            "\"tilecount\":{count},\"columns\":{columns},\"margin\":0,\"spacing\":0,",
+            "\"image\":{image},\"imagewidth\":{iw},\"imageheight\":{ih}}}]}}\n"
+        ),
+        w = maze.width(),
+        h = maze.height(),
+        ts = TILE_SIZE,
+        props = properties.join(","),
+        data = data.join(","),
+        count = TILESET_COUNT,
+        columns = TILESET_COLUMNS,
+        image = json_string(tileset_image),
+        iw = TILESET_COLUMNS * TILE_SIZE,
+        ih = TILESET_COUNT / TILESET_COLUMNS * TILE_SIZE,
+    )
+}
+
+// Cells whose character falls outside the tileset export as plain wall
+fn tile_id(cell: Cell) -> u64 {
+    let ch = cell.to_char();
+    if (ch as u32) < TILESET_COUNT as u32 {
+        ch as u64
+    } else {
+        eprintln!("Warning: '{}' has no tile, exporting it as '#'", ch);
+        '#' as u64
+    }
+}
+
+fn parse_tiled(contents: &str) -> Result<MapFile, String> {
+    let root = JsonParser::new(contents).parse()?;
+    let layer = root
+        .get("layers")
+        .and_then(Json::as_array)
+        .and_then(|layers| layers.iter().find(|layer| layer.get("type").and_then(Json::as_str) == Some("tilelayer")))
+        .ok_or("no tile layer")?;
+    let width = layer.get("width").and_then(Json::as_f64).ok_or("tile layer has no width")? as usize;
+    let data = layer
+        .get("data")
+        .and_then(Json::as_array)
+        .ok_or("tile layer data must be stored as CSV, not base64")?;
+    let first_gid = root
+        .get("tilesets")
+        .and_then(Json::as_array)
+        .and_then(|tilesets| tilesets.first())
+        .and_then(|tileset| tileset.get("firstgid"))
+        .and_then(Json::as_f64)
+        .unwrap_or(1.0) as u64;
+    if width == 0 {
+        return Err("tile layer is empty".to_string());
+    }
+
+    let cells: Vec<Cell> = data
+        .iter()
+        .map(|value| {
+            let gid = value.as_f64().unwrap_or(0.0) as u64 & TILED_FLIP_MASK;
+            match gid.checked_sub(first_gid) {
+                Some(id) if id < TILESET_COUNT as u64 => Cell::from_char(id as u8 as char),
+                Some(_) => Cell::Wall { texture: '#' },
+                None => Cell::Empty, // gid 0 is an empty tile
+            }
+        })
+        .collect();
+
+    let meta = root
+        .get("properties")
+        .and_then(Json::as_array)
+        .map(|properties| {
+            properties
+                .iter()
+                .filter_map(|property| {
+                    let name = property.get("name")?.as_str()?;
+                    let value = match property.get("value")? {
+                        Json::Str(text) => text.clone(),
+                        Json::Number(number) => number.to_string(),
+                        Json::Bool(flag) => flag.to_string(),
+                        _ => return None,
+                    };
+                    Some((name.to_string(), value))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(MapFile { meta, maze: Maze::from_rows(cells.chunks(width).map(|row| row.to_vec()).collect()) })
+}
+
+// Map colors with spawn and goal called out; each wall character gets its own shade
+fn tile_color(cell: Cell) -> Color {
+    match cell {
+        Cell::Spawn => Color::new(60, 180, 75, 255),
+        Cell::Goal => Color::new(230, 190, 40, 255),
+        Cell::Coin => Color::new(200, 160, 40, 255),
+        Cell::Hint(_) => Color::new(60, 60, 90, 255),
+        Cell::ArenaTrigger => Color::new(120, 40, 40, 255),
+        Cell::AmbushSpawn => Color::new(90, 40, 90, 255),
+        Cell::Wall { texture } => {
+            let code = texture as u32;
+            Color::new(
+                (90 + code * 37 % 70) as u8,
+                (90 + code * 61 % 70) as u8,
+                (90 + code * 89 % 70) as u8,
+                255,
+            )
+        }
+        _ => minimap_cell_color(cell),
+    }
+}
+
+fn draw_tile(image: &mut Image, x: i32, y: i32, cell: Cell) {
+    image.draw_rectangle(x, y, TILE_SIZE, TILE_SIZE, tile_color(cell));
+
+    // Thin and diagonal walls as a line across their cell, like the minimap
+    if is_partial_wall(cell) {
+        let (x0, y0, x1, y1) = match cell {
+            Cell::Fence { vertical: false } => (0, TILE_SIZE / 2, TILE_SIZE, TILE_SIZE / 2),
+            Cell::Fence { vertical: true } => (TILE_SIZE / 2, 0, TILE_SIZE / 2, TILE_SIZE),
+            Cell::Diagonal { rising: true } => (0, TILE_SIZE, TILE_SIZE, 0),
+            _ => (0, 0, TILE_SIZE, TILE_SIZE),
+        };
+        image.draw_line(x + x0, y + y0, x + x1, y + y1, Color::new(160, 160, 160, 255));
+    }
+}
+
+fn render_map(maze: &Maze) -> Image {
+    let mut image = Image::gen_image_color(maze.width() as i32 * TILE_SIZE, maze.height() as i32 * TILE_SIZE, Color::BLACK);
+    for (x, y, cell) in maze.cells() {
+        draw_tile(&mut image, x as i32 * TILE_SIZE, y as i32 * TILE_SIZE, cell);
+    }
+    image
+}
+
+// Tile n of the tileset is map character n
+fn render_tileset() -> Image {
+    let rows = TILESET_COUNT / TILESET_COLUMNS;
+    let mut image = Image::gen_image_color(TILESET_COLUMNS * TILE_SIZE, rows * TILE_SIZE, Color::BLACK);
+    for id in 32..TILESET_COUNT {
+        let x = id % TILESET_COLUMNS * TILE_SIZE;
+        let y = id / TILESET_COLUMNS * TILE_SIZE;
+        draw_tile(&mut image, x, y, Cell::from_char(id as u8 as char));
+    }
+    image
+}
+
+pub fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            ch if (ch as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Just enough JSON to read Tiled maps
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(text: &'a str) -> Self {
+        JsonParser { text: text.as_bytes(), pos: 0 }
+    }
+
+    fn parse(&mut self) -> Result<Json, String> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        if self.pos < self.text.len() {
+            return Err(format!("unexpected data at byte {}", self.pos));
+        }
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.text.len() && self.text[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.text.get(self.pos) != Some(&byte) {
+            return Err(format!("expected '{}' at byte {}", byte as char, self.pos));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        if !self.text[self.pos..].starts_with(word.as_bytes()) {
+            return Err(format!("unexpected token at byte {}", self.pos));
+        }
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.text.get(self.pos) {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(Json::Str(self.string()?)),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(_) => self.number(),
+            None => Err("unexpected end of file".to_string()),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.text.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.text.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(format!("expected ',' or '}}' at byte {}", self.pos)),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.text.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.text.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(format!("expected ',' or ']' at byte {}", self.pos)),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(&byte) = self.text.get(self.pos) else {
+                return Err("unterminated string".to_string());
+            };
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = *self.text.get(self.pos).ok_or("unterminated string")?;
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => bytes.push(b'\n'),
+                        b't' => bytes.push(b'\t'),
+                        b'r' => bytes.push(b'\r'),
+                        b'b' => bytes.push(0x08),
+                        b'f' => bytes.push(0x0c),
+                        b'u' => {
+                            let hex = self.text.get(self.pos..self.pos + 4).ok_or("bad \\u escape")?;
+                            let code = u32::from_str_radix(std::str::from_utf8(hex).unwrap_or(""), 16)
+                                .map_err(|_| "bad \\u escape")?;
+                            self.pos += 4;
+                            let ch = char::from_u32(code).unwrap_or('?');
+                            bytes.extend_from_slice(ch.to_string().as_bytes());
+                        }
+                        other => bytes.push(other),
+                    }
+                }
+                _ => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| "string is not valid UTF-8".to_string())
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while self.pos < self.text.len() && matches!(self.text[self.pos], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.text[start..self.pos])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .map(Json::Number)
+            .ok_or_else(|| format!("unexpected token at byte {}", start))
+    }
+}
@@ -0,0 +1,159 @@
+// crash.rs
+
+use raylib::prelude::*;
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// A panic writes crash.log with the recent log lines and where the player was,
+// then shows a last message in the game window if it is still usable.
+
+pub const CRASH_LOG_PATH: &str = "crash.log";
+const RECENT_LINES: usize = 50; // Log lines kept for the crash report
+
+// What the game was doing, updated every frame
+struct CrashContext {
+    map: Option<&'static str>,
+    player_pos: (f32, f32),
+}
+
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext { map: None, player_pos: (0.0, 0.0) });
+static LOG_TO_STDERR: AtomicBool = AtomicBool::new(false); // Set by tools whose stdout is their output
+
+// println! that is also kept for the crash report, eprintln! once log_to_stderr was called
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let line = format!($($arg)*);
+        if $crate::crash::logs_to_stderr() {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+        $crate::crash::record(line);
+    }};
+}
+
+// eprintln! that is also kept for the crash report
+macro_rules! elog {
+    ($($arg:tt)*) => {{
+        let line = format!($($arg)*);
+        eprintln!("{}", line);
+        $crate::crash::record(line);
+    }};
+}
+
+// A panic inside a locked section must not hide the log from the report
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn record(line: String) {
+    let mut recent = lock(&RECENT_LOG);
+    if recent.len() == RECENT_LINES {
+        recent.pop_front();
+    }
+    recent.push_back(line);
+}
+
+// For command line tools like --report, so the game's own logging stays out of what they print
+pub fn log_to_stderr() {
+    LOG_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+pub fn logs_to_stderr() -> bool {
+    LOG_TO_STDERR.load(Ordering::Relaxed)
+}
+
+// None while in the menus
+pub fn set_context(map: Option<&'static str>, player_pos: Vector2) {
+    let mut context = lock(&CONTEXT);
+    context.map = map;
+    context.player_pos = (player_pos.x, player_pos.y);
+}
+
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let saved = write_crash_log(info);
+        show_crash_message(saved);
+    }));
+}
+
+fn write_crash_log(info: &PanicHookInfo) -> bool {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    let mut report = String::from("# Raycaster Dungeon crash report\n");
+    report.push_str(&format!("time: {} (unix seconds)\n", seconds));
+    report.push_str(&format!("panic: {}\n", info));
+
+    // try_lock: the panic may have happened while one of these was held
+    match CONTEXT.try_lock() {
+        Ok(context) => {
+            report.push_str(&format!("map: {}\n", context.map.unwrap_or("none (menus)")));
+            report.push_str(&format!("player: {:.1}, {:.1}\n", context.player_pos.0, context.player_pos.1));
+        }
+        Err(_) => report.push_str("map: unknown\n"),
+    }
+    report.push_str("\nrecent log:\n");
+    if let Ok(recent) = RECENT_LOG.try_lock() {
+        for line in recent.iter() {
+            report.push_str(line);
+            report.push('\n');
+        }
+    }
+    report.push_str(&format!("\nbacktrace:\n{}\n", Backtrace::force_capture()));
+
+    match fs::write(CRASH_LOG_PATH, report) {
+        Ok(_) => {
+            eprintln!("Crash log saved to {}", CRASH_LOG_PATH);
+            true
+        }
+        Err(e) => {
+            eprintln!("Warning: Could not write {}: {:?}", CRASH_LOG_PATH, e);
+            false
+        }
+    }
+}
+
+// Drawn straight through raylib, the game's own handles are mid-unwind.
+// Waits for any key or button so the message can be read.
+fn show_crash_message(saved: bool) {
+    let message = if saved {
+        format!("The game crashed. Log saved to {}", CRASH_LOG_PATH)
+    } else {
+        "The game crashed. The crash log could not be saved.".to_string()
+    };
+    let Ok(message) = CString::new(message) else {
+        return;
+    };
+    let Ok(hint) = CString::new("Press any key to close") else {
+        return;
+    };
+
+    unsafe {
+        if !raylib::ffi::IsWindowReady() {
+            return;
+        }
+        raylib::ffi::EnableCursor();
+        while !raylib::ffi::WindowShouldClose() {
+            if raylib::ffi::GetKeyPressed() != 0 || raylib::ffi::GetGamepadButtonPressed() != 0 {
+                break;
+            }
+            let width = raylib::ffi::GetScreenWidth();
+            let height = raylib::ffi::GetScreenHeight();
+            raylib::ffi::BeginDrawing();
+            raylib::ffi::ClearBackground(Color::BLACK.into());
+            let message_width = raylib::ffi::MeasureText(message.as_ptr(), 30);
+            raylib::ffi::DrawText(message.as_ptr(), (width - message_width) / 2, height / 2 - 30, 30, Color::RED.into());
+            let hint_width = raylib::ffi::MeasureText(hint.as_ptr(), 20);
+            raylib::ffi::DrawText(hint.as_ptr(), (width - hint_width) / 2, height / 2 + 20, 20, Color::LIGHTGRAY.into());
+            raylib::ffi::EndDrawing();
+        }
+    }
+}
@@ -0,0 +1,194 @@
+// debugview.rs
+
+use raylib::prelude::*;
+
+use crate::caster::cast_ray;
+use crate::enemy::{AnimationState, CHASE_RANGE, Enemy, MovementPattern, SHIELD_ARC};
+use crate::framebuffer::Framebuffer;
+use crate::minimap_cell_color;
+use crate::world::{BACKSTAB_ARC, ENEMY_ATTACK_RANGE, World};
+
+// Overhead view for debugging the caster and the enemy AI, toggled with F4 while playing.
+// The whole map is fitted into its own panel beside the 3D view and drawn with raylib, so
+// nothing here touches the framebuffer the 3D view is rendered into.
+// F6 adds the enemy AI overlay, on this view and on the full map: what each enemy is doing,
+// how close it is to noticing the player, which way it faces with the arc its shield covers
+// and the arc it can be backstabbed from, and a line to whatever it is heading for. Enemies
+// keep no memory of where they last saw the player, so there is no last-known spot to mark.
+
+const DEBUG_RAYS: usize = 120; // Rays in the fan, the same at any window size
+const PANEL_MARGIN: i32 = 20;
+
+pub struct DebugView {
+    pub enabled: bool,
+    rays: Vec<Vector2>, // Where each ray of the fan stopped, in world units
+}
+
+impl DebugView {
+    pub fn new() -> Self {
+        DebugView { enabled: false, rays: Vec::new() }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        log!("Debug view {}", if self.enabled { "on" } else { "off" });
+    }
+
+    // Cast the fan for this frame. The caster wants the framebuffer but is told not to draw into it.
+    pub fn trace(&mut self, framebuffer: &mut Framebuffer, world: &World) {
+        self.rays.clear();
+        let Some(maze) = world.maze().filter(|_| self.enabled) else {
+            return;
+        };
+
+        let player = &world.player;
+        for i in 0..=DEBUG_RAYS {
+            let a = player.a - player.fov / 2.0 + player.fov * i as f32 / DEBUG_RAYS as f32;
+            let hit = cast_ray(framebuffer, maze, player, a, world.block_size, false);
+            self.rays.push(Vector2::new(player.pos.x + hit.distance * a.cos(), player.pos.y + hit.distance * a.sin()));
+        }
+    }
+
+    // Map, visited cells, footprints, ray fan, then enemies with how far they notice and reach the player
+    pub fn draw(&self, d: &mut RaylibDrawHandle, world: &World, ai_overlay: bool, screen_width: i32, screen_height: i32) {
+        let Some(maze) = world.maze().filter(|_| self.enabled) else {
+            return;
+        };
+
+        // Largest whole-pixel cell size that fits the panel, which takes up to half the screen width
+        let panel_width = screen_width / 2 - PANEL_MARGIN;
+        let panel_height = screen_height - PANEL_MARGIN * 2;
+        let cell_size = (panel_width / maze.width() as i32).min(panel_height / maze.height() as i32).max(1);
+        let map_width = cell_size * maze.width() as i32;
+        let map_height = cell_size * maze.height() as i32;
+        let origin_x = screen_width - map_width - PANEL_MARGIN;
+        let origin_y = PANEL_MARGIN;
+        let scale = cell_size as f32 / world.block_size as f32; // Screen pixels per world unit
+        let to_screen = |pos: Vector2| Vector2::new(origin_x as f32 + pos.x * scale, origin_y as f32 + pos.y * scale);
+
+        d.draw_rectangle(origin_x - 5, origin_y - 5, map_width + 10, map_height + 10, Color::new(0, 0, 0, 200));
+        d.draw_rectangle_lines(origin_x - 5, origin_y - 5, map_width + 10, map_height + 10, Color::WHITE);
+
+        for (x, y, cell) in maze.cells() {
+            let pixel_x = origin_x + x as i32 * cell_size;
+            let pixel_y = origin_y + y as i32 * cell_size;
+            d.draw_rectangle(pixel_x, pixel_y, cell_size, cell_size, minimap_cell_color(cell));
+            if world.visited.contains_key(&(x, y)) {
+                d.draw_rectangle(pixel_x, pixel_y, cell_size, cell_size, Color::new(80, 200, 120, 70));
+            }
+        }
+
+        for print in &world.footprints {
+            d.draw_circle_v(to_screen(print.pos), (cell_size as f32 * 0.08).max(1.5), print.color());
+        }
+
+        let player_pos = to_screen(world.player.pos);
+        for &end in &self.rays {
+            d.draw_line_ex(player_pos, to_screen(end), 1.0, Color::new(255, 240, 120, 90));
+        }
+
+        for enemy in world.enemies.iter().filter(|enemy| !enemy.is_dead) {
+            let pos = to_screen(enemy.pos);
+            let color = match enemy.movement_pattern {
+                MovementPattern::Stationary => Color::ORANGE,
+                MovementPattern::Patrol => Color::BLUE,
+                MovementPattern::Wander => Color::GREEN,
+                MovementPattern::Chase => Color::PURPLE,
+            };
+
+            // Enemies sense the player all around them, so their vision is a full circle:
+            // chasers start moving inside the outer one, everyone swings inside the inner one
+            if enemy.movement_pattern == MovementPattern::Chase {
+                let radius = CHASE_RANGE * cell_size as f32;
+                d.draw_circle_v(pos, radius, Color::new(color.r, color.g, color.b, 40));
+                d.draw_circle_lines(pos.x as i32, pos.y as i32, radius, color);
+            }
+            d.draw_circle_lines(pos.x as i32, pos.y as i32, ENEMY_ATTACK_RANGE * cell_size as f32, Color::new(255, 60, 60, 160));
+
+            // Where patrollers and wanderers are walking to
+            if matches!(enemy.movement_pattern, MovementPattern::Patrol | MovementPattern::Wander) {
+                d.draw_line_ex(pos, to_screen(enemy.target_pos), 1.0, Color::new(color.r, color.g, color.b, 160));
+            }
+            d.draw_circle_v(pos, (cell_size as f32 * 0.2).max(2.0), color);
+        }
+
+        if ai_overlay {
+            draw_enemy_ai(d, world, to_screen, cell_size);
+        }
+        d.draw_circle_v(player_pos, (cell_size as f32 * 0.25).max(3.0), Color::RED);
+        d.draw_text(&format!("Debug view (F4, F6 for AI) | {} rays | {} cells visited | Director {:?} {:.2}", self.rays.len(), world.visited.len(),
+                             world.director.pace, world.director.intensity),
+                    origin_x, origin_y + map_height + 10, 16, Color::WHITE);
+    }
+}
+
+// What the AI is up to, as a short label over the enemy
+fn ai_state(enemy: &Enemy, world: &World) -> String {
+    if enemy.grudge.is_some() {
+        return "feuding".to_string();
+    }
+    if enemy.distracted > 0.0 {
+        return format!("distracted {:.0}s", enemy.distracted.ceil());
+    }
+    if world.revive.is_downed() {
+        return "hunting wisp".to_string();
+    }
+    if enemy.animation_state == AnimationState::Attack {
+        return "attacking".to_string();
+    }
+    match enemy.movement_pattern {
+        MovementPattern::Chase if enemy.awareness >= 1.0 => "chasing".to_string(),
+        MovementPattern::Chase if enemy.awareness > 0.0 => format!("noticing {:.0}%", enemy.awareness * 100.0),
+        MovementPattern::Chase => "waiting".to_string(),
+        MovementPattern::Patrol => "patrolling".to_string(),
+        MovementPattern::Wander => "wandering".to_string(),
+        MovementPattern::Stationary => "guarding".to_string(),
+    }
+}
+
+// The AI overlay for every living enemy, `to_screen` maps world units onto the map being drawn
+pub fn draw_enemy_ai(d: &mut RaylibDrawHandle, world: &World, to_screen: impl Fn(Vector2) -> Vector2, cell_size: i32) {
+    let cell = cell_size as f32;
+    for enemy in world.enemies.iter().filter(|enemy| !enemy.is_dead) {
+        let pos = to_screen(enemy.pos);
+
+        // Awareness fills the chase range as it builds up
+        if enemy.movement_pattern == MovementPattern::Chase && enemy.awareness > 0.0 {
+            d.draw_circle_v(pos, CHASE_RANGE * cell, Color::new(255, 200, 60, (70.0 * enemy.awareness) as u8));
+        }
+
+        // Facing, the shield's arc in front while it is up and the backstab arc behind
+        let heading = enemy.heading.to_degrees();
+        let arc_radius = (cell * 1.2).max(10.0);
+        if enemy.shield_raised() {
+            let half = SHIELD_ARC.to_degrees();
+            d.draw_circle_sector(pos, arc_radius, heading - half, heading + half, 16, Color::new(170, 200, 255, 90));
+        }
+        let half = BACKSTAB_ARC.to_degrees();
+        d.draw_circle_sector(pos, arc_radius * 0.8, heading + 180.0 - half, heading + 180.0 + half, 16, Color::new(255, 60, 60, 60));
+        let facing = Vector2::new(pos.x + enemy.heading.cos() * arc_radius, pos.y + enemy.heading.sin() * arc_radius);
+        d.draw_line_ex(pos, facing, 2.0, Color::WHITE);
+
+        // What it is going for: a rival, the wisp, the player, or its own patrol or wander point
+        let companion = world.companion.as_ref().map(|companion| companion.pos);
+        let target = match enemy.grudge.and_then(|rival| world.enemies.get(rival)) {
+            Some(rival) => Some((rival.pos, Color::MAGENTA)),
+            None if enemy.distracted > 0.0 || world.revive.is_downed() => companion.map(|pos| (pos, Color::SKYBLUE)),
+            None => match enemy.movement_pattern {
+                MovementPattern::Chase if enemy.awareness >= 1.0 => Some((world.player.pos, Color::RED)),
+                MovementPattern::Patrol | MovementPattern::Wander => Some((enemy.target_pos, Color::LIGHTGRAY)),
+                _ => None,
+            },
+        };
+        if let Some((target, color)) = target {
+            let end = to_screen(target);
+            d.draw_line_ex(pos, end, 1.5, color);
+            d.draw_circle_v(end, 3.0, color);
+        }
+
+        let label = ai_state(enemy, world);
+        let label_width = 12 * label.len() as i32 / 2; // Approximate text width
+        d.draw_rectangle(pos.x as i32 - label_width / 2 - 2, pos.y as i32 - 24, label_width + 4, 14, Color::new(0, 0, 0, 160));
+        d.draw_text(&label, pos.x as i32 - label_width / 2, pos.y as i32 - 23, 12, Color::WHITE);
+    }
+}
@@ -0,0 +1,113 @@
+// deployables.rs
+
+use raylib::prelude::*;
+
+use crate::enemy::Enemy;
+use crate::has_line_of_sight;
+use crate::maze::Maze;
+use crate::projectiles::Projectile;
+use crate::status::StatusKind;
+
+// Items the player sets down on the cell they stand in. Caltrops slow any enemy that walks
+// over them, the dart turret shoots poisoned darts at the closest enemy it can see. Both
+// wear out after a while, the turret sooner if it runs out of darts. At most one per cell.
+
+const CALTROPS_LIFETIME: f32 = 30.0; // Seconds
+const TURRET_LIFETIME: f32 = 40.0;
+const TURRET_RANGE: f32 = 6.0;       // Cells
+const TURRET_RELOAD: f32 = 1.0;      // Seconds between darts
+const TURRET_DARTS: u32 = 12;
+const DART_SPEED: f32 = 10.0;        // Cells per second
+const DART_DAMAGE: f32 = 6.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeployableKind {
+    Caltrops,
+    DartTurret,
+}
+
+pub const DEPLOYABLES: [DeployableKind; 2] = [DeployableKind::Caltrops, DeployableKind::DartTurret];
+
+impl DeployableKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            DeployableKind::Caltrops => "Caltrops",
+            DeployableKind::DartTurret => "Dart Turret",
+        }
+    }
+
+    fn lifetime(self) -> f32 {
+        match self {
+            DeployableKind::Caltrops => CALTROPS_LIFETIME,
+            DeployableKind::DartTurret => TURRET_LIFETIME,
+        }
+    }
+}
+
+pub struct Deployable {
+    pub kind: DeployableKind,
+    pub pos: Vector2,   // Center of the cell it was set down in
+    pub remaining: f32, // Seconds until it wears out
+    reload: f32,
+    darts: u32,
+}
+
+// Set one down in the middle of the cell at `pos`, false if that cell already has one
+pub fn deploy(deployables: &mut Vec<Deployable>, kind: DeployableKind, pos: Vector2, block_size: usize) -> bool {
+    let block = block_size as f32;
+    let center = Vector2::new(((pos.x / block).floor() + 0.5) * block, ((pos.y / block).floor() + 0.5) * block);
+    if deployables.iter().any(|deployable| deployable.pos == center) {
+        return false;
+    }
+    deployables.push(Deployable { kind, pos: center, remaining: kind.lifetime(), reload: 0.0, darts: TURRET_DARTS });
+    true
+}
+
+pub fn update_deployables(deployables: &mut Vec<Deployable>, enemies: &mut [Enemy], projectiles: &mut Vec<Projectile>,
+                          maze: &Maze, block_size: usize, delta_time: f32) {
+    let block = block_size as f32;
+    let cell = |pos: Vector2| ((pos.x / block) as usize, (pos.y / block) as usize);
+
+    for deployable in deployables.iter_mut() {
+        deployable.remaining -= delta_time;
+        match deployable.kind {
+            DeployableKind::Caltrops => {
+                for enemy in enemies.iter_mut().filter(|enemy| !enemy.is_dead && cell(enemy.pos) == cell(deployable.pos)) {
+                    enemy.status.apply(StatusKind::Slowed);
+                }
+            }
+            DeployableKind::DartTurret => {
+                deployable.reload -= delta_time;
+                if deployable.reload > 0.0 {
+                    continue;
+                }
+                let distance = |enemy: &Enemy| ((enemy.pos.x - deployable.pos.x).powi(2) + (enemy.pos.y - deployable.pos.y).powi(2)).sqrt();
+                let target = enemies
+                    .iter()
+                    .filter(|enemy| !enemy.is_dead && distance(enemy) <= TURRET_RANGE * block)
+                    .filter(|enemy| has_line_of_sight(deployable.pos, enemy.pos, maze, block_size))
+                    .min_by(|a, b| distance(a).total_cmp(&distance(b)));
+                let Some(enemy) = target else {
+                    continue;
+                };
+
+                let gap = distance(enemy).max(0.001);
+                let direction = Vector2::new((enemy.pos.x - deployable.pos.x) / gap, (enemy.pos.y - deployable.pos.y) / gap);
+                projectiles.push(Projectile {
+                    pos: deployable.pos,
+                    direction,
+                    speed: DART_SPEED,
+                    damage: DART_DAMAGE,
+                    inflicts: Some(StatusKind::Poisoned),
+                    range: TURRET_RANGE + 1.0,
+                });
+                deployable.reload = TURRET_RELOAD;
+                deployable.darts -= 1;
+                if deployable.darts == 0 {
+                    deployable.remaining = 0.0;
+                }
+            }
+        }
+    }
+    deployables.retain(|deployable| deployable.remaining > 0.0);
+}
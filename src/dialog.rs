@@ -0,0 +1,103 @@
+// dialog.rs
+
+use raylib::prelude::*;
+
+// Reusable modal Yes/No confirmation box, drawn on top of whatever screen opened it
+pub struct ConfirmDialog {
+    pub message: String,
+    pub detail: String,
+    yes_selected: bool,
+}
+
+impl ConfirmDialog {
+    pub fn new(message: &str, detail: &str) -> Self {
+        ConfirmDialog {
+            message: message.to_string(),
+            detail: detail.to_string(),
+            yes_selected: false, // Default to the safe option
+        }
+    }
+
+    // Returns Some(true) when confirmed, Some(false) when cancelled, None while still open
+    pub fn handle_input(&mut self, rl: &RaylibHandle) -> Option<bool> {
+        let gamepad_available = rl.is_gamepad_available(0);
+
+        // Move between Yes and No
+        if rl.is_key_pressed(KeyboardKey::KEY_LEFT) || rl.is_key_pressed(KeyboardKey::KEY_RIGHT) ||
+           rl.is_key_pressed(KeyboardKey::KEY_A) || rl.is_key_pressed(KeyboardKey::KEY_D) ||
+           rl.is_key_pressed(KeyboardKey::KEY_TAB) {
+            self.yes_selected = !self.yes_selected;
+        }
+        if gamepad_available &&
+           (rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT) ||
+            rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT)) {
+            self.yes_selected = !self.yes_selected;
+        }
+
+        // Direct shortcuts
+        if rl.is_key_pressed(KeyboardKey::KEY_Y) {
+            return Some(true);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_N) || rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return Some(false);
+        }
+
+        // Confirm current selection
+        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) || rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+            return Some(self.yes_selected);
+        }
+
+        if gamepad_available {
+            // X (Cross) / A confirms the highlighted option, Circle / B always cancels
+            if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN) {
+                return Some(self.yes_selected);
+            }
+            if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT) {
+                return Some(false);
+            }
+        }
+
+        None
+    }
+
+    // The box in the middle of the screen: x, y, width, height
+    pub fn panel(screen_width: i32, screen_height: i32) -> (i32, i32, i32, i32) {
+        let (box_width, box_height) = (460, 180);
+        ((screen_width - box_width) / 2, (screen_height - box_height) / 2, box_width, box_height)
+    }
+
+    // The screen behind is dimmed by the dialog's backdrop, see Screen::backdrop
+    pub fn draw(&self, d: &mut RaylibDrawHandle, screen_width: i32, screen_height: i32) {
+        let (box_x, box_y, box_width, box_height) = ConfirmDialog::panel(screen_width, screen_height);
+
+        d.draw_rectangle(box_x, box_y, box_width, box_height, Color::new(30, 15, 15, 200));
+        d.draw_rectangle_lines(box_x, box_y, box_width, box_height, Color::new(200, 60, 60, 255));
+
+        let message_width = 22 * self.message.len() as i32 / 2; // Approximate text width
+        d.draw_text(&self.message, box_x + (box_width - message_width) / 2, box_y + 25, 22, Color::WHITE);
+
+        let detail_width = 16 * self.detail.len() as i32 / 2;
+        d.draw_text(&self.detail, box_x + (box_width - detail_width) / 2, box_y + 60, 16, Color::LIGHTGRAY);
+
+        // Yes / No buttons
+        let button_width = 120;
+        let button_height = 40;
+        let button_y = box_y + box_height - button_height - 25;
+        let options = [("Yes", true), ("No", false)];
+
+        for (i, (label, is_yes)) in options.iter().enumerate() {
+            let button_x = box_x + box_width / 2 - button_width - 20 + i as i32 * (button_width + 40);
+            let selected = *is_yes == self.yes_selected;
+
+            let bg_color = if selected { Color::new(120, 30, 30, 255) } else { Color::new(50, 40, 40, 255) };
+            let text_color = if selected { Color::YELLOW } else { Color::WHITE };
+
+            d.draw_rectangle(button_x, button_y, button_width, button_height, bg_color);
+            d.draw_rectangle_lines(button_x, button_y, button_width, button_height,
+                                   if selected { Color::YELLOW } else { Color::GRAY });
+
+            let label_width = 20 * label.len() as i32 / 2;
+            d.draw_text(label, button_x + (button_width - label_width) / 2, button_y + 10, 20, text_color);
+        }
+    }
+}
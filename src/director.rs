@@ -0,0 +1,151 @@
+// director.rs
+
+use raylib::prelude::*;
+
+use crate::enemy::{Enemy, MovementPattern};
+use crate::maze::Maze;
+use crate::rng::Rng;
+use crate::sim::{Vec2, enemy_blocked, line_of_sight};
+
+// Pacing for a map: the director watches how hard the player is being pushed and turns the
+// pressure up and down in waves. Quiet stretches build up: wanderers grow restless, drifting
+// toward the player and walking faster, and after long enough without a fight it sends a
+// wanderer in from somewhere out of sight. Fighting and taking damage raise the intensity; once
+// it peaks, or the player is badly hurt, the director backs off for a while and keeps the
+// wanderers calm, then starts building again. Difficulty picks how fast it builds, how long it
+// rests and how many enemies it may add to one map.
+
+const DAMAGE_INTENSITY: f32 = 2.5;  // Intensity per fraction of max health lost
+const HIT_INTENSITY: f32 = 0.08;    // Per blow the player lands
+const INTENSITY_DECAY: f32 = 0.05;  // Per second
+const PEAK_INTENSITY: f32 = 1.0;
+const RELAX_INTENSITY: f32 = 0.3;   // The rest doesn't end until intensity is under this
+const HURT_HEALTH: f32 = 0.35;      // Health ratio that calls for a rest whatever the intensity
+const PEAK_CALM: f32 = 3.0;         // Seconds without a blow that end a peak
+const SPAWN_DISTANCE: (f32, f32) = (5.0, 10.0); // Cells from the player a reinforcement appears
+const SPAWN_ATTEMPTS: usize = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+pub const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+// How the director paces a map at one difficulty
+pub struct Pacing {
+    pub build_time: f32,     // Seconds of calm for wanderers to reach full aggression
+    pub max_aggression: f32, // 0.0 to 1.0
+    pub spawn_after: f32,    // Seconds without a fight before a reinforcement comes
+    pub max_spawns: usize,   // Per map
+    pub relax_time: f32,     // Shortest rest after a peak
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn pacing(&self) -> Pacing {
+        match self {
+            Difficulty::Easy => Pacing { build_time: 90.0, max_aggression: 0.4, spawn_after: 75.0, max_spawns: 2, relax_time: 30.0 },
+            Difficulty::Normal => Pacing { build_time: 60.0, max_aggression: 0.7, spawn_after: 50.0, max_spawns: 4, relax_time: 20.0 },
+            Difficulty::Hard => Pacing { build_time: 40.0, max_aggression: 1.0, spawn_after: 30.0, max_spawns: 6, relax_time: 12.0 },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pace {
+    BuildUp,
+    Peak,
+    Relax,
+}
+
+pub struct Director {
+    pacing: Pacing,
+    pub pace: Pace,
+    pub intensity: f32,
+    calm: f32,       // Seconds since the last fight
+    rest: f32,       // Seconds into the current rest
+    spawned: usize,  // Reinforcements sent on this map
+}
+
+impl Director {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Director { pacing: difficulty.pacing(), pace: Pace::BuildUp, intensity: 0.0, calm: 0.0, rest: 0.0, spawned: 0 }
+    }
+
+    // How restless wanderers are, 0.0 to 1.0
+    pub fn aggression(&self) -> f32 {
+        match self.pace {
+            Pace::BuildUp => self.pacing.max_aggression * (self.calm / self.pacing.build_time).min(1.0),
+            Pace::Peak => self.pacing.max_aggression,
+            Pace::Relax => 0.0,
+        }
+    }
+
+    // Once a frame with the fraction of max health lost this frame and the blows the player landed.
+    // True when a reinforcement should be sent in.
+    pub fn update(&mut self, delta_time: f32, health_ratio: f32, damage: f32, hits: usize) -> bool {
+        self.intensity += damage * DAMAGE_INTENSITY + hits as f32 * HIT_INTENSITY;
+        let fighting = damage > 0.0 || hits > 0;
+        if fighting {
+            self.calm = 0.0;
+        } else {
+            self.calm += delta_time;
+        }
+        self.intensity = (self.intensity - INTENSITY_DECAY * delta_time).max(0.0);
+
+        let next = match self.pace {
+            Pace::BuildUp | Pace::Peak if health_ratio < HURT_HEALTH => Pace::Relax,
+            Pace::BuildUp if self.intensity >= PEAK_INTENSITY => Pace::Peak,
+            Pace::Peak if self.calm > PEAK_CALM => Pace::Relax,
+            Pace::Relax if self.rest >= self.pacing.relax_time && self.intensity < RELAX_INTENSITY => Pace::BuildUp,
+            pace => pace,
+        };
+        if next != self.pace {
+            log!("Director: {:?} -> {:?} (intensity {:.2})", self.pace, next, self.intensity);
+            self.pace = next;
+            self.rest = 0.0;
+            self.calm = 0.0;
+        }
+        if self.pace == Pace::Relax {
+            self.rest += delta_time;
+        }
+
+        let spawn = self.pace == Pace::BuildUp && self.calm >= self.pacing.spawn_after && self.spawned < self.pacing.max_spawns;
+        if spawn {
+            self.spawned += 1;
+            self.calm = 0.0;
+        }
+        spawn
+    }
+}
+
+// A wanderer somewhere the player can't see, a few cells off. None if no spot turns up or there is
+// no enemy to copy the look of.
+pub fn spawn_reinforcement(enemies: &[Enemy], player_pos: Vector2, maze: &Maze, block_size: usize, rng: &mut Rng) -> Option<Enemy> {
+    let def = enemies.iter().find(|enemy| enemy.movement_pattern == MovementPattern::Wander).or(enemies.first())?.def;
+    let block = block_size as f32;
+    let player = Vec2::from(player_pos);
+    for _ in 0..SPAWN_ATTEMPTS {
+        let (x, y) = (rng.range(maze.width()), rng.range(maze.height()));
+        let pos = Vec2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block);
+        let distance = pos.distance(player) / block;
+        if distance < SPAWN_DISTANCE.0 || distance > SPAWN_DISTANCE.1 || enemy_blocked(maze, pos, block_size) || line_of_sight(player, pos, maze, block_size) {
+            continue;
+        }
+        let mut enemy = Enemy::new_wander(pos.x, pos.y, def, block * 1.2);
+        enemy.rng = rng.fork();
+        log!("Director sent a wanderer to cell ({}, {})", x, y);
+        return Some(enemy);
+    }
+    None
+}
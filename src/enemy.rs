@@ -1,6 +1,25 @@
 use raylib::prelude::*;
-use crate::textures::TextureManager;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::fs;
+use crate::textures::{SHIELD_SHEET, TextureManager};
+use crate::factions::HORDE;
 use crate::maze::Maze;
+use crate::mods;
+use crate::rng::Rng;
+use crate::sim::{enemy_blocked, from_front, normalize_angle};
+use crate::status::{StatusEffects, StatusKind};
+
+pub const ENEMY_DEFS_PATH: &str = "enemies.def";
+const MAX_VARIANTS: usize = 4; // Idle variants, and separately fidgets, per enemy
+const DEFAULT_FIDGET_INTERVAL: (f32, f32) = (4.0, 10.0);
+pub const SHIELD_ARC: f32 = PI / 3.0;       // Either side of straight ahead that a raised shield covers
+const SHIELD_TURN_RATE: f32 = PI * 0.6;  // Radians per second a shield bearer can turn, flanking outpaces it
+const SHIELD_SPEED: f32 = 0.55;          // Cells per second, slower than other chasers
+pub const BLOCK_FLASH: f32 = 0.25;           // Seconds the shield gleams after turning a blow aside
+const CLOAK_ALPHA: f32 = 0.12;          // Opacity of a cloaked enemy out of the lantern's light
+const REVEAL_TIME: f32 = 2.0;           // Seconds a hit leaves a cloaked enemy showing
+const FADE_RATE: f32 = 3.0;             // Opacity a cloak gains or loses per second
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum AnimationState {
@@ -18,16 +37,187 @@ pub enum MovementPattern {
     Chase,          // Moves toward the player when close
 }
 
+// One row of the sprite sheet played as an animation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationDef {
+    pub row: usize,    // Sprite sheet row
+    pub frames: usize, // Frames actually drawn in that row
+    pub fps: f32,
+}
+
+impl AnimationDef {
+    // Parses "<row> <frames> <fps>"
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split_whitespace();
+        let row = parts.next()?.parse().ok()?;
+        let frames: usize = parts.next()?.parse().ok()?;
+        let fps: f32 = parts.next()?.parse().ok()?;
+        if parts.next().is_some() || frames == 0 || fps <= 0.0 {
+            return None;
+        }
+        Some(AnimationDef { row, frames, fps })
+    }
+}
+
+// Parses "<min> <max>" seconds
+fn parse_interval(value: &str) -> Option<(f32, f32)> {
+    let mut parts = value.split_whitespace();
+    let min: f32 = parts.next()?.parse().ok()?;
+    let max: f32 = parts.next()?.parse().ok()?;
+    (parts.next().is_none() && min > 0.0 && max >= min).then_some((min, max))
+}
+
+// Into the first free slot, None when all are taken
+fn add_variant(slots: &mut [Option<AnimationDef>; MAX_VARIANTS], animation: AnimationDef) -> Option<()> {
+    *slots.iter_mut().find(|slot| slot.is_none())? = Some(animation);
+    Some(())
+}
+
+// Animation and lifetime settings for one enemy sprite sheet
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnemyDef {
+    pub texture_key: char, // key to fetch the sprite sheet from TextureManager
+    pub idle: AnimationDef,
+    pub idle_variants: [Option<AnimationDef>; MAX_VARIANTS], // Alternatives to `idle`, one is picked each time an idle loop ends
+    pub fidgets: [Option<AnimationDef>; MAX_VARIANTS],       // Played once every so often while idle, e.g. a blink
+    pub fidget_interval: (f32, f32),                          // Seconds between fidgets, at least and at most
+    pub walk: AnimationDef,
+    pub attack: AnimationDef,
+    pub death: AnimationDef,
+    pub despawn_time: f32, // Seconds a dead enemy stays on the floor
+    pub inflicts: Option<StatusKind>, // Effect its hits leave on the player
+    pub shield: Option<AnimationDef>, // Row with the shield raised; the enemy blocks frontal blows while alert
+}
+
+impl EnemyDef {
+    // Layout of the bundled 4x3 sheet, used when the definition file is missing
+    pub fn default_for(texture_key: char) -> Self {
+        EnemyDef {
+            texture_key,
+            idle: AnimationDef { row: 0, frames: 4, fps: 5.0 },
+            idle_variants: [None; MAX_VARIANTS],
+            fidgets: [None; MAX_VARIANTS],
+            fidget_interval: DEFAULT_FIDGET_INTERVAL,
+            walk: AnimationDef { row: 1, frames: 4, fps: 5.0 },
+            attack: AnimationDef { row: 2, frames: 4, fps: 5.0 },
+            death: AnimationDef { row: 2, frames: 4, fps: 5.0 }, // The sheet has no death row yet
+            despawn_time: 3.0,
+            inflicts: None,
+            shield: (texture_key == SHIELD_SHEET).then_some(AnimationDef { row: 3, frames: 4, fps: 5.0 }),
+        }
+    }
+
+    pub fn animation(&self, state: AnimationState) -> &AnimationDef {
+        match state {
+            AnimationState::Idle => &self.idle,
+            AnimationState::Walking => &self.walk,
+            AnimationState::Attack => &self.attack,
+            AnimationState::Death => &self.death,
+        }
+    }
+}
+
+// Definitions are "[key]" sections of "name = value" lines, see enemies.def
+pub fn load_enemy_defs(path: &str) -> HashMap<char, EnemyDef> {
+    let mut defs = HashMap::new();
+
+    let contents = match fs::read_to_string(mods::resolve(path)) {
+        Ok(contents) => contents,
+        Err(_) => {
+            log!("No enemy definitions found at {}, using defaults", path);
+            return defs;
+        }
+    };
+
+    let mut current: Option<EnemyDef> = None;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // A new section starts from the defaults for its sprite sheet
+        if let Some(key) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            if let Some(def) = current.take() {
+                defs.insert(def.texture_key, def);
+            }
+            let mut key_chars = key.trim().chars();
+            match (key_chars.next(), key_chars.next()) {
+                (Some(texture_key), None) => current = Some(EnemyDef::default_for(texture_key)),
+                _ => elog!("Warning: Ignoring enemy section with a bad key in {}: {}", path, line),
+            }
+            continue;
+        }
+
+        let Some(def) = current.as_mut() else {
+            elog!("Warning: Ignoring enemy definition outside a section in {}: {}", path, line);
+            continue;
+        };
+        let parsed = line.split_once('=').and_then(|(name, value)| {
+            let value = value.trim();
+            match name.trim() {
+                "idle" => def.idle = AnimationDef::parse(value)?,
+                "idle_variant" => add_variant(&mut def.idle_variants, AnimationDef::parse(value)?)?,
+                "fidget" => add_variant(&mut def.fidgets, AnimationDef::parse(value)?)?,
+                "fidget_interval" => def.fidget_interval = parse_interval(value)?,
+                "walk" => def.walk = AnimationDef::parse(value)?,
+                "attack" => def.attack = AnimationDef::parse(value)?,
+                "death" => def.death = AnimationDef::parse(value)?,
+                "despawn" => def.despawn_time = value.parse().ok()?,
+                "inflicts" => def.inflicts = Some(StatusKind::parse(value)?),
+                "shield" => def.shield = Some(AnimationDef::parse(value)?),
+                _ => return None,
+            }
+            Some(())
+        });
+        if parsed.is_none() {
+            elog!("Warning: Ignoring malformed enemy definition line in {}: {}", path, line);
+        }
+    }
+    if let Some(def) = current {
+        defs.insert(def.texture_key, def);
+    }
+
+    log!("Loaded {} enemy definitions from {}", defs.len(), path);
+    defs
+}
+
+// Floating number shown above an enemy after it takes damage
+pub struct DamageNumber {
+    pub amount: u32,
+    pub age: f32, // Seconds since the hit
+    pub critical: bool,
+}
+
+pub const DAMAGE_NUMBER_LIFETIME: f32 = 1.0;
+pub const HEALTH_BAR_DURATION: f32 = 3.0; // Seconds the health bar stays up after a hit
+pub const CHASE_RANGE: f32 = 3.0; // Cells, chasers go after the player this close in any direction
+const DETECTION_RATE: f32 = 3.0; // Awareness gained per second in range of a fully lit player
+const AWARENESS_DECAY: f32 = 0.5; // Awareness lost per second once the player is out of range
+const GRUDGE_STOP_DISTANCE: f32 = 0.6; // Cells, fighting enemies close to inside their reach and stop
+const WANDER_DRIFT: f32 = 0.35; // Share of the way to the player a fully restless wanderer moves its area each turn
+const RESTLESS_SPEED: f32 = 0.5; // Extra speed of a fully restless wanderer
+
 pub struct Enemy {
     pub pos: Vector2,
-    pub texture_key: char, // key to fetch texture from TextureManager
+    pub def: EnemyDef,
     pub animation_state: AnimationState,
     pub current_frame: usize,
-    pub animation_timer: f32,
-    pub frame_duration: f32, // Time per frame in seconds
+    pub animation_timer: f32, // Time into the current frame
+    pub idle: AnimationDef,   // Idle loop being played, `def.idle` or one of its variants
+    pub fidget: Option<AnimationDef>, // One-off idle animation playing instead of the loop
+    fidget_timer: Option<f32>, // Seconds until the next fidget, drawn the first time the enemy idles
+    animation_rng: Rng,        // Cosmetic choices, kept apart from `rng` so they never change what the enemy does
     pub facing_left: bool, // Direction the sprite is facing
+    pub heading: f32,      // Radians, the way it last moved; its back is the other way, for backstabs
     pub is_dead: bool, // Track if enemy is dead
     pub death_timer: f32, // How long the enemy has been dead
+    pub health: f32,
+    pub max_health: f32,
+    pub health_bar_timer: f32, // Counts down after a hit, bar is hidden at zero
+    pub damage_numbers: Vec<DamageNumber>,
+    pub attack_cooldown: f32, // Time until this enemy can hurt the player again
+    pub arena: bool, // Must die before the arena doors open
     
     // Movement properties
     pub movement_pattern: MovementPattern,
@@ -38,38 +228,74 @@ pub struct Enemy {
     pub wander_center: Vector2,
     pub wander_radius: f32,
     pub movement_timer: f32,
+    pub wander_interval: f32, // Seconds until the next wander target
     pub target_pos: Vector2,
+    pub rng: Rng, // Own stream so each enemy's choices don't depend on the others
+    pub footprint_mark: Vector2, // Where it last left a footprint
+    pub status: StatusEffects, // Burning, poisoned or slowed
+    pub awareness: f32, // 0.0 to 1.0, chasers give chase once it fills up
+    pub distracted: f32, // Seconds left busy with the companion instead of the player
+    pub faction: usize,  // factions::HORDE unless the map placed it in another
+    pub grudge: Option<usize>, // Index of the other-faction enemy it is fighting, see factions.rs
+    pub aggression: f32, // 0.0 to 1.0, how restless a wanderer is, set by the director (see director.rs)
+    pub block_timer: f32, // Seconds left of the gleam after its shield turned a blow aside
+    pub cloaked: bool,    // Nearly invisible unless the lantern shines on it or it was just hit
+    pub opacity: f32,     // 0.0 to 1.0, how solid the sprite is drawn
+    reveal_timer: f32,    // Seconds left showing after a hit
 }
 
 impl Enemy {
-    pub fn new(x: f32, y: f32, texture_key: char) -> Self {
+    pub fn new(x: f32, y: f32, def: EnemyDef) -> Self {
         Enemy {
             pos: Vector2::new(x, y),
-            texture_key,
+            def,
             animation_state: AnimationState::Idle,
             current_frame: 0,
             animation_timer: 0.0,
-            frame_duration: 0.2, // 200ms per frame = 5 FPS animation
+            idle: def.idle,
+            fidget: None,
+            fidget_timer: None,
+            animation_rng: Rng::new(((x.to_bits() as u64) << 32) | y.to_bits() as u64),
             facing_left: false,
+            heading: 0.0,
             is_dead: false,
             death_timer: 0.0,
+            health: 30.0,
+            max_health: 30.0,
+            health_bar_timer: 0.0,
+            damage_numbers: Vec::new(),
+            attack_cooldown: 0.0,
+            arena: false,
             
             // Movement defaults
             movement_pattern: MovementPattern::Stationary,
-            movement_speed: 50.0, // pixels per second
+            movement_speed: 0.5, // Cells per second
             patrol_start: Vector2::new(x, y),
             patrol_end: Vector2::new(x, y),
             patrol_direction: 1.0,
             wander_center: Vector2::new(x, y),
-            wander_radius: 100.0,
+            wander_radius: 100.0, // World units, new_wander sets it from the map's block size
             movement_timer: 0.0,
+            wander_interval: 2.0,
             target_pos: Vector2::new(x, y),
+            rng: Rng::new(0), // Reseeded from the map's generator when spawned
+            footprint_mark: Vector2::new(x, y),
+            status: StatusEffects::new(),
+            awareness: 0.0,
+            distracted: 0.0,
+            faction: HORDE,
+            grudge: None,
+            aggression: 0.0,
+            block_timer: 0.0,
+            cloaked: false,
+            opacity: 1.0,
+            reveal_timer: 0.0,
         }
     }
 
     // Constructor for patrol enemies
-    pub fn new_patrol(x: f32, y: f32, texture_key: char, end_x: f32, end_y: f32) -> Self {
-        let mut enemy = Self::new(x, y, texture_key);
+    pub fn new_patrol(x: f32, y: f32, def: EnemyDef, end_x: f32, end_y: f32) -> Self {
+        let mut enemy = Self::new(x, y, def);
         enemy.movement_pattern = MovementPattern::Patrol;
         enemy.patrol_start = Vector2::new(x, y);
         enemy.patrol_end = Vector2::new(end_x, end_y);
@@ -78,56 +304,165 @@ impl Enemy {
     }
 
     // Constructor for wandering enemies
-    pub fn new_wander(x: f32, y: f32, texture_key: char, radius: f32) -> Self {
-        let mut enemy = Self::new(x, y, texture_key);
+    pub fn new_wander(x: f32, y: f32, def: EnemyDef, radius: f32) -> Self {
+        let mut enemy = Self::new(x, y, def);
         enemy.movement_pattern = MovementPattern::Wander;
         enemy.wander_radius = radius;
         enemy
     }
 
     // Constructor for chasing enemies
-    pub fn new_chase(x: f32, y: f32, texture_key: char) -> Self {
-        let mut enemy = Self::new(x, y, texture_key);
+    pub fn new_chase(x: f32, y: f32, def: EnemyDef) -> Self {
+        let mut enemy = Self::new(x, y, def);
         enemy.movement_pattern = MovementPattern::Chase;
-        enemy.movement_speed = 75.0; // Slightly faster for chase
+        enemy.movement_speed = 0.75; // Slightly faster for chase
         enemy
     }
 
-    pub fn update(&mut self, delta_time: f32, player_pos: Vector2, maze: &Maze, block_size: usize) {
+    // Chasers behind a shield are slower, and turn slowly enough to be flanked
+    pub fn new_shield_bearer(x: f32, y: f32, def: EnemyDef) -> Self {
+        let mut enemy = Self::new_chase(x, y, def);
+        enemy.movement_speed = SHIELD_SPEED;
+        enemy
+    }
+
+    // Cloaked chasers fade to a shimmer, the lantern's beam or a lucky hit shows where they are
+    pub fn new_cloaked(x: f32, y: f32, def: EnemyDef) -> Self {
+        let mut enemy = Self::new_chase(x, y, def);
+        enemy.cloaked = true;
+        enemy.opacity = CLOAK_ALPHA;
+        enemy
+    }
+
+    // Fade a cloaked enemy in while `lit` by the lantern or shortly after a hit, and back out
+    // after. Dead enemies drop the cloak for good.
+    pub fn update_cloak(&mut self, delta_time: f32, lit: bool) {
+        if !self.cloaked {
+            return;
+        }
+        self.reveal_timer = (self.reveal_timer - delta_time).max(0.0);
+        let target = if lit || self.reveal_timer > 0.0 || self.is_dead { 1.0 } else { CLOAK_ALPHA };
+        let step = FADE_RATE * delta_time;
+        self.opacity = if self.opacity < target { (self.opacity + step).min(target) } else { (self.opacity - step).max(target) };
+    }
+
+    // The shield is up once the enemy knows the player is there, an unaware one can still be ambushed
+    pub fn shield_raised(&self) -> bool {
+        self.def.shield.is_some() && !self.is_dead && self.awareness >= 1.0
+    }
+
+    // True if a blow coming from `from` lands on the raised shield
+    pub fn blocks(&self, from: Vector2) -> bool {
+        self.shield_raised() && from_front(self.pos.into(), self.heading, from.into(), SHIELD_ARC)
+    }
+
+    pub fn block(&mut self) {
+        self.block_timer = BLOCK_FLASH;
+    }
+
+    // `player_pos` is whatever the enemy is after, `visibility` how well lit it is (see stealth.rs)
+    pub fn update(&mut self, delta_time: f32, player_pos: Vector2, visibility: f32, maze: &Maze, block_size: usize) {
         // Update death timer if dead
         if self.is_dead {
             self.death_timer += delta_time;
             // Don't move if dead
         } else {
             // Handle movement based on pattern
-            self.update_movement(delta_time, player_pos, maze, block_size);
+            self.update_movement(delta_time, player_pos, visibility, maze, block_size);
         }
         
-        // Update animation timer
+        self.attack_cooldown = (self.attack_cooldown - delta_time).max(0.0);
+        self.distracted = (self.distracted - delta_time).max(0.0);
+        self.block_timer = (self.block_timer - delta_time).max(0.0);
+
+        // Age hit feedback
+        self.health_bar_timer = (self.health_bar_timer - delta_time).max(0.0);
+        for number in self.damage_numbers.iter_mut() {
+            number.age += delta_time;
+        }
+        self.damage_numbers.retain(|number| number.age < DAMAGE_NUMBER_LIFETIME);
+
+        if self.animation_state == AnimationState::Idle && self.fidget.is_none() {
+            self.update_fidget_timer(delta_time);
+        }
+
+        // Update animation timer, carrying the leftover time so playback speed doesn't depend on the frame rate.
+        // The animation can change when an idle loop ends, so its speed is looked up every frame.
         self.animation_timer += delta_time;
-        
-        if self.animation_timer >= self.frame_duration {
-            self.animation_timer = 0.0;
-            
-            // Determine number of frames for current animation
-            let max_frames = match self.animation_state {
-                AnimationState::Idle => 4,     // 4 idle frames
-                AnimationState::Walking => 4,  // 4 walking frames  
-                AnimationState::Attack => 4,   // 4 attack frames
-                AnimationState::Death => 4,    // 4 death frames
-            };
+        loop {
+            let animation = self.current_animation();
+            let frame_duration = 1.0 / animation.fps;
+            if self.animation_timer < frame_duration {
+                break;
+            }
+            self.animation_timer -= frame_duration;
             
-            // If dead, don't loop the death animation, stay on last frame
+            // If dead, don't loop the death animation, stay on its last frame
             if self.is_dead && self.animation_state == AnimationState::Death {
-                self.current_frame = (self.current_frame + 1).min(max_frames - 1);
+                self.current_frame = (self.current_frame + 1).min(animation.frames - 1);
+            } else if self.animation_state == AnimationState::Idle && self.current_frame + 1 >= animation.frames {
+                self.current_frame = 0;
+                self.end_idle_loop();
             } else {
-                self.current_frame = (self.current_frame + 1) % max_frames;
+                self.current_frame = (self.current_frame + 1) % animation.frames;
             }
         }
     }
 
-    fn update_movement(&mut self, delta_time: f32, player_pos: Vector2, maze: &Maze, block_size: usize) {
+    // The animation being played, which sheet row to draw
+    pub fn current_animation(&self) -> AnimationDef {
+        if let Some(shield) = self.def.shield.filter(|_| self.shield_raised()) {
+            return shield;
+        }
+        match self.animation_state {
+            AnimationState::Idle => self.fidget.unwrap_or(self.idle),
+            state => *self.def.animation(state),
+        }
+    }
+
+    // Counts down to the next fidget and starts one when it is due
+    fn update_fidget_timer(&mut self, delta_time: f32) {
+        let count = self.def.fidgets.iter().flatten().count();
+        if count == 0 {
+            return;
+        }
+        let (min, max) = self.def.fidget_interval;
+        let timer = match self.fidget_timer {
+            Some(timer) => timer - delta_time,
+            None => self.animation_rng.range_f32(min, max), // Spread out, so enemies placed together don't fidget in step
+        };
+        if timer > 0.0 {
+            self.fidget_timer = Some(timer);
+            return;
+        }
+
+        self.fidget = self.def.fidgets.iter().flatten().nth(self.animation_rng.range(count)).copied();
+        self.current_frame = 0;
+        self.animation_timer = 0.0;
+        self.fidget_timer = Some(self.animation_rng.range_f32(min, max));
+    }
+
+    // A fidget plays once, otherwise the next loop may switch to another idle variant
+    fn end_idle_loop(&mut self) {
+        if self.fidget.take().is_some() {
+            return;
+        }
+        let count = self.def.idle_variants.iter().flatten().count();
+        if count > 0 {
+            // The main loop counts as one of the choices
+            let pick = self.animation_rng.range(count + 1);
+            self.idle = self.def.idle_variants.iter().flatten().nth(pick).copied().unwrap_or(self.def.idle);
+        }
+    }
+
+    fn update_movement(&mut self, delta_time: f32, player_pos: Vector2, visibility: f32, maze: &Maze, block_size: usize) {
         self.movement_timer += delta_time;
+
+        // A fight with another faction overrides the usual pattern, `player_pos` is then the opponent
+        if self.grudge.is_some() {
+            self.pursue(delta_time, player_pos, GRUDGE_STOP_DISTANCE, maze, block_size);
+            return;
+        }
         
         match self.movement_pattern {
             MovementPattern::Stationary => {
@@ -140,24 +475,24 @@ impl Enemy {
             }
             
             MovementPattern::Wander => {
-                self.update_wander_movement(delta_time, maze, block_size);
+                self.update_wander_movement(delta_time, player_pos, maze, block_size);
             }
             
             MovementPattern::Chase => {
-                self.update_chase_movement(delta_time, player_pos, maze, block_size);
+                self.update_chase_movement(delta_time, player_pos, visibility, maze, block_size);
             }
         }
     }
 
     fn update_patrol_movement(&mut self, delta_time: f32, maze: &Maze, block_size: usize) {
-        let move_distance = self.movement_speed * delta_time;
+        let move_distance = self.movement_speed * self.status.speed_multiplier() * block_size as f32 * delta_time;
         
         // Calculate direction to target
         let dx = self.target_pos.x - self.pos.x;
         let dy = self.target_pos.y - self.pos.y;
         let distance_to_target = (dx * dx + dy * dy).sqrt();
         
-        if distance_to_target < 10.0 {
+        if distance_to_target < block_size as f32 * 0.1 {
             // Reached target, switch direction
             if self.target_pos.x == self.patrol_end.x && self.target_pos.y == self.patrol_end.y {
                 self.target_pos = self.patrol_start;
@@ -171,26 +506,35 @@ impl Enemy {
             
             let new_pos = Vector2::new(self.pos.x + move_x, self.pos.y + move_y);
             
-            if !self.would_collide_with_wall(new_pos, maze, block_size) {
+            if !enemy_blocked(maze, new_pos.into(), block_size) {
                 self.pos = new_pos;
                 self.set_animation(AnimationState::Walking);
-                
-                // Update facing direction
-                self.facing_left = move_x < 0.0;
+                self.face(move_x, move_y, delta_time);
             } else {
                 self.set_animation(AnimationState::Idle);
             }
         }
     }
 
-    fn update_wander_movement(&mut self, delta_time: f32, maze: &Maze, block_size: usize) {
+    fn update_wander_movement(&mut self, delta_time: f32, player_pos: Vector2, maze: &Maze, block_size: usize) {
         // Change direction every 2-4 seconds
-        if self.movement_timer > 2.0 + (self.pos.x as i32 % 3) as f32 {
+        if self.movement_timer > self.wander_interval {
             self.movement_timer = 0.0;
+            self.wander_interval = self.rng.range_f32(2.0, 4.0);
+
+            // Restless wanderers drift their area toward the player, as long as it stays on open floor
+            let drift = self.aggression * WANDER_DRIFT;
+            let center = Vector2::new(
+                self.wander_center.x + (player_pos.x - self.wander_center.x) * drift,
+                self.wander_center.y + (player_pos.y - self.wander_center.y) * drift,
+            );
+            if !enemy_blocked(maze, center.into(), block_size) {
+                self.wander_center = center;
+            }
             
             // Pick a random point within wander radius
-            let angle = (self.pos.x + self.pos.y) * 0.01; // Pseudo-random based on position
-            let distance = self.wander_radius * 0.5 + (self.wander_radius * 0.5 * angle.sin().abs());
+            let angle = self.rng.range_f32(0.0, 2.0 * PI);
+            let distance = self.wander_radius * self.rng.range_f32(0.5, 1.0);
             
             self.target_pos = Vector2::new(
                 self.wander_center.x + distance * angle.cos(),
@@ -199,21 +543,22 @@ impl Enemy {
         }
         
         // Move toward current target
-        let move_distance = self.movement_speed * delta_time * 0.7; // Slower wandering
+        let restless = 1.0 + self.aggression * RESTLESS_SPEED;
+        let move_distance = self.movement_speed * self.status.speed_multiplier() * block_size as f32 * delta_time * 0.7 * restless; // Slower wandering
         let dx = self.target_pos.x - self.pos.x;
         let dy = self.target_pos.y - self.pos.y;
         let distance_to_target = (dx * dx + dy * dy).sqrt();
         
-        if distance_to_target > 5.0 {
+        if distance_to_target > block_size as f32 * 0.05 {
             let move_x = (dx / distance_to_target) * move_distance;
             let move_y = (dy / distance_to_target) * move_distance;
             
             let new_pos = Vector2::new(self.pos.x + move_x, self.pos.y + move_y);
             
-            if !self.would_collide_with_wall(new_pos, maze, block_size) {
+            if !enemy_blocked(maze, new_pos.into(), block_size) {
                 self.pos = new_pos;
                 self.set_animation(AnimationState::Walking);
-                self.facing_left = move_x < 0.0;
+                self.face(move_x, move_y, delta_time);
             } else {
                 self.set_animation(AnimationState::Idle);
             }
@@ -222,55 +567,87 @@ impl Enemy {
         }
     }
 
-    fn update_chase_movement(&mut self, delta_time: f32, player_pos: Vector2, maze: &Maze, block_size: usize) {
+    fn update_chase_movement(&mut self, delta_time: f32, player_pos: Vector2, visibility: f32, maze: &Maze, block_size: usize) {
         let dx = player_pos.x - self.pos.x;
         let dy = player_pos.y - self.pos.y;
         let distance_to_player = (dx * dx + dy * dy).sqrt();
-        
-        // Only chase if player is within reasonable range
-        if distance_to_player < 300.0 && distance_to_player > 20.0 {
-            let move_distance = self.movement_speed * delta_time;
-            let move_x = (dx / distance_to_player) * move_distance;
-            let move_y = (dy / distance_to_player) * move_distance;
-            
-            let new_pos = Vector2::new(self.pos.x + move_x, self.pos.y + move_y);
-            
-            if !self.would_collide_with_wall(new_pos, maze, block_size) {
-                self.pos = new_pos;
-                self.set_animation(AnimationState::Walking);
-                self.facing_left = move_x < 0.0;
-            } else {
-                self.set_animation(AnimationState::Idle);
-            }
+
+        // Noticing the player takes longer the darker it is around them
+        if distance_to_player < block_size as f32 * CHASE_RANGE {
+            self.awareness = (self.awareness + DETECTION_RATE * visibility * delta_time).min(1.0);
+        } else {
+            self.awareness = (self.awareness - AWARENESS_DECAY * delta_time).max(0.0);
+        }
+
+        // Only chase a noticed player within reasonable range
+        if self.awareness >= 1.0 && distance_to_player < block_size as f32 * CHASE_RANGE {
+            self.pursue(delta_time, player_pos, 0.2, maze, block_size);
         } else {
             self.set_animation(AnimationState::Idle);
         }
     }
 
-    fn would_collide_with_wall(&self, new_pos: Vector2, maze: &Maze, block_size: usize) -> bool {
-        let margin = 20.0; // Collision margin around enemy
-        
-        // Check corners of enemy collision box
-        let corners = [
-            (new_pos.x - margin, new_pos.y - margin),
-            (new_pos.x + margin, new_pos.y - margin),
-            (new_pos.x - margin, new_pos.y + margin),
-            (new_pos.x + margin, new_pos.y + margin),
-        ];
-        
-        for (x, y) in corners.iter() {
-            let maze_x = (*x / block_size as f32) as usize;
-            let maze_y = (*y / block_size as f32) as usize;
-            
-            if maze_y < maze.len() && maze_x < maze[0].len() {
-                if maze[maze_y][maze_x] != ' ' {
-                    return true; // Would collide with wall
-                }
-            } else {
-                return true; // Out of bounds
-            }
+    // Head straight for `target` at full speed, stopping `stop_distance` cells short of it
+    fn pursue(&mut self, delta_time: f32, target: Vector2, stop_distance: f32, maze: &Maze, block_size: usize) {
+        let dx = target.x - self.pos.x;
+        let dy = target.y - self.pos.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance <= block_size as f32 * stop_distance {
+            self.set_animation(AnimationState::Idle);
+            return;
+        }
+
+        let move_distance = self.movement_speed * self.status.speed_multiplier() * block_size as f32 * delta_time;
+        let move_x = (dx / distance) * move_distance;
+        let move_y = (dy / distance) * move_distance;
+
+        let new_pos = Vector2::new(self.pos.x + move_x, self.pos.y + move_y);
+
+        if !enemy_blocked(maze, new_pos.into(), block_size) {
+            self.pos = new_pos;
+            self.set_animation(AnimationState::Walking);
+            self.face(move_x, move_y, delta_time);
+        } else {
+            self.set_animation(AnimationState::Idle);
+        }
+    }
+
+    // Sprite flip and heading from a step it just took; a shield bearer only turns so fast
+    fn face(&mut self, move_x: f32, move_y: f32, delta_time: f32) {
+        self.facing_left = move_x < 0.0;
+        let heading = move_y.atan2(move_x);
+        if self.def.shield.is_none() {
+            self.heading = heading;
+            return;
+        }
+        let turn = normalize_angle(heading - self.heading);
+        let limit = SHIELD_TURN_RATE * delta_time;
+        self.heading = normalize_angle(self.heading + turn.clamp(-limit, limit));
+    }
+
+    // Apply a hit, returns true if it killed the enemy
+    pub fn take_damage(&mut self, amount: f32) -> bool {
+        self.take_hit(amount, false)
+    }
+
+    // A critical hit shows a larger damage number
+    pub fn take_hit(&mut self, amount: f32, critical: bool) -> bool {
+        if self.is_dead {
+            return false;
+        }
+
+        self.health = (self.health - amount).max(0.0);
+        self.health_bar_timer = HEALTH_BAR_DURATION;
+        if self.cloaked {
+            self.reveal_timer = REVEAL_TIME;
+            self.opacity = 1.0;
+        }
+        self.damage_numbers.push(DamageNumber { amount: amount.round() as u32, age: 0.0, critical });
+
+        if self.health <= 0.0 {
+            self.kill();
+            return true;
         }
-        
         false
     }
 
@@ -285,7 +662,7 @@ impl Enemy {
     }
 
     pub fn should_despawn(&self) -> bool {
-        self.is_dead && self.death_timer > 3.0 // Despawn after 3 seconds
+        self.is_dead && self.death_timer > self.def.despawn_time
     }
 
     pub fn set_animation(&mut self, new_state: AnimationState) {
@@ -297,6 +674,7 @@ impl Enemy {
             self.animation_state = new_state;
             self.current_frame = 0;
             self.animation_timer = 0.0;
+            self.fidget = None;
         }
     }
 }
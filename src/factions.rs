@@ -0,0 +1,107 @@
+// factions.rs
+
+use raylib::prelude::*;
+
+use crate::enemy::Enemy;
+use crate::maze::{MapArea, parse_area};
+
+// Enemies belong to the map's horde unless its header places them in another faction. A map
+// tags rectangles of cells, every enemy that starts inside one joins it, later lines win:
+//   faction = cultists 0 10 16 10   (name, x, y, width, height in cells)
+//   faction = cultists              (no rectangle: the whole map)
+// Factions leave each other alone until one hurts the other. An enemy swinging at the player
+// catches other-faction enemies standing beside it, and the one that got hit turns on the one
+// that hit it, which fights back. A grudge lasts until one of them dies or they drift apart,
+// and meanwhile neither pays the player any attention.
+
+pub const HORDE: usize = 0;      // Everyone not placed in a named faction
+const GRUDGE_RANGE: f32 = 6.0;   // Cells apart at which a fight is given up
+
+// The `faction` lines of a map's metadata, each name with the area it covers
+pub fn faction_areas(meta: &[(String, String)]) -> Vec<(String, MapArea)> {
+    let mut areas = Vec::new();
+    for (key, value) in meta.iter().filter(|(key, _)| key == "faction") {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        let Some(name) = parts.first() else {
+            elog!("Warning: '{} = {}' needs a faction name", key, value);
+            continue;
+        };
+        if let Some(area) = parse_area(key, value, &parts[1..]) {
+            areas.push((name.to_string(), area));
+        }
+    }
+    areas
+}
+
+// Number each named faction in order of first mention, after the horde, and sort the enemies into them
+pub fn assign_factions(enemies: &mut [Enemy], areas: &[(String, MapArea)], block_size: usize) {
+    let mut names: Vec<&str> = Vec::new();
+    for (name, _) in areas {
+        if !names.contains(&name.as_str()) {
+            names.push(name);
+        }
+    }
+    for enemy in enemies.iter_mut() {
+        let cell = (enemy.pos.x as usize / block_size, enemy.pos.y as usize / block_size);
+        let area = areas.iter().rev().find(|(_, area)| area.contains(cell));
+        enemy.faction = area.and_then(|(area_name, _)| names.iter().position(|name| name == area_name)).map_or(HORDE, |index| index + 1);
+    }
+    for (index, name) in names.iter().enumerate() {
+        let members = enemies.iter().filter(|enemy| enemy.faction == index + 1).count();
+        log!("Faction {}: {} enemies", name, members);
+    }
+}
+
+// `attacker` hurt `victim`: if they are on different sides and the victim isn't busy fighting
+// someone else, it turns on the attacker
+pub fn provoke(enemies: &mut [Enemy], victim: usize, attacker: usize) {
+    if victim == attacker || enemies[victim].faction == enemies[attacker].faction {
+        return;
+    }
+    let enemy = &mut enemies[victim];
+    if !enemy.is_dead && enemy.grudge.is_none() {
+        enemy.grudge = Some(attacker);
+    }
+}
+
+// Drop grudges against the dead and the far away
+pub fn update_grudges(enemies: &mut [Enemy], block_size: usize) {
+    let range = GRUDGE_RANGE * block_size as f32;
+    for index in 0..enemies.len() {
+        let Some(target) = enemies[index].grudge else {
+            continue;
+        };
+        let (pos, other) = (enemies[index].pos, &enemies[target]);
+        let distance = ((other.pos.x - pos.x).powi(2) + (other.pos.y - pos.y).powi(2)).sqrt();
+        if enemies[index].is_dead || other.is_dead || distance > range {
+            enemies[index].grudge = None;
+        }
+    }
+}
+
+// Where each enemy's opponent stands, for the ones that are fighting
+pub fn grudge_targets(enemies: &[Enemy]) -> Vec<Option<Vector2>> {
+    enemies.iter().map(|enemy| enemy.grudge.map(|target| enemies[target].pos)).collect()
+}
+
+// Remove enemies that should despawn, keeping grudges pointed at the same enemies
+pub fn despawn(enemies: &mut Vec<Enemy>) {
+    if !enemies.iter().any(|enemy| enemy.should_despawn()) {
+        return;
+    }
+    let mut kept = 0;
+    let new_index: Vec<Option<usize>> = enemies
+        .iter()
+        .map(|enemy| {
+            if enemy.should_despawn() {
+                return None;
+            }
+            kept += 1;
+            Some(kept - 1)
+        })
+        .collect();
+    enemies.retain(|enemy| !enemy.should_despawn());
+    for enemy in enemies.iter_mut() {
+        enemy.grudge = enemy.grudge.and_then(|target| new_index[target]);
+    }
+}
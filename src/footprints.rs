@@ -0,0 +1,69 @@
+// footprints.rs
+
+use raylib::prelude::*;
+
+// Prints left on the floor by the player and the enemies, fading after a while. The 3D view
+// has no floor casting to project them onto yet, so they show on the minimap and the debug
+// view only: enough to see where you have been and which way something walked.
+
+pub const FOOTPRINT_LIFETIME: f32 = 20.0; // Seconds a print stays visible
+const STEP_LENGTH: f32 = 0.5;             // Cells walked between prints
+const TELEPORT_DISTANCE: f32 = 2.0;       // Cells; a longer jump (a respawn, a new map) leaves no print
+const MAX_FOOTPRINTS: usize = 600;        // Oldest prints go first past this
+const BLOOD_HEALTH: f32 = 0.35;           // Below this share of health the player leaves blood instead
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FootprintKind {
+    Player,
+    Blood,
+    Enemy,
+}
+
+impl FootprintKind {
+    // What the player leaves at the given share of their health
+    pub fn for_player(health_ratio: f32) -> Self {
+        if health_ratio < BLOOD_HEALTH { FootprintKind::Blood } else { FootprintKind::Player }
+    }
+}
+
+pub struct Footprint {
+    pub pos: Vector2,
+    pub kind: FootprintKind,
+    pub age: f32,
+}
+
+impl Footprint {
+    // Fades out over its lifetime
+    pub fn color(&self) -> Color {
+        let alpha = ((1.0 - self.age / FOOTPRINT_LIFETIME).clamp(0.0, 1.0) * 200.0) as u8;
+        match self.kind {
+            FootprintKind::Player => Color::new(230, 210, 170, alpha),
+            FootprintKind::Blood => Color::new(200, 20, 20, alpha),
+            FootprintKind::Enemy => Color::new(150, 90, 200, alpha),
+        }
+    }
+}
+
+// Drop a print once `pos` is a step away from `mark`, the walker's last print
+pub fn step(prints: &mut Vec<Footprint>, mark: &mut Vector2, pos: Vector2, kind: FootprintKind, block_size: usize) {
+    let walked = ((pos.x - mark.x).powi(2) + (pos.y - mark.y).powi(2)).sqrt() / block_size as f32;
+    if walked < STEP_LENGTH {
+        return;
+    }
+    *mark = pos;
+    if walked > TELEPORT_DISTANCE {
+        return;
+    }
+
+    if prints.len() >= MAX_FOOTPRINTS {
+        prints.remove(0);
+    }
+    prints.push(Footprint { pos, kind, age: 0.0 });
+}
+
+pub fn update_footprints(prints: &mut Vec<Footprint>, delta_time: f32) {
+    for print in prints.iter_mut() {
+        print.age += delta_time;
+    }
+    prints.retain(|print| print.age < FOOTPRINT_LIFETIME);
+}
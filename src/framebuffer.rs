@@ -1,6 +1,36 @@
 // framebuffer.rs
 
 use raylib::prelude::*;
+use std::slice;
+
+use crate::color;
+
+// Blended pixels at least this opaque take the depth, so what is drawn behind them later stays hidden
+const DEPTH_WRITE_ALPHA: u8 = 128;
+
+// 4x4 Bayer matrix, thresholds for ordered dithering in sixteenths
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// 3x5 bitmaps for 0-9, one row per entry with the leftmost pixel in the highest bit
+const DIGIT_GLYPHS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+// Ordered dithering: whether pixel (x, y) is drawn at a coverage of `amount` (0 to 1). Each pixel of
+// a 4x4 tile has its own threshold, so a partial coverage becomes an even screen-door pattern that
+// fades without blending.
+pub fn dither(x: u32, y: u32, amount: f32) -> bool {
+    amount * 16.0 > BAYER_4X4[(y & 3) as usize][(x & 3) as usize] as f32 + 0.5
+}
 
 pub struct Framebuffer {
     pub width: u32,
@@ -50,6 +80,27 @@ impl Framebuffer {
         false
     }
 
+    // Depth tested pixel that honors the current color's alpha: solid colors replace what is there,
+    // see-through ones mix over it. Faint ones leave the depth alone, so a sprite drawn behind glass
+    // or smoke later still shows; the frame itself stays opaque either way.
+    pub fn blend_pixel_with_depth(&mut self, x: u32, y: u32, depth: f32) -> bool {
+        let color = self.current_color;
+        match color.a {
+            0 => false,
+            255 => self.set_pixel_with_depth(x, y, depth),
+            alpha => {
+                if x >= self.width || y >= self.height || depth >= self.depth_buffer[(y * self.width + x) as usize] {
+                    return false;
+                }
+                self.blend_with_depth(x, y, depth, color, alpha as f32 / 255.0);
+                if alpha >= DEPTH_WRITE_ALPHA {
+                    self.depth_buffer[(y * self.width + x) as usize] = depth;
+                }
+                true
+            }
+        }
+    }
+
     // Get depth at pixel (for sprite rendering)
     pub fn get_depth(&self, x: u32, y: u32) -> f32 {
         if x < self.width && y < self.height {
@@ -60,6 +111,65 @@ impl Framebuffer {
         }
     }
 
+    // Raw RGBA bytes of the color buffer, for passes that touch the whole frame
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        if self.color_buffer.data.is_null() {
+            return &mut [];
+        }
+        let len = (self.width * self.height * 4) as usize;
+        unsafe { slice::from_raw_parts_mut(self.color_buffer.data as *mut u8, len) }
+    }
+
+    // Filled rectangle that still respects the depth buffer and the current color's alpha, for
+    // world-space HUD elements
+    pub fn fill_rect_with_depth(&mut self, x: i32, y: i32, width: i32, height: i32, depth: f32) {
+        for py in y.max(0)..(y + height).min(self.height as i32) {
+            for px in x.max(0)..(x + width).min(self.width as i32) {
+                self.blend_pixel_with_depth(px as u32, py as u32, depth);
+            }
+        }
+    }
+
+    // Blend what is already there toward `color` by `amount` (0 to 1) if `depth` is in front of it, leaving
+    // the depth buffer alone so the sprites drawn afterwards still cover it
+    pub fn blend_with_depth(&mut self, x: u32, y: u32, depth: f32, color: Color, amount: f32) {
+        if x >= self.width || y >= self.height || depth >= self.depth_buffer[(y * self.width + x) as usize] {
+            return;
+        }
+        let index = ((y * self.width + x) * 4) as usize;
+        if let Some(pixel) = self.pixels_mut().get_mut(index..index + 3) {
+            for (channel, target) in pixel.iter_mut().zip([color.r, color.g, color.b]) {
+                *channel = color::lerp_channel(*channel, target, amount);
+            }
+        }
+    }
+
+    // Draw a number with a tiny 3x5 pixel font, centered on center_x, depth tested like sprites
+    pub fn draw_number_with_depth(&mut self, value: u32, center_x: i32, top_y: i32, pixel_size: i32, depth: f32) {
+        let digits = value.to_string();
+        let glyph_advance = 4 * pixel_size; // 3 pixels wide plus 1 pixel spacing
+        let total_width = digits.len() as i32 * glyph_advance - pixel_size;
+        let mut x = center_x - total_width / 2;
+
+        for digit in digits.bytes() {
+            let glyph = DIGIT_GLYPHS[(digit - b'0') as usize];
+            for (row, bits) in glyph.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) != 0 {
+                        self.fill_rect_with_depth(
+                            x + col * pixel_size,
+                            top_y + row as i32 * pixel_size,
+                            pixel_size,
+                            pixel_size,
+                            depth,
+                        );
+                    }
+                }
+            }
+            x += glyph_advance;
+        }
+    }
+
     pub fn set_background_color(&mut self, color: Color) {
         self.background_color = color;
     }
@@ -0,0 +1,73 @@
+// framediff.rs
+
+use raylib::prelude::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::color;
+use crate::framebuffer::Framebuffer;
+use crate::textures::TextureManager;
+use crate::world::World;
+
+// Debug check for renderer changes: the current frame is rendered once with each of two
+// configurations, both are saved, and a heatmap of how far every pixel differs is saved next
+// to them. Matching pixels are black, any difference at all shows as dark red, brightening
+// through red to yellow for the largest. An optimization that is meant to leave the picture
+// alone should give an all black heatmap when compared against the path it replaces.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderConfig {
+    pub name: &'static str, // Used in the file names
+    pub performance_mode: bool,
+}
+
+pub const QUALITY: RenderConfig = RenderConfig { name: "quality", performance_mode: false };
+pub const PERFORMANCE: RenderConfig = RenderConfig { name: "performance", performance_mode: true };
+
+pub struct FrameDiff {
+    pub path: String,      // The heatmap
+    pub changed: usize,    // Pixels that differ at all
+    pub total: usize,
+    pub max_difference: u8, // Largest difference in any color channel
+}
+
+// Leaves the heatmap in the framebuffer, the next frame's render clears it
+pub fn capture(framebuffer: &mut Framebuffer, world: &World, texture_cache: &TextureManager, shadows: bool, a: RenderConfig, b: RenderConfig) -> FrameDiff {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+    let mut render = |config: RenderConfig| -> Vec<u8> {
+        framebuffer.clear();
+        world.render(framebuffer, texture_cache, config.performance_mode, shadows, None);
+        framebuffer.color_buffer.export_image(&format!("framediff_{}_{}.png", stamp, config.name));
+        framebuffer.pixels_mut().to_vec()
+    };
+    let first = render(a);
+    let second = render(b);
+
+    let mut changed = 0;
+    let mut max_difference = 0;
+    let heatmap = framebuffer.pixels_mut();
+    for ((out, pixel_a), pixel_b) in heatmap.chunks_exact_mut(4).zip(first.chunks_exact(4)).zip(second.chunks_exact(4)) {
+        let difference = (0..3).map(|channel| pixel_a[channel].abs_diff(pixel_b[channel])).max().unwrap_or(0);
+        if difference > 0 {
+            changed += 1;
+        }
+        max_difference = max_difference.max(difference);
+        let color = heat_color(difference);
+        out.copy_from_slice(&[color.r, color.g, color.b, 255]);
+    }
+
+    let path = format!("framediff_{}_{}_vs_{}.png", stamp, a.name, b.name);
+    framebuffer.color_buffer.export_image(&path);
+    FrameDiff { path, changed, total: first.len() / 4, max_difference }
+}
+
+fn heat_color(difference: u8) -> Color {
+    if difference == 0 {
+        return Color::BLACK;
+    }
+    let t = difference as f32 / 255.0;
+    if t < 0.5 {
+        color::lerp(Color::new(110, 0, 0, 255), Color::RED, t * 2.0)
+    } else {
+        color::lerp(Color::RED, Color::YELLOW, (t - 0.5) * 2.0)
+    }
+}
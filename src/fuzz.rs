@@ -0,0 +1,97 @@
+// fuzz.rs
+
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+use crate::hub::HUB_MAP;
+use crate::inventory::WEAPONS;
+use crate::mapgen::generate_maze;
+use crate::maze::{DEFAULT_BLOCK_SIZE, MazeData, load_maze_with_player, maze_data_from};
+use crate::player::{Player, apply_input};
+use crate::rng::Rng;
+use crate::sim::{PlayerInput, player_blocked};
+use crate::world::check_attack_collision;
+use crate::{AVAILABLE_MAPS, create_enemies_for_maze};
+
+// Random input thrown at the simulation on every bundled map. Each sequence is recorded up
+// front from its own seed and then played back frame by frame, moving and swinging the player
+// while the enemies run their AI around it, and after every frame the invariants are checked:
+// the player is never inside a wall, nothing is at a NaN position and no enemy leaves the map.
+// A failure names the map, seed and frame along with the input that broke it, so
+// `play(map, seed)` on its own replays it exactly.
+
+const SEQUENCES: u64 = 600; // Per map
+const FRAMES: usize = 90;   // Per sequence, a second and a half
+const DELTA_TIME: f32 = 1.0 / 60.0;
+const GENERATED_SIZE: (usize, usize) = (16, 12); // In maze.py cells
+
+// The hand-made maps, the hub and a generated floor like the Daily Dungeon's
+fn bundled_maps() -> Vec<(String, MazeData)> {
+    let load = |filename: &str| load_maze_with_player(filename).unwrap_or_else(|e| panic!("{}", e));
+    let mut maps: Vec<(String, MazeData)> = AVAILABLE_MAPS.iter().map(|map| (map.filename.to_string(), load(map.filename))).collect();
+    maps.push((HUB_MAP.to_string(), load(HUB_MAP)));
+    maps.push(("generated".to_string(), maze_data_from(generate_maze(GENERATED_SIZE.0, GENERATED_SIZE.1, 1), DEFAULT_BLOCK_SIZE)));
+    maps
+}
+
+fn record(rng: &mut Rng) -> Vec<PlayerInput> {
+    (0..FRAMES)
+        .map(|_| {
+            let mut input = PlayerInput::default();
+            if rng.range(3) == 0 {
+                input.turn = rng.range_f32(-PI / 4.0, PI / 4.0);
+            }
+            for _ in 0..rng.range(4) {
+                // Half the time a whole step in one of the four directions, otherwise a stick push at any angle
+                let step = if rng.range(2) == 0 {
+                    (rng.range(4) as f32 * PI / 2.0, 1.0)
+                } else {
+                    (rng.range_f32(-PI, PI), rng.range_f32(-1.0, 1.0))
+                };
+                input.steps.push(step);
+            }
+            input.sprint = rng.range(3) == 0;
+            input.attack = rng.range(8) == 0;
+            input
+        })
+        .collect()
+}
+
+fn play(name: &str, data: &MazeData, seed: u64) {
+    let (maze, block_size) = (&data.maze, data.block_size);
+    let (width, height) = (maze.width() as f32 * block_size as f32, maze.height() as f32 * block_size as f32);
+    let mut rng = Rng::new(seed);
+    let inputs = record(&mut rng);
+
+    let mut player = Player::new(data.player_start, rng.range_f32(-PI, PI), PI / 3.0, 0.0);
+    player.move_speed_multiplier = rng.range_f32(1.0, 2.0); // Speed upgrades from the shop
+    let mut enemies = create_enemies_for_maze(maze, block_size, None, &mut rng);
+    let weapon = WEAPONS[rng.range(WEAPONS.len())];
+    let (mut pickups, mut events) = (Vec::new(), Vec::new());
+
+    for (frame, input) in inputs.iter().enumerate() {
+        apply_input(&mut player, input, maze, block_size, DELTA_TIME);
+        check_attack_collision(&mut player, &mut enemies, &weapon, &mut pickups, &mut rng, block_size, &mut events);
+        for enemy in enemies.iter_mut() {
+            enemy.update(DELTA_TIME, player.pos, 1.0, maze, block_size);
+        }
+
+        let failure = |problem: String| format!("{} on {} with seed {} at frame {} after {:?}", problem, name, seed, frame, input);
+        let pos = player.pos;
+        assert!(pos.x.is_finite() && pos.y.is_finite(), "{}", failure(format!("Player at {:?}", pos)));
+        assert!(!player_blocked(maze, pos.into(), block_size), "{}", failure(format!("Player inside a wall at {:?}", pos)));
+        for (index, enemy) in enemies.iter().enumerate() {
+            let inside = |pos: Vector2| (0.0..width).contains(&pos.x) && (0.0..height).contains(&pos.y);
+            assert!(inside(enemy.pos), "{}", failure(format!("Enemy {} out of the map at {:?}", index, enemy.pos)));
+        }
+    }
+}
+
+#[test]
+fn random_input_keeps_the_simulation_sane() {
+    for (name, data) in bundled_maps() {
+        for seed in 0..SEQUENCES {
+            play(&name, &data, seed);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+// grading.rs
+
+use crate::mapstats::MapStats;
+use crate::maze::load_map_meta;
+use crate::summary::Tally;
+
+// A finished hand-made map earns a grade from S down to C on four marks, each against a
+// threshold the map's header can set, or a default worked out from the map:
+//   grade_time = 60      seconds, the map's par time by default
+//   grade_damage = 30    health lost at most, 40 by default
+//   grade_kills = 12     enemies killed at least, half the map's own enemies by default
+//   grade_secrets = 5    coins found at least, all of the map's own by default
+// The maps have no hidden rooms, so the coins lying around stand in for secrets.
+// A mark met counts 1 and one missed by a little (half again the time, twice the damage, or
+// half the kills or coins) counts a half. 3.5 or more is S, 2.5 A, 1.5 B, anything less C.
+
+const DEFAULT_DAMAGE: f32 = 40.0;
+const DEFAULT_TIME: f32 = 120.0; // Seconds, for a map without a par time
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Grade {
+    C,
+    B,
+    A,
+    S,
+}
+
+impl Grade {
+    fn from_points(points: f32) -> Grade {
+        match points {
+            p if p >= 3.5 => Grade::S,
+            p if p >= 2.5 => Grade::A,
+            p if p >= 1.5 => Grade::B,
+            _ => Grade::C,
+        }
+    }
+
+    pub fn letter(&self) -> &'static str {
+        match self {
+            Grade::S => "S",
+            Grade::A => "A",
+            Grade::B => "B",
+            Grade::C => "C",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Grade> {
+        match text {
+            "S" => Some(Grade::S),
+            "A" => Some(Grade::A),
+            "B" => Some(Grade::B),
+            "C" => Some(Grade::C),
+            _ => None,
+        }
+    }
+}
+
+// One of the four marks: what the player did, what the map asked for, and the points it earned
+pub struct Mark {
+    pub name: &'static str,
+    pub value: String,
+    pub target: String,
+    pub points: f32, // 1.0, 0.5 or 0.0
+}
+
+pub struct Grading {
+    pub grade: Grade,
+    pub marks: Vec<Mark>,
+}
+
+// Per-map thresholds, from the header or the defaults above
+struct Thresholds {
+    time: f32,
+    damage: f32,
+    kills: u32,
+    secrets: usize,
+}
+
+impl Thresholds {
+    fn for_map(filename: &str, stats: &MapStats) -> Self {
+        let enemies = stats.patrol + stats.wander + stats.chase + stats.guard + stats.arena;
+        let mut thresholds = Thresholds {
+            time: stats.par.unwrap_or(DEFAULT_TIME),
+            damage: DEFAULT_DAMAGE,
+            kills: enemies.div_ceil(2) as u32,
+            secrets: stats.coins,
+        };
+        for (key, value) in load_map_meta(filename).iter().filter(|(key, _)| key.starts_with("grade_")) {
+            let parsed = match key.as_str() {
+                "grade_time" => value.parse().ok().filter(|&seconds: &f32| seconds > 0.0).map(|seconds| thresholds.time = seconds),
+                "grade_damage" => value.parse().ok().filter(|&damage: &f32| damage >= 0.0).map(|damage| thresholds.damage = damage),
+                "grade_kills" => value.parse().ok().map(|kills| thresholds.kills = kills),
+                "grade_secrets" => value.parse().ok().map(|secrets| thresholds.secrets = secrets),
+                _ => None,
+            };
+            if parsed.is_none() {
+                elog!("Warning: Ignoring invalid grade threshold in {}: {} = {}", filename, key, value);
+            }
+        }
+        thresholds
+    }
+}
+
+fn mm_ss(seconds: f32) -> String {
+    format!("{:02}:{:02}", seconds as u32 / 60, seconds as u32 % 60)
+}
+
+// Full points for meeting the threshold, half for a near miss
+fn points(met: bool, close: bool) -> f32 {
+    if met { 1.0 } else if close { 0.5 } else { 0.0 }
+}
+
+// Grade a finish of `filename` in `time` seconds with `coins_found` of the map's own coins
+pub fn grade_map(filename: &str, stats: &MapStats, time: f32, tally: &Tally, coins_found: usize) -> Grading {
+    let thresholds = Thresholds::for_map(filename, stats);
+    let marks = vec![
+        Mark {
+            name: "Time",
+            value: mm_ss(time),
+            target: mm_ss(thresholds.time),
+            points: points(time <= thresholds.time, time <= thresholds.time * 1.5),
+        },
+        Mark {
+            name: "Damage",
+            value: format!("{:.0}", tally.damage_taken),
+            target: format!("{:.0}", thresholds.damage),
+            points: points(tally.damage_taken <= thresholds.damage, tally.damage_taken <= thresholds.damage * 2.0),
+        },
+        Mark {
+            name: "Kills",
+            value: tally.kills.to_string(),
+            target: thresholds.kills.to_string(),
+            points: points(tally.kills >= thresholds.kills, tally.kills * 2 >= thresholds.kills),
+        },
+        Mark {
+            name: "Secrets",
+            value: coins_found.to_string(),
+            target: thresholds.secrets.to_string(),
+            points: points(coins_found >= thresholds.secrets, coins_found * 2 >= thresholds.secrets),
+        },
+    ];
+    let grade = Grade::from_points(marks.iter().map(|mark| mark.points).sum());
+    log!("Graded {}: {}", filename, grade.letter());
+    Grading { grade, marks }
+}
@@ -0,0 +1,137 @@
+// gyro.rs
+
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+
+// Gyro aiming for controllers with motion sensors, such as the DualSense and the DualShock 4.
+// raylib has no motion input, but on Linux their drivers put the gyro on an evdev device of its
+// own, "<controller name> Motion Sensors", which is read here directly. Turning the controller
+// left or right turns the view on top of whatever the right stick and the mouse do, so the stick
+// makes the big turns and the gyro the fine aim. Switched on, with a sensitivity, in Settings.
+// Other systems, and controllers without the device or without read access to it, get no gyro.
+
+const DEVICE_SUFFIX: &str = "Motion Sensors";
+const UNITS_PER_DEGREE: f32 = 1024.0; // Per degree per second, as hid-playstation and hid-sony report it
+const DEADZONE: f32 = 1.5;            // Degrees per second, the drift of a controller lying still
+const RESCAN_TIME: f32 = 2.0;         // Seconds between looks for a newly plugged controller
+const EVENT_SIZE: usize = 24;         // struct input_event on 64-bit Linux
+const EV_ABS: u16 = 3;
+const ABS_RY: u16 = 4;                // The gyro's yaw axis, positive turning left
+
+pub struct Gyro {
+    device: Option<File>,
+    yaw_rate: f32,        // Degrees per second, as last reported
+    rescan: f32,          // Seconds until the next look for a device
+    reported: bool,       // A failed look was logged, so later ones stay quiet
+}
+
+impl Gyro {
+    pub fn new() -> Self {
+        Gyro { device: None, yaw_rate: 0.0, rescan: 0.0, reported: false }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.device.is_some()
+    }
+
+    // Once a frame while gyro aim is on: find a device, then read everything it reported since
+    pub fn update(&mut self, delta_time: f32) {
+        if self.device.is_none() {
+            self.rescan -= delta_time;
+            if self.rescan > 0.0 {
+                return;
+            }
+            self.rescan = RESCAN_TIME;
+            match open_device() {
+                Ok(file) => {
+                    self.device = Some(file);
+                    self.reported = false;
+                }
+                Err(message) if !self.reported => {
+                    log!("Gyro: {}", message);
+                    self.reported = true;
+                }
+                Err(_) => {}
+            }
+            return;
+        }
+
+        let mut buffer = [0u8; EVENT_SIZE * 64];
+        loop {
+            let Some(device) = self.device.as_mut() else {
+                return;
+            };
+            match device.read(&mut buffer) {
+                Ok(0) => return,
+                Ok(read) => {
+                    for event in buffer[..read].chunks_exact(EVENT_SIZE) {
+                        let kind = u16::from_ne_bytes([event[16], event[17]]);
+                        let code = u16::from_ne_bytes([event[18], event[19]]);
+                        let value = i32::from_ne_bytes([event[20], event[21], event[22], event[23]]);
+                        if kind == EV_ABS && code == ABS_RY {
+                            self.yaw_rate = value as f32 / UNITS_PER_DEGREE;
+                        }
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(e) => {
+                    // Unplugged: look for it again later
+                    log!("Gyro: Controller lost ({:?})", e.kind());
+                    self.release();
+                    return;
+                }
+            }
+        }
+    }
+
+    // Stop reading, when gyro aim is switched off or the controller goes away
+    pub fn release(&mut self) {
+        self.device = None;
+        self.yaw_rate = 0.0;
+        self.rescan = 0.0;
+    }
+
+    // Radians to turn the view this frame, positive to the right like the mouse and stick
+    pub fn turn(&self, delta_time: f32, sensitivity: f32) -> f32 {
+        if self.yaw_rate.abs() < DEADZONE {
+            return 0.0;
+        }
+        -self.yaw_rate.to_radians() * sensitivity * delta_time
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open_device() -> Result<File, String> {
+    use std::fs;
+    use std::os::unix::fs::OpenOptionsExt;
+    const O_NONBLOCK: i32 = 0o4000;
+
+    let entries = fs::read_dir("/sys/class/input").map_err(|e| format!("Cannot list input devices: {:?}", e.kind()))?;
+    for entry in entries.flatten() {
+        let node = entry.file_name().to_string_lossy().to_string();
+        if !node.starts_with("event") {
+            continue;
+        }
+        let Ok(name) = fs::read_to_string(entry.path().join("device/name")) else {
+            continue;
+        };
+        let name = name.trim();
+        if !name.ends_with(DEVICE_SUFFIX) {
+            continue;
+        }
+        let path = format!("/dev/input/{}", node);
+        return match fs::OpenOptions::new().read(true).custom_flags(O_NONBLOCK).open(&path) {
+            Ok(file) => {
+                log!("Gyro: Reading {} from {}", name, path);
+                Ok(file)
+            }
+            Err(e) => Err(format!("Found {} at {} but cannot read it ({:?})", name, path, e.kind())),
+        };
+    }
+    Err("No controller with motion sensors found".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_device() -> Result<File, String> {
+    Err("Motion sensors are only read on Linux".to_string())
+}
@@ -0,0 +1,52 @@
+// heatmap.rs
+
+use raylib::prelude::*;
+use std::collections::HashMap;
+
+use crate::color;
+
+// Where the player went on a map: every cell counts how many times the player walked into it and
+// how long they stood there. The full-screen map can tint the cells by their count, from cool
+// blue for a cell passed once to hot red for the most walked one, so map authors can see which
+// parts of a map players actually explore and which they never find. H toggles it while
+// playing; the victory and death screens open the map straight into it to look back over the route.
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Visits {
+    pub count: u32,   // Times the player stepped into the cell
+    pub seconds: f32, // Time spent standing in it
+}
+
+// Once a frame with the player's cell and the one it stood in the frame before
+pub fn record_visit(visited: &mut HashMap<(usize, usize), Visits>, cell: (usize, usize), previous: Option<(usize, usize)>, delta_time: f32) {
+    let visits = visited.entry(cell).or_default();
+    if previous != Some(cell) {
+        visits.count += 1;
+    }
+    visits.seconds += delta_time;
+}
+
+// Blue through yellow to red as `count` nears the busiest cell's
+fn heat_color(count: u32, busiest: u32) -> Color {
+    let heat = if busiest > 1 { count.saturating_sub(1) as f32 / (busiest - 1) as f32 } else { 0.0 };
+    let (cold, warm, hot) = (Color::new(40, 90, 230, 255), Color::new(240, 220, 60, 255), Color::new(230, 40, 30, 255));
+    if heat < 0.5 { color::lerp(cold, warm, heat * 2.0) } else { color::lerp(warm, hot, heat * 2.0 - 1.0) }
+}
+
+// Tint the visited cells of a map drawn at `origin` with `cell_size` pixels per cell, and a legend at `legend`
+pub fn draw_heatmap(d: &mut RaylibDrawHandle, visited: &HashMap<(usize, usize), Visits>, origin_x: i32, origin_y: i32, cell_size: i32, legend: (i32, i32)) {
+    let busiest = visited.values().map(|visits| visits.count).max().unwrap_or(0);
+    for (&(x, y), visits) in visited.iter() {
+        let mut tint = heat_color(visits.count, busiest);
+        tint.a = 170;
+        d.draw_rectangle(origin_x + x as i32 * cell_size, origin_y + y as i32 * cell_size, cell_size, cell_size, tint);
+    }
+
+    let width = 160;
+    for step in 0..width {
+        let count = 1 + (step as f32 / (width - 1) as f32 * busiest.saturating_sub(1) as f32).round() as u32;
+        d.draw_rectangle(legend.0 + step, legend.1, 1, 10, heat_color(count, busiest));
+    }
+    let label = format!("1 to {} visits | {} cells explored", busiest.max(1), visited.len());
+    d.draw_text(&label, legend.0 + width + 10, legend.1 - 3, 16, Color::LIGHTGRAY);
+}
@@ -0,0 +1,122 @@
+// hints.rs
+
+use raylib::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::maze::{Cell, is_hint_trigger};
+use crate::mods;
+
+const HINT_DURATION: f32 = 5.0; // Seconds a callout stays on screen
+const HINT_FADE: f32 = 0.5; // Fade in/out time at each end
+
+// Hints live next to the map: maze.txt -> maze.hints, with lines like "1: Press W to move"
+pub fn load_hints(map_filename: &str) -> HashMap<char, String> {
+    let mut hints = HashMap::new();
+    let hints_path = mods::resolve(&Path::new(map_filename).with_extension("hints").to_string_lossy());
+
+    let file = match File::open(&hints_path) {
+        Ok(file) => file,
+        Err(_) => return hints, // Maps without hints are fine
+    };
+
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // Key must be a single trigger digit
+        let parsed = line.split_once(':').and_then(|(key, text)| {
+            let mut key_chars = key.trim().chars();
+            match (key_chars.next(), key_chars.next()) {
+                (Some(trigger), None) if is_hint_trigger(Cell::from_char(trigger)) => Some((trigger, text.trim().to_string())),
+                _ => None,
+            }
+        });
+
+        match parsed {
+            Some((trigger, text)) => {
+                hints.insert(trigger, text);
+            }
+            None => elog!("Warning: Ignoring malformed hint line in {}: {}", hints_path, line),
+        }
+    }
+
+    log!("Loaded {} hints from {}", hints.len(), hints_path);
+    hints
+}
+
+// Timed HUD callouts driven by trigger cells
+pub struct HintSystem {
+    hints: HashMap<char, String>,
+    shown: HashSet<char>,
+    current: Option<String>,
+    timer: f32,
+}
+
+impl HintSystem {
+    pub fn new() -> Self {
+        HintSystem {
+            hints: HashMap::new(),
+            shown: HashSet::new(),
+            current: None,
+            timer: 0.0,
+        }
+    }
+
+    pub fn load(&mut self, map_filename: &str) {
+        self.hints = load_hints(map_filename);
+        self.shown.clear();
+        self.current = None;
+        self.timer = 0.0;
+    }
+
+    // Call every frame with the maze cell under the player
+    pub fn update(&mut self, cell: Cell, delta_time: f32) {
+        let trigger = cell.to_char();
+        if is_hint_trigger(cell) && !self.shown.contains(&trigger) && let Some(text) = self.hints.get(&trigger) {
+            self.shown.insert(trigger);
+            self.current = Some(text.clone());
+            self.timer = HINT_DURATION;
+        }
+
+        if self.current.is_some() {
+            self.timer -= delta_time;
+            if self.timer <= 0.0 {
+                self.current = None;
+            }
+        }
+    }
+
+    // A callout that isn't tied to a trigger cell, e.g. a quest delivery note
+    pub fn show(&mut self, text: String) {
+        self.current = Some(text);
+        self.timer = HINT_DURATION;
+    }
+
+    pub fn draw(&self, d: &mut RaylibDrawHandle, screen_width: i32, screen_height: i32) {
+        let text = match self.current {
+            Some(ref text) => text,
+            None => return,
+        };
+
+        // Fade in right after triggering and out before disappearing
+        let elapsed = HINT_DURATION - self.timer;
+        let fade = (elapsed / HINT_FADE).min(self.timer / HINT_FADE).clamp(0.0, 1.0);
+        let alpha = (fade * 255.0) as u8;
+
+        let font_size = 22;
+        let text_width = font_size * text.len() as i32 / 2; // Approximate text width
+        let box_width = text_width + 40;
+        let box_height = 50;
+        let box_x = (screen_width - box_width) / 2;
+        let box_y = screen_height - 200;
+
+        d.draw_rectangle(box_x, box_y, box_width, box_height, Color::new(20, 10, 10, (200.0 * fade) as u8));
+        d.draw_rectangle_lines(box_x, box_y, box_width, box_height, Color::new(200, 170, 60, alpha));
+        d.draw_text(text, box_x + 20, box_y + 14, font_size, Color::new(255, 240, 200, alpha));
+    }
+}
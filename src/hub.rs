@@ -0,0 +1,53 @@
+// hub.rs
+
+use crate::caster::cast_ray;
+use crate::framebuffer::Framebuffer;
+use crate::player::Player;
+use crate::profile::Profile;
+use crate::maze::Maze;
+
+// The campaign hub is a small hand-made map whose portals lead to the campaign maps.
+// Each portal is an 'o' wall cell, tied to a map by a header line in the hub file:
+//   portal = <x> <y> <map file>
+// A portal opens once the map before its own in the campaign has been finished.
+
+pub const HUB_MAP: &str = "hub.map";
+const PORTAL_REACH: f32 = 1.5; // Cells; how close the player must face a portal to use it
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Portal {
+    pub cell: (usize, usize),
+    pub map_index: usize, // Index into the campaign maps
+}
+
+// Portals from a map header, `maps` being the campaign's map files in order
+pub fn portals(meta: &[(String, String)], maps: &[&str]) -> Vec<Portal> {
+    let mut portals = Vec::new();
+    for (_, value) in meta.iter().filter(|(key, _)| key == "portal") {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        let parsed = match parts[..] {
+            [x, y, file] => x.parse().ok().zip(y.parse().ok()).zip(maps.iter().position(|map| *map == file)),
+            _ => None,
+        };
+        match parsed {
+            Some((cell, map_index)) => portals.push(Portal { cell, map_index }),
+            None => elog!("Warning: Ignoring portal '{}', expected x, y and one of the campaign's map files", value),
+        }
+    }
+    portals
+}
+
+// The first map is always open, every other one once the map before it is finished,
+// in New Game Plus once it is finished there
+pub fn is_unlocked(profile: &Profile, maps: &[&str], map_index: usize, ng_plus: bool) -> bool {
+    map_index == 0 || maps.get(map_index - 1).is_some_and(|previous| profile.map_completed(previous, ng_plus))
+}
+
+// Portal straight ahead of the player and within reach
+pub fn facing_portal(framebuffer: &mut Framebuffer, maze: &Maze, player: &Player, portals: &[Portal], block_size: usize) -> Option<Portal> {
+    let ahead = cast_ray(framebuffer, maze, player, player.a, block_size, false);
+    if ahead.distance > PORTAL_REACH * block_size as f32 {
+        return None;
+    }
+    portals.iter().find(|portal| portal.cell == (ahead.cell_x, ahead.cell_y)).copied()
+}
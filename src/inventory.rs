@@ -0,0 +1,158 @@
+// inventory.rs
+
+use raylib::prelude::*;
+
+use crate::deployables::{DEPLOYABLES, DeployableKind};
+use crate::status::StatusKind;
+
+// Melee weapon stats used by the attack code and the viewmodel tint
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Weapon {
+    pub name: &'static str,
+    pub damage: f32,
+    pub range: f32,
+    pub arc: f32,   // Half-angle of the hit cone in radians
+    pub tint: Color, // Applied to the sword sprite so upgrades are visible
+    pub inflicts: Option<StatusKind>, // Effect its hits leave on enemies
+}
+
+pub const WEAPONS: [Weapon; 3] = [
+    Weapon { name: "Rusty Sword", damage: 10.0, range: 1.5, arc: std::f32::consts::PI / 6.0, tint: Color::new(255, 255, 255, 255), inflicts: None },
+    Weapon { name: "Claymore", damage: 16.0, range: 1.7, arc: std::f32::consts::PI / 5.0, tint: Color::new(200, 220, 255, 255), inflicts: None },
+    Weapon { name: "Dragonslayer", damage: 26.0, range: 1.9, arc: std::f32::consts::PI / 4.5, tint: Color::new(140, 130, 130, 255), inflicts: Some(StatusKind::Burning) },
+];
+
+pub const POTION_HEAL: f32 = 40.0;
+pub const STARTING_CALTROPS: u32 = 3;
+pub const STARTING_TURRETS: u32 = 1;
+
+// What the player carries: coins from the map, consumables, deployables and the equipped weapon
+pub struct Inventory {
+    pub coins: u32,
+    pub potions: u32,
+    pub weapon: Weapon,
+    pub caltrops: u32,
+    pub turrets: u32,
+    pub deployable: DeployableKind, // Selected for the interact key
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Inventory {
+            coins: 0,
+            potions: 0,
+            weapon: WEAPONS[0],
+            caltrops: STARTING_CALTROPS,
+            turrets: STARTING_TURRETS,
+            deployable: DeployableKind::Caltrops,
+        }
+    }
+
+    pub fn spend(&mut self, price: u32) -> bool {
+        if self.coins < price {
+            return false;
+        }
+        self.coins -= price;
+        true
+    }
+
+    // Returns the amount of health to restore, or None if there is nothing to drink
+    pub fn use_potion(&mut self) -> Option<f32> {
+        if self.potions == 0 {
+            return None;
+        }
+        self.potions -= 1;
+        Some(POTION_HEAL)
+    }
+
+    pub fn deployables_left(&self, kind: DeployableKind) -> u32 {
+        match kind {
+            DeployableKind::Caltrops => self.caltrops,
+            DeployableKind::DartTurret => self.turrets,
+        }
+    }
+
+    pub fn next_deployable(&mut self) {
+        let index = DEPLOYABLES.iter().position(|&kind| kind == self.deployable).unwrap_or(0);
+        self.deployable = DEPLOYABLES[(index + 1) % DEPLOYABLES.len()];
+    }
+
+    // Uses up one of the selected deployable, or None if there are none left
+    pub fn take_deployable(&mut self) -> Option<DeployableKind> {
+        let count = match self.deployable {
+            DeployableKind::Caltrops => &mut self.caltrops,
+            DeployableKind::DartTurret => &mut self.turrets,
+        };
+        if *count == 0 {
+            return None;
+        }
+        *count -= 1;
+        Some(self.deployable)
+    }
+}
+
+// Items sold in the between-floor shop
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShopItem {
+    Potion,
+    TrapKit, // Caltrops and a turret
+    Weapon(usize), // Index into WEAPONS
+}
+
+pub const SHOP_ITEMS: [ShopItem; 4] = [ShopItem::Potion, ShopItem::TrapKit, ShopItem::Weapon(1), ShopItem::Weapon(2)];
+
+impl ShopItem {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShopItem::Potion => "Health Potion",
+            ShopItem::TrapKit => "Trap Kit",
+            ShopItem::Weapon(index) => WEAPONS[*index].name,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            ShopItem::Potion => format!("Restores {:.0} health (H to drink)", POTION_HEAL),
+            ShopItem::TrapKit => format!("{} caltrops and {} dart turret (F to set down)", STARTING_CALTROPS, STARTING_TURRETS),
+            ShopItem::Weapon(index) => match WEAPONS[*index].inflicts {
+                Some(kind) => format!("{:.0} damage, longer reach and wider swing, leaves enemies {}", WEAPONS[*index].damage,
+                                      kind.name().to_lowercase()),
+                None => format!("{:.0} damage, longer reach and wider swing", WEAPONS[*index].damage),
+            },
+        }
+    }
+
+    // Prices climb by a quarter of the base price for every floor below the first
+    pub fn price(&self, depth: u32) -> u32 {
+        let base = match self {
+            ShopItem::Potion => 8,
+            ShopItem::TrapKit => 12,
+            ShopItem::Weapon(1) => 25,
+            ShopItem::Weapon(_) => 60,
+        };
+        base + base * depth.saturating_sub(1) / 4
+    }
+
+    // Weapons already owned (or worse than the current one) cannot be bought again
+    pub fn available(&self, inventory: &Inventory) -> bool {
+        match self {
+            ShopItem::Potion | ShopItem::TrapKit => true,
+            ShopItem::Weapon(index) => WEAPONS[*index].damage > inventory.weapon.damage,
+        }
+    }
+
+    pub fn buy(&self, inventory: &mut Inventory, depth: u32) -> bool {
+        if !self.available(inventory) || !inventory.spend(self.price(depth)) {
+            return false;
+        }
+        match self {
+            ShopItem::Potion => inventory.potions += 1,
+            ShopItem::TrapKit => {
+                inventory.caltrops += STARTING_CALTROPS;
+                inventory.turrets += STARTING_TURRETS;
+            }
+            ShopItem::Weapon(index) => inventory.weapon = WEAPONS[*index],
+        }
+        true
+    }
+}
@@ -0,0 +1,123 @@
+// lantern.rs
+
+use raylib::prelude::*;
+
+use crate::maze::{Maze, is_open_floor};
+use crate::rng::Rng;
+use crate::sim::{Vec2, from_front};
+
+// The player's lantern burns oil while it is lit. L puts it out and lights it again; dark, the
+// view shrinks to what is right in front of you but you are much harder to spot and the oil
+// lasts. Flasks lying around the maps top it up. Under a fifth of a tank the flame gutters,
+// dipping the light radius at random, and an empty lantern gives no more light than an unlit one.
+
+pub const FULL_TANK: f32 = 1.0;
+const BURN_TIME: f32 = 150.0;      // Seconds a full tank lasts
+const LOW_FUEL: f32 = 0.2;         // Below this the flame starts to gutter
+const DARK_RADIUS: f32 = 0.7;      // Cells of light radius with the lantern out
+const GUTTER_DEPTH: f32 = 0.45;    // Largest dip of the radius on an almost empty tank
+pub const FLASK_FUEL: f32 = 0.35;  // Of a tank, per flask
+const FLASK_RADIUS: f32 = 0.4;     // Cells, how close the player must walk to pick one up
+const CELLS_PER_FLASK: usize = 60; // Open floor cells for each flask scattered on a map
+const BEAM_ARC: f32 = 0.5;         // Radians either side of straight ahead the lantern's beam reaches
+
+pub struct Lantern {
+    pub lit: bool,
+    pub fuel: f32, // 0.0 to FULL_TANK
+    pub smothered: bool, // Held out by a no_lantern zone, it gives no light whether lit or not
+    flicker: f32,  // Seconds the flame has burned, drives the guttering
+}
+
+impl Lantern {
+    pub fn new() -> Self {
+        Lantern { lit: true, fuel: FULL_TANK, smothered: false, flicker: 0.0 }
+    }
+
+    pub fn toggle(&mut self) {
+        self.lit = !self.lit;
+        log!("Lantern {}", if self.lit { "lit" } else { "out" });
+    }
+
+    pub fn burning(&self) -> bool {
+        self.lit && self.fuel > 0.0 && !self.smothered
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.burning() && self.fuel < LOW_FUEL
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        if self.burning() {
+            self.fuel = (self.fuel - delta_time / BURN_TIME).max(0.0);
+            self.flicker += delta_time;
+        }
+    }
+
+    pub fn refuel(&mut self, amount: f32) {
+        self.fuel = (self.fuel + amount).min(FULL_TANK);
+    }
+
+    // Light radius in cells for a lantern that reaches `full_radius` when burning steadily
+    pub fn radius(&self, full_radius: f32) -> f32 {
+        if !self.burning() {
+            return DARK_RADIUS.min(full_radius);
+        }
+        if self.fuel >= LOW_FUEL {
+            return full_radius;
+        }
+        // Two out-of-step waves, only their peaks dip the flame, deeper the emptier the tank
+        let t = self.flicker;
+        let gutter = ((t * 11.0).sin() * (t * 4.7).sin()).max(0.0);
+        let depth = GUTTER_DEPTH * (1.0 - self.fuel / LOW_FUEL);
+        (full_radius * (1.0 - gutter * depth)).max(DARK_RADIUS)
+    }
+
+    // True if the burning lantern, held by someone at `holder` facing `facing`, shines on `target`.
+    // The beam reaches as far as the light radius, and only ahead.
+    pub fn lights(&self, holder: Vector2, facing: f32, target: Vector2, full_radius: f32, block_size: usize) -> bool {
+        let (holder, target) = (Vec2::from(holder), Vec2::from(target));
+        let in_reach = holder.distance(target) < self.radius(full_radius) * block_size as f32;
+        self.burning() && in_reach && from_front(holder, facing, target, BEAM_ARC)
+    }
+}
+
+// Upright oil gauge for the HUD, bottom at `bottom_y`. It blinks red while the flame gutters and
+// shows dim while the lantern is out.
+pub fn draw_fuel_gauge(d: &mut RaylibDrawHandle, lantern: &Lantern, x: i32, bottom_y: i32, time: f32) {
+    let (width, height) = (12, 44);
+    let filled = (height as f32 * lantern.fuel / FULL_TANK).round() as i32;
+    let color = if lantern.is_low() {
+        if (time * 4.0).fract() < 0.5 { Color::new(230, 60, 40, 255) } else { Color::new(120, 30, 20, 255) }
+    } else if lantern.lit && !lantern.smothered {
+        Color::new(240, 160, 50, 255)
+    } else {
+        Color::new(110, 80, 40, 255)
+    };
+    d.draw_rectangle(x, bottom_y - height, width, height, Color::new(0, 0, 0, 160));
+    d.draw_rectangle(x, bottom_y - filled, width, filled, color);
+    d.draw_rectangle_lines(x, bottom_y - height, width, height, Color::WHITE);
+    d.draw_text(if lantern.lit { "L" } else { "off" }, x, bottom_y + 4, 12, Color::LIGHTGRAY);
+}
+
+// A few flasks on random open floor, deterministic for the map's rng
+pub fn scatter_flasks(maze: &Maze, block_size: usize, rng: &mut Rng) -> Vec<Vector2> {
+    let open_cells: Vec<(usize, usize)> = maze.cells().filter(|&(_, _, cell)| is_open_floor(cell)).map(|(x, y, _)| (x, y)).collect();
+    if open_cells.is_empty() {
+        return Vec::new();
+    }
+    let block = block_size as f32;
+    (0..(open_cells.len() / CELLS_PER_FLASK).max(1))
+        .map(|_| {
+            let (x, y) = open_cells[rng.range(open_cells.len())];
+            Vector2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block)
+        })
+        .collect()
+}
+
+// Remove the flasks the player is touching and return how many were picked up
+pub fn collect_flasks(flasks: &mut Vec<Vector2>, player_pos: Vector2, block_size: usize) -> usize {
+    let before = flasks.len();
+    let reach = FLASK_RADIUS * block_size as f32;
+    flasks.retain(|flask| ((flask.x - player_pos.x).powi(2) + (flask.y - player_pos.y).powi(2)).sqrt() > reach);
+    before - flasks.len()
+}
@@ -2,151 +2,330 @@
 #![allow(unused_imports)]
 #![allow(dead_code)]
 
+#[macro_use]
+mod crash; // First, so its log macros are visible to every other module
 mod line;
 mod framebuffer;
+mod framediff;
 mod maze;
 mod caster;
 mod player;
 mod textures;
 mod audio;
+mod title;
+mod dialog;
+mod postfx;
+mod hints;
+mod mapgen;
+mod mods;
+mod profile;
+mod savefile;
+mod savegame;
+mod settings;
+mod run;
+mod inventory;
+mod pickups;
+mod splits;
+mod replay;
+mod prompts;
+mod ui;
+mod color;
+mod world;
+mod arena;
+mod hub;
+mod debugview;
+mod ping;
+mod footprints;
+mod status;
+mod stealth;
+mod pathfind;
+mod companion;
+mod projectiles;
+mod deployables;
+mod rng;
+mod reverb;
+mod mapstats;
+mod mapdelta;
+mod sim;
+mod sway;
+mod factions;
+mod quest;
+mod automap;
+mod lantern;
+mod director;
+mod weather;
+mod props;
+mod summary;
+mod heatmap;
+mod gyro;
+mod actions;
+mod swarm;
+mod zones;
+mod grading;
+mod ngplus;
+mod chatter;
+mod revive;
+mod visualizer;
+mod timescale;
+#[cfg(test)]
+mod fuzz;
+mod convert;
+mod report;
+mod cli;
+mod stress;
+mod screens;
 
 use line::line;
-use maze::{Maze, MazeData, load_maze, load_maze_with_player};
-use caster::{cast_ray, Intersect};
-use framebuffer::Framebuffer;
+use maze::{Cell, Maze, MazeData, DEFAULT_BLOCK_SIZE, load_maze, load_map_meta, load_maze_with_player, maze_data_from, is_hint_trigger, is_checkpoint, is_partial_wall, is_see_through_wall, is_floor_zone, is_open_floor, is_walkable};
+use caster::{cast_ray, cast_ray_layers, Intersect, WallFace};
+use framebuffer::{Framebuffer, dither};
 use player::{Player, process_events};
-use textures::TextureManager;
-use audio::AudioManager;
+use sway::SWAY_TILT;
+use textures::{CEILING_TEXTURE, FLOOR_TEXTURE, SHIELD_SHEET, TextureId, TextureManager, WISP_FRAMES, WISP_SHEET, is_transparent_color};
+use audio::{AudioManager, Listener};
+use title::TitleCamera;
+use dialog::ConfirmDialog;
+use postfx::{PostProcessor, PostEffect, PostFxContext};
+use hints::HintSystem;
+use mapgen::{generate_maze, today, daily_seed, date_label};
+use profile::{Profile, PROFILE_PATH};
+use settings::{AIM_ASSIST, DESKTOP_PRESET, DisplayPreset, GYRO_SENSITIVITY, HANDHELD_PRESET, MINIMAP_SIZES, MINIMAP_ZOOMS, RENDER_SCALES, SETTINGS_PATH, Settings, UI_SCALES};
+use mods::ModList;
+use run::RunState;
+use rng::{Rng, seed_from_clock};
+use reverb::reverb_areas;
+use quest::QuestChain;
+use factions::{assign_factions, faction_areas};
+use mapstats::MapStats;
+use grading::{Grade, Grading};
+use ngplus::{SpawnTable, toughen};
+use inventory::{Inventory, ShopItem, Weapon, SHOP_ITEMS};
+use pickups::{Pickup, coins_from_maze, scatter_coins, collect_pickups};
+use splits::SplitTimer;
+use replay::{Replay, DEMO_PATH};
+use world::World;
+use arena::Arena;
+use ping::Ping;
+use automap::Marker;
+use lantern::scatter_flasks;
+use weather::{Weather, WeatherKind, weather_areas};
+use props::props_from_meta;
+use swarm::swarms_from_meta;
+use zones::{Zones, zones_from_meta};
+use revive::Revive;
+use chatter::{CHATTER_PATH, ChatterLine, load_chatter_defs};
+use summary::Tally;
+use gyro::Gyro;
+use actions::{ActionMap, INPUT_PRESETS, InputPreset};
+use director::{DIFFICULTIES, Difficulty};
+use footprints::Footprint;
+use hub::{HUB_MAP, portals};
+use visualizer::MusicVisualizer;
+use timescale::TimeScale;
+use screens::{ControlsRow, Game, StartMenu, PlayingScreen, ScreenStack, SplashScreen, StartScreen};
+use cli::LaunchOptions;
+use stress::StressMap;
+use prompts::{ButtonPrompts, InputDevice, Prompt};
+use ui::{KEYBOARD_HEIGHT, KeyRepeat, Layout, OnScreenKeyboard, UiEvent, UiInput, VolumePopup, Widget};
 
 use raylib::prelude::*;
 use std::thread;
 use std::time::Duration;
 use std::f32::consts::PI;
 mod enemy;
-use enemy::{Enemy, AnimationState};
-
-const TRANSPARENT_COLOR: Color = Color::new(152, 0, 136, 255);
-
-// Function to check if a color should be treated as transparent
-fn is_transparent_color(color: Color) -> bool {
-    // Check for exact transparent color match
-    if color == TRANSPARENT_COLOR {
-        return true;
-    }
-    
-    // Check for alpha transparency
-    if color.a < 128 {
-        return true;
-    }
-    
-    // Specific check for your sprite sheet's background color
-    // Looking at your sprite, the background appears to be a dark gray around RGB(64, 64, 64)
-    // Let's check for colors in that range
-    
-    // Dark gray background (around 50-85 range for all components)
-    if color.r >= 50 && color.r <= 85 &&
-       color.g >= 50 && color.g <= 85 &&
-       color.b >= 50 && color.b <= 85 {
-        return true;
-    }
-    
-    // Also check for slightly lighter grays (75-115 range)
-    if color.r >= 75 && color.r <= 115 &&
-       color.g >= 75 && color.g <= 115 &&
-       color.b >= 75 && color.b <= 115 {
-        return true;
-    }
-    
-    // Check for very dark colors (near black)
-    if color.r < 25 && color.g < 25 && color.b < 25 {
-        return true;
-    }
-    
-    // Check for very light colors (near white)
-    if color.r > 230 && color.g > 230 && color.b > 230 {
-        return true;
-    }
-    
-    false
-}
-
-#[derive(PartialEq)]
-enum GameState {
-    StartScreen,
-    Playing,
-    Paused,
-    Victory,
-}
+use companion::Companion;
+use deployables::{Deployable, DeployableKind};
+use projectiles::Projectile;
+use enemy::{Enemy, EnemyDef, AnimationState, BLOCK_FLASH, DAMAGE_NUMBER_LIFETIME, ENEMY_DEFS_PATH, load_enemy_defs};
+
+const SPLASH_DURATION: f32 = 3.0; // Seconds the intro splash stays on screen
+const DISTANCE_TO_PROJECTION_PLANE: f32 = 0.7; // In cells, shared by walls and sprites
+const BASE_FOV: f32 = PI / 3.0;                 // Horizontal field of view at BASE_ASPECT
+const BASE_ASPECT: f32 = 1980.0 / 1200.0;       // The default window, what the projection was tuned for
+const FOV_LIMITS: (f32, f32) = (0.5, 2.5);      // Radians; past these tall or wide views squash instead
+const TEX_FIXED_SHIFT: u32 = 16; // Fractional bits of the fixed-point wall texture coordinate
+const TEX_FIXED_ONE: f32 = (1 << TEX_FIXED_SHIFT) as f32;
+const TEXTURE_LOD_DISTANCE: f32 = 6.0; // Cells past which walls sample the half-resolution textures
+const FOG_COLOR: Color = Color::new(60, 60, 90, 255); // Distant walls and sprites fade into this
+const SPRITE_NEAR_PLANE: f32 = 0.1; // In cells, sprite columns closer than this (in view depth) are clipped
+const FOG_DENSITY: f32 = 0.3333; // Fog added per cell past the light radius
+const EAST_WEST_SHADE: f32 = 0.8; // East and west wall faces are darker, so corners read without real lighting
+const FLOOR_SHADE: f32 = 0.55;   // Cast floor and ceiling textures are dimmed under the walls
+const CEILING_SHADE: f32 = 0.45;
+const SPRITE_FADE: (f32, f32) = (4.0, 8.0); // Cells past the light radius where far sprites start dithering out, and are gone
+const ENEMY_SPRITE_SCALE: f32 = 1.0; // Enemy sprite height relative to a wall
+const ENEMY_SHADOW_WIDTH: f32 = 0.3; // Shadow blob half widths relative to a wall, they are a quarter as tall
+const COIN_SHADOW_WIDTH: f32 = 0.08;
+const SHADOW_STRENGTH: f32 = 0.55;   // How much the middle of a shadow darkens the floor
+const HIGHLIGHT_OUTLINE: i32 = 2;    // Pixels, thickness of the outline around what the player can use
+const STATUS_TINT: f32 = 0.4; // How far a status effect pulls an enemy sprite toward its color
+const BLOCK_TINT: f32 = 0.6;  // How white a shield bearer flashes as it blocks
+const WISP_FPS: f32 = 8.0; // Flicker speed of the companion wisp
 
 struct MapInfo {
     name: &'static str,
     filename: &'static str,
-    description: &'static str,
 }
 
 const AVAILABLE_MAPS: &[MapInfo] = &[
+    MapInfo {
+        name: "Tutorial",
+        filename: "tutorial.txt",
+    },
     MapInfo {
         name: "Classic Dungeon",
         filename: "maze.txt",
-        description: "A simple maze to get started",
     },
     MapInfo {
         name: "Complex Maze",
         filename: "maze2.txt", 
-        description: "A more challenging labyrinth",
     },
     MapInfo {
         name: "Advanced Layout",
         filename: "maze3.txt",
-        description: "An intricate dungeon design",
     },
 ];
 
+// The Daily Dungeon is listed after the hand-made maps and generated from today's date
+const DAILY_MAP_INDEX: usize = AVAILABLE_MAPS.len();
+const DAILY_MAZE_WIDTH: usize = 12; // In maze.py cells
+const DAILY_MAZE_HEIGHT: usize = 8;
+const RUN_MAP_INDEX: usize = DAILY_MAP_INDEX + 1; // Rogue-lite run of generated floors
+const HUB_MAP_INDEX: usize = RUN_MAP_INDEX + 1;   // Campaign hub with portals to the hand-made maps
+const STRESS_MAP_INDEX: usize = HUB_MAP_INDEX + 1; // --stress benchmark maze, not on the start screen
+
+fn map_name(map_index: usize) -> &'static str {
+  if map_index == DAILY_MAP_INDEX {
+    "Daily Dungeon"
+  } else if map_index == RUN_MAP_INDEX {
+    "Rogue-lite Run"
+  } else if map_index == HUB_MAP_INDEX {
+    "Campaign Hub"
+  } else if map_index == STRESS_MAP_INDEX {
+    "Stress Test"
+  } else {
+    AVAILABLE_MAPS[map_index].name
+  }
+}
+
+// Personal-best splits are kept per map, per day for the daily and once for runs
+fn split_key(map_index: usize, daily_date: &str) -> String {
+  if map_index == DAILY_MAP_INDEX {
+    format!("daily_{}", daily_date)
+  } else if map_index == RUN_MAP_INDEX {
+    "run".to_string()
+  } else if map_index == HUB_MAP_INDEX {
+    "hub".to_string()
+  } else if map_index == STRESS_MAP_INDEX {
+    "stress".to_string()
+  } else {
+    AVAILABLE_MAPS[map_index].filename.trim_end_matches(".txt").to_string()
+  }
+}
+
+// Generated maps have no file a replay could be played back on
+fn map_filename(map_index: usize) -> Option<&'static str> {
+  if map_index == HUB_MAP_INDEX {
+    return Some(HUB_MAP);
+  }
+  AVAILABLE_MAPS.get(map_index).map(|map| map.filename)
+}
+
+// Index of a hand-made map or the hub from its file
+fn map_index_of(filename: &str) -> Option<usize> {
+  if filename == HUB_MAP {
+    return Some(HUB_MAP_INDEX);
+  }
+  AVAILABLE_MAPS.iter().position(|map| map.filename == filename)
+}
+
+// The campaign's maps in the order their portals open
+fn campaign_maps() -> Vec<&'static str> {
+  AVAILABLE_MAPS.iter().map(|map| map.filename).collect()
+}
+
+// The title screen demo and the maze it was recorded on
+fn load_demo() -> Option<(Replay, MazeData)> {
+  let replay = Replay::load(DEMO_PATH)?;
+  match load_maze_with_player(&replay.map) {
+    Ok(data) => Some((replay, data)),
+    Err(e) => {
+      elog!("Warning: {}, attract mode disabled", e);
+      None
+    }
+  }
+}
+
+fn generate_daily_maze() -> MazeData {
+  let date = today();
+  log!("Generating Daily Dungeon for {}", date_label(date));
+  maze_data_from(generate_maze(DAILY_MAZE_WIDTH, DAILY_MAZE_HEIGHT, daily_seed(date)), DEFAULT_BLOCK_SIZE)
+}
+
 // Function to check if there's a wall between two points (line of sight check)
 fn has_line_of_sight(from: Vector2, to: Vector2, maze: &Maze, block_size: usize) -> bool {
-    let dx = to.x - from.x;
-    let dy = to.y - from.y;
-    let distance = (dx * dx + dy * dy).sqrt();
-    
-    // Check points along the line from player to enemy
-    let steps = (distance / (block_size as f32 * 0.25)) as i32; // Check every quarter block
-    
-    for i in 0..=steps {
-        let t = if steps == 0 { 0.0 } else { i as f32 / steps as f32 };
-        let check_x = from.x + dx * t;
-        let check_y = from.y + dy * t;
-        
-        // Convert to maze coordinates
-        let maze_x = (check_x / block_size as f32) as usize;
-        let maze_y = (check_y / block_size as f32) as usize;
-        
-        // Check if this position is inside the maze bounds
-        if maze_y < maze.len() && maze_x < maze[0].len() {
-            // If we hit a wall, line of sight is blocked
-            if maze[maze_y][maze_x] != ' ' {
-                return false;
-            }
+    sim::line_of_sight(from.into(), to.into(), maze, block_size)
+}
+
+// One sprite sheet frame billboarded in the world, what draw_sprite needs from an enemy or the companion
+struct Billboard {
+    pos: Vector2,
+    texture_key: char,     // Sprite sheet, the plain enemy texture stands in if it is missing
+    frame: (usize, usize), // Column and row in the sheet
+    flip: bool,            // Mirror it, for sprites facing left
+    scale: f32,            // Height relative to a wall
+    lift: f32,             // Raised off the floor, as a share of wall height
+    tint: Option<(Color, f32)>, // Color blended in, and how much
+    alpha: f32,            // 1.0 draws it solid, less blends it over what is behind (dithers in performance mode)
+    distance_fade: bool,   // Dither out far past the light, see sprite_fade
+}
+
+impl Billboard {
+    fn enemy(enemy: &Enemy) -> Self {
+        Billboard {
+            pos: enemy.pos,
+            texture_key: enemy.def.texture_key,
+            frame: (enemy.current_frame, enemy.current_animation().row),
+            flip: enemy.facing_left,
+            scale: ENEMY_SPRITE_SCALE,
+            lift: 0.0,
+            // A shield gleams as it turns a blow aside, burning, poisoned or slowed enemies take on the color of the effect
+            tint: if enemy.block_timer > 0.0 {
+                Some((Color::new(235, 240, 255, 255), BLOCK_TINT * enemy.block_timer / BLOCK_FLASH))
+            } else {
+                enemy.status.tint().filter(|_| !enemy.is_dead).map(|tint| (tint, STATUS_TINT))
+            },
+            alpha: enemy.opacity,
+            distance_fade: true,
         }
     }
-    
-    true // No walls found along the line
 }
 
+// Where a drawn sprite landed on screen: center column, top row, height and depth
+struct SpriteRect {
+    center_x: i32,
+    top: f32,
+    size: f32,
+    depth: f32,
+}
+
+// Returns where the sprite was drawn, or None when it is hidden or its center is past the near plane
 fn draw_sprite(
     framebuffer: &mut Framebuffer,
     player: &Player,
-    enemy: &Enemy,
+    sprite: &Billboard,
     texture_manager: &TextureManager,
     maze: &Maze,
     block_size: usize,
-) {
-    // First check if there's line of sight between player and enemy
-    if !has_line_of_sight(player.pos, enemy.pos, maze, block_size) {
-        return; // Enemy is behind a wall, don't draw
+    performance_mode: bool,
+) -> Option<SpriteRect> {
+    // First check if there's line of sight between player and sprite
+    if !has_line_of_sight(player.pos, sprite.pos, maze, block_size) {
+        return None; // Behind a wall, don't draw
     }
 
-    // Calculate angle from player to enemy
-    let sprite_a = (enemy.pos.y - player.pos.y).atan2(enemy.pos.x - player.pos.x);
+    // Calculate angle from player to sprite
+    let sprite_a = (sprite.pos.y - player.pos.y).atan2(sprite.pos.x - player.pos.x);
 
     // Normalize angle difference to [-PI, PI]
     let mut angle_diff = sprite_a - player.a;
@@ -157,127 +336,189 @@ fn draw_sprite(
         angle_diff += 2.0 * std::f32::consts::PI;
     }
 
-    // If enemy is outside player's FOV, skip drawing
-    if angle_diff.abs() > player.fov / 2.0 {
-        return;
+    // Sprites behind the camera can never reach the screen
+    if angle_diff.abs() >= std::f32::consts::FRAC_PI_2 {
+        return None;
     }
 
-    // Distance from player to enemy
-    let sprite_d = ((player.pos.x - enemy.pos.x).powi(2) + (player.pos.y - enemy.pos.y).powi(2)).sqrt();
+    // Distance from player to sprite
+    let sprite_d = ((player.pos.x - sprite.pos.x).powi(2) + (player.pos.y - sprite.pos.y).powi(2)).sqrt();
+    if sprite_d < 1.0 {
+        return None;
+    }
 
-    if sprite_d < 50.0 || sprite_d > 1000.0 {
-        return;
+    // Far sprites dither out instead of popping, and see-through ones dither in performance mode
+    let fade = if sprite.distance_fade { sprite_fade(sprite_d / block_size as f32, player.sight_radius()) } else { 1.0 };
+    if fade <= 0.0 {
+        return None;
     }
+    let coverage = fade * if performance_mode { sprite.alpha } else { 1.0 };
+    let blend_alpha = sprite.alpha < 1.0 && !performance_mode;
 
-    let screen_height = framebuffer.height as f32;
+    let hh = framebuffer.height as f32 / 2.0;
     let screen_width = framebuffer.width as f32;
 
-    // Calculate sprite size on screen (scale inversely proportional to distance)
-    let sprite_size = (screen_height / sprite_d) * 70.0;
+    // Same projection as the walls, so a sprite stands exactly as tall as a wall times its scale
+    let wall_height = projected_height(hh, sprite_d, block_size);
+    let sprite_size = wall_height * sprite.scale;
+    let near_plane = SPRITE_NEAR_PLANE * block_size as f32;
+
+    // Feet rest on the floor line at this distance, unless the sprite floats
+    let sprite_bottom = hh + wall_height / 2.0 - wall_height * sprite.lift;
+    let sprite_top = sprite_bottom - sprite_size;
+
+    // World width of the billboard that keeps the sprite square on screen
+    let world_width = sprite_size * sprite_d * player.fov / screen_width;
+    let half_angle = (world_width / 2.0 / sprite_d).atan();
+
+    // Screen columns covered by the billboard, using the same angle-per-column mapping as render_world
+    let first_x = (((angle_diff - half_angle) / player.fov + 0.5) * screen_width).floor().max(0.0) as u32;
+    let last_x = (((angle_diff + half_angle) / player.fov + 0.5) * screen_width).ceil().min(screen_width) as u32;
+
+    let start_y = sprite_top.max(0.0) as u32;
+    let end_y = sprite_bottom.min(framebuffer.height as f32).max(0.0) as u32;
+
+    let (frame_x, frame_y) = sprite.frame;
+
+    // Resolve the sheet frame (or fallback texture) once, so the pixel loop only indexes slices
+    let frame = texture_manager.sprite_sheet(sprite.texture_key).map(|sheet| sheet.frame(frame_x, frame_y));
+    let fallback = match frame {
+        Some(_) => None,
+        None => match texture_manager.texture_id('e') {
+            Some(id) => Some(texture_manager.pixels(id)),
+            None => return None,
+        },
+    };
+    let has_sheet = frame.is_some();
+    let (frame_width, frame_height) = match frame {
+        Some(ref frame) => (frame.width, frame.height),
+        None => (128, 128), // Fallback single sprite texture
+    };
+
+    for x in first_x..last_x {
+        // Angle of this column's ray, relative to the direction of the sprite
+        let column_angle = (x as f32 / screen_width - 0.5) * player.fov;
+        let relative = column_angle - angle_diff;
 
-    // Calculate horizontal screen position (centered)
-    let screen_x = ((angle_diff / player.fov) + 0.5) * screen_width;
+        // Clip against the near plane per column, so close sprites are cut instead of vanishing
+        let ray_distance = sprite_d / relative.cos();
+        let view_depth = ray_distance * column_angle.cos();
+        if view_depth < near_plane {
+            continue;
+        }
 
-    // Calculate top-left corner of sprite on screen
-    let start_x = (screen_x - sprite_size / 2.0).max(0.0) as usize;
-    let start_y = (screen_height / 2.0 - sprite_size / 2.0).max(0.0) as usize;
+        // Horizontal position across the billboard, 0.0 (left edge) to 1.0 (right edge)
+        let u = (sprite_d * relative.tan()) / world_width + 0.5;
+        if !(0.0..1.0).contains(&u) {
+            continue;
+        }
+        let tx = ((u * frame_width as f32) as u32).min(frame_width - 1);
+
+        // Handle sprite flipping if facing left
+        let final_tx = if has_sheet && sprite.flip { frame_width - 1 - tx } else { tx };
+
+        // Only walk the rows of this column that hold opaque texels, skipping empty columns entirely
+        let (span_top, span_bottom) = match frame {
+            Some(ref frame) => match frame.column_span(final_tx) {
+                Some(span) => span,
+                None => continue,
+            },
+            None => (0, frame_height - 1),
+        };
+        let column_top = (sprite_top + span_top as f32 / frame_height as f32 * sprite_size).max(start_y as f32) as u32;
+        let column_bottom = ((sprite_top + (span_bottom + 1) as f32 / frame_height as f32 * sprite_size).ceil().max(0.0) as u32).min(end_y);
 
-    let sprite_size_usize = sprite_size as usize;
+        for y in column_top..column_bottom {
+            // Depth test first, so texels behind walls are never sampled
+            if ray_distance >= framebuffer.get_depth(x, y) {
+                continue;
+            }
+            if coverage < 1.0 && !dither(x, y, coverage) {
+                continue;
+            }
 
-    let end_x = (start_x + sprite_size_usize).min(framebuffer.width as usize);
-    let end_y = (start_y + sprite_size_usize).min(framebuffer.height as usize);
+            let v = (y as f32 - sprite_top) / sprite_size;
+            let ty = ((v * frame_height as f32) as u32).min(frame_height - 1);
 
-    for x in start_x..end_x {
-        for y in start_y..end_y {
-            // Determine which sprite frame to use based on animation state and frame
-            let (frame_x, frame_y) = match enemy.animation_state {
-                AnimationState::Idle => (enemy.current_frame, 0),
-                AnimationState::Walking => (enemy.current_frame, 1), 
-                AnimationState::Attack => (enemy.current_frame, 2),
-                AnimationState::Death => (enemy.current_frame, 2), // Use attack row for death for now
+            let color = match (&frame, &fallback) {
+                (Some(frame), _) => frame.color(final_tx, ty),
+                (None, Some(pixels)) => pixels.color(tx, ty),
+                (None, None) => continue,
             };
 
-            // Check if we have an animated sprite sheet first
-            let color = if texture_manager.has_sprite_sheet('a') {
-                // Get frame size from sprite sheet
-                let (frame_width, frame_height) = texture_manager.get_sprite_frame_size('a').unwrap_or((32, 32));
-                
-                // Map screen pixel to texture coordinates within the frame
-                let tx = ((x - start_x) * frame_width as usize / sprite_size_usize) as u32;
-                let ty = ((y - start_y) * frame_height as usize / sprite_size_usize) as u32;
-                
-                // Handle sprite flipping if facing left
-                let final_tx = if enemy.facing_left {
-                    frame_width - 1 - tx.min(frame_width - 1)
-                } else {
-                    tx.min(frame_width - 1)
-                };
-                
-                texture_manager.get_sprite_frame_color('a', frame_x, frame_y, final_tx, ty.min(frame_height - 1))
-            } else {
-                // Fallback to single sprite texture
-                let tx = ((x - start_x) * 128 / sprite_size_usize) as u32;
-                let ty = ((y - start_y) * 128 / sprite_size_usize) as u32;
-                texture_manager.get_pixel_color('e', tx, ty)
+            // Skip transparent pixels, soft edges and see-through texels are blended below
+            if is_transparent_color(color) {
+                continue;
+            }
+            let texel_alpha = color.a;
+
+            let color = match sprite.tint {
+                Some((tint, amount)) => color::lerp(color, tint, amount),
+                None => color,
             };
 
-            // Skip transparent pixels
-            if !is_transparent_color(color) {
-                // Check depth buffer - only render if sprite is closer than existing pixel
-                let current_depth = framebuffer.get_depth(x as u32, y as u32);
-                if sprite_d < current_depth {
-                    framebuffer.set_current_color(color);
-                    framebuffer.set_pixel_with_depth(x as u32, y as u32, sprite_d);
-                }
+            // Far sprites sink into the same fog as the walls
+            let color = if performance_mode {
+                apply_fog_dithered(color, ray_distance / block_size as f32, player.sight_radius(), x, y)
+            } else {
+                apply_fog(color, ray_distance / block_size as f32, player.sight_radius())
+            };
+            // A see-through sprite only tints what is behind it and leaves the depth alone
+            if blend_alpha {
+                framebuffer.blend_with_depth(x, y, ray_distance, color, sprite.alpha * texel_alpha as f32 / 255.0);
+                continue;
             }
+            framebuffer.set_current_color(Color::new(color.r, color.g, color.b, texel_alpha));
+            framebuffer.blend_pixel_with_depth(x, y, ray_distance);
         }
     }
-}
 
-
-fn draw_cell(
-  framebuffer: &mut Framebuffer,
-  xo: usize,
-  yo: usize,
-  block_size: usize,
-  cell: char,
-) {
-  if cell == ' ' {
-    return;
-  }
-  framebuffer.set_current_color(Color::WHITE);
-
-  for x in xo..xo + block_size {
-    for y in yo..yo + block_size {
-      framebuffer.set_pixel(x as u32, y as u32);
+    if sprite_d * angle_diff.cos() < near_plane {
+        return None;
     }
-  }
+    let center_x = ((angle_diff / player.fov + 0.5) * screen_width) as i32;
+    Some(SpriteRect { center_x, top: sprite_top, size: sprite_size, depth: sprite_d - 1.0 })
 }
 
-pub fn render_maze(
-  framebuffer: &mut Framebuffer,
-  maze: &Maze,
-  block_size: usize,
-  player: &Player,
+// Health bar and floating damage numbers, drawn through the depth buffer so walls hide them
+fn draw_hit_feedback(
+    framebuffer: &mut Framebuffer,
+    enemy: &Enemy,
+    center_x: i32,
+    sprite_top: f32,
+    sprite_size: f32,
+    depth: f32,
 ) {
-  for (row_index, row) in maze.iter().enumerate() {
-    for (col_index, &cell) in row.iter().enumerate() {
-      let xo = col_index * block_size;
-      let yo = row_index * block_size;
-      draw_cell(framebuffer, xo, yo, block_size, cell);
+    if !enemy.is_dead && enemy.health_bar_timer > 0.0 && enemy.health < enemy.max_health {
+        let bar_width = (sprite_size * 0.4).max(24.0) as i32;
+        let bar_height = (sprite_size * 0.04).max(3.0) as i32;
+        let bar_x = center_x - bar_width / 2;
+        let bar_y = (sprite_top + sprite_size * 0.1) as i32;
+        let filled = (bar_width as f32 * enemy.health / enemy.max_health) as i32;
+
+        framebuffer.set_current_color(Color::new(20, 0, 0, 255));
+        framebuffer.fill_rect_with_depth(bar_x - 1, bar_y - 1, bar_width + 2, bar_height + 2, depth);
+        framebuffer.set_current_color(Color::new(200, 30, 30, 255));
+        framebuffer.fill_rect_with_depth(bar_x, bar_y, filled, bar_height, depth - 0.1);
     }
-  }
-
-  framebuffer.set_current_color(Color::WHITESMOKE);
 
-  let num_rays = 5;
-  for i in 0..num_rays {
-    let current_ray = i as f32 / num_rays as f32;
-    let a = player.a - (player.fov / 2.0) + (player.fov * current_ray);
-    cast_ray(framebuffer, &maze, &player, a, block_size, true);
-  }
+    let base_pixel_size = (sprite_size / 60.0).clamp(1.0, 6.0);
+    for number in enemy.damage_numbers.iter() {
+        let progress = number.age / DAMAGE_NUMBER_LIFETIME;
+
+        // Rise and shift from yellow to red as the number ages, fading over its second half, critical hits
+        // twice the size and starting white
+        let pixel_size = if number.critical { (base_pixel_size * 2.0).max(2.0) } else { base_pixel_size } as i32;
+        let start_color = if number.critical { Color::WHITE } else { Color::new(255, 230, 30, 255) };
+        let rise = progress * sprite_size * 0.3;
+        let y = (sprite_top + sprite_size * 0.05 - rise) as i32 - pixel_size * 5;
+        let color = color::lerp(start_color, Color::new(255, 0, 30, 255), progress);
+        framebuffer.set_current_color(color::with_alpha(color, (1.0 - progress) * 2.0));
+        framebuffer.draw_number_with_depth(number.amount, center_x, y, pixel_size, depth - 0.2);
+    }
 }
 
+
 fn render_world(
   framebuffer: &mut Framebuffer,
   maze: &Maze,
@@ -285,12 +526,16 @@ fn render_world(
   player: &Player,
   texture_cache: &TextureManager,
   performance_mode: bool,
+  highlight: Option<((usize, usize), Color)>,
 ) {
   let num_rays = framebuffer.width;
   let hh = framebuffer.height as f32 / 2.0;
 
   // Draw sky and floor - use simple or detailed based on performance mode
-  if performance_mode {
+  let surfaces = texture_cache.texture_id(FLOOR_TEXTURE).zip(texture_cache.texture_id(CEILING_TEXTURE));
+  if let (false, Some((floor, ceiling))) = (performance_mode, surfaces) {
+    cast_floor_and_ceiling(framebuffer, player, block_size, texture_cache, floor, ceiling);
+  } else if performance_mode {
     // Simple, fast sky and floor for performance mode - Reddish Berserk tone
     framebuffer.set_current_color(Color::new(120, 40, 40, 255)); // Dark reddish sky
     for i in 0..framebuffer.width {
@@ -312,24 +557,14 @@ fn render_world(
     for j in 0..(framebuffer.height / 2) {
       let gradient_factor = j as f32 / (framebuffer.height as f32 / 2.0);
       // Reddish Berserk-style sky gradient - dark crimson to lighter red
-      sky_colors.push(Color::new(
-        (60.0 + gradient_factor * 120.0) as u8,  // Red component: 60-180
-        (20.0 + gradient_factor * 40.0) as u8,   // Green component: 20-60  
-        (20.0 + gradient_factor * 30.0) as u8,   // Blue component: 20-50
-        255
-      ));
+      sky_colors.push(color::lerp(Color::new(60, 20, 20, 255), Color::new(180, 60, 50, 255), gradient_factor));
     }
     
     for j in 0..(framebuffer.height / 2) {
       let distance_from_center = j as f32;
       let fog_factor = (distance_from_center / (framebuffer.height as f32 / 2.0)).min(1.0);
       // Black to dark red gradient for Berserk aesthetic
-      floor_colors.push(Color::new(
-        (10.0 + fog_factor * 50.0) as u8,  // Red component: 10-60
-        (5.0 + fog_factor * 10.0) as u8,   // Green component: 5-15
-        (5.0 + fog_factor * 10.0) as u8,   // Blue component: 5-15
-        255
-      ));
+      floor_colors.push(color::lerp(Color::new(10, 5, 5, 255), Color::new(60, 15, 15, 255), fog_factor));
     }
 
     // Draw sky and floor with pre-calculated colors
@@ -353,171 +588,631 @@ fn render_world(
 
   framebuffer.set_current_color(Color::WHITESMOKE);
 
+  // Goal beacon pulse, shared by every goal column this frame
+  let time = unsafe { raylib::ffi::GetTime() } as f32;
+  let beacon_strength = ((time * 3.0).sin() * 0.5 + 0.5) * 0.35 + 0.15;
+
+  let pass = WallPass { hh, texture_cache, performance_mode, beacon_strength, light_radius: player.sight_radius(), block_size };
+  let mut layers = Vec::new();
+  let mut highlighted = Vec::new(); // Per column, the span and depth of the highlighted cell's wall
+
   for i in 0..num_rays {
     let current_ray = i as f32 / num_rays as f32;
     let a = player.a - (player.fov / 2.0) + (player.fov * current_ray);
-    let intersect = cast_ray(framebuffer, &maze, &player, a, block_size, false);
+    cast_ray_layers(maze, player, a, block_size, &mut layers);
+
+    // Draw back to front: the opaque wall first, then any fences or grates in front of it
+    for intersect in layers.iter().rev() {
+      let see_through = is_see_through_wall(intersect.impact);
+      let face_shade = if intersect.face.is_east_west() { EAST_WEST_SHADE } else { 1.0 };
+      let shade = face_shade * if performance_mode { 1.0 } else { corner_occlusion(maze, intersect) };
+      draw_wall_slice(framebuffer, i, intersect, &pass, see_through, shade);
+    }
+
+    if let Some((cell, _)) = highlight {
+      let span = layers.iter().find(|intersect| (intersect.cell_x, intersect.cell_y) == cell).map(|intersect| {
+        let height = projected_height(hh, intersect.distance, block_size);
+        ((hh - height / 2.0) as i32, (hh + height / 2.0) as i32, intersect.distance)
+      });
+      highlighted.push(span);
+    }
+  }
 
-    let distance_to_wall = intersect.distance;
-    let distance_to_projection_plane = 70.0;
-    let stake_height = (hh / distance_to_wall) * distance_to_projection_plane;
+  if let Some((_, color)) = highlight {
+    outline_highlight(framebuffer, &highlighted, color, time);
+  }
+}
 
-    let stake_top = (hh - (stake_height / 2.0)) as usize;
-    let stake_bottom = (hh + (stake_height / 2.0)) as usize;
+// Textured floor and ceiling, cast a screen row at a time. A row below the horizon shows the floor
+// where a wall's bottom edge would land on that row, so every pixel in it is the same distance
+// along its ray, and the ceiling mirrors the floor above. Distances run along the ray like the
+// walls', so the two meet without a seam. Depth stays at the far plane, as with the gradients,
+// so walls and sprites always cover it.
+fn cast_floor_and_ceiling(
+  framebuffer: &mut Framebuffer,
+  player: &Player,
+  block_size: usize,
+  texture_cache: &TextureManager,
+  floor: TextureId,
+  ceiling: TextureId,
+) {
+  let hh = framebuffer.height as f32 / 2.0;
+  let block = block_size as f32;
+  let light_radius = player.sight_radius();
 
-    for y in stake_top..stake_bottom {
-      // Calculate texture Y coordinate as a ratio (0.0 to 1.0) and scale by actual texture height
-      let ty_ratio = (y as f32 - stake_top as f32) / (stake_bottom as f32 - stake_top as f32);
-      let ty = (ty_ratio * 127.0).max(0.0).min(127.0) as u32; // Clamp to valid range
-      
-      // Ensure tx is also within valid bounds
-      let tx = (intersect.tx as u32).min(127);
+  // Ray directions once per column, spread by angle the same way render_world spreads them
+  let num_rays = framebuffer.width;
+  let directions: Vec<(f32, f32)> = (0..num_rays).map(|i| {
+    let a = player.a - (player.fov / 2.0) + (player.fov * i as f32 / num_rays as f32);
+    (a.cos(), a.sin())
+  }).collect();
+
+  for y in 0..framebuffer.height {
+    // Row centers, so the rows next to the horizon are far away rather than infinitely so
+    let from_horizon = (y as f32 + 0.5 - hh).abs();
+    let distance = hh * DISTANCE_TO_PROJECTION_PLANE * block / (2.0 * from_horizon);
+    let cells = distance / block;
+    let (id, shade) = if (y as f32) < hh { (ceiling, CEILING_SHADE) } else { (floor, FLOOR_SHADE) };
+    let pixels = if cells > TEXTURE_LOD_DISTANCE { texture_cache.half_pixels(id) } else { texture_cache.pixels(id) };
+    let texels_per_unit = (pixels.width as f32 / block, pixels.height as f32 / block);
+
+    for (x, &(dir_x, dir_y)) in directions.iter().enumerate() {
+      let world_x = player.pos.x + dir_x * distance;
+      let world_y = player.pos.y + dir_y * distance;
+      let tx = (world_x.rem_euclid(block) * texels_per_unit.0) as u32;
+      let ty = (world_y.rem_euclid(block) * texels_per_unit.1) as u32;
+      let color = color::scale(color::opaque(pixels.color(tx, ty)), shade);
+      framebuffer.set_current_color(apply_fog(color, cells, light_radius));
+      framebuffer.set_pixel_with_depth(x as u32, y, 10000.0);
+    }
+  }
+}
 
-      let mut color = texture_cache.get_pixel_color(intersect.impact, tx, ty);
-      
-      // Only apply fog in quality mode for better performance
-      if !performance_mode && distance_to_wall > 200.0 {
-        let fog_factor = ((distance_to_wall - 200.0) * 0.003333).min(0.7); // Pre-calculate division
-        
-        // Faster color blending
-        let inv_fog = 1.0 - fog_factor;
-        color = Color::new(
-          (color.r as f32 * inv_fog + 60.0 * fog_factor) as u8,
-          (color.g as f32 * inv_fog + 60.0 * fog_factor) as u8,
-          (color.b as f32 * inv_fog + 90.0 * fog_factor) as u8,
-          255
-        );
-      }
-      
-      framebuffer.set_current_color(color);
-      framebuffer.set_pixel_with_depth(i, y as u32, distance_to_wall);
+// Screen-space outline around the wall face of something the player can use, and a slow brightness
+// pulse across it. Its pixels are the spans of the columns that hit it; a pixel is on the outline
+// when one within HIGHLIGHT_OUTLINE of it is not. Nearer walls and fences still cover it.
+fn outline_highlight(framebuffer: &mut Framebuffer, spans: &[Option<(i32, i32, f32)>], color: Color, time: f32) {
+  let inside = |x: i32, y: i32| x >= 0 && spans.get(x as usize).copied().flatten().is_some_and(|(top, bottom, _)| y >= top && y < bottom);
+  let pulse = (time * 4.0).sin() * 0.5 + 0.5;
+  let width = HIGHLIGHT_OUTLINE;
+
+  for (x, span) in spans.iter().enumerate() {
+    let Some((top, bottom, depth)) = *span else {
+      continue;
+    };
+    let x = x as i32;
+    // Just in front of the wall, so the depth test passes on it and nowhere nearer
+    let depth = depth - 1.0;
+    for y in top.max(0)..bottom.min(framebuffer.height as i32) {
+      let edge = (1..=width).any(|d| !inside(x - d, y) || !inside(x + d, y) || !inside(x, y - d) || !inside(x, y + d));
+      let amount = if edge { 0.6 + 0.3 * pulse } else { 0.12 * pulse };
+      framebuffer.blend_with_depth(x as u32, y as u32, depth, color, amount);
     }
   }
 }
 
-// Function to check if player's attack hits enemies
-fn check_attack_collision(
-  player: &mut Player, 
-  enemies: &mut Vec<Enemy>, 
-  _block_size: usize, 
-  audio_manager: &AudioManager,
-  sword_sound: &Option<Sound>,
-  hit_sound: &Option<Sound>,
-  death_sound: &Option<Sound>
-) {
-  if !player.is_attacking {
-    return;
+// Cells that fully block light for the ambient occlusion pass
+fn is_solid_cell(maze: &Maze, x: i32, y: i32) -> bool {
+  match maze.get_i32(x, y) {
+    Some(cell) => !is_walkable(cell) && !is_partial_wall(cell),
+    None => true,
   }
+}
 
-  let attack_range = 150.0; // Range in which attacks can hit
-  let attack_angle = PI / 6.0; // 30-degree cone in front of player
-  
-  // Only process attack collision during the peak of the attack (middle third)
-  let attack_progress = player.get_attack_progress();
-  if attack_progress < 0.2 || attack_progress > 0.8 {
-    return;
+// Fake ambient occlusion: darken the ends of a wall face that meet another wall in an inner corner
+fn corner_occlusion(maze: &Maze, intersect: &Intersect) -> f32 {
+  if is_partial_wall(intersect.impact) {
+    return 1.0;
+  }
+
+  let cx = intersect.cell_x as i32;
+  let cy = intersect.cell_y as i32;
+
+  // Cells diagonally in front of the face, at the start (tx = 0) and end (tx = 127) of the texture
+  let (start_neighbor, end_neighbor) = match intersect.face {
+    WallFace::West => ((cx - 1, cy - 1), (cx - 1, cy + 1)),
+    WallFace::East => ((cx + 1, cy - 1), (cx + 1, cy + 1)),
+    WallFace::North => ((cx - 1, cy - 1), (cx + 1, cy - 1)),
+    WallFace::South => ((cx - 1, cy + 1), (cx + 1, cy + 1)),
+  };
+
+  let t = intersect.tx as f32 / 127.0;
+  let corner_width = 0.25; // Fraction of the face that gets darkened
+  let max_darkening = 0.45;
+
+  let mut shade: f32 = 1.0;
+  if t < corner_width && is_solid_cell(maze, start_neighbor.0, start_neighbor.1) {
+    shade = shade.min(1.0 - max_darkening * (1.0 - t / corner_width));
+  }
+  if t > 1.0 - corner_width && is_solid_cell(maze, end_neighbor.0, end_neighbor.1) {
+    shade = shade.min(1.0 - max_darkening * (1.0 - (1.0 - t) / corner_width));
+  }
+  shade
+}
+
+// Horizontal field of view for a view `width` by `height` pixels. Wall and sprite heights
+// follow the view's height alone, and rays and sprite columns are spread evenly by angle, so
+// the field of view grows in step with the aspect ratio (Hor+): every window shows the world
+// in the proportions of the default one, wider windows just see more to the sides.
+pub fn view_fov(width: u32, height: u32) -> f32 {
+  let aspect = width as f32 / height.max(1) as f32;
+  (BASE_FOV * aspect / BASE_ASPECT).clamp(FOV_LIMITS.0, FOV_LIMITS.1)
+}
+
+// On-screen height of a wall-sized object at the given distance
+fn projected_height(hh: f32, distance: f32, block_size: usize) -> f32 {
+  (hh / distance) * DISTANCE_TO_PROJECTION_PLANE * block_size as f32
+}
+
+// How far toward the fog color something is with distance. Both distances are in cells.
+fn fog_amount(distance: f32, fog_start: f32) -> f32 {
+  ((distance - fog_start) * FOG_DENSITY).clamp(0.0, 0.7)
+}
+
+// Blend toward the fog color with distance, shared by walls and sprites
+fn apply_fog(color: Color, distance: f32, fog_start: f32) -> Color {
+  if distance <= fog_start {
+    return color;
   }
+  color::opaque(color::lerp(color, FOG_COLOR, fog_amount(distance, fog_start)))
+}
+
+// Performance mode's fog on pixel (x, y): instead of blending, a growing share of pixels in an
+// ordered dither pattern takes the fog color
+fn apply_fog_dithered(color: Color, distance: f32, fog_start: f32, x: u32, y: u32) -> Color {
+  if dither(x, y, fog_amount(distance, fog_start)) { FOG_COLOR } else { color }
+}
+
+// How much of a sprite is left at `distance` cells: far past the light it dithers away rather than
+// staying on screen as a fogged silhouette however far it is
+fn sprite_fade(distance: f32, light_radius: f32) -> f32 {
+  let (start, end) = SPRITE_FADE;
+  1.0 - ((distance - light_radius - start) / (end - start)).clamp(0.0, 1.0)
+}
+
+// What every wall slice of a frame has in common
+#[derive(Clone, Copy)]
+struct WallPass<'a> {
+  hh: f32,
+  texture_cache: &'a TextureManager,
+  performance_mode: bool,
+  beacon_strength: f32, // Goal and portal pulse
+  light_radius: f32,
+  block_size: usize,
+}
+
+fn draw_wall_slice(
+  framebuffer: &mut Framebuffer,
+  column: u32,
+  intersect: &Intersect,
+  pass: &WallPass,
+  skip_transparent: bool,
+  shade: f32, // Whole column, for the face's side and inner corners
+) {
+  let WallPass { hh, texture_cache, performance_mode, beacon_strength, light_radius, block_size } = *pass;
+  let distance_to_wall = intersect.distance;
+  let stake_height = projected_height(hh, distance_to_wall, block_size);
+
+  let stake_top = hh - (stake_height / 2.0);
+  let draw_top = stake_top.max(0.0) as usize;
+  let draw_bottom = (hh + (stake_height / 2.0)).min(framebuffer.height as f32).max(0.0) as usize;
+
+  // One texture lookup per column instead of one per pixel. Far walls cover fewer screen rows than
+  // their texture has texels, so they read the half-resolution copy: less memory to hop through,
+  // and less shimmer than skipping over most of the full texture's rows as the view moves.
+  let far = distance_to_wall / block_size as f32 > TEXTURE_LOD_DISTANCE;
+  let pixels = match texture_cache.texture_id(intersect.impact.to_char()) {
+    Some(id) if far => texture_cache.half_pixels(id),
+    Some(id) => texture_cache.pixels(id),
+    None => return,
+  };
+  let texture_column = pixels.column(intersect.tx as u32 * pixels.width / 128); // tx runs 0 to 127 across any texture
+  let texture_height = texture_column.height;
+
+  // 16.16 fixed-point texture Y, stepped once per screen row like the classic raycasters
+  let step = (texture_height as f32 / stake_height * TEX_FIXED_ONE) as u32;
+  let mut tex_pos = ((draw_top as f32 - stake_top) * texture_height as f32 / stake_height * TEX_FIXED_ONE) as u32;
+  let inv_texture_height = 1.0 / texture_height.max(1) as f32;
+
+  for y in draw_top..draw_bottom {
+    let ty = tex_pos >> TEX_FIXED_SHIFT;
+    tex_pos += step;
 
-  // Play sword swing sound only once per attack when no enemy is hit
-  if !player.enemy_hit_this_attack {
-    let mut any_enemy_hit = false;
+    let mut color = texture_column.color(ty);
+
+    // Gaps in fences and grates show whatever is behind them, and half see-through texels like
+    // glass are mixed over it. Solid walls ignore their textures' alpha.
+    let alpha = if skip_transparent { color.a } else { 255 };
+    if alpha == 0 {
+      continue;
+    }
+
+    // Side shading and ambient occlusion: inner corners plus a soft contact shadow where the wall meets floor and ceiling
+    if !performance_mode {
+      let ty_ratio = ty as f32 * inv_texture_height;
+      let junction_shade = if ty_ratio > 0.85 {
+        1.0 - (ty_ratio - 0.85) / 0.15 * 0.35
+      } else if ty_ratio < 0.08 {
+        1.0 - (0.08 - ty_ratio) / 0.08 * 0.2
+      } else {
+        1.0
+      };
+      color = color::scale(color, shade * junction_shade);
+    } else if shade < 1.0 {
+      color = color::scale(color, shade);
+    }
+
+    // Pulsing golden tint on the goal so it can be spotted from afar, blue on hub portals
+    if intersect.impact == Cell::Goal {
+      color = color::opaque(color::lerp(color, Color::new(255, 200, 60, 255), beacon_strength));
+    } else if intersect.impact == Cell::Portal {
+      color = color::opaque(color::lerp(color, Color::new(80, 160, 255, 255), beacon_strength + 0.2));
+    }
+    
+    // Quality mode blends the fog in, performance mode dithers it
+    if !performance_mode {
+      color = apply_fog(color, distance_to_wall / block_size as f32, light_radius);
+    } else {
+      color = apply_fog_dithered(color, distance_to_wall / block_size as f32, light_radius, column, y as u32);
+    }
     
-    for enemy in enemies.iter_mut() {
-      if enemy.is_dead {
-        continue;
+    framebuffer.set_current_color(Color::new(color.r, color.g, color.b, alpha));
+    framebuffer.blend_pixel_with_depth(column, y as u32, distance_to_wall);
+  }
+}
+
+// Spinning gold coins, billboarded with the same projection as enemies
+fn render_pickups(
+  framebuffer: &mut Framebuffer,
+  player: &Player,
+  pickups: &[Pickup],
+  maze: &Maze,
+  block_size: usize,
+  performance_mode: bool,
+) {
+  let hh = framebuffer.height as f32 / 2.0;
+  let screen_width = framebuffer.width as f32;
+  let time = unsafe { raylib::ffi::GetTime() } as f32;
+  let spin = (time * 3.0).cos().abs().max(0.15); // Coin width shrinks as it turns edge-on
+
+  for pickup in pickups.iter() {
+    let dx = pickup.pos.x - player.pos.x;
+    let dy = pickup.pos.y - player.pos.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+
+    let mut angle_diff = dy.atan2(dx) - player.a;
+    while angle_diff > PI {
+      angle_diff -= 2.0 * PI;
+    }
+    while angle_diff < -PI {
+      angle_diff += 2.0 * PI;
+    }
+
+    // Skip coins behind the camera, too close to project, or hidden by walls
+    if angle_diff.abs() > player.fov / 2.0 + 0.2 || distance * angle_diff.cos() < SPRITE_NEAR_PLANE * block_size as f32 {
+      continue;
+    }
+    if !has_line_of_sight(player.pos, pickup.pos, maze, block_size) {
+      continue;
+    }
+
+    // Coins are a fifth of a wall tall and rest near the floor
+    let wall_height = projected_height(hh, distance, block_size);
+    let radius = wall_height * 0.1;
+    let center_x = (angle_diff / player.fov + 0.5) * screen_width;
+    let center_y = hh + wall_height / 2.0 - radius * 1.5;
+
+    let mut color = Color::new(255, 200, 40, 255);
+    if !performance_mode {
+      color = apply_fog(color, distance / block_size as f32, player.sight_radius());
+    }
+    framebuffer.set_current_color(color);
+
+    let radius_x = (radius * spin).max(1.0);
+    let min_x = (center_x - radius_x).max(0.0) as i32;
+    let max_x = (center_x + radius_x).min(screen_width - 1.0) as i32;
+    let min_y = (center_y - radius).max(0.0) as i32;
+    let max_y = (center_y + radius).min(framebuffer.height as f32 - 1.0) as i32;
+
+    for x in min_x..=max_x {
+      for y in min_y..=max_y {
+        let nx = (x as f32 - center_x) / radius_x;
+        let ny = (y as f32 - center_y) / radius;
+        if nx * nx + ny * ny <= 1.0 {
+          framebuffer.set_pixel_with_depth(x as u32, y as u32, distance);
+        }
       }
+    }
+  }
+}
 
-      // Calculate distance to enemy
-      let dx = enemy.pos.x - player.pos.x;
-      let dy = enemy.pos.y - player.pos.y;
-      let distance = (dx * dx + dy * dy).sqrt();
-      
-      if distance > attack_range {
-        continue;
+// Soft dark ellipses on the floor under enemies and coins, so they look like they stand on it rather
+// than float in front of it. Drawn after the walls and before the sprites: walls in front of the
+// spot hide it through the depth buffer and the sprite itself then covers its upper half.
+fn render_shadows(framebuffer: &mut Framebuffer, player: &Player, enemies: &[Enemy], pickups: &[Pickup], block_size: usize) {
+  let hh = framebuffer.height as f32 / 2.0;
+  let screen_width = framebuffer.width as f32;
+  let standing = enemies.iter().filter(|enemy| !enemy.is_dead).map(|enemy| (enemy.pos, ENEMY_SHADOW_WIDTH));
+  let coins = pickups.iter().map(|pickup| (pickup.pos, COIN_SHADOW_WIDTH));
+
+  for (pos, width) in standing.chain(coins) {
+    let dx = pos.x - player.pos.x;
+    let dy = pos.y - player.pos.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+    let angle_diff = sim::normalize_angle(dy.atan2(dx) - player.a);
+    if angle_diff.abs() > player.fov / 2.0 + 0.2 || distance * angle_diff.cos() < SPRITE_NEAR_PLANE * block_size as f32 {
+      continue;
+    }
+
+    // Centered on the floor line at this distance, so it shrinks with the sprite
+    let wall_height = projected_height(hh, distance, block_size);
+    let radius_x = (wall_height * width).max(1.0);
+    let radius_y = (radius_x * 0.25).max(1.0);
+    let center_x = (angle_diff / player.fov + 0.5) * screen_width;
+    let center_y = hh + wall_height / 2.0;
+
+    let min_x = (center_x - radius_x).max(0.0) as u32;
+    let max_x = (center_x + radius_x).min(screen_width - 1.0).max(0.0) as u32;
+    let min_y = (center_y - radius_y).max(0.0) as u32;
+    let max_y = (center_y + radius_y).min(framebuffer.height as f32 - 1.0).max(0.0) as u32;
+    for y in min_y..=max_y {
+      for x in min_x..=max_x {
+        let nx = (x as f32 - center_x) / radius_x;
+        let ny = (y as f32 - center_y) / radius_y;
+        let r2 = nx * nx + ny * ny;
+        if r2 < 1.0 {
+          framebuffer.blend_with_depth(x, y, distance, Color::BLACK, SHADOW_STRENGTH * (1.0 - r2));
+        }
       }
+    }
+  }
+}
 
-      // Calculate angle to enemy relative to player's facing direction
-      let angle_to_enemy = dy.atan2(dx);
-      let mut angle_diff = angle_to_enemy - player.a;
-      
-      // Normalize angle difference to [-PI, PI]
-      while angle_diff > PI {
-        angle_diff -= 2.0 * PI;
+// Flat colored box standing on the floor, sized in fractions of a wall: (position, width, height,
+// lift above the floor, color)
+type FloorItem = (Vector2, f32, f32, f32, Color);
+
+// Deployables and darts as floor boxes: caltrops are a few low spikes, the turret a post with a
+// brass head, a dart a short streak
+fn deployable_boxes(player: &Player, deployables: &[Deployable], projectiles: &[Projectile], block_size: usize) -> Vec<FloorItem> {
+  let block = block_size as f32;
+  let right = Vector2::new(-player.a.sin(), player.a.cos()); // Across the view, to spread the spikes
+
+  let mut boxes = Vec::new();
+  for deployable in deployables.iter() {
+    match deployable.kind {
+      DeployableKind::Caltrops => {
+        for offset in [-0.2, -0.07, 0.07, 0.2] {
+          let pos = Vector2::new(deployable.pos.x + right.x * offset * block, deployable.pos.y + right.y * offset * block);
+          boxes.push((pos, 0.03, 0.08, 0.0, Color::new(170, 170, 180, 255)));
+        }
+        boxes.push((deployable.pos, 0.5, 0.02, 0.0, Color::new(90, 90, 100, 255)));
       }
-      while angle_diff < -PI {
-        angle_diff += 2.0 * PI;
+      DeployableKind::DartTurret => {
+        boxes.push((deployable.pos, 0.08, 0.3, 0.0, Color::new(70, 55, 40, 255)));
+        boxes.push((deployable.pos, 0.22, 0.12, 0.3, Color::new(200, 160, 60, 255)));
       }
+    }
+  }
+  for projectile in projectiles.iter() {
+    boxes.push((projectile.pos, 0.1, 0.03, 0.36, Color::new(120, 230, 90, 255)));
+  }
+  boxes
+}
 
-      // Check if enemy is within attack cone
-      if angle_diff.abs() <= attack_angle {
-        // Hit the enemy
-        any_enemy_hit = true;
-        player.enemy_hit_this_attack = true;
-        
-        // Play hit sound
-        if let Some(sound) = hit_sound {
-          audio_manager.play_enemy_hit(sound);
-        }
-        
-        // Kill the enemy and play death sound
-        enemy.kill();
-        if let Some(sound) = death_sound {
-          audio_manager.play_enemy_death(sound);
-        }
-        
-        println!("Enemy hit! Distance: {:.1}, Angle: {:.1}°", distance, angle_diff.to_degrees());
+// Floor boxes billboarded like coins and hidden by walls the same way
+fn render_floor_items(
+  framebuffer: &mut Framebuffer,
+  player: &Player,
+  items: &[FloorItem],
+  maze: &Maze,
+  block_size: usize,
+  performance_mode: bool,
+) {
+  let hh = framebuffer.height as f32 / 2.0;
+  let screen_width = framebuffer.width as f32;
+  let block = block_size as f32;
+
+  for &(pos, width, height, lift, base_color) in items {
+    let dx = pos.x - player.pos.x;
+    let dy = pos.y - player.pos.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+
+    let mut angle_diff = dy.atan2(dx) - player.a;
+    while angle_diff > PI {
+      angle_diff -= 2.0 * PI;
+    }
+    while angle_diff < -PI {
+      angle_diff += 2.0 * PI;
+    }
+
+    // Same culling as coins: behind the camera, too close to project, or hidden by walls
+    if angle_diff.abs() > player.fov / 2.0 + 0.2 || distance * angle_diff.cos() < SPRITE_NEAR_PLANE * block {
+      continue;
+    }
+    if !has_line_of_sight(player.pos, pos, maze, block_size) {
+      continue;
+    }
+
+    let wall_height = projected_height(hh, distance, block_size);
+    let center_x = (angle_diff / player.fov + 0.5) * screen_width;
+    let floor_y = hh + wall_height / 2.0;
+    let half_width = (width * wall_height / 2.0).max(0.5);
+
+    let mut color = base_color;
+    if !performance_mode {
+      color = apply_fog(color, distance / block, player.sight_radius());
+    }
+    framebuffer.set_current_color(color);
+
+    let min_x = (center_x - half_width).max(0.0) as i32;
+    let max_x = (center_x + half_width).min(screen_width - 1.0) as i32;
+    let min_y = (floor_y - (lift + height) * wall_height).max(0.0) as i32;
+    let max_y = (floor_y - lift * wall_height).min(framebuffer.height as f32 - 1.0) as i32;
+    for x in min_x..=max_x {
+      for y in min_y..=max_y {
+        framebuffer.set_pixel_with_depth(x as u32, y as u32, distance);
       }
     }
-    
-    // If no enemy was hit, play sword swing sound
-    if !any_enemy_hit {
-      if let Some(sound) = sword_sound {
-        audio_manager.play_sword_swing(sound);
+  }
+}
+
+// Ping beacons: a light pillar from the floor up past the ceiling, thinning and fading as the ping expires.
+// Depth tested so walls in front hide it, but it is not fogged so it reads from far away.
+fn render_pings(framebuffer: &mut Framebuffer, player: &Player, pings: &[Ping], block_size: usize) {
+  let hh = framebuffer.height as f32 / 2.0;
+  let screen_width = framebuffer.width as f32;
+
+  for ping in pings.iter() {
+    let dx = ping.pos.x - player.pos.x;
+    let dy = ping.pos.y - player.pos.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+
+    let mut angle_diff = dy.atan2(dx) - player.a;
+    while angle_diff > PI {
+      angle_diff -= 2.0 * PI;
+    }
+    while angle_diff < -PI {
+      angle_diff += 2.0 * PI;
+    }
+    if angle_diff.abs() > player.fov / 2.0 + 0.2 || distance * angle_diff.cos() < SPRITE_NEAR_PLANE * block_size as f32 {
+      continue;
+    }
+
+    let wall_height = projected_height(hh, distance, block_size);
+    let half_width = (wall_height * 0.04 * ping.strength()).max(1.0);
+    let center_x = (angle_diff / player.fov + 0.5) * screen_width;
+    let bottom = (hh + wall_height / 2.0).min(framebuffer.height as f32 - 1.0);
+
+    // Brighter core, and a slow pulse so it stands out from the walls
+    let pulse = 0.8 + 0.2 * (ping.age * 6.0).sin();
+    let min_x = (center_x - half_width).max(0.0) as i32;
+    let max_x = (center_x + half_width).min(screen_width - 1.0) as i32;
+    for x in min_x..=max_x {
+      let edge = ((x as f32 - center_x) / half_width).abs();
+      let color = color::scale(ping.color(), pulse * (1.0 - 0.5 * edge));
+      framebuffer.set_current_color(color::with_alpha(color, ping.strength().sqrt()));
+      for y in 0..=bottom.max(0.0) as i32 {
+        framebuffer.blend_pixel_with_depth(x as u32, y as u32, distance);
       }
-      player.enemy_hit_this_attack = true; // Prevent multiple sword sounds
     }
   }
 }
 
-fn render_enemies(framebuffer: &mut Framebuffer, player: &Player, enemies: &mut Vec<Enemy>, texture_cache: &TextureManager, delta_time: f32, maze: &Maze, block_size: usize) {
-  // Remove enemies that should despawn
-  enemies.retain(|enemy| !enemy.should_despawn());
+// Weather particles as soft dots, drip streaks and fog puffs blended over the scene. Depth tested
+// so walls and sprites in front hide them; performance mode draws every third one.
+fn render_weather(framebuffer: &mut Framebuffer, player: &Player, weather: &Weather, block_size: usize, performance_mode: bool) {
+  let hh = framebuffer.height as f32 / 2.0;
+  let screen_width = framebuffer.width as f32;
+  let block = block_size as f32;
 
-  for enemy in enemies.iter_mut() {
-    // Update animation and movement
-    enemy.update(delta_time, player.pos, maze, block_size);
-    
-    // Skip AI updates if enemy is dead
-    if enemy.is_dead {
-      draw_sprite(framebuffer, &player, enemy, texture_cache, maze, block_size);
+  for particle in weather.particles.iter().step_by(if performance_mode { 3 } else { 1 }) {
+    let dx = particle.pos.x - player.pos.x;
+    let dy = particle.pos.y - player.pos.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+
+    let mut angle_diff = dy.atan2(dx) - player.a;
+    while angle_diff > PI {
+      angle_diff -= 2.0 * PI;
+    }
+    while angle_diff < -PI {
+      angle_diff += 2.0 * PI;
+    }
+    if angle_diff.abs() > player.fov / 2.0 + 0.2 || distance * angle_diff.cos() < SPRITE_NEAR_PLANE * block {
       continue;
     }
-    
-    // Enhanced AI based on distance to player - only for combat, movement is handled in enemy.update()
-    let distance_to_player = ((player.pos.x - enemy.pos.x).powi(2) + (player.pos.y - enemy.pos.y).powi(2)).sqrt();
-    
-    if distance_to_player < 150.0 {
-      // Close - attack animation (override movement animation)
-      enemy.set_animation(AnimationState::Attack);
+
+    let wall_height = projected_height(hh, distance, block_size);
+    let center_x = (angle_diff / player.fov + 0.5) * screen_width;
+    let center_y = hh + wall_height / 2.0 - particle.height * wall_height;
+    let (width, height) = particle.kind.size();
+    // Up close a fog puff would cover the screen, cap it so the blending stays cheap
+    let half_width = (width * wall_height / 2.0).clamp(0.5, 120.0);
+    let half_height = (height * wall_height / 2.0).clamp(0.5, 80.0);
+
+    let color = if performance_mode { particle.kind.color() } else { apply_fog(particle.kind.color(), distance / block, player.sight_radius()) };
+    let amount = particle.kind.opacity() * particle.strength();
+    let min_x = (center_x - half_width).max(0.0) as i32;
+    let max_x = (center_x + half_width).min(screen_width - 1.0) as i32;
+    let min_y = (center_y - half_height).max(0.0) as i32;
+    let max_y = (center_y + half_height).min(framebuffer.height as f32 - 1.0) as i32;
+    for x in min_x..=max_x {
+      for y in min_y..=max_y {
+        // Fog puffs thin out toward their edges, the small particles are solid
+        let falloff = if particle.kind == WeatherKind::Fog {
+          let (u, v) = ((x as f32 - center_x) / half_width, (y as f32 - center_y) / half_height);
+          (1.0 - (u * u + v * v)).max(0.0)
+        } else {
+          1.0
+        };
+        framebuffer.blend_with_depth(x as u32, y as u32, distance, color, amount * falloff);
+      }
     }
-    // Note: Walking and Idle animations are now handled by the movement system
-    
-    draw_sprite(framebuffer, &player, enemy, texture_cache, maze, block_size);
   }
 }
 
+// The companion wisp floats at chest height, bobbing, and flares up when it finds something
+fn render_companion(framebuffer: &mut Framebuffer, player: &Player, companion: &Companion, texture_cache: &TextureManager, maze: &Maze, block_size: usize, performance_mode: bool) {
+  let sprite = Billboard {
+    pos: companion.pos,
+    texture_key: WISP_SHEET,
+    frame: ((companion.age * WISP_FPS) as usize % WISP_FRAMES, 0),
+    flip: false,
+    scale: 0.25 + 0.1 * companion.pulse,
+    lift: 0.4 + 0.05 * (companion.age * 2.0).sin(),
+    tint: Some((Color::WHITE, 0.6 * companion.pulse)),
+    alpha: 1.0,
+    distance_fade: false, // It glows, so it stays in sight
+  };
+  draw_sprite(framebuffer, player, &sprite, texture_cache, maze, block_size, performance_mode);
+}
+
+fn render_enemies(framebuffer: &mut Framebuffer, player: &Player, enemies: &[Enemy], texture_cache: &TextureManager, maze: &Maze, block_size: usize, performance_mode: bool) {
+  // Solid enemies first, so a see-through one blends over whatever stands behind it
+  let (solid, cloaked): (Vec<&Enemy>, Vec<&Enemy>) = enemies.iter().partition(|enemy| enemy.opacity >= 1.0);
+  for enemy in solid.into_iter().chain(cloaked) {
+    // Hit feedback floats above the sprite
+    if let Some(rect) = draw_sprite(framebuffer, player, &Billboard::enemy(enemy), texture_cache, maze, block_size, performance_mode) {
+      draw_hit_feedback(framebuffer, enemy, rect.center_x, rect.top, rect.size, rect.depth);
+    }
+  }
+}
+
+// Top-down color of a cell, shared by the minimap and map renders
+fn minimap_cell_color(cell: Cell) -> Color {
+  match cell {
+    Cell::Empty => Color::new(40, 40, 40, 255),        // Floor - dark gray
+    Cell::Hint(_) => Color::new(40, 40, 40, 255),      // Hint triggers are plain floor
+    Cell::Coin => Color::new(40, 40, 40, 255),         // Coins lie on plain floor
+    Cell::Checkpoint => Color::new(40, 110, 110, 255), // Checkpoint - teal
+    Cell::Fence { .. } | Cell::Diagonal { .. } => Color::new(40, 40, 40, 255), // Thin walls sit on a floor cell
+    Cell::Window => Color::new(70, 90, 120, 255),      // Window - bluish gray
+    Cell::Water => Color::new(30, 60, 120, 255),       // Water - blue
+    Cell::Swamp => Color::new(45, 70, 35, 255),        // Swamp - murky green
+    Cell::FireTrap => Color::new(150, 60, 20, 255),    // Fire trap - embers
+    Cell::Door { open: true } => Color::new(90, 60, 30, 255), // Open arena door - brown
+    Cell::Door { open: false } => Color::new(150, 40, 40, 255), // Sealed arena door - red
+    Cell::ArenaTrigger | Cell::AmbushSpawn => Color::new(40, 40, 40, 255), // Arena floor
+    Cell::Portal => Color::new(60, 120, 220, 255),     // Hub portal - blue
+    _ => Color::new(100, 100, 100, 255),               // Wall - light gray
+  }
+}
+
+// The maze around the player with its enemies, pings, footprints and markers
 fn render_minimap(
   d: &mut RaylibDrawHandle,
-  maze: &Maze,
-  player: &Player,
-  enemies: &Vec<Enemy>,
-  block_size: usize,
+  world: &World,
+  minimap_size: i32,  // Width and height in pixels
+  minimap_scale: i32, // Pixels per maze cell, larger zooms in and shows fewer cells
   screen_width: i32,
   screen_height: i32,
 ) {
-  let minimap_size = 200; // Size of the minimap in pixels
-  let minimap_scale = 8;  // Each maze cell will be 8x8 pixels in the minimap
-  
+  let Some(maze) = world.maze() else {
+    return;
+  };
+  let (player, block_size) = (&world.player, world.block_size);
+  let (enemies, pings, footprints, markers) = (&world.enemies, &world.pings, &world.footprints, &world.markers);
+
   // Position minimap in lower middle of screen
   let minimap_x = (screen_width - minimap_size) / 2;
   let minimap_y = screen_height - minimap_size - 20; // 20 pixels from bottom
@@ -540,27 +1235,54 @@ fn render_minimap(
       let maze_y = player_maze_y + dy;
       
       // Check bounds
-      if maze_y >= 0 && maze_y < maze.len() as i32 && 
-         maze_x >= 0 && maze_x < maze[0].len() as i32 {
-        
-        let cell = maze[maze_y as usize][maze_x as usize];
-        let color = match cell {
-          ' ' => Color::new(40, 40, 40, 255),   // Floor - dark gray
-          _ => Color::new(100, 100, 100, 255),  // Wall - light gray
-        };
+      if let Some(cell) = maze.get_i32(maze_x, maze_y) {
+        let color = minimap_cell_color(cell);
         
         let pixel_x = minimap_x + (dx + half_cells) * minimap_scale;
         let pixel_y = minimap_y + (dy + half_cells) * minimap_scale;
         
         d.draw_rectangle(pixel_x, pixel_y, minimap_scale, minimap_scale, color);
+
+        // Draw thin and diagonal walls as a line across their cell
+        if is_partial_wall(cell) {
+          let (x0, y0, x1, y1) = match cell {
+            Cell::Fence { vertical: false } => (0, minimap_scale / 2, minimap_scale, minimap_scale / 2),
+            Cell::Fence { vertical: true } => (minimap_scale / 2, 0, minimap_scale / 2, minimap_scale),
+            Cell::Diagonal { rising: true } => (0, minimap_scale, minimap_scale, 0),
+            _ => (0, 0, minimap_scale, minimap_scale),
+          };
+          d.draw_line(pixel_x + x0, pixel_y + y0, pixel_x + x1, pixel_y + y1, Color::new(100, 100, 100, 255));
+        }
       }
     }
   }
   
+  // Footprints under everything else, unlike pings they are not pinned to the edge
+  for print in footprints.iter() {
+    let dx = (print.pos.x - player.pos.x) / block_size as f32;
+    let dy = (print.pos.y - player.pos.y) / block_size as f32;
+    if dx.abs() < half_cells as f32 && dy.abs() < half_cells as f32 {
+      let print_pixel_x = minimap_x + minimap_size / 2 + (dx * minimap_scale as f32) as i32;
+      let print_pixel_y = minimap_y + minimap_size / 2 + (dy * minimap_scale as f32) as i32;
+      d.draw_circle(print_pixel_x, print_pixel_y, 1.5, print.color());
+    }
+  }
+
+  // Map markers the player has placed, small enough to leave the cell visible
+  for marker in markers.iter() {
+    let dx = marker.cell.0 as i32 - player_maze_x;
+    let dy = marker.cell.1 as i32 - player_maze_y;
+    if dx.abs() < half_cells && dy.abs() < half_cells {
+      let marker_pixel_x = minimap_x + (dx + half_cells) * minimap_scale + minimap_scale / 2;
+      let marker_pixel_y = minimap_y + (dy + half_cells) * minimap_scale + minimap_scale / 2;
+      d.draw_circle(marker_pixel_x, marker_pixel_y, (minimap_scale as f32 * 0.35).max(2.0), marker.kind.color());
+    }
+  }
+
   // Draw enemies on minimap
   for enemy in enemies.iter() {
-    // Skip dead enemies
-    if enemy.is_dead {
+    // Skip dead enemies, and cloaked ones nothing has shown
+    if enemy.is_dead || enemy.opacity < 1.0 {
       continue;
     }
     
@@ -592,6 +1314,17 @@ fn render_minimap(
     }
   }
   
+  // Draw pings as pulsing rings, pinned to the minimap edge when out of range
+  for ping in pings.iter() {
+    let dx = ((ping.pos.x - player.pos.x) / block_size as f32).clamp(-(half_cells as f32), half_cells as f32);
+    let dy = ((ping.pos.y - player.pos.y) / block_size as f32).clamp(-(half_cells as f32), half_cells as f32);
+    let ping_pixel_x = minimap_x + minimap_size / 2 + (dx * minimap_scale as f32) as i32;
+    let ping_pixel_y = minimap_y + minimap_size / 2 + (dy * minimap_scale as f32) as i32;
+    let pulse = (ping.age * 6.0).sin() * 1.5;
+    d.draw_circle(ping_pixel_x, ping_pixel_y, 2.5, ping.color());
+    d.draw_circle_lines(ping_pixel_x, ping_pixel_y, 5.0 + pulse, ping.color());
+  }
+  
   // Draw player position as a red dot in the center (draw last so it's on top)
   let player_pixel_x = minimap_x + minimap_size / 2;
   let player_pixel_y = minimap_y + minimap_size / 2;
@@ -630,12 +1363,16 @@ fn render_minimap(
   
   d.draw_circle(legend_x + 10, legend_y + 85, 3.0, Color::RED);
   d.draw_text("You", legend_x + 20, legend_y + 80, 12, Color::WHITE);
+
+  d.draw_circle_lines(legend_x + 10, legend_y + 105, 4.0, ping::PING_COLORS[0]);
+  d.draw_text("Ping", legend_x + 20, legend_y + 100, 12, Color::WHITE);
 }
 
 fn render_sword(
   d: &mut RaylibDrawHandle,
   player: &Player,
   texture_manager: &TextureManager,
+  weapon: &Weapon,
   screen_width: i32,
   screen_height: i32,
 ) {
@@ -649,25 +1386,30 @@ fn render_sword(
     let base_y = screen_height as f32 * 0.7;  // Slightly lower (was 0.65)
     let base_rotation = -15.0; // Slightly more angled for better visual
     
-    // Attack animation modifiers - LEFT and DOWN movement
+    // Attack animation modifiers, one motion per hit of the combo
     let (attack_offset_x, attack_offset_y, attack_rotation_offset, attack_scale) = if player.is_attacking {
       let attack_progress = player.get_attack_progress();
       
-      // Attack motion: swing LEFT and DOWN (opposite of before)
-      let swing_x = -attack_progress * 100.0; // Move LEFT during attack (negative)
-      let swing_y = attack_progress * 80.0;   // Move DOWN during attack (positive)
-      let swing_rotation = -attack_progress * 60.0; // Rotate counterclockwise (negative)
-      let scale_increase = attack_progress * 0.4; // Slightly bigger size increase
-      
-      (swing_x, swing_y, swing_rotation, scale_increase)
+      match player.combo_step {
+        // Opening slash: swing LEFT and DOWN, rotating counterclockwise
+        0 => (-attack_progress * 100.0, attack_progress * 80.0, -attack_progress * 60.0, attack_progress * 0.4),
+        // Backhand: starts from the left and sweeps back RIGHT, rotating clockwise
+        1 => (-100.0 + attack_progress * 160.0, 60.0 - attack_progress * 30.0, -40.0 + attack_progress * 80.0, 0.3),
+        // Finisher: raised high, then brought straight DOWN through the center
+        _ => {
+          let chop = (attack_progress * 2.0 - 0.5).clamp(0.0, 1.0);
+          (-60.0, -80.0 + chop * 220.0, 20.0 - chop * 50.0, 0.5 + chop * 0.2)
+        }
+      }
     } else {
       (0.0, 0.0, 0.0, 0.0) // No attack animation
     };
     
-    // Final position and properties
-    let final_x = base_x + attack_offset_x;
-    let final_y = base_y + attack_offset_y;
-    let final_rotation = base_rotation + attack_rotation_offset;
+    // Final position and properties, trailing the camera by the sway
+    let sway = player.sway.offset;
+    let final_x = base_x + attack_offset_x + sway.x;
+    let final_y = base_y + attack_offset_y + sway.y;
+    let final_rotation = base_rotation + attack_rotation_offset + sway.x * SWAY_TILT;
     let final_scale = 1.0 + attack_scale;
     let final_width = base_sword_width * final_scale;
     let final_height = base_sword_height * final_scale;
@@ -683,7 +1425,7 @@ fn render_sword(
     let source_rect = Rectangle::new(0.0, 0.0, sword_texture.width as f32, sword_texture.height as f32);
     let dest_rect = Rectangle::new(final_x, final_y, final_width, final_height);
     let origin = Vector2::new(final_width * 0.5, final_height * 0.85); // Rotation point near handle
-    let tint = Color::new(255, 255, 255, alpha);
+    let tint = Color::new(weapon.tint.r, weapon.tint.g, weapon.tint.b, alpha);
     
     d.draw_texture_pro(
       sword_texture,
@@ -695,34 +1437,134 @@ fn render_sword(
     );
   }
 }
+const PAUSE_OPTIONS: [&str; 6] = ["Resume", "Restart Map", "Save Game", "Settings", "Controls", "Back to Main Menu"];
+const PERFORMANCE_OPTIONS: [&str; 2] = ["Quality", "High"];
+const PRESET_OPTIONS: [&str; 3] = ["Custom", "Desktop", "Handheld"]; // Custom: the settings match no preset
+const RENDER_SCALE_OPTIONS: [&str; 3] = ["50%", "75%", "100%"]; // Same order as settings::RENDER_SCALES
+const UI_SCALE_OPTIONS: [&str; 5] = ["75%", "100%", "125%", "150%", "200%"]; // Same order as settings::UI_SCALES
+const MINIMAP_SIZE_OPTIONS: [&str; 3] = ["Small", "Medium", "Large"]; // Same order as settings::MINIMAP_SIZES
+const MINIMAP_ZOOM_OPTIONS: [&str; 5] = ["50%", "75%", "100%", "150%", "200%"]; // Same order as settings::MINIMAP_ZOOMS
+const DIFFICULTY_OPTIONS: [&str; 3] = ["Easy", "Normal", "Hard"]; // Same order as director::DIFFICULTIES
+const INPUT_PRESET_OPTIONS: [&str; 3] = ["Default", "Left-Handed", "Southpaw"]; // Same order as actions::INPUT_PRESETS
+
+// What a settings widget controls, so the screen acts on the setting rather than on its row
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SettingId {
+  DisplayPreset,
+  PerformanceMode,
+  RenderScale,
+  UiScale,
+  Minimap,
+  MinimapSize,
+  MinimapZoom,
+  Music,
+  MasterVolume,
+  MusicVolume,
+  SfxVolume,
+  Berserk,
+  Scanlines,
+  FeedbackFx,
+  Companion,
+  Shadows,
+  Difficulty,
+  GyroAim,
+  GyroSensitivity,
+  InputPreset,
+  HudPillarbox,
+  AimAssist,
+  Controls,
+  ProfileName,
+  Mods,
+  Back,
+}
+
+// Settings screen widgets built from the current state, in menu order, each with the setting it controls
+fn settings_widgets(settings: &Settings, open_dropdown: Option<SettingId>) -> Vec<(SettingId, Widget)> {
+  let display = DisplayPreset { performance_mode: settings.performance_mode, render_scale: settings.render_scale, ui_scale: settings.ui_scale };
+  let preset = [DESKTOP_PRESET, HANDHELD_PRESET].iter().position(|preset| *preset == display).map_or(0, |index| index + 1);
+  let scale_index = |value: f32, choices: &[f32]| choices.iter().position(|choice| *choice == value).unwrap_or(0);
+  let size_index = |value: i32, choices: &[i32]| choices.iter().position(|choice| *choice == value).unwrap_or(0);
+  let open = |id: SettingId| open_dropdown == Some(id);
+  vec![
+    (SettingId::DisplayPreset, Widget::Dropdown { label: "Display Preset", options: &PRESET_OPTIONS, selected: preset, open: open(SettingId::DisplayPreset) }),
+    (SettingId::PerformanceMode, Widget::Dropdown { label: "Performance Mode", options: &PERFORMANCE_OPTIONS, selected: settings.performance_mode as usize, open: open(SettingId::PerformanceMode) }),
+    (SettingId::RenderScale, Widget::Dropdown { label: "Render Scale", options: &RENDER_SCALE_OPTIONS, selected: scale_index(settings.render_scale, &RENDER_SCALES), open: open(SettingId::RenderScale) }),
+    (SettingId::UiScale, Widget::Dropdown { label: "UI Scale", options: &UI_SCALE_OPTIONS, selected: scale_index(settings.ui_scale, &UI_SCALES), open: open(SettingId::UiScale) }),
+    (SettingId::Minimap, Widget::Toggle { label: "Minimap", value: settings.show_minimap }),
+    (SettingId::MinimapSize, Widget::Dropdown { label: "Minimap Size", options: &MINIMAP_SIZE_OPTIONS, selected: size_index(settings.minimap_size, &MINIMAP_SIZES), open: open(SettingId::MinimapSize) }),
+    (SettingId::MinimapZoom, Widget::Dropdown { label: "Minimap Zoom", options: &MINIMAP_ZOOM_OPTIONS, selected: size_index(settings.minimap_zoom, &MINIMAP_ZOOMS), open: open(SettingId::MinimapZoom) }),
+    (SettingId::Music, Widget::Toggle { label: "Music", value: settings.music_enabled }),
+    (SettingId::MasterVolume, Widget::Slider { label: "Master Volume", value: settings.master_volume, min: 0.0, max: 1.0, step: VOLUME_STEP }),
+    (SettingId::MusicVolume, Widget::Slider { label: "Music Volume", value: settings.music_volume, min: 0.0, max: 1.0, step: VOLUME_STEP }),
+    (SettingId::SfxVolume, Widget::Slider { label: "SFX Volume", value: settings.sfx_volume, min: 0.0, max: 1.0, step: VOLUME_STEP }),
+    (SettingId::Berserk, Widget::Toggle { label: "Berserk Filter", value: settings.berserk }),
+    (SettingId::Scanlines, Widget::Toggle { label: "CRT Scanlines", value: settings.scanlines }),
+    (SettingId::FeedbackFx, Widget::Toggle { label: "Damage/Water FX", value: settings.feedback_fx }),
+    (SettingId::Companion, Widget::Toggle { label: "Wisp Companion", value: settings.companion }),
+    (SettingId::Shadows, Widget::Toggle { label: "Sprite Shadows", value: settings.shadows }),
+    (SettingId::Difficulty, Widget::Dropdown { label: "Difficulty", options: &DIFFICULTY_OPTIONS, selected: DIFFICULTIES.iter().position(|difficulty| *difficulty == settings.difficulty).unwrap_or(1), open: open(SettingId::Difficulty) }),
+    (SettingId::GyroAim, Widget::Toggle { label: "Gyro Aim", value: settings.gyro_aim }),
+    (SettingId::GyroSensitivity, Widget::Slider { label: "Gyro Sensitivity", value: settings.gyro_sensitivity, min: GYRO_SENSITIVITY.0, max: GYRO_SENSITIVITY.1, step: GYRO_SENSITIVITY.2 }),
+    (SettingId::InputPreset, Widget::Dropdown { label: "Input Preset", options: &INPUT_PRESET_OPTIONS, selected: INPUT_PRESETS.iter().position(|preset| *preset == settings.input_preset).unwrap_or(0), open: open(SettingId::InputPreset) }),
+    (SettingId::HudPillarbox, Widget::Toggle { label: "Pillarbox HUD", value: settings.hud_pillarbox }),
+    (SettingId::AimAssist, Widget::Slider { label: "Aim Assist", value: settings.aim_assist, min: AIM_ASSIST.0, max: AIM_ASSIST.1, step: AIM_ASSIST.2 }),
+    (SettingId::Controls, Widget::Button { label: "Controls" }),
+    (SettingId::ProfileName, Widget::Button { label: "Profile Name" }),
+    (SettingId::Mods, Widget::Button { label: "Mods" }),
+    (SettingId::Back, Widget::Button { label: "Back" }),
+  ]
+}
+
+const SETTINGS_ROWS: i32 = 26;
+
+// Rows shrink to fit short screens, such as handhelds or a large UI scale
+fn settings_layout(screen_width: i32, screen_height: i32) -> Layout {
+  let menu_width = 520;
+  let row_height = ((screen_height - 140) / SETTINGS_ROWS).clamp(28, 40);
+  let menu_height = 120 + SETTINGS_ROWS * row_height;
+  Layout {
+    x: (screen_width - menu_width) / 2,
+    y: (screen_height - menu_height) / 2 + 65,
+    width: menu_width,
+    row_height,
+  }
+}
+
+// The pause menu's box in the middle of the screen: x, y, width, height
+fn pause_menu_panel(screen_width: i32, screen_height: i32) -> (i32, i32, i32, i32) {
+  let (menu_width, menu_height) = (360, 460);
+  ((screen_width - menu_width) / 2, (screen_height - menu_height) / 2, menu_width, menu_height)
+}
+
 fn render_pause_menu(
   d: &mut RaylibDrawHandle,
   selected_option: usize,
+  (map_name, run_time): (&str, f32),
+  message: Option<&str>,
+  prompts: &ButtonPrompts,
   screen_width: i32,
   screen_height: i32,
 ) {
-  // Draw semi-transparent overlay
-  d.draw_rectangle(0, 0, screen_width, screen_height, Color::new(0, 0, 0, 180));
+  // The game behind is dimmed and blurred by the pause screen's backdrop
+  let (menu_x, menu_y, menu_width, menu_height) = pause_menu_panel(screen_width, screen_height);
   
-  // Calculate menu position (center of screen)
-  let menu_width = 300;
-  let menu_height = 200;
-  let menu_x = (screen_width - menu_width) / 2;
-  let menu_y = (screen_height - menu_height) / 2;
-  
-  // Draw menu background
-  d.draw_rectangle(menu_x, menu_y, menu_width, menu_height, Color::new(40, 40, 40, 240));
+  // Draw menu background, see-through enough for the blur to show
+  d.draw_rectangle(menu_x, menu_y, menu_width, menu_height, Color::new(40, 40, 40, 190));
   d.draw_rectangle_lines(menu_x, menu_y, menu_width, menu_height, Color::WHITE);
   
   // Draw title
   let title = "GAME PAUSED";
   let title_width = 24 * title.len() as i32 / 2; // Approximate text width
-  d.draw_text(title, menu_x + (menu_width - title_width) / 2, menu_y + 30, 24, Color::WHITE);
+  d.draw_text(title, menu_x + (menu_width - title_width) / 2, menu_y + 20, 24, Color::WHITE);
+
+  // Current objective and elapsed time
+  d.draw_text(&format!("Map: {}", map_name), menu_x + 20, menu_y + 60, 16, Color::LIGHTGRAY);
+  d.draw_text("Objective: Find the exit", menu_x + 20, menu_y + 80, 16, Color::GOLD);
+  d.draw_text(&format!("Time: {}", format_time(run_time)), menu_x + 20, menu_y + 100, 16, Color::LIGHTGRAY);
   
   // Draw menu options
-  let options = ["Resume", "Back to Main Menu"];
-  for (i, option) in options.iter().enumerate() {
-    let y_pos = menu_y + 80 + (i as i32 * 40);
+  for (i, option) in PAUSE_OPTIONS.iter().enumerate() {
+    let y_pos = menu_y + 140 + (i as i32 * 40);
     let color = if i == selected_option { Color::YELLOW } else { Color::WHITE };
     let prefix = if i == selected_option { "> " } else { "  " };
     
@@ -730,43 +1572,270 @@ fn render_pause_menu(
     let text_width = 20 * text.len() as i32 / 2; // Approximate text width
     d.draw_text(&text, menu_x + (menu_width - text_width) / 2, y_pos, 20, color);
   }
+
+  // Result of the last save
+  if let Some(message) = message {
+    let message_width = 16 * message.len() as i32 / 2; // Approximate text width
+    d.draw_text(message, menu_x + (menu_width - message_width) / 2, menu_y + 385, 16, Color::GOLD);
+  }
   
   // Draw controls
-  d.draw_text("Use UP/DOWN or W/S to navigate", menu_x + 20, menu_y + menu_height - 40, 14, Color::LIGHTGRAY);
-  d.draw_text("Press ENTER or SPACE to select", menu_x + 20, menu_y + menu_height - 20, 14, Color::LIGHTGRAY);
+  prompts.draw_row_centered(d, &[(Prompt::Navigate, "Navigate"), (Prompt::Confirm, "Select"), (Prompt::Back, "Resume")],
+                            menu_x + menu_width / 2, menu_y + menu_height - 40, 22, Color::LIGHTGRAY);
+}
+
+fn render_settings_menu(
+  d: &mut RaylibDrawHandle,
+  selected_option: usize,
+  widgets: &[Widget],
+  prompts: &ButtonPrompts,
+  screen_width: i32,
+  screen_height: i32,
+) {
+  // Opened from the pause menu, whose overlay already dims the game
+  let layout = settings_layout(screen_width, screen_height);
+  let menu_x = layout.x;
+  let menu_y = layout.y - 65;
+  let menu_width = layout.width;
+  let menu_height = 120 + SETTINGS_ROWS * layout.row_height;
+
+  d.draw_rectangle(menu_x, menu_y, menu_width, menu_height, Color::new(40, 40, 40, 240));
+  d.draw_rectangle_lines(menu_x, menu_y, menu_width, menu_height, Color::WHITE);
+
+  let title = "SETTINGS";
+  let title_width = 24 * title.len() as i32 / 2; // Approximate text width
+  d.draw_text(title, menu_x + (menu_width - title_width) / 2, menu_y + 20, 24, Color::WHITE);
+
+  ui::draw(d, widgets, selected_option, &layout);
+
+  prompts.draw_row_centered(d, &[(Prompt::Navigate, "Navigate"), (Prompt::Adjust, "Change"), (Prompt::Back, "Back")],
+                            menu_x + menu_width / 2, menu_y + menu_height - 36, 22, Color::LIGHTGRAY);
+}
+
+fn mods_layout(rows: usize, screen_width: i32, screen_height: i32) -> Layout {
+  let menu_width = 520;
+  let menu_height = 160 + rows as i32 * 40;
+  Layout {
+    x: (screen_width - menu_width) / 2,
+    y: (screen_height - menu_height) / 2 + 65,
+    width: menu_width,
+    row_height: 40,
+  }
+}
+
+// Mod list over the settings menu: one switch per mod folder, then Back
+fn render_mods_menu(
+  d: &mut RaylibDrawHandle,
+  selected_option: usize,
+  widgets: &[Widget],
+  needs_restart: bool,
+  prompts: &ButtonPrompts,
+  screen_width: i32,
+  screen_height: i32,
+) {
+  let layout = mods_layout(widgets.len(), screen_width, screen_height);
+  let menu_x = layout.x;
+  let menu_y = layout.y - 65;
+  let menu_width = layout.width;
+  let menu_height = 160 + widgets.len() as i32 * 40;
+
+  d.draw_rectangle(0, 0, screen_width, screen_height, Color::new(0, 0, 0, 120));
+  d.draw_rectangle(menu_x, menu_y, menu_width, menu_height, Color::new(40, 40, 40, 240));
+  d.draw_rectangle_lines(menu_x, menu_y, menu_width, menu_height, Color::WHITE);
+
+  let title = "MODS";
+  let title_width = 24 * title.len() as i32 / 2; // Approximate text width
+  d.draw_text(title, menu_x + (menu_width - title_width) / 2, menu_y + 20, 24, Color::WHITE);
+
+  ui::draw(d, widgets, selected_option, &layout);
+
+  let note_y = layout.y + widgets.len() as i32 * layout.row_height + 10;
+  if widgets.len() == 1 {
+    d.draw_text("No mods found. Add mod folders to mods/", menu_x + 20, note_y, 18, Color::LIGHTGRAY);
+  } else if needs_restart {
+    d.draw_text("Restart the game to apply changes", menu_x + 20, note_y, 18, Color::GOLD);
+  }
+
+  prompts.draw_row_centered(d, &[(Prompt::Navigate, "Navigate"), (Prompt::Confirm, "Toggle"), (Prompt::Back, "Back")],
+                            menu_x + menu_width / 2, menu_y + menu_height - 36, 22, Color::LIGHTGRAY);
+}
+
+// Controls list over the settings or pause menu: every action with its keys and buttons, the
+// binding being edited boxed, and anything that clashes in red
+fn render_controls_menu(
+  d: &mut RaylibDrawHandle,
+  rows: &[ControlsRow],
+  cursor: (usize, usize), // Selected row, and 0 for the keyboard column or 1 for the controller
+  waiting: bool,
+  prompts: &ButtonPrompts,
+  screen_width: i32,
+  screen_height: i32,
+) {
+  let row_height = 30;
+  let menu_width = 760;
+  let menu_height = 200 + (rows.len() as i32 + 2) * row_height;
+  let menu_x = (screen_width - menu_width) / 2;
+  let menu_y = (screen_height - menu_height) / 2;
+  let columns_x = [menu_x + 280, menu_x + 520];
+  let (selected_option, column) = cursor;
+  let clash_color = Color::new(255, 90, 90, 255);
+
+  d.draw_rectangle(0, 0, screen_width, screen_height, Color::new(0, 0, 0, 120));
+  d.draw_rectangle(menu_x, menu_y, menu_width, menu_height, Color::new(40, 40, 40, 240));
+  d.draw_rectangle_lines(menu_x, menu_y, menu_width, menu_height, Color::WHITE);
+
+  let title = "CONTROLS";
+  let title_width = 24 * title.len() as i32 / 2; // Approximate text width
+  d.draw_text(title, menu_x + (menu_width - title_width) / 2, menu_y + 20, 24, Color::WHITE);
+  d.draw_text("Keyboard", columns_x[0], menu_y + 60, 18, Color::GRAY);
+  d.draw_text("Controller", columns_x[1], menu_y + 60, 18, Color::GRAY);
+
+  let list_y = menu_y + 90;
+  for (i, row) in rows.iter().enumerate() {
+    let y_pos = list_y + i as i32 * row_height;
+    let is_selected = i == selected_option;
+    if is_selected {
+      d.draw_rectangle(menu_x + 10, y_pos - 5, menu_width - 20, row_height, Color::new(80, 80, 120, 200));
+    }
+    d.draw_text(row.label, menu_x + 25, y_pos, 20, if is_selected { Color::YELLOW } else { Color::WHITE });
+
+    for (c, (text, clash)) in [(&row.keys, row.key_clash), (&row.buttons, row.button_clash)].into_iter().enumerate() {
+      let editing = is_selected && c == column;
+      let text = match (editing && waiting, c) {
+        (true, 0) => "Press a key...",
+        (true, _) => "Press a button...",
+        _ => text.as_str(),
+      };
+      let color = if clash { clash_color } else if editing { Color::YELLOW } else { Color::LIGHTGRAY };
+      if editing {
+        d.draw_rectangle_lines(columns_x[c] - 8, y_pos - 4, 220, row_height - 2, Color::YELLOW);
+      }
+      d.draw_text(text, columns_x[c], y_pos, 18, color);
+    }
+  }
+
+  for (i, option) in ["Reset to Preset", "Back"].iter().enumerate() {
+    let index = rows.len() + i;
+    let y_pos = list_y + index as i32 * row_height + 10;
+    let is_selected = index == selected_option;
+    if is_selected {
+      d.draw_rectangle(menu_x + 10, y_pos - 5, menu_width - 20, row_height, Color::new(80, 80, 120, 200));
+    }
+    d.draw_text(option, menu_x + 25, y_pos, 20, if is_selected { Color::YELLOW } else { Color::WHITE });
+  }
+
+  if let Some(note) = rows.get(selected_option).and_then(|row| row.note.as_deref()) {
+    d.draw_text(note, menu_x + 25, menu_y + menu_height - 80, 16, clash_color);
+  }
+
+  let hints: &[(Prompt, &str)] = if waiting {
+    &[(Prompt::Pause, "Cancel")]
+  } else {
+    &[(Prompt::Navigate, "Navigate"), (Prompt::Adjust, "Column"), (Prompt::Confirm, "Rebind"), (Prompt::Back, "Back")]
+  };
+  prompts.draw_row_centered(d, hints, menu_x + menu_width / 2, menu_y + menu_height - 46, 22, Color::LIGHTGRAY);
+}
+
+// On-screen keyboard over a menu, with the prompts for whichever device is in use
+fn render_name_entry(d: &mut RaylibDrawHandle, keyboard: &OnScreenKeyboard, prompts: &ButtonPrompts, screen_width: i32, screen_height: i32) {
+  d.draw_rectangle(0, 0, screen_width, screen_height, Color::new(0, 0, 0, 120));
+  keyboard.draw(d, screen_width, screen_height);
+
+  let prompts_y = (screen_height + KEYBOARD_HEIGHT) / 2 - 46;
+  if prompts.device == InputDevice::Keyboard {
+    prompts.draw_row_centered(d, &[(Prompt::Done, "Done"), (Prompt::Erase, "Delete"), (Prompt::Back, "Cancel")],
+                              screen_width / 2, prompts_y, 22, Color::LIGHTGRAY);
+  } else {
+    prompts.draw_row_centered(d, &[(Prompt::Confirm, "Type"), (Prompt::Erase, "Delete"), (Prompt::Done, "Done"), (Prompt::Back, "Cancel")],
+                              screen_width / 2, prompts_y, 22, Color::LIGHTGRAY);
+  }
+}
+
+// Save slots over the start screen: a title and a details line for each
+fn render_load_menu(
+  d: &mut RaylibDrawHandle,
+  selected_option: usize,
+  rows: &[(String, String, bool)],
+  error: Option<&str>,
+  prompts: &ButtonPrompts,
+  screen_width: i32,
+  screen_height: i32,
+) {
+  let menu_width = 640;
+  let menu_height = 180 + rows.len() as i32 * 60;
+  let menu_x = (screen_width - menu_width) / 2;
+  let menu_y = (screen_height - menu_height) / 2;
+
+  d.draw_rectangle(0, 0, screen_width, screen_height, Color::new(0, 0, 0, 120));
+  d.draw_rectangle(menu_x, menu_y, menu_width, menu_height, Color::new(40, 40, 40, 240));
+  d.draw_rectangle_lines(menu_x, menu_y, menu_width, menu_height, Color::WHITE);
+
+  let title = "LOAD GAME";
+  let title_width = 24 * title.len() as i32 / 2; // Approximate text width
+  d.draw_text(title, menu_x + (menu_width - title_width) / 2, menu_y + 20, 24, Color::WHITE);
+
+  for (i, (slot_title, detail, loadable)) in rows.iter().enumerate() {
+    let y_pos = menu_y + 70 + i as i32 * 60;
+    let is_selected = i == selected_option;
+    if is_selected {
+      d.draw_rectangle(menu_x + 10, y_pos - 6, menu_width - 20, 54, Color::new(80, 80, 120, 200));
+    }
+    let title_color = if is_selected { Color::YELLOW } else { Color::WHITE };
+    d.draw_text(slot_title, menu_x + 25, y_pos, 20, title_color);
+    d.draw_text(detail, menu_x + 25, y_pos + 24, 14, if *loadable { Color::LIGHTGRAY } else { Color::GRAY });
+  }
+
+  if let Some(error) = error {
+    d.draw_text(error, menu_x + 25, menu_y + menu_height - 80, 16, Color::RED);
+  }
+
+  prompts.draw_row_centered(d, &[(Prompt::Navigate, "Navigate"), (Prompt::Confirm, "Load"), (Prompt::Back, "Back")],
+                            menu_x + menu_width / 2, menu_y + menu_height - 46, 22, Color::LIGHTGRAY);
 }
 
 fn render_start_screen(
   d: &mut RaylibDrawHandle,
-  selected_map: usize,
+  menu: &StartMenu,
+  prompts: &ButtonPrompts,
+  visualizer: &MusicVisualizer,
   screen_width: i32,
   screen_height: i32,
-  gamepad_available: bool,
-  gamepad_name: &str,
 ) {
-  // Simple background color
-  d.clear_background(Color::new(30, 30, 70, 255));
+  let StartMenu { selected_map, daily_date, daily_best, run_best_floor, meta_currency, maps_cleared, ng_plus, player_name, map_stats, .. } = *menu;
+  let (best_grades, load_errors) = (&menu.best_grades, &menu.load_errors);
+  // Darken the animated map fly-through so the menu stays readable
+  d.draw_rectangle(0, 0, screen_width, screen_height, Color::new(30, 30, 70, 150));
+
+  let player = format!("Player: {}", player_name);
+  d.draw_text(&player, screen_width - 20 * player.len() as i32 / 2 - 30, 30, 20, Color::LIGHTGRAY);
   
+  // Short screens (handhelds, a large UI scale) pull the header up and squeeze the cards
+  let compact = screen_height < 1000;
+  let (title_y, subtitle_y, start_y) = if compact { (30, 90, 140) } else { (100, 180, 280) };
+  let card_step = ((screen_height - start_y - 110) / (HUB_MAP_INDEX as i32 + 1)).clamp(60, 120);
+  let card_height = card_step * 5 / 6;
+  let text_offset = ((card_height - 46) / 2).min(15); // Name, then the map stats 30 pixels below
+
   // Title
   let title = "RAYCASTER DUNGEON";
-  let title_size = 48;
+  let title_size = (48.0 * visualizer.pulse()) as i32; // Swells with the music
   let title_width = title.len() as i32 * title_size / 2;
-  d.draw_text(title, (screen_width - title_width) / 2, 100, title_size, Color::WHITE);
+  let title_center = Vector2::new(screen_width as f32 / 2.0, (title_y + 24) as f32);
+  visualizer.draw_sparks(d, title_center);
+  d.draw_text(title, (screen_width - title_width) / 2, title_y + (48 - title_size) / 2, title_size, Color::WHITE);
   
   let subtitle = "Select Your Map";
   let subtitle_size = 24;
   let subtitle_width = subtitle.len() as i32 * subtitle_size / 3;
-  d.draw_text(subtitle, (screen_width - subtitle_width) / 2, 180, subtitle_size, Color::LIGHTGRAY);
+  d.draw_text(subtitle, (screen_width - subtitle_width) / 2, subtitle_y, subtitle_size, Color::LIGHTGRAY);
   
   // Map selection
-  let start_y = 280;
   for (i, map) in AVAILABLE_MAPS.iter().enumerate() {
-    let y_pos = start_y + (i as i32 * 120);
+    let y_pos = start_y + i as i32 * card_step;
     let is_selected = i == selected_map;
     
     // Map card background
     let card_width = 600;
-    let card_height = 100;
     let card_x = (screen_width - card_width) / 2;
     
     let bg_color = if is_selected {
@@ -781,34 +1850,138 @@ fn render_start_screen(
     
     // Map name
     let name_color = if is_selected { Color::YELLOW } else { Color::WHITE };
-    d.draw_text(&format!("{}. {}", i + 1, map.name), card_x + 20, y_pos + 15, 24, name_color);
+    d.draw_text(&format!("{}. {}", i + 1, map.name), card_x + 20, y_pos + text_offset, 24, name_color);
     
-    // Map description
-    d.draw_text(map.description, card_x + 20, y_pos + 45, 16, Color::LIGHTGRAY);
+    // Size, enemies and par time, worked out when the maps were scanned
+    if let Some(stats) = map_stats.get(i) {
+      let mut summary = stats.summary();
+      let (best, ng_plus_best) = best_grades.get(i).copied().unwrap_or((None, None));
+      if let Some(grade) = best {
+        summary.push_str(&format!(" | Best: {}", grade.letter()));
+      }
+      if let Some(grade) = ng_plus_best {
+        summary.push_str(&format!(" | NG+: {}", grade.letter()));
+      }
+      d.draw_text(&summary, card_x + 20, y_pos + text_offset + 30, 14, Color::LIGHTGRAY);
+    }
     
     // Selection indicator
     if is_selected {
-      d.draw_text(">", card_x - 30, y_pos + 25, 30, Color::YELLOW);
+      d.draw_text(">", card_x - 30, y_pos + card_height / 4, 30, Color::YELLOW);
     }
   }
+
+  // Generated modes follow the hand-made maps
+  let best_text = match daily_best {
+    Some(best) => format!("Same maze for everyone today | Your best: {}", format_time(best)),
+    None => "Same maze for everyone today | No time set yet".to_string(),
+  };
+  draw_mode_card(d, DAILY_MAP_INDEX, selected_map, start_y + DAILY_MAP_INDEX as i32 * card_step, card_height, screen_width,
+                 (&format!("Daily Dungeon ({})", daily_date), &best_text));
+  draw_mode_card(d, RUN_MAP_INDEX, selected_map, start_y + RUN_MAP_INDEX as i32 * card_step, card_height, screen_width,
+                 ("Rogue-lite Run", &format!("Descend until you fall | Deepest floor: {} | Marks: {}", run_best_floor, meta_currency)));
+  let (hub_title, hub_detail) = match ng_plus {
+    Some(true) => ("Campaign Hub: New Game Plus", format!("Tougher enemies, new placements | Maps cleared: {} of {} | Left/Right: Normal", maps_cleared, AVAILABLE_MAPS.len())),
+    Some(false) => ("Campaign Hub", format!("Walk through portals to each map | Maps cleared: {} of {} | Left/Right: NG+", maps_cleared, AVAILABLE_MAPS.len())),
+    None => ("Campaign Hub", format!("Walk through portals to each map | Maps cleared: {} of {}", maps_cleared, AVAILABLE_MAPS.len())),
+  };
+  draw_mode_card(d, HUB_MAP_INDEX, selected_map, start_y + HUB_MAP_INDEX as i32 * card_step, card_height, screen_width,
+                 (hub_title, &hub_detail));
   
   // Instructions
-  let instructions_y = start_y + (AVAILABLE_MAPS.len() as i32 * 120) + 50;
+  let instructions_y = start_y + (HUB_MAP_INDEX + 1) as i32 * card_step + if compact { 10 } else { 50 };
   
   // Controller status
-  if gamepad_available {
+  if let Some(gamepad_name) = &menu.gamepad {
     d.draw_text(&format!("Controller: {}", gamepad_name), (screen_width - 300) / 2, instructions_y, 18, Color::GREEN);
-    d.draw_text("D-Pad: Navigate | X/A: Select | ESC: Quit", (screen_width - 400) / 2, instructions_y + 25, 16, Color::LIGHTGRAY);
   } else {
-    d.draw_text("Controller: Not Connected", (screen_width - 300) / 2, instructions_y, 18, Color::GRAY);
-  }
-  
-  d.draw_text("Keyboard: UP/DOWN arrows to navigate", (screen_width - 350) / 2, instructions_y + 50, 16, Color::LIGHTGRAY);
-  d.draw_text("Press ENTER to start | ESC to quit", (screen_width - 300) / 2, instructions_y + 70, 16, Color::LIGHTGRAY);
+    d.draw_text("Controller: Not Connected", (screen_width - 300) / 2, instructions_y, 18, Color::GRAY);
+  }
+
+  // Only the keyboard can quit from here
+  if prompts.device == InputDevice::Keyboard {
+    prompts.draw_row_centered(d, &[(Prompt::Navigate, "Navigate"), (Prompt::Confirm, "Start"), (Prompt::Load, "Load Game"),
+                                   (Prompt::Back, "Quit")],
+                              screen_width / 2, instructions_y + 35, 26, Color::LIGHTGRAY);
+  } else {
+    prompts.draw_row_centered(d, &[(Prompt::Navigate, "Navigate"), (Prompt::Confirm, "Start"), (Prompt::Load, "Load Game")],
+                              screen_width / 2, instructions_y + 35, 26, Color::LIGHTGRAY);
+  }
+
+  // Unusable profile or settings files, so nobody wonders where their progress went
+  for (i, error) in load_errors.iter().enumerate() {
+    let error_width = error.len() as i32 * 18 / 2;
+    d.draw_text(error, (screen_width - error_width) / 2, instructions_y + 80 + i as i32 * 26, 18, Color::RED);
+  }
+}
+
+// Card for a generated mode or the hub, same look as the map cards with a crimson accent
+fn draw_mode_card(
+  d: &mut RaylibDrawHandle,
+  index: usize,
+  selected_map: usize,
+  y_pos: i32,
+  card_height: i32,
+  screen_width: i32,
+  (title, detail): (&str, &str),
+) {
+  let is_selected = selected_map == index;
+  let card_width = 600;
+  let card_x = (screen_width - card_width) / 2;
+  let text_offset = ((card_height - 46) / 2).min(15);
+
+  d.draw_rectangle(card_x, y_pos, card_width, card_height,
+                   if is_selected { Color::new(120, 50, 50, 200) } else { Color::new(60, 30, 30, 150) });
+  d.draw_rectangle_lines(card_x, y_pos, card_width, card_height,
+                         if is_selected { Color::YELLOW } else { Color::new(150, 60, 60, 255) });
+  d.draw_text(&format!("{}. {}", index + 1, title), card_x + 20, y_pos + text_offset, 24,
+              if is_selected { Color::YELLOW } else { Color::WHITE });
+  d.draw_text(detail, card_x + 20, y_pos + text_offset + 30, 16, Color::LIGHTGRAY);
+
+  if is_selected {
+    d.draw_text(">", card_x - 30, y_pos + card_height / 4, 30, Color::YELLOW);
+  }
+}
+
+fn render_splash_screen(
+  d: &mut RaylibDrawHandle,
+  elapsed: f32,
+  screen_width: i32,
+  screen_height: i32,
+) {
+  d.clear_background(Color::BLACK);
+
+  // Fade in, hold, then fade out
+  let fade = if elapsed < 0.8 {
+    elapsed / 0.8
+  } else if elapsed > SPLASH_DURATION - 0.8 {
+    ((SPLASH_DURATION - elapsed) / 0.8).max(0.0)
+  } else {
+    1.0
+  };
+  let alpha = (fade * 255.0) as u8;
+
+  // Slowly growing blood-red title
+  let title = "RAYCASTER DUNGEON";
+  let title_size = (56.0 + elapsed * 4.0) as i32;
+  let title_width = title.len() as i32 * title_size / 2;
+  let title_y = screen_height / 2 - title_size;
+  d.draw_text(title, (screen_width - title_width) / 2 + 3, title_y + 3, title_size, Color::new(0, 0, 0, alpha));
+  d.draw_text(title, (screen_width - title_width) / 2, title_y, title_size, Color::new(200, 30, 30, alpha));
+
+  let subtitle = "Proyecto 1 - Graficas por Computadora";
+  let subtitle_size = 20;
+  let subtitle_width = subtitle.len() as i32 * subtitle_size / 2;
+  d.draw_text(subtitle, (screen_width - subtitle_width) / 2, title_y + title_size + 30, subtitle_size, Color::new(200, 200, 200, alpha));
+
+  d.draw_text("Press any key to skip", screen_width - 240, screen_height - 40, 16, Color::new(120, 120, 120, alpha));
 }
 
 fn render_victory_screen(
   d: &mut RaylibDrawHandle,
+  campaign: bool, // Continuing goes back to the hub instead of the map selection
+  grading: Option<&(Grading, bool)>, // Grade of a finished map file, and whether it beat the best
+  prompts: &ButtonPrompts,
   screen_width: i32,
   screen_height: i32,
 ) {
@@ -863,21 +2036,42 @@ fn render_victory_screen(
   let stats_box_y = stats_y + 40;
   let glow_intensity = ((time * 6.0).sin() * 0.3 + 0.7 * 255.0) as u8;
   
-  d.draw_rectangle_lines(stats_box_x - 2, stats_box_y - 2, 404, 84, Color::new(255, 215, 0, glow_intensity));
-  d.draw_rectangle_lines(stats_box_x - 1, stats_box_y - 1, 402, 82, Color::new(255, 255, 0, glow_intensity));
-  d.draw_rectangle(stats_box_x, stats_box_y, 400, 80, Color::new(0, 0, 0, 150));
+  // Graded maps get a taller box with the grade and its four marks
+  let stats_box_height = if grading.is_some() { 130 } else { 80 };
+  d.draw_rectangle_lines(stats_box_x - 2, stats_box_y - 2, 404, stats_box_height + 4, Color::new(255, 215, 0, glow_intensity));
+  d.draw_rectangle_lines(stats_box_x - 1, stats_box_y - 1, 402, stats_box_height + 2, Color::new(255, 255, 0, glow_intensity));
+  d.draw_rectangle(stats_box_x, stats_box_y, 400, stats_box_height, Color::new(0, 0, 0, 150));
   
-  d.draw_text("🏆 DUNGEON EXPLORER 🏆", stats_box_x + 50, stats_box_y + 15, 18, Color::new(255, 215, 0, 255));
-  d.draw_text("You've mastered the labyrinth!", stats_box_x + 70, stats_box_y + 45, 16, Color::new(200, 200, 200, 255));
+  match grading {
+    Some((grading, best)) => {
+      d.draw_text(grading.grade.letter(), stats_box_x + 40, stats_box_y + 20, 80, Color::new(255, 215, 0, 255));
+      if *best {
+        d.draw_text("New best!", stats_box_x + 25, stats_box_y + 105, 16, Color::new(255, 255, 200, 255));
+      }
+      // Met marks in green, near misses in yellow, the rest in grey
+      for (i, mark) in grading.marks.iter().enumerate() {
+        let color = match mark.points {
+          p if p >= 1.0 => Color::new(120, 230, 120, 255),
+          p if p > 0.0 => Color::new(240, 220, 100, 255),
+          _ => Color::new(170, 170, 170, 255),
+        };
+        let line = format!("{:<8} {} / {}", mark.name, mark.value, mark.target);
+        d.draw_text(&line, stats_box_x + 150, stats_box_y + 15 + i as i32 * 26, 18, color);
+      }
+    }
+    None => {
+      d.draw_text("🏆 DUNGEON EXPLORER 🏆", stats_box_x + 50, stats_box_y + 15, 18, Color::new(255, 215, 0, 255));
+      d.draw_text("You've mastered the labyrinth!", stats_box_x + 70, stats_box_y + 45, 16, Color::new(200, 200, 200, 255));
+    }
+  }
   
   // Instructions with gentle pulsing
   let instruction_alpha = ((time * 2.0).sin() * 0.3 + 0.7 * 255.0) as u8;
   let instructions_y = screen_height - 150;
   
-  d.draw_text("Press ENTER to return to map selection", (screen_width - 420) / 2, instructions_y, 18, 
-             Color::new(255, 255, 255, instruction_alpha));
-  d.draw_text("Press ESC to quit", (screen_width - 180) / 2, instructions_y + 30, 18, 
-             Color::new(200, 200, 200, instruction_alpha));
+  let next = if campaign { "Return to the hub" } else { "Return to map selection" };
+  prompts.draw_row_centered(d, &[(Prompt::Confirm, next), (Prompt::Back, "Quit")],
+                            screen_width / 2, instructions_y, 28, Color::new(255, 255, 255, instruction_alpha));
   
   // Sparkle effects
   for i in 0..10 {
@@ -890,57 +2084,200 @@ fn render_victory_screen(
   }
 }
 
+fn render_upgrade_screen(
+  d: &mut RaylibDrawHandle,
+  run: &RunState,
+  prompts: &ButtonPrompts,
+  screen_width: i32,
+  screen_height: i32,
+) {
+  d.clear_background(Color::new(15, 5, 5, 255));
+
+  let title = format!("FLOOR {} CLEARED", run.floor);
+  let title_width = 40 * title.len() as i32 / 2; // Approximate text width
+  d.draw_text(&title, (screen_width - title_width) / 2, 120, 40, Color::GOLD);
+
+  let subtitle = "Choose an upgrade for the descent";
+  let subtitle_width = 22 * subtitle.len() as i32 / 2;
+  d.draw_text(subtitle, (screen_width - subtitle_width) / 2, 180, 22, Color::LIGHTGRAY);
+
+  // Three cards side by side
+  let card_width = 320;
+  let card_height = 200;
+  let spacing = 40;
+  let total_width = card_width * run.offered.len() as i32 + spacing * (run.offered.len() as i32 - 1);
+  let start_x = (screen_width - total_width) / 2;
+  let card_y = screen_height / 2 - card_height / 2;
+
+  for (i, upgrade) in run.offered.iter().enumerate() {
+    let card_x = start_x + i as i32 * (card_width + spacing);
+    let selected = i == run.selected;
+
+    d.draw_rectangle(card_x, card_y, card_width, card_height,
+                     if selected { Color::new(90, 30, 30, 240) } else { Color::new(40, 20, 20, 220) });
+    d.draw_rectangle_lines(card_x, card_y, card_width, card_height, if selected { Color::YELLOW } else { Color::GRAY });
+
+    d.draw_text(upgrade.name(), card_x + 20, card_y + 30, 26, if selected { Color::YELLOW } else { Color::WHITE });
+    d.draw_text(upgrade.description(), card_x + 20, card_y + 90, 18, Color::LIGHTGRAY);
+  }
+
+  if !run.taken.is_empty() {
+    let taken: Vec<&str> = run.taken.iter().map(|upgrade| upgrade.name()).collect();
+    d.draw_text(&format!("Upgrades so far: {}", taken.join(", ")), start_x, card_y + card_height + 40, 16, Color::GRAY);
+  }
+
+  prompts.draw_row_centered(d, &[(Prompt::Adjust, "Choose"), (Prompt::Confirm, "Take upgrade")],
+                            screen_width / 2, screen_height - 120, 28, Color::LIGHTGRAY);
+}
+
+fn render_shop_screen(
+  d: &mut RaylibDrawHandle,
+  selected_option: usize,
+  inventory: &Inventory,
+  depth: u32,
+  prompts: &ButtonPrompts,
+  screen_width: i32,
+  screen_height: i32,
+) {
+  d.clear_background(Color::new(15, 10, 5, 255));
+
+  let title = "WANDERING MERCHANT";
+  let title_width = 40 * title.len() as i32 / 2; // Approximate text width
+  d.draw_text(title, (screen_width - title_width) / 2, 100, 40, Color::GOLD);
+  d.draw_text(&format!("Coins: {}   Potions: {}   Traps: {}/{}   Weapon: {}", inventory.coins, inventory.potions, inventory.caltrops,
+                       inventory.turrets, inventory.weapon.name),
+              (screen_width - 700) / 2, 170, 20, Color::LIGHTGRAY);
+
+  let row_width = 700;
+  let row_x = (screen_width - row_width) / 2;
+  let start_y = 240;
+
+  for (i, item) in SHOP_ITEMS.iter().enumerate() {
+    let y_pos = start_y + i as i32 * 80;
+    let selected = i == selected_option;
+    let price = item.price(depth);
+    let affordable = inventory.coins >= price && item.available(inventory);
+
+    d.draw_rectangle(row_x, y_pos, row_width, 75, if selected { Color::new(80, 60, 20, 230) } else { Color::new(40, 30, 15, 200) });
+    d.draw_rectangle_lines(row_x, y_pos, row_width, 75, if selected { Color::YELLOW } else { Color::GRAY });
+
+    let name_color = if !item.available(inventory) { Color::GRAY } else if selected { Color::YELLOW } else { Color::WHITE };
+    d.draw_text(item.name(), row_x + 20, y_pos + 12, 24, name_color);
+    d.draw_text(&item.description(), row_x + 20, y_pos + 45, 16, Color::LIGHTGRAY);
+
+    let price_text = if item.available(inventory) { format!("{} coins", price) } else { "Owned".to_string() };
+    d.draw_text(&price_text, row_x + row_width - 150, y_pos + 25, 22, if affordable { Color::GOLD } else { Color::new(150, 60, 60, 255) });
+  }
+
+  // Leave option
+  let descend_y = start_y + SHOP_ITEMS.len() as i32 * 80 + 20;
+  let descend_selected = selected_option == SHOP_ITEMS.len();
+  let descend_text = format!("{}Descend to floor {}", if descend_selected { "> " } else { "  " }, depth);
+  d.draw_text(&descend_text, row_x + 20, descend_y, 26, if descend_selected { Color::YELLOW } else { Color::WHITE });
+
+  prompts.draw_row_centered(d, &[(Prompt::Navigate, "Browse"), (Prompt::Confirm, "Buy"), (Prompt::Back, "Descend")],
+                            screen_width / 2, screen_height - 120, 28, Color::LIGHTGRAY);
+}
+
+fn render_game_over_screen(
+  d: &mut RaylibDrawHandle,
+  run: Option<&RunState>,
+  meta_currency: u32,
+  prompts: &ButtonPrompts,
+  screen_width: i32,
+  screen_height: i32,
+) {
+  d.clear_background(Color::new(20, 0, 0, 255));
+
+  let title = "YOU DIED";
+  let title_size = 60;
+  let title_width = title.len() as i32 * title_size / 2;
+  d.draw_text(title, (screen_width - title_width) / 2 + 3, 203, title_size, Color::BLACK);
+  d.draw_text(title, (screen_width - title_width) / 2, 200, title_size, Color::new(200, 20, 20, 255));
+
+  match run {
+    Some(run) => {
+      let summary = format!("Run ended on floor {} | {} floors cleared", run.floor, run.floors_cleared());
+      let earned = format!("Earned {} marks (total: {})", run.currency_earned(), meta_currency);
+      d.draw_text(&summary, (screen_width - 20 * summary.len() as i32 / 2) / 2, 320, 20, Color::WHITE);
+      d.draw_text(&earned, (screen_width - 20 * earned.len() as i32 / 2) / 2, 350, 20, Color::GOLD);
+      prompts.draw_row_centered(d, &[(Prompt::Confirm, "Menu")], screen_width / 2, screen_height - 150, 28, Color::LIGHTGRAY);
+    }
+    None => {
+      prompts.draw_row_centered(d, &[(Prompt::Confirm, "Retry"), (Prompt::Back, "Menu")], screen_width / 2, screen_height - 150, 28, Color::LIGHTGRAY);
+    }
+  }
+}
+
 fn check_goal_reached(player: &Player, maze: &Maze, block_size: usize) -> bool {
-  let player_maze_x = (player.pos.x / block_size as f32) as usize;
-  let player_maze_y = (player.pos.y / block_size as f32) as usize;
-  
-  // Check current cell and adjacent cells within threshold
-  let threshold = 1; // Check cells within 1 block radius
-  
-  for dy in -(threshold as i32)..=(threshold as i32) {
-    for dx in -(threshold as i32)..=(threshold as i32) {
-      let check_x = player_maze_x as i32 + dx;
-      let check_y = player_maze_y as i32 + dy;
-      
-      if check_x >= 0 && check_y >= 0 {
-        let check_x_usize = check_x as usize;
-        let check_y_usize = check_y as usize;
-        
-        if check_y_usize < maze.len() && check_x_usize < maze[0].len() {
-          if maze[check_y_usize][check_x_usize] == 'g' {
-            // Calculate distance to goal center
-            let goal_center_x = check_x_usize as f32 * block_size as f32 + block_size as f32 / 2.0;
-            let goal_center_y = check_y_usize as f32 * block_size as f32 + block_size as f32 / 2.0;
-            
-            let distance = ((player.pos.x - goal_center_x).powi(2) + (player.pos.y - goal_center_y).powi(2)).sqrt();
-            let detection_radius = block_size as f32 * 0.7; // 70% of block size
-            
-            println!("Found goal at ({}, {}), distance: {}, threshold: {}", check_x_usize, check_y_usize, distance, detection_radius);
-            
-            if distance <= detection_radius {
-              return true;
-            }
-          }
-        }
-      }
+  sim::goal_reached(maze, player.pos.into(), block_size)
+}
+
+// Find the center of the goal cell closest to a world position
+fn find_nearest_goal(maze: &Maze, block_size: usize, pos: Vector2) -> Option<Vector2> {
+  let mut nearest: Option<(Vector2, f32)> = None;
+
+  for (col_index, row_index, cell) in maze.cells() {
+    if cell != Cell::Goal {
+      continue;
+    }
+    let center = Vector2::new(
+      col_index as f32 * block_size as f32 + block_size as f32 / 2.0,
+      row_index as f32 * block_size as f32 + block_size as f32 / 2.0,
+    );
+    let distance = ((center.x - pos.x).powi(2) + (center.y - pos.y).powi(2)).sqrt();
+    if nearest.is_none_or(|(_, best)| distance < best) {
+      nearest = Some((center, distance));
     }
   }
-  
-  false
+
+  nearest.map(|(center, _)| center)
+}
+
+// HUD hint pointing the player toward the exit when it is in view or close by
+fn goal_prompt(framebuffer: &mut Framebuffer, player: &Player, maze: &Maze, block_size: usize) -> Option<String> {
+  let center_ray = cast_ray(framebuffer, maze, player, player.a, block_size, false);
+  if center_ray.impact == Cell::Goal {
+    if center_ray.distance < block_size as f32 * 3.0 {
+      return Some("The exit gate! Walk into it to escape".to_string());
+    }
+    return Some("Exit ahead - follow the golden light".to_string());
+  }
+
+  let goal = find_nearest_goal(maze, block_size, player.pos)?;
+  let dx = goal.x - player.pos.x;
+  let dy = goal.y - player.pos.y;
+  let cells_away = (dx * dx + dy * dy).sqrt() / block_size as f32;
+  if cells_away > 4.0 {
+    return None;
+  }
+
+  // Normalize angle difference to [-PI, PI]
+  let mut angle_diff = dy.atan2(dx) - player.a;
+  while angle_diff > PI {
+    angle_diff -= 2.0 * PI;
+  }
+  while angle_diff < -PI {
+    angle_diff += 2.0 * PI;
+  }
+
+  let direction = if angle_diff.abs() < PI / 4.0 {
+    "ahead"
+  } else if angle_diff.abs() > PI * 3.0 / 4.0 {
+    "behind you"
+  } else if angle_diff > 0.0 {
+    "to your right"
+  } else {
+    "to your left"
+  };
+
+  Some(format!("The exit is close... {} ({:.0} cells)", direction, cells_away))
 }
 
 // Helper function to check if a position is valid for enemy placement
 fn is_valid_enemy_position(x: f32, y: f32, maze: &Maze, block_size: usize) -> bool {
-  let maze_x = (x / block_size as f32) as usize;
-  let maze_y = (y / block_size as f32) as usize;
-  
-  // Check bounds
-  if maze_y >= maze.len() || maze_x >= maze[0].len() {
-    return false;
-  }
-  
-  // Check if position is not a wall
-  maze[maze_y][maze_x] == ' '
+  // Out of bounds or a wall is not valid
+  maze.at_world(x, y, block_size) == Some(Cell::Empty)
 }
 
 // Helper function to find a valid position near a given coordinate
@@ -964,19 +2301,24 @@ fn find_valid_position_near(x: f32, y: f32, maze: &Maze, block_size: usize, max_
   }
   
   // If no valid position found, return a default safe position
-  Vector2::new(150.0, 150.0)
+  Vector2::new(block_size as f32 * 1.5, block_size as f32 * 1.5)
 }
 
-// Function to create enemies in valid positions for a given maze
-fn create_enemies_for_maze(maze: &Maze, block_size: usize) -> Vec<Enemy> {
+// Function to create enemies in valid positions for a given maze, or where a New Game Plus spawn table puts them
+fn create_enemies_for_maze(maze: &Maze, block_size: usize, table: Option<&SpawnTable>, rng: &mut Rng) -> Vec<Enemy> {
   let mut enemies = Vec::new();
+
+  // Every enemy uses the 'a' sprite sheet, except shield bearers
+  let defs = load_enemy_defs(ENEMY_DEFS_PATH);
+  let def = defs.get(&'a').copied().unwrap_or(EnemyDef::default_for('a'));
+  let shield_def = defs.get(&SHIELD_SHEET).copied().unwrap_or(EnemyDef::default_for(SHIELD_SHEET));
   
   // Calculate maze dimensions in world coordinates
-  let maze_width = maze[0].len() as f32 * block_size as f32;
-  let maze_height = maze.len() as f32 * block_size as f32;
+  let maze_width = maze.width() as f32 * block_size as f32;
+  let maze_height = maze.height() as f32 * block_size as f32;
   
-  println!("Creating enemies for maze: {}x{} blocks, {}x{} world coords", 
-           maze[0].len(), maze.len(), maze_width, maze_height);
+  log!("Creating enemies for maze: {}x{} blocks, {}x{} world coords", 
+           maze.width(), maze.height(), maze_width, maze_height);
   
   // Create enemy positions based on maze proportions rather than fixed coordinates
   let mut enemy_configs = Vec::new();
@@ -987,7 +2329,7 @@ fn create_enemies_for_maze(maze: &Maze, block_size: usize) -> Vec<Enemy> {
     let base_y = (i as f32 + 1.0) * maze_height / 6.0;
     
     // Horizontal patrol
-    let patrol_distance = (maze_width * 0.15).min(200.0); // 15% of map width or 200px max
+    let patrol_distance = (maze_width * 0.15).min(block_size as f32 * 2.0); // 15% of map width or 2 cells max
     enemy_configs.push((
       base_x,
       base_y,
@@ -996,7 +2338,7 @@ fn create_enemies_for_maze(maze: &Maze, block_size: usize) -> Vec<Enemy> {
     ));
     
     // Vertical patrol
-    let vertical_patrol_distance = (maze_height * 0.15).min(200.0);
+    let vertical_patrol_distance = (maze_height * 0.15).min(block_size as f32 * 2.0);
     enemy_configs.push((
       base_x + maze_width * 0.1,
       base_y + maze_height * 0.1,
@@ -1020,16 +2362,20 @@ fn create_enemies_for_maze(maze: &Maze, block_size: usize) -> Vec<Enemy> {
     ));
   }
   
-  // Chasing enemies - positioned strategically
+  // Chasing enemies - positioned strategically, the first cloaked and the last two behind shields
   let chase_positions = [
     (0.2, 0.4), (0.8, 0.6), (0.6, 0.2), (0.4, 0.8), (0.5, 0.5)
   ];
   
-  for (x_ratio, y_ratio) in chase_positions.iter() {
+  for (i, (x_ratio, y_ratio)) in chase_positions.iter().enumerate() {
     enemy_configs.push((
       x_ratio * maze_width,
       y_ratio * maze_height,
-      "chase",
+      match i {
+        0 => "cloaked",
+        3.. => "shield",
+        _ => "chase",
+      },
       None
     ));
   }
@@ -1049,13 +2395,18 @@ fn create_enemies_for_maze(maze: &Maze, block_size: usize) -> Vec<Enemy> {
     ));
   }
   
+  // A New Game Plus spawn table replaces the whole placement, see ngplus.rs
+  if let Some(table) = table.filter(|table| !table.enemies.is_empty()) {
+    enemy_configs = table.enemies.iter().map(|entry| (entry.pos.x, entry.pos.y, entry.kind, entry.patrol_end.map(|end| (end.x, end.y)))).collect();
+  }
+
   // Create enemies from configurations
   for (i, (x, y, enemy_type, patrol_end)) in enemy_configs.iter().enumerate() {
     let valid_pos = find_valid_position_near(*x, *y, maze, block_size, 5.0); // Increased search radius
     
     // Verify the position is actually valid before creating enemy
     if !is_valid_enemy_position(valid_pos.x, valid_pos.y, maze, block_size) {
-      println!("Warning: Could not find valid position for enemy {} at ({}, {})", i, x, y);
+      log!("Warning: Could not find valid position for enemy {} at ({}, {})", i, x, y);
       continue;
     }
     
@@ -1064,41 +2415,222 @@ fn create_enemies_for_maze(maze: &Maze, block_size: usize) -> Vec<Enemy> {
         if let Some((end_x, end_y)) = patrol_end {
           let valid_end = find_valid_position_near(*end_x, *end_y, maze, block_size, 5.0);
           if is_valid_enemy_position(valid_end.x, valid_end.y, maze, block_size) {
-            enemies.push(Enemy::new_patrol(valid_pos.x, valid_pos.y, 'a', valid_end.x, valid_end.y));
-            println!("Created patrol enemy at ({:.1}, {:.1}) -> ({:.1}, {:.1})", 
+            enemies.push(Enemy::new_patrol(valid_pos.x, valid_pos.y, def, valid_end.x, valid_end.y));
+            log!("Created patrol enemy at ({:.1}, {:.1}) -> ({:.1}, {:.1})", 
                      valid_pos.x, valid_pos.y, valid_end.x, valid_end.y);
           } else {
-            println!("Warning: Could not find valid end position for patrol enemy {}", i);
+            log!("Warning: Could not find valid end position for patrol enemy {}", i);
           }
         }
       }
       &"wander" => {
-        let wander_radius = (maze_width.min(maze_height) * 0.1).max(50.0).min(120.0); // Adaptive radius
-        enemies.push(Enemy::new_wander(valid_pos.x, valid_pos.y, 'a', wander_radius));
-        println!("Created wandering enemy at ({:.1}, {:.1}) with radius {:.1}", 
+        let wander_radius = (maze_width.min(maze_height) * 0.1).clamp(block_size as f32 * 0.5, block_size as f32 * 1.2); // Adaptive radius
+        enemies.push(Enemy::new_wander(valid_pos.x, valid_pos.y, def, wander_radius));
+        log!("Created wandering enemy at ({:.1}, {:.1}) with radius {:.1}", 
                  valid_pos.x, valid_pos.y, wander_radius);
       }
       &"chase" => {
-        enemies.push(Enemy::new_chase(valid_pos.x, valid_pos.y, 'a'));
-        println!("Created chase enemy at ({:.1}, {:.1})", valid_pos.x, valid_pos.y);
+        enemies.push(Enemy::new_chase(valid_pos.x, valid_pos.y, def));
+        log!("Created chase enemy at ({:.1}, {:.1})", valid_pos.x, valid_pos.y);
+      }
+      &"cloaked" => {
+        enemies.push(Enemy::new_cloaked(valid_pos.x, valid_pos.y, def));
+        log!("Created cloaked enemy at ({:.1}, {:.1})", valid_pos.x, valid_pos.y);
+      }
+      &"shield" => {
+        enemies.push(Enemy::new_shield_bearer(valid_pos.x, valid_pos.y, shield_def));
+        log!("Created shield bearer at ({:.1}, {:.1})", valid_pos.x, valid_pos.y);
       }
       &"guard" => {
-        enemies.push(Enemy::new(valid_pos.x, valid_pos.y, 'a'));
-        println!("Created guard enemy at ({:.1}, {:.1})", valid_pos.x, valid_pos.y);
+        enemies.push(Enemy::new(valid_pos.x, valid_pos.y, def));
+        log!("Created guard enemy at ({:.1}, {:.1})", valid_pos.x, valid_pos.y);
       }
       _ => {}
     }
   }
   
-  println!("Total enemies created: {}", enemies.len());
+  // Ambush spawns hold the enemies that keep an arena locked
+  for (x, y, cell) in maze.cells() {
+    if cell == Cell::AmbushSpawn {
+      let mut enemy = Enemy::new_chase(
+        (x as f32 + 0.5) * block_size as f32,
+        (y as f32 + 0.5) * block_size as f32,
+        def,
+      );
+      enemy.arena = true;
+      enemies.push(enemy);
+      log!("Created arena enemy at cell ({}, {})", x, y);
+    }
+  }
+  
+  // Each enemy wanders on its own stream of the map's generator
+  for enemy in enemies.iter_mut() {
+    enemy.rng = rng.fork();
+  }
+  
+  log!("Total enemies created: {}", enemies.len());
   enemies
 }
 
+// Load a map by index, placing the player at its spawn and creating fresh enemies.
+// Generated maps are seeded by their date or run floor, hand-made maps use `seed`.
+// The world takes on the map's block size, generated maps use the default.
+// A map file that cannot be read is an error for the caller to show.
+fn load_map(world: &mut World, map_index: usize, run: &RunState, seed: u64) -> Result<(), String> {
+  world.seed = if map_index == DAILY_MAP_INDEX {
+    daily_seed(today())
+  } else if map_index == RUN_MAP_INDEX {
+    run.floor_seed()
+  } else {
+    seed
+  };
+  let mut rng = Rng::new(world.seed);
+  let stress = world.stress.filter(|_| map_index == STRESS_MAP_INDEX);
+  world.quest = None; // Only hand-made maps have quest chains
+
+  let data = if map_index == DAILY_MAP_INDEX {
+    world.hints = HintSystem::new(); // Generated mazes have no hints or reverb zones
+    world.reverb_areas.clear();
+    let data = generate_daily_maze();
+    world.pickups = scatter_coins(&data.maze, data.block_size, world.seed, 8);
+    data
+  } else if map_index == RUN_MAP_INDEX {
+    world.hints = HintSystem::new();
+    world.reverb_areas.clear();
+    let (width, height) = run.floor_size();
+    log!("Generating run floor {} ({}x{})", run.floor, width, height);
+    let data = maze_data_from(generate_maze(width, height, world.seed), DEFAULT_BLOCK_SIZE);
+    world.pickups = scatter_coins(&data.maze, data.block_size, world.seed, 6 + run.floor as usize * 2);
+    data
+  } else if let Some(stress) = stress {
+    world.hints = HintSystem::new();
+    world.reverb_areas.clear();
+    log!("Generating stress map ({}x{}, {} enemies)", stress.width, stress.height, stress.enemies);
+    let data = maze_data_from(stress.build(world.seed), DEFAULT_BLOCK_SIZE);
+    world.pickups = Vec::new();
+    data
+  } else {
+    let filename = map_filename(map_index).unwrap_or(AVAILABLE_MAPS[0].filename);
+    let data = load_maze_with_player(filename)?;
+    world.hints.load(filename);
+    world.reverb_areas = reverb_areas(&load_map_meta(filename));
+    world.quest = QuestChain::from_meta(&load_map_meta(filename));
+    world.pickups = coins_from_maze(&data.maze, data.block_size);
+    data
+  };
+  let block_size = data.block_size;
+  world.block_size = block_size;
+  // New Game Plus plays the campaign maps from their second spawn table, if they have one
+  let ng_plus_table = AVAILABLE_MAPS.get(map_index).filter(|_| world.new_game_plus)
+    .map(|map| SpawnTable::from_meta(&load_map_meta(map.filename), block_size));
+  if let Some(table) = ng_plus_table.as_ref().filter(|table| !table.coins.is_empty()) {
+    world.pickups = table.coin_pickups();
+  }
+  world.portals = if map_index == HUB_MAP_INDEX { portals(&load_map_meta(HUB_MAP), &campaign_maps()) } else { Vec::new() };
+
+  let player = &mut world.player;
+  player.pos = data.player_start;
+  player.a = PI / 3.0;
+  player.is_attacking = false;
+  player.attack_timer = 0.0;
+  player.attack_cooldown = 0.0;
+  player.health = player.max_health;
+  player.status.clear();
+
+  // Create fresh enemies for the new maze, the hub is a safe room
+  world.enemies = if map_index == HUB_MAP_INDEX {
+    Vec::new()
+  } else if let Some(stress) = stress {
+    stress.spawn_enemies(&data.maze, block_size, &mut rng)
+  } else {
+    create_enemies_for_maze(&data.maze, block_size, ng_plus_table.as_ref(), &mut rng)
+  };
+  if ng_plus_table.is_some() {
+    toughen(&mut world.enemies);
+  }
+  // Hand-made maps can split their enemies into factions
+  if let Some(filename) = map_filename(map_index).filter(|_| stress.is_none()) {
+    assign_factions(&mut world.enemies, &faction_areas(&load_map_meta(filename)), block_size);
+  }
+  world.rng = rng.fork();
+  world.flasks = if map_index == HUB_MAP_INDEX { Vec::new() } else { scatter_flasks(&data.maze, block_size, &mut rng.fork()) };
+  if let Some(table) = ng_plus_table.as_ref().filter(|table| !table.flasks.is_empty()) {
+    world.flasks = table.flasks.clone();
+  }
+  let weather = map_filename(map_index).filter(|_| stress.is_none()).map_or(Vec::new(), |filename| weather_areas(&load_map_meta(filename)));
+  world.weather = Weather::new(weather, rng.fork());
+  world.props = map_filename(map_index).filter(|_| stress.is_none()).map_or(Vec::new(), |filename| props_from_meta(&load_map_meta(filename), block_size));
+  world.swarms = if let Some(stress) = stress {
+    stress.spawn_swarms(&data.maze, block_size, &mut rng.fork())
+  } else {
+    map_filename(map_index).map_or(Vec::new(), |filename| swarms_from_meta(&load_map_meta(filename), block_size, &mut rng.fork()))
+  };
+  world.arena = Arena::from_maze(&data.maze);
+  let zones = map_filename(map_index).filter(|_| stress.is_none()).map_or(Vec::new(), |filename| zones_from_meta(&load_map_meta(filename), &data.maze));
+  world.zones = Zones::new(zones);
+  world.revive = Revive::new();
+  world.dying = false;
+  world.clear_visits();
+  world.tally = Tally::default();
+  world.footprints.clear();
+  world.deployables.clear();
+  world.projectiles.clear();
+  world.markers.clear();
+  world.remember_original(&data.maze);
+  world.maze_data = Some(data);
+  Ok(())
+}
+
+// Screen tint while standing in water or swamp
+fn water_tint(floor: Cell) -> Color {
+  match floor {
+    Cell::Swamp => Color::new(60, 90, 30, 255),
+    _ => Color::new(20, 70, 140, 255),
+  }
+}
+
+const VOLUME_STEP: f32 = 0.05;
+
+// Moves a volume by whole steps, snapping to the 5% grid
+fn step_volume(volume: f32, steps: i32) -> f32 {
+  (((volume / VOLUME_STEP).round() + steps as f32) * VOLUME_STEP).clamp(0.0, 1.0)
+}
+
+// Format seconds as mm:ss.t for the run timer
+fn format_time(seconds: f32) -> String {
+  let minutes = (seconds / 60.0) as u32;
+  let remaining = seconds - minutes as f32 * 60.0;
+  format!("{:02}:{:04.1}", minutes, remaining)
+}
+
 fn main() {
+  crash::install_panic_hook();
+
+  // Map authoring tools run without opening a window
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args.first().map(String::as_str) == Some("--convert") {
+    std::process::exit(convert::run(&args[1..]));
+  }
+  if args.first().map(String::as_str) == Some("--report") {
+    std::process::exit(report::run(&args[1..]));
+  }
+
+  let options = match LaunchOptions::parse(&args) {
+    Ok(options) => options,
+    Err(e) => {
+      elog!("Error: {}", e);
+      elog!("{}", cli::USAGE);
+      std::process::exit(2);
+    }
+  };
+  if options.help {
+    log!("{}", cli::USAGE);
+    return;
+  }
+
   // Use your actual screen resolution
-  let mut window_width = 1980;
-  let mut window_height = 1200;
-  let block_size = 100;
+  let mut window_width = options.width.unwrap_or(1980);
+  let mut window_height = options.height.unwrap_or(1200);
 
   let (mut window, raylib_thread) = raylib::init()
     .size(window_width, window_height)
@@ -1112,91 +2644,104 @@ fn main() {
   window.set_exit_key(None);
 
   // Start in fullscreen mode and get the actual screen dimensions
-  window.toggle_fullscreen();
-  
-  // Wait a frame for fullscreen to take effect
-  std::thread::sleep(std::time::Duration::from_millis(100));
-  
-  // Check what raylib reports vs what we know is correct
-  let reported_width = window.get_screen_width();
-  let reported_height = window.get_screen_height();
-  
-  println!("Your actual screen: 1980x1200");
-  println!("Raylib reports: {}x{}", reported_width, reported_height);
-  
-  // Use the correct screen dimensions
-  window_width = 1980;
-  window_height = 1200;
+  if !options.windowed {
+    window.toggle_fullscreen();
+    
+    // Wait a frame for fullscreen to take effect
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    
+    // Check what raylib reports vs what we know is correct
+    let reported_width = window.get_screen_width();
+    let reported_height = window.get_screen_height();
+    
+    log!("Your actual screen: {}x{}", window_width, window_height);
+    log!("Raylib reports: {}x{}", reported_width, reported_height);
+    
+    // Use the correct screen dimensions
+    window_width = options.width.unwrap_or(1980);
+    window_height = options.height.unwrap_or(1200);
+  }
 
   let mut framebuffer = Framebuffer::new(window_width as u32, window_height as u32);
   framebuffer.set_background_color(Color::new(50, 50, 100, 255));
 
-  // Game state variables
-  let mut game_state = GameState::StartScreen;
-  let mut selected_map = 0;
-  
-  // Game variables (will be initialized when map is selected)
-  let mut maze_data: Option<MazeData> = None;
-  let mut player = Player::new(
-    Vector2::new(150.0, 150.0), // Temporary default
-    PI / 3.0,
-    PI / 3.0,
-    0.01,
+  // Map, player and enemies; the map is loaded when one is selected
+  let mut world = World::new(
+    Player::new(
+      Vector2::new(150.0, 150.0), // Temporary default
+      PI / 3.0,
+      view_fov(window_width as u32, window_height as u32),
+      0.01,
+    ),
+    DEFAULT_BLOCK_SIZE,
   );
-
-  // Initialize empty enemy list - enemies will be created when map is loaded
-  let mut enemies: Vec<Enemy> = Vec::new();
+  world.stress = options.stress;
 
   // Start with cursor enabled for menu navigation
   window.enable_cursor();
 
   // Initialize texture cache once
+  // Mods are picked before anything is loaded, so their files replace the base ones
+  let mods = ModList::scan();
+  mods.activate();
+
   let texture_cache = TextureManager::new(&mut window, &raylib_thread);
+  let prompts = ButtonPrompts::new(&mut window, &raylib_thread);
+
+  // Player profile and the Daily Dungeon date it was launched on
+  let profile = Profile::load(PROFILE_PATH);
+  let daily_date = date_label(today());
+  let selected_map = if options.stress.is_some() { STRESS_MAP_INDEX } else { options.map.unwrap_or(0) };
 
   // Initialize audio system
   let audio_device = match RaylibAudio::init_audio_device() {
     Ok(audio) => Some(audio),
     Err(e) => {
-      eprintln!("Warning: Could not initialize audio device: {:?}", e);
+      elog!("Warning: Could not initialize audio device: {:?}", e);
       None
     }
   };
 
   // Load all background music tracks
-  let mut music_tracks: Vec<Option<Music>> = vec![None, None, None];
+  let mut music_tracks: Vec<Option<Music>> = vec![None, None, None, None, None, None, None, None];
+  let mut music_paths: Vec<Option<String>> = vec![None; music_tracks.len()]; // For the menu visualizer
   if let Some(ref audio) = audio_device {
     // Load music for each map
     let music_files = [
+      "assets/sounds/music/Gats.mp3",          // Tutorial
       "assets/sounds/music/blood_guts.mp3",    // Map 1
       "assets/sounds/music/behelit.mp3",   // Map 2
-      "assets/sounds/music/ghosts.mp3" // Map 3
+      "assets/sounds/music/ghosts.mp3", // Map 3
+      "assets/sounds/music/Gats.mp3",   // Daily Dungeon
+      "assets/sounds/music/blood_guts.mp3", // Rogue-lite run
+      "assets/sounds/music/ghosts.mp3", // Campaign hub
+      "assets/sounds/music/behelit.mp3" // Stress test
     ];
     
     for (i, music_file) in music_files.iter().enumerate() {
-      match audio.new_music(music_file) {
+      let path = mods::resolve(music_file);
+      match audio.new_music(&path) {
         Ok(music) => {
           music_tracks[i] = Some(music);
-          println!("Successfully loaded music track {}: {}", i + 1, music_file);
+          music_paths[i] = Some(path);
+          log!("Successfully loaded music track {}: {}", i + 1, music_file);
         }
         Err(e) => {
-          eprintln!("Warning: Could not load music track {}: {:?}", i + 1, e);
+          elog!("Warning: Could not load music track {}: {:?}", i + 1, e);
         }
       }
     }
   }
 
-  // Initialize audio manager
-  let mut audio_manager = AudioManager::new();
-
   // Load walking sound
   let walking_sound = if let Some(ref audio) = audio_device {
-    match audio.new_sound("assets/sounds/walk.mp3") {
+    match audio.new_sound(&mods::resolve("assets/sounds/walk.mp3")) {
       Ok(sound) => {
-        println!("Successfully loaded walking sound");
+        log!("Successfully loaded walking sound");
         Some(sound)
       }
       Err(e) => {
-        eprintln!("Warning: Could not load walking sound: {:?}", e);
+        elog!("Warning: Could not load walking sound: {:?}", e);
         None
       }
     }
@@ -1206,13 +2751,13 @@ fn main() {
 
   // Load combat sounds
   let mut sword_sound = if let Some(ref audio) = audio_device {
-    match audio.new_sound("assets/sounds/sword_sound.mp3") {
+    match audio.new_sound(&mods::resolve("assets/sounds/sword_sound.mp3")) {
       Ok(sound) => {
-        println!("Successfully loaded sword sound");
+        log!("Successfully loaded sword sound");
         Some(sound)
       }
       Err(e) => {
-        eprintln!("Warning: Could not load sword sound: {:?}", e);
+        elog!("Warning: Could not load sword sound: {:?}", e);
         None
       }
     }
@@ -1221,13 +2766,13 @@ fn main() {
   };
 
   let mut hit_sound = if let Some(ref audio) = audio_device {
-    match audio.new_sound("assets/sounds/splat.mp3") {
+    match audio.new_sound(&mods::resolve("assets/sounds/splat.mp3")) {
       Ok(sound) => {
-        println!("Successfully loaded hit sound");
+        log!("Successfully loaded hit sound");
         Some(sound)
       }
       Err(e) => {
-        eprintln!("Warning: Could not load hit sound: {:?}", e);
+        elog!("Warning: Could not load hit sound: {:?}", e);
         None
       }
     }
@@ -1236,13 +2781,39 @@ fn main() {
   };
 
   let mut death_sound = if let Some(ref audio) = audio_device {
-    match audio.new_sound("assets/sounds/death.mp3") {
+    match audio.new_sound(&mods::resolve("assets/sounds/death.mp3")) {
       Ok(sound) => {
-        println!("Successfully loaded death sound");
+        log!("Successfully loaded death sound");
         Some(sound)
       }
       Err(e) => {
-        eprintln!("Warning: Could not load death sound: {:?}", e);
+        elog!("Warning: Could not load death sound: {:?}", e);
+        None
+      }
+    }
+  } else {
+    None
+  };
+
+  // Arena gate cue, a second copy of the death sound so its pitch can differ
+  let mut gate_sound = if let Some(ref audio) = audio_device {
+    match audio.new_sound(&mods::resolve("assets/sounds/death.mp3")) {
+      Ok(sound) => Some(sound),
+      Err(e) => {
+        elog!("Warning: Could not load gate sound: {:?}", e);
+        None
+      }
+    }
+  } else {
+    None
+  };
+
+  // Critical hit cue, a second copy of the hit sound so its pitch can differ
+  let mut critical_sound = if let Some(ref audio) = audio_device {
+    match audio.new_sound(&mods::resolve("assets/sounds/splat.mp3")) {
+      Ok(sound) => Some(sound),
+      Err(e) => {
+        elog!("Warning: Could not load critical hit sound: {:?}", e);
         None
       }
     }
@@ -1250,13 +2821,104 @@ fn main() {
     None
   };
 
+  // Radio chatter: every line keeps its subtitle, even when its clip can't be played
+  let chatter: Vec<ChatterLine> = load_chatter_defs(CHATTER_PATH)
+    .into_iter()
+    .map(|def| {
+      let sound = match (audio_device.as_ref(), def.clip.as_ref()) {
+        (Some(audio), Some(clip)) => audio.new_sound(clip)
+          .inspect_err(|e| elog!("Warning: Could not load chatter clip {}: {:?}", clip, e))
+          .ok(),
+        _ => None,
+      };
+      ChatterLine { def, sound }
+    })
+    .collect();
+
+  // The audio manager keeps the music, footsteps and chatter, which it updates every frame
+  let audio_manager = AudioManager::new(music_tracks, walking_sound, chatter);
+
   // Setup combat sounds
   audio_manager.setup_combat_sounds(&mut sword_sound, &mut hit_sound, &mut death_sound);
+  audio_manager.setup_gate_sound(&mut gate_sound);
+  audio_manager.setup_critical_sound(&mut critical_sound);
+
+  let mut game = Game {
+    framebuffer,
+    window_width,
+    window_height,
+    time: 0.0,
+    selected_map,
+    world,
+    texture_cache,
+    prompts,
+    run_state: RunState::new(options.seed.unwrap_or_else(seed_from_clock)),
+    fixed_seed: options.seed,
+    time_scale: TimeScale::new(options.timescale.unwrap_or(1.0)),
+    splits: SplitTimer::new(&split_key(selected_map, &daily_date), &profile),
+    profile,
+    daily_date,
+    demo: load_demo(),
+    recording: Replay::new("", 0),
+    run_time: 0.0,
+    resumed: false,
+    campaign: false,
+    new_game_plus: false,
+    audio_manager,
+    visualizer: MusicVisualizer::new(music_paths),
+    sword_sound,
+    hit_sound,
+    death_sound,
+    gate_sound,
+    critical_sound,
+    show_minimap: false,
+    minimap_zoom: 8,
+    minimap_default_zoom: 8,
+    minimap_size: 200,
+    performance_mode: false,
+    post_processor: PostProcessor::new(),
+    settings_error: None,
+    map_error: None,
+    muted: None,
+    mods,
+    ui_scale: 1.0,
+    render_scale: 1.0,
+    companion: false,
+    shadows: true,
+    difficulty: Difficulty::Normal,
+    gyro: Gyro::new(),
+    gyro_aim: false,
+    gyro_sensitivity: 1.0,
+    actions: ActionMap::new(InputPreset::Default),
+    hud_pillarbox: false,
+    aim_assist: 0.5,
+    ai_overlay: false,
+  };
+  match Settings::load(SETTINGS_PATH) {
+    Ok(settings) => game.apply_settings(&settings),
+    Err(e) => {
+      let message = format!("Settings {} {}. Defaults are used and changes will not be saved.", SETTINGS_PATH, e);
+      elog!("Error: {}", message);
+      game.settings_error = Some(message);
+    }
+  }
+  if options.mute {
+    game.mute();
+  }
 
-  let mut show_minimap = false; // Toggle for minimap display
-  let mut selected_menu_option = 0; // 0 = Resume, 1 = Back to Main Menu  
-  let mut performance_mode = false; // Toggle for performance vs quality
-  let mut music_enabled = true; // Toggle for music on/off
+  // The start screen sits at the bottom of the stack with the intro splash over it
+  let mut screens = ScreenStack::new(Box::new(StartScreen::new(&game)));
+  if options.skip_menu || options.stress.is_some() {
+    // Straight into the selected map; leaving it still returns to the start screen
+    match game.start_new_game(&mut window) {
+      Ok(()) => screens.push(Box::new(PlayingScreen::new())),
+      Err(e) => {
+        game.map_failed(&mut window, e);
+      }
+    }
+  } else {
+    screens.push(Box::new(SplashScreen::new()));
+  }
 
   window.set_target_fps(60); // Set target FPS to 60 for consistent performance
 
@@ -1267,423 +2929,46 @@ fn main() {
     let current_time = unsafe { raylib::ffi::GetTime() } as f32;
     let delta_time = current_time - last_time;
     last_time = current_time;
+    game.time = current_time;
 
-    // Update audio stream every frame for current music track
-    if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-      music.update_stream();
-      
-      // Handle looping manually - restart if music finished and should be playing
-      if music_enabled && !music.is_stream_playing() && music.get_time_played() > 0.0 {
-        music.play_stream();
-        music.set_volume(audio_manager.get_music_volume());
-      }
-    }
+    // Music streaming, fades and footsteps, all in one place
+    let listener = Listener {
+      moving: game.world.player.take_moved(),
+      floor: game.world.floor(),
+      reverb: game.world.reverb_zone(),
+    };
+    game.audio_manager.update(delta_time, &listener);
 
-    // Always ensure framebuffer matches current window size
+    // Always ensure framebuffer matches current window size and render scale
     let current_width = window.get_screen_width();
     let current_height = window.get_screen_height();
-    if current_width != window_width || current_height != window_height || 
-       framebuffer.width != current_width as u32 || framebuffer.height != current_height as u32 {
-      window_width = current_width;
-      window_height = current_height;
-      framebuffer = Framebuffer::new(window_width as u32, window_height as u32);
-      framebuffer.set_background_color(Color::new(50, 50, 100, 255));
+    if current_width != game.window_width || current_height != game.window_height || 
+       (game.framebuffer.width, game.framebuffer.height) != game.framebuffer_size() {
+      game.window_width = current_width;
+      game.window_height = current_height;
+      game.resize_framebuffer();
     }
 
     // Toggle fullscreen with F11 (works in all states)
     if window.is_key_pressed(KeyboardKey::KEY_F11) {
       window.toggle_fullscreen();
-      window_width = window.get_screen_width();
-      window_height = window.get_screen_height();
-      framebuffer = Framebuffer::new(window_width as u32, window_height as u32);
-      framebuffer.set_background_color(Color::new(50, 50, 100, 255));
+      game.window_width = window.get_screen_width();
+      game.window_height = window.get_screen_height();
+      game.resize_framebuffer();
     }
 
-    match game_state {
-      GameState::StartScreen => {
-        // Check for controller connection
-        let gamepad_available = window.is_gamepad_available(0);
-        
-        // Handle start screen input - Controller takes priority
-        let mut input_handled = false;
-        
-        if gamepad_available {
-          // D-Pad navigation
-          if window.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP) && selected_map > 0 {
-            selected_map -= 1;
-            input_handled = true;
-          }
-          if window.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN) && selected_map < AVAILABLE_MAPS.len() - 1 {
-            selected_map += 1;
-            input_handled = true;
-          }
-          
-          // X button (Cross) or A button to confirm
-          if window.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN) ||
-             window.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT) {
-            // Load selected map
-            let map_info = &AVAILABLE_MAPS[selected_map];
-            maze_data = Some(load_maze_with_player(map_info.filename, block_size));
-            if let Some(ref data) = maze_data {
-              player.pos = data.player_start;
-              // Create fresh enemies for the new maze
-              enemies = create_enemies_for_maze(&data.maze, block_size);
-            }
-            game_state = GameState::Playing;
-            window.disable_cursor();
-            window.set_mouse_position(Vector2::new(window_width as f32 / 2.0, window_height as f32 / 2.0));
-            
-            // Start background music when entering the game
-            if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-              if music_enabled {
-                music.play_stream();
-                music.set_volume(audio_manager.get_music_volume());
-              }
-            }
-            input_handled = true;
-          }
-        }
-        
-        // Keyboard fallback if no controller input
-        if !input_handled {
-          if window.is_key_pressed(KeyboardKey::KEY_UP) && selected_map > 0 {
-            selected_map -= 1;
-          }
-          if window.is_key_pressed(KeyboardKey::KEY_DOWN) && selected_map < AVAILABLE_MAPS.len() - 1 {
-            selected_map += 1;
-          }
-          
-          if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
-            // Load selected map
-            let map_info = &AVAILABLE_MAPS[selected_map];
-            maze_data = Some(load_maze_with_player(map_info.filename, block_size));
-            if let Some(ref data) = maze_data {
-              player.pos = data.player_start;
-              // Create fresh enemies for the new maze
-              enemies = create_enemies_for_maze(&data.maze, block_size);
-            }
-            game_state = GameState::Playing;
-            window.disable_cursor();
-            window.set_mouse_position(Vector2::new(window_width as f32 / 2.0, window_height as f32 / 2.0));
-            
-            // Start background music when entering the game
-            if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-              if music_enabled {
-                music.play_stream();
-                music.set_volume(audio_manager.get_music_volume());
-              }
-            }
-          }
-        }
-        
-        if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
-          break; // Exit game from start screen
-        }
-        
-        // Get gamepad info before rendering
-        let gamepad_name = if gamepad_available {
-          window.get_gamepad_name(0).unwrap_or("Controller".to_string())
-        } else {
-          "Not Connected".to_string()
-        };
-        
-        // Render start screen
-        let mut d = window.begin_drawing(&raylib_thread);
-        render_start_screen(&mut d, selected_map, window_width, window_height, gamepad_available, &gamepad_name);
-      }
-      
-      GameState::Playing => {
-        framebuffer.clear();
-
-        // Check for controller connection
-        let gamepad_available = window.is_gamepad_available(0);
-
-        // ESC key to pause OR controller Options button
-        if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) ||
-           (gamepad_available && window.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT)) {
-          game_state = GameState::Paused;
-          window.enable_cursor();
-          // Pause music when game is paused
-          if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-            if music_enabled && music.is_stream_playing() {
-              music.pause_stream();
-            }
-          }
-        }
-
-        // Process player input and movement
-        if let Some(ref data) = maze_data {
-          process_events(&mut player, &window, &data.maze, block_size, window_width, window_height, &audio_manager, &walking_sound, delta_time);
-          
-          // Check if player reached the goal
-          if check_goal_reached(&player, &data.maze, block_size) {
-            game_state = GameState::Victory;
-            window.enable_cursor();
-          }
-        }
-
-        // Toggle minimap with M key
-        if window.is_key_pressed(KeyboardKey::KEY_M) {
-          show_minimap = !show_minimap;
-        }
-
-        // Toggle performance mode with P key
-        if window.is_key_pressed(KeyboardKey::KEY_P) {
-          performance_mode = !performance_mode;
-        }
-
-        // Toggle music with N key
-        if window.is_key_pressed(KeyboardKey::KEY_N) {
-          music_enabled = !music_enabled;
-          if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-            if music_enabled {
-              if !music.is_stream_playing() {
-                music.play_stream();
-                music.set_volume(audio_manager.get_music_volume());
-              }
-            } else {
-              music.pause_stream();
-            }
-          }
-        }
-
-        // Volume controls
-        if window.is_key_down(KeyboardKey::KEY_EQUAL) || window.is_key_down(KeyboardKey::KEY_KP_ADD) {
-          let current_volume = audio_manager.get_music_volume();
-          let new_volume = (current_volume + 0.01).min(1.0);
-          audio_manager.set_music_volume(new_volume);
-          if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-            music.set_volume(new_volume);
-          }
-        }
-        if window.is_key_down(KeyboardKey::KEY_MINUS) || window.is_key_down(KeyboardKey::KEY_KP_SUBTRACT) {
-          let current_volume = audio_manager.get_music_volume();
-          let new_volume = (current_volume - 0.01).max(0.0);
-          audio_manager.set_music_volume(new_volume);
-          if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-            music.set_volume(new_volume);
-          }
-        }
-
-        // Render the world
-        if let Some(ref data) = maze_data {
-          render_world(&mut framebuffer, &data.maze, block_size, &player, &texture_cache, performance_mode);
-          render_enemies(&mut framebuffer, &player, &mut enemies, &texture_cache, delta_time, &data.maze, block_size);
-          
-          // Check for attack collisions
-          check_attack_collision(&mut player, &mut enemies, block_size, &audio_manager, &sword_sound, &hit_sound, &death_sound);
-        }
-
-        // Check gamepad status before rendering
-        let gamepad_available = window.is_gamepad_available(0);
-        let gamepad_name = if gamepad_available {
-          window.get_gamepad_name(0).unwrap_or("Controller".to_string())
-        } else {
-          "Not Connected".to_string()
-        };
-
-        // Create texture from framebuffer and render
-        if let Ok(framebuffer_texture) = framebuffer.get_texture(&mut window, &raylib_thread) {
-          let mut d = window.begin_drawing(&raylib_thread);
-          d.clear_background(Color::BLACK);
-          
-          d.draw_texture_ex(&framebuffer_texture, Vector2::zero(), 0.0, 1.0, Color::WHITE);
-          
-          // Render sword (always visible, with attack animation when attacking)
-          render_sword(&mut d, &player, &texture_cache, window_width, window_height);
-          
-          // Draw UI elements
-          let alive_enemies = enemies.iter().filter(|e| !e.is_dead).count();
-          
-          d.draw_text(&format!("FPS: {}", d.get_fps()), 10, 10, 20, Color::WHITE);
-          d.draw_text(&format!("Enemies: {}", alive_enemies), 10, 35, 18, Color::YELLOW);
-          
-          // Controller status
-          if gamepad_available {
-            d.draw_text(&format!("Controller: {}", gamepad_name), 10, 55, 16, Color::GREEN);
-            d.draw_text("Options: Pause | D-Pad: Move | Right Stick: Look | R2/Square: Attack", 10, 75, 14, Color::LIGHTGRAY);
-          } else {
-            d.draw_text("Controller: Not Connected", 10, 55, 16, Color::GRAY);
-          }
-          
-          d.draw_text("ESC/Options: Pause menu", 10, 95, 16, Color::WHITE);
-          d.draw_text("SPACE/E/LMB: Attack", 10, 115, 16, Color::YELLOW);
-          d.draw_text("M: Toggle minimap", 10, 135, 16, Color::WHITE);
-          d.draw_text("P: Toggle performance mode", 10, 155, 16, Color::WHITE);
-          d.draw_text("N: Toggle music", 10, 175, 16, Color::WHITE);
-          d.draw_text("+/-: Volume control", 10, 195, 16, Color::WHITE);
-          d.draw_text("F11: Toggle fullscreen", 10, 215, 16, Color::WHITE);
-          d.draw_text(&format!("Minimap: {}", if show_minimap { "ON" } else { "OFF" }), 10, 235, 16, Color::WHITE);
-          d.draw_text(&format!("Performance: {}", if performance_mode { "HIGH" } else { "QUALITY" }), 10, 255, 16, Color::WHITE);
-          d.draw_text(&format!("Music: {} (Vol: {:.0}%)", if music_enabled { "ON" } else { "OFF" }, audio_manager.get_music_volume() * 100.0), 10, 275, 16, Color::WHITE);
-          
-          // Render minimap if enabled
-          if let Some(ref data) = maze_data {
-            if show_minimap {
-              render_minimap(&mut d, &data.maze, &player, &enemies, block_size, window_width, window_height);
-            }
-          }
-        }
-      }
-      
-      GameState::Paused => {
-        // Check for controller connection
-        let gamepad_available = window.is_gamepad_available(0);
-        
-        // Handle pause menu input - Controller takes priority
-        let mut input_handled = false;
-        
-        if gamepad_available {
-          // D-Pad navigation
-          if window.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP) {
-            selected_menu_option = if selected_menu_option == 0 { 1 } else { 0 };
-            input_handled = true;
-          }
-          if window.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN) {
-            selected_menu_option = if selected_menu_option == 1 { 0 } else { 1 };
-            input_handled = true;
-          }
-
-          // X button (Cross) or A button to confirm
-          if window.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN) ||
-             window.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT) {
-            match selected_menu_option {
-              0 => {
-                // Resume game
-                game_state = GameState::Playing;
-                window.disable_cursor();
-                window.set_mouse_position(Vector2::new(window_width as f32 / 2.0, window_height as f32 / 2.0));
-                // Resume music when game resumes
-                if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-                  if music_enabled {
-                    music.resume_stream();
-                  }
-                }
-              }
-              1 => {
-                // Back to start screen
-                game_state = GameState::StartScreen;
-                maze_data = None;
-                enemies.clear(); // Clear enemies when going back to main menu
-                window.enable_cursor();
-                // Stop music when returning to main menu
-                if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-                  music.stop_stream();
-                }
-              }
-              _ => {}
-            }
-            input_handled = true;
-          }
-
-          // Options button to resume (alternative)
-          if window.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT) {
-            // Resume game
-            game_state = GameState::Playing;
-            window.disable_cursor();
-            window.set_mouse_position(Vector2::new(window_width as f32 / 2.0, window_height as f32 / 2.0));
-            // Resume music when game resumes
-            if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-              if music_enabled {
-                music.resume_stream();
-              }
-            }
-            input_handled = true;
-          }
-        }
-        
-        // Keyboard fallback if no controller input
-        if !input_handled {
-          if window.is_key_pressed(KeyboardKey::KEY_UP) || window.is_key_pressed(KeyboardKey::KEY_W) {
-            selected_menu_option = if selected_menu_option == 0 { 1 } else { 0 };
-          }
-          if window.is_key_pressed(KeyboardKey::KEY_DOWN) || window.is_key_pressed(KeyboardKey::KEY_S) {
-            selected_menu_option = if selected_menu_option == 1 { 0 } else { 1 };
-          }
-
-          if window.is_key_pressed(KeyboardKey::KEY_ENTER) || window.is_key_pressed(KeyboardKey::KEY_SPACE) {
-            match selected_menu_option {
-              0 => {
-                // Resume game
-                game_state = GameState::Playing;
-                window.disable_cursor();
-                window.set_mouse_position(Vector2::new(window_width as f32 / 2.0, window_height as f32 / 2.0));
-                // Resume music when game resumes
-                if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-                  if music_enabled {
-                    music.resume_stream();
-                  }
-                }
-              }
-              1 => {
-                // Back to start screen
-                game_state = GameState::StartScreen;
-                maze_data = None;
-                enemies.clear(); // Clear enemies when going back to main menu
-                window.enable_cursor();
-                // Stop music when returning to main menu
-                if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-                  music.stop_stream();
-                }
-              }
-              _ => {}
-            }
-          }
-
-          if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
-            // Resume game
-            game_state = GameState::Playing;
-            window.disable_cursor();
-            window.set_mouse_position(Vector2::new(window_width as f32 / 2.0, window_height as f32 / 2.0));
-            // Resume music when game resumes
-            if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-              if music_enabled {
-                music.resume_stream();
-              }
-            }
-          }
-        }
-
-        // Render paused game background
-        if let Some(ref data) = maze_data {
-          render_world(&mut framebuffer, &data.maze, block_size, &player, &texture_cache, performance_mode);
-          render_enemies(&mut framebuffer, &player, &mut enemies, &texture_cache, delta_time, &data.maze, block_size);
-        }
-
-        // Create texture from framebuffer and render with pause overlay
-        if let Ok(framebuffer_texture) = framebuffer.get_texture(&mut window, &raylib_thread) {
-          let mut d = window.begin_drawing(&raylib_thread);
-          d.clear_background(Color::BLACK);
-          
-          d.draw_texture_ex(&framebuffer_texture, Vector2::zero(), 0.0, 1.0, Color::WHITE);
-          
-          // Draw pause menu overlay
-          render_pause_menu(&mut d, selected_menu_option, window_width, window_height);
-        }
-      }
-      
-      GameState::Victory => {
-        // Handle victory screen input
-        if window.is_key_pressed(KeyboardKey::KEY_ENTER) || window.is_key_pressed(KeyboardKey::KEY_SPACE) {
-          // Back to start screen
-          game_state = GameState::StartScreen;
-          maze_data = None;
-          enemies.clear(); // Clear enemies when going back to main menu
-          window.enable_cursor();
-          // Stop music when returning to main menu
-          if let Some(ref music) = music_tracks.get(selected_map).and_then(|m| m.as_ref()) {
-            music.stop_stream();
-          }
-        }
+    game.prompts.update_device(&window);
 
-        if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
-          break; // Exit game from victory screen
-        }
+    // Where the player is, in case the frame panics
+    let current_map = game.world.maze_data.is_some().then(|| map_name(game.selected_map));
+    crash::set_context(current_map, game.world.player.pos);
 
-        // Render victory screen
-        let mut d = window.begin_drawing(&raylib_thread);
-        render_victory_screen(&mut d, window_width, window_height);
-      }
+    if !screens.update(&mut game, &mut window, delta_time) {
+      break;
     }
+    screens.draw(&mut game, &mut window, &raylib_thread, delta_time);
   }
+
+  // Quick keys change settings in game too
+  game.save_settings();
 }
\ No newline at end of file
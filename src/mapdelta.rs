@@ -0,0 +1,64 @@
+// mapdelta.rs
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::maze::Cell;
+
+// How a map differs from its file after some play: cells rewritten in game, coin cells
+// already picked up, coins dropped by enemies that are still lying around, and whether its
+// arena was cleared. Saves carry the delta of the map being played so loading puts the map
+// back the way the player left it instead of resetting it. Arena doors are not kept as
+// cells: a cleared arena opens them, one that was not cleared starts over closed.
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapDelta {
+    pub cells: BTreeMap<(usize, usize), Cell>, // Cells that no longer match the map file
+    pub collected: BTreeSet<(usize, usize)>,   // Coin cells whose coin was picked up
+    pub drops: Vec<(f32, f32, u32)>,           // x, y and value of coins enemies dropped
+    pub arena_cleared: bool,
+}
+
+impl MapDelta {
+    // Save file values, each a comma-separated list: "x:y:c" cells, "x:y" coins, "x:y:value" drops.
+    // Cells are stored by character code since map characters include ',', ':' and ' '.
+    pub fn encode(&self) -> [(&'static str, String); 4] {
+        let cells: Vec<String> = self.cells.iter().map(|((x, y), cell)| format!("{}:{}:{}", x, y, cell.to_char() as u32)).collect();
+        let collected: Vec<String> = self.collected.iter().map(|(x, y)| format!("{}:{}", x, y)).collect();
+        let drops: Vec<String> = self.drops.iter().map(|(x, y, value)| format!("{:.1}:{:.1}:{}", x, y, value)).collect();
+        [
+            ("changed_cells", cells.join(",")),
+            ("collected", collected.join(",")),
+            ("drops", drops.join(",")),
+            ("arena_cleared", self.arena_cleared.to_string()),
+        ]
+    }
+
+    // Entries that do not parse are skipped
+    pub fn decode(values: &BTreeMap<String, String>) -> Self {
+        let list = |key: &str| -> Vec<Vec<String>> {
+            values
+                .get(key)
+                .map(|value| value.split(',').filter(|item| !item.is_empty()).map(|item| item.split(':').map(str::to_string).collect()).collect())
+                .unwrap_or_default()
+        };
+
+        let mut delta = MapDelta::default();
+        for parts in list("changed_cells") {
+            if let [x, y, cell] = parts.as_slice() && let (Ok(x), Ok(y), Some(cell)) = (x.parse(), y.parse(), cell.parse().ok().and_then(char::from_u32)) {
+                delta.cells.insert((x, y), Cell::from_char(cell));
+            }
+        }
+        for parts in list("collected") {
+            if let [x, y] = parts.as_slice() && let (Ok(x), Ok(y)) = (x.parse(), y.parse()) {
+                delta.collected.insert((x, y));
+            }
+        }
+        for parts in list("drops") {
+            if let [x, y, value] = parts.as_slice() && let (Ok(x), Ok(y), Ok(value)) = (x.parse(), y.parse(), value.parse()) {
+                delta.drops.push((x, y, value));
+            }
+        }
+        delta.arena_cleared = values.get("arena_cleared").is_some_and(|value| value == "true");
+        delta
+    }
+}
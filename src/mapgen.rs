@@ -0,0 +1,118 @@
+// mapgen.rs
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::maze::{Cell, Maze};
+use crate::rng::Rng;
+
+// Same layout as maze.py: "+--+" corner/wall rows and "|  |" corridor rows, 'p' top-left and 'g' bottom-right
+pub fn generate_maze(width: usize, height: usize, seed: u64) -> Maze {
+    let mut rng = Rng::new(seed);
+
+    let mut visited = vec![vec![false; width]; height];
+    let mut horizontal = vec![vec![true; width]; height + 1]; // Wall above each cell
+    let mut vertical = vec![vec![true; width + 1]; height]; // Wall left of each cell
+
+    // Iterative recursive backtracker
+    let mut stack = vec![(rng.range(width), rng.range(height))];
+    visited[stack[0].1][stack[0].0] = true;
+
+    while let Some(&(x, y)) = stack.last() {
+        let mut neighbors = Vec::with_capacity(4);
+        if x > 0 && !visited[y][x - 1] {
+            neighbors.push((x - 1, y));
+        }
+        if x + 1 < width && !visited[y][x + 1] {
+            neighbors.push((x + 1, y));
+        }
+        if y > 0 && !visited[y - 1][x] {
+            neighbors.push((x, y - 1));
+        }
+        if y + 1 < height && !visited[y + 1][x] {
+            neighbors.push((x, y + 1));
+        }
+
+        if neighbors.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let (nx, ny) = neighbors[rng.range(neighbors.len())];
+        if nx == x {
+            horizontal[y.max(ny)][x] = false;
+        } else {
+            vertical[y][x.max(nx)] = false;
+        }
+        visited[ny][nx] = true;
+        stack.push((nx, ny));
+    }
+
+    // Same cells maze.py writes as text
+    let corner = Cell::Wall { texture: '+' };
+    let mut rows: Vec<Vec<Cell>> = Vec::with_capacity(height * 2 + 1);
+    for y in 0..=height {
+        let mut wall_row = Vec::with_capacity(width * 3 + 1);
+        for &closed in &horizontal[y] {
+            wall_row.push(corner);
+            let segment = if closed { Cell::Wall { texture: '-' } } else { Cell::Empty };
+            wall_row.push(segment);
+            wall_row.push(segment);
+        }
+        wall_row.push(corner);
+        rows.push(wall_row);
+
+        if y == height {
+            break;
+        }
+
+        let mut cell_row = Vec::with_capacity(width * 3 + 1);
+        for (x, &closed) in vertical[y].iter().enumerate() {
+            cell_row.push(if closed { Cell::Wall { texture: '|' } } else { Cell::Empty });
+            if x < width {
+                cell_row.push(Cell::Empty);
+                cell_row.push(Cell::Empty);
+            }
+        }
+        rows.push(cell_row);
+    }
+
+    let mut maze = Maze::from_rows(rows);
+    maze.set(1, 1, Cell::Spawn);
+    maze.set(maze.width() - 2, maze.height() - 2, Cell::Goal);
+
+    maze
+}
+
+// Today's date in UTC as (year, month, day)
+pub fn today() -> (i32, u32, u32) {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    civil_from_days((seconds / 86_400) as i64)
+}
+
+// Days since 1970-01-01 to a calendar date (Howard Hinnant's algorithm)
+pub fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+    (year, month, day)
+}
+
+// Everyone playing on the same day gets the same seed
+pub fn daily_seed(date: (i32, u32, u32)) -> u64 {
+    let (year, month, day) = date;
+    year as u64 * 10_000 + month as u64 * 100 + day as u64
+}
+
+// "2024-05-17", used for display and as the profile key
+pub fn date_label(date: (i32, u32, u32)) -> String {
+    format!("{:04}-{:02}-{:02}", date.0, date.1, date.2)
+}
@@ -0,0 +1,128 @@
+// mapstats.rs
+
+use std::collections::VecDeque;
+
+use crate::enemy::MovementPattern;
+use crate::maze::{Cell, Maze, MazeData, is_walkable, load_map_meta};
+use crate::rng::Rng;
+
+// What a map card on the start screen tells about a hand-made map, worked out from the
+// map itself when the maps are scanned. A map header can set its own par time in seconds:
+//   par = 75
+
+const WALK_SPEED: f32 = 6.0;   // Cells per second, the player's base move speed at 60 FPS
+const PAR_SLACK: f32 = 1.5;    // Room for turning, fighting and the odd wrong turn
+const PAR_ROUNDING: f32 = 5.0; // Estimated par times are rounded up to whole 5 seconds
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MapStats {
+    pub width: usize, // In cells
+    pub height: usize,
+    pub patrol: usize,
+    pub wander: usize,
+    pub chase: usize,
+    pub guard: usize,
+    pub arena: usize, // Ambush enemies that keep the arena doors locked
+    pub coins: usize,
+    pub par: Option<f32>, // Seconds, None when the goal cannot be reached from the spawn
+}
+
+impl MapStats {
+    // Places the enemies the same way starting the map does, so the counts match what the player meets
+    pub fn scan(filename: &str, data: &MazeData) -> Self {
+        let maze = &data.maze;
+        let mut stats = MapStats { width: maze.width(), height: maze.height(), ..MapStats::default() };
+
+        for enemy in crate::create_enemies_for_maze(maze, data.block_size, None, &mut Rng::new(0)) {
+            if enemy.arena {
+                stats.arena += 1;
+                continue;
+            }
+            match enemy.movement_pattern {
+                MovementPattern::Patrol => stats.patrol += 1,
+                MovementPattern::Wander => stats.wander += 1,
+                MovementPattern::Chase => stats.chase += 1,
+                MovementPattern::Stationary => stats.guard += 1,
+            }
+        }
+        stats.coins = maze.cells().filter(|&(_, _, cell)| cell == Cell::Coin).count();
+
+        // Every invalid par line is warned about, the last valid one counts
+        let meta_par = load_map_meta(filename)
+            .into_iter()
+            .filter(|(key, _)| key == "par")
+            .filter_map(|(_, value)| match value.parse::<f32>() {
+                Ok(seconds) if seconds > 0.0 => Some(seconds),
+                _ => {
+                    elog!("Warning: Ignoring invalid par time in {}: {}", filename, value);
+                    None
+                }
+            })
+            .collect::<Vec<f32>>()
+            .pop();
+        stats.par = meta_par.or_else(|| {
+            let cells = shortest_path_to_goal(maze)?;
+            let seconds = cells as f32 / WALK_SPEED * PAR_SLACK;
+            Some((seconds / PAR_ROUNDING).ceil().max(1.0) * PAR_ROUNDING)
+        });
+        stats
+    }
+
+    // One line for the map card, e.g. "21x13 | 10 patrol, 8 wander, 5 chase, 8 guard | 6 coins | Par 00:45"
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "{}x{} | {} patrol, {} wander, {} chase, {} guard",
+            self.width, self.height, self.patrol, self.wander, self.chase, self.guard
+        );
+        if self.arena > 0 {
+            text.push_str(&format!(", {} arena", self.arena));
+        }
+        if self.coins > 0 {
+            text.push_str(&format!(" | {} coins", self.coins));
+        }
+        match self.par {
+            Some(par) => text.push_str(&format!(" | Par {:02}:{:02}", par as u32 / 60, par as u32 % 60)),
+            None => text.push_str(" | No par"),
+        }
+        text
+    }
+}
+
+// Fewest cell steps from the player spawn to the nearest goal, walking between walkable cells
+fn shortest_path_to_goal(maze: &Maze) -> Option<usize> {
+    let (start_x, start_y, _) = maze.cells().find(|&(_, _, cell)| cell == Cell::Spawn)?;
+    let distance = walk_distances(maze, &[(start_x, start_y)]);
+    maze.cells()
+        .filter(|&(_, _, cell)| cell == Cell::Goal)
+        .map(|(x, y, _)| distance[y * maze.width() + x])
+        .filter(|&steps| steps != usize::MAX)
+        .min()
+}
+
+// Fewest cell steps from the nearest of `starts` to every cell, row by row, usize::MAX where
+// it can't be walked to. Goals count as floor, stepping onto one ends the map but not the walk.
+pub fn walk_distances(maze: &Maze, starts: &[(usize, usize)]) -> Vec<usize> {
+    let mut distance = vec![usize::MAX; maze.width() * maze.height()];
+    let mut queue = VecDeque::new();
+    for &(x, y) in starts {
+        distance[y * maze.width() + x] = 0;
+        queue.push_back((x, y));
+    }
+
+    while let Some((x, y)) = queue.pop_front() {
+        let steps = distance[y * maze.width() + x];
+        let neighbours = [(x as i32 + 1, y as i32), (x as i32 - 1, y as i32), (x as i32, y as i32 + 1), (x as i32, y as i32 - 1)];
+        for (next_x, next_y) in neighbours {
+            let Some(cell) = maze.get_i32(next_x, next_y) else { continue };
+            if !(is_walkable(cell) || cell == Cell::Goal) {
+                continue;
+            }
+            let index = next_y as usize * maze.width() + next_x as usize;
+            if distance[index] == usize::MAX {
+                distance[index] = steps + 1;
+                queue.push_back((next_x as usize, next_y as usize));
+            }
+        }
+    }
+    distance
+}
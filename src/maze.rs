@@ -1,54 +1,364 @@
 // maze.rs
 
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 use raylib::prelude::Vector2;
 
-pub type Maze = Vec<Vec<char>>;
+use crate::mods;
+
+// One grid cell, parsed from its map character once at load time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cell {
+    Empty,                     // ' '
+    Spawn,                     // 'p', player start
+    Goal,                      // 'g'
+    Coin,                      // 'c', floor with a coin on it
+    Checkpoint,                // 'k', speedrun checkpoint floor
+    Hint(u8),                  // '0'-'9', floor that triggers a hint callout (see hints.rs)
+    Water,                     // '~', shallow water
+    Swamp,                     // '%', also poisons (see status.rs)
+    FireTrap,                  // '^', floor that sets whoever crosses it burning
+    Wall { texture: char },    // Solid block, textured by its map character ('+', '-', '|', '#', ...)
+    Window,                    // 'w', barred window
+    Fence { vertical: bool },  // '=' horizontal, '!' vertical
+    Diagonal { rising: bool }, // '/' rising, '\\' falling
+    Door { open: bool },       // 'd', arena door: open floor until the arena locks (see arena.rs)
+    ArenaTrigger,              // 'A', floor that locks the arena doors
+    AmbushSpawn,               // 'b', floor where an arena enemy starts
+    Portal,                    // 'o', hub wall leading to a campaign map (see hub.rs)
+}
+
+impl Cell {
+    pub fn from_char(ch: char) -> Cell {
+        match ch {
+            ' ' => Cell::Empty,
+            'p' => Cell::Spawn,
+            'g' => Cell::Goal,
+            'c' => Cell::Coin,
+            'k' => Cell::Checkpoint,
+            '0'..='9' => Cell::Hint(ch as u8 - b'0'),
+            '~' => Cell::Water,
+            '%' => Cell::Swamp,
+            '^' => Cell::FireTrap,
+            'w' => Cell::Window,
+            '=' => Cell::Fence { vertical: false },
+            '!' => Cell::Fence { vertical: true },
+            '/' => Cell::Diagonal { rising: true },
+            '\\' => Cell::Diagonal { rising: false },
+            'd' => Cell::Door { open: true },
+            'A' => Cell::ArenaTrigger,
+            'b' => Cell::AmbushSpawn,
+            'o' => Cell::Portal,
+            _ => Cell::Wall { texture: ch },
+        }
+    }
+
+    // Map character, also the texture key for anything that gets drawn as a wall
+    pub fn to_char(self) -> char {
+        match self {
+            Cell::Empty => ' ',
+            Cell::Spawn => 'p',
+            Cell::Goal => 'g',
+            Cell::Coin => 'c',
+            Cell::Checkpoint => 'k',
+            Cell::Hint(n) => (b'0' + n) as char,
+            Cell::Water => '~',
+            Cell::Swamp => '%',
+            Cell::FireTrap => '^',
+            Cell::Wall { texture } => texture,
+            Cell::Window => 'w',
+            Cell::Fence { vertical: false } => '=',
+            Cell::Fence { vertical: true } => '!',
+            Cell::Diagonal { rising: true } => '/',
+            Cell::Diagonal { rising: false } => '\\',
+            Cell::Door { .. } => 'd',
+            Cell::ArenaTrigger => 'A',
+            Cell::AmbushSpawn => 'b',
+            Cell::Portal => 'o',
+        }
+    }
+}
+
+// Grid stored row-major in one Vec; rows shorter than the widest one are padded with empty floor
+#[derive(Clone, Debug)]
+pub struct Maze {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Maze {
+    pub fn from_rows(rows: Vec<Vec<Cell>>) -> Maze {
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let height = rows.len();
+        let mut cells = Vec::with_capacity(width * height);
+        for row in rows {
+            let padding = width - row.len();
+            cells.extend(row);
+            cells.extend(std::iter::repeat_n(Cell::Empty, padding));
+        }
+
+        Maze { width, height, cells }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // None outside the map
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> Option<Cell> {
+        if x < self.width && y < self.height {
+            Some(self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    // Signed version for neighbour lookups that may step off the edge
+    #[inline]
+    pub fn get_i32(&self, x: i32, y: i32) -> Option<Cell> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        self.get(x as usize, y as usize)
+    }
+
+    // Cell under a world position
+    #[inline]
+    pub fn at_world(&self, x: f32, y: f32, block_size: usize) -> Option<Cell> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        self.get(x as usize / block_size, y as usize / block_size)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = cell;
+        }
+    }
+
+    // Outside the map counts as solid
+    pub fn is_walkable(&self, x: usize, y: usize) -> bool {
+        self.get(x, y).is_some_and(is_walkable)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    // Every cell with its (x, y) grid position, row by row
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, Cell)> + '_ {
+        self.cells.iter().enumerate().map(|(index, &cell)| (index % self.width, index / self.width, cell))
+    }
+}
+
+// World units per cell for generated maps and maps whose header does not set `block_size = <n>`.
+// Distances that should look and play the same at any scale are kept in cells and multiplied by this.
+pub const DEFAULT_BLOCK_SIZE: usize = 100;
+const MIN_BLOCK_SIZE: usize = 16;
+const MAX_BLOCK_SIZE: usize = 1024;
+
+// Thin wall collision half-thickness, in cells
+pub const PARTIAL_WALL_THICKNESS: f32 = 0.08;
+
+// Fences and diagonal walls only occupy part of their cell
+pub fn is_partial_wall(cell: Cell) -> bool {
+    matches!(cell, Cell::Fence { .. } | Cell::Diagonal { .. })
+}
+
+// Fences, grates and barred windows let rays continue through the gaps in their texture
+pub fn is_see_through_wall(cell: Cell) -> bool {
+    matches!(cell, Cell::Fence { .. } | Cell::Window)
+}
+
+// Shallow water and swamp are walkable floor cells with their own movement and screen effects
+pub fn is_floor_zone(cell: Cell) -> bool {
+    matches!(cell, Cell::Water | Cell::Swamp)
+}
+
+pub fn is_hint_trigger(cell: Cell) -> bool {
+    matches!(cell, Cell::Hint(_))
+}
+
+// Speedrun checkpoint floor, splits the timer when crossed
+pub fn is_checkpoint(cell: Cell) -> bool {
+    cell == Cell::Checkpoint
+}
+
+// Plain floor, floor zones, fire traps, trigger cells, coin spots, checkpoints and open doors; enemies roam these
+pub fn is_open_floor(cell: Cell) -> bool {
+    matches!(
+        cell,
+        Cell::Empty | Cell::Coin | Cell::Checkpoint | Cell::Hint(_) | Cell::Water | Cell::Swamp | Cell::FireTrap |
+            Cell::Door { open: true } | Cell::ArenaTrigger | Cell::AmbushSpawn
+    )
+}
+
+// Cells the player can stand on
+pub fn is_walkable(cell: Cell) -> bool {
+    cell == Cell::Spawn || is_open_floor(cell)
+}
+
+// Movement speed multiplier for the floor the player is standing on
+pub fn floor_speed_multiplier(cell: Cell) -> f32 {
+    match cell {
+        Cell::Water => 0.7,
+        Cell::Swamp => 0.5,
+        _ => 1.0,
+    }
+}
+
+// Signed distance from a point (relative to the cell's top-left corner) to the cell's wall segment
+pub fn partial_wall_offset(cell: Cell, local_x: f32, local_y: f32, block_size: f32) -> f32 {
+    match cell {
+        Cell::Fence { vertical: false } => local_y - block_size / 2.0,
+        Cell::Fence { vertical: true } => local_x - block_size / 2.0,
+        Cell::Diagonal { rising: true } => (local_x + local_y - block_size) * std::f32::consts::FRAC_1_SQRT_2,
+        Cell::Diagonal { rising: false } => (local_x - local_y) * std::f32::consts::FRAC_1_SQRT_2,
+        _ => 0.0,
+    }
+}
+
+// True if a world position touches a thin or diagonal wall segment
+pub fn touches_partial_wall(cell: Cell, x: f32, y: f32, block_size: usize) -> bool {
+    let local_x = x - (x as usize / block_size * block_size) as f32;
+    let local_y = y - (y as usize / block_size * block_size) as f32;
+    partial_wall_offset(cell, local_x, local_y, block_size as f32).abs() < PARTIAL_WALL_THICKNESS * block_size as f32
+}
 
 pub struct MazeData {
     pub maze: Maze,
     pub player_start: Vector2,
+    pub block_size: usize, // World units per cell
 }
 
-pub fn load_maze(filename: &str) -> Maze {
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
+// Extended maps put "key = value" metadata above this line and the grid below it
+pub const MAZE_SECTION: &str = "[maze]";
+
+// A missing map, or one that is not text, is an error for the caller to show: mods can replace map files
+pub fn load_maze(filename: &str) -> Result<Maze, String> {
+    let path = mods::resolve(filename);
+    let contents = fs::read_to_string(&path).map_err(|e| format!("Map {} could not be read ({})", path, e))?;
+    let lines: Vec<&str> = contents.lines().collect();
+
+    // Only the grid matters to the game, skip any metadata header
+    let grid_start = lines.iter().position(|line| line.trim() == MAZE_SECTION).map_or(0, |index| index + 1);
+    Ok(Maze::from_rows(
+        lines[grid_start..]
+            .iter()
+            .map(|line| line.chars().map(Cell::from_char).collect())
+            .collect(),
+    ))
+}
+
+// "key = value" lines from the header of an extended map, comments and blank lines skipped
+pub fn parse_map_meta(header: &[&str]) -> Vec<(String, String)> {
+    let mut meta = Vec::new();
+    for line in header.iter() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once('=') {
+            Some((key, value)) => meta.push((key.trim().to_string(), value.trim().to_string())),
+            None => elog!("Warning: Ignoring malformed map metadata line: {}", line),
+        }
+    }
+    meta
+}
 
-    reader
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect()
+// Metadata of an extended map, empty for plain maps
+pub fn load_map_meta(filename: &str) -> Vec<(String, String)> {
+    let Ok(contents) = fs::read_to_string(mods::resolve(filename)) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = contents.lines().collect();
+    match lines.iter().position(|line| line.trim() == MAZE_SECTION) {
+        Some(section) => parse_map_meta(&lines[..section]),
+        None => Vec::new(),
+    }
 }
 
-pub fn load_maze_with_player(filename: &str, block_size: usize) -> MazeData {
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
+// Where a header line's tag applies: the whole map, or a rectangle of cells
+#[derive(Clone, Copy, Debug)]
+pub enum MapArea {
+    Map,
+    Rect(usize, usize, usize, usize), // x, y, width, height
+}
 
-    let maze: Maze = reader
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect();
+impl MapArea {
+    pub fn contains(&self, cell: (usize, usize)) -> bool {
+        match *self {
+            MapArea::Map => true,
+            MapArea::Rect(x, y, width, height) => cell.0 >= x && cell.0 < x + width && cell.1 >= y && cell.1 < y + height,
+        }
+    }
+}
 
-    // Find player start position
-    let mut player_start = Vector2::new(150.0, 150.0); // Default fallback
-    
-    for (row_index, row) in maze.iter().enumerate() {
-        for (col_index, &cell) in row.iter().enumerate() {
-            if cell == 'p' {
-                // Convert maze coordinates to world coordinates
-                player_start = Vector2::new(
-                    col_index as f32 * block_size as f32 + block_size as f32 / 2.0,
-                    row_index as f32 * block_size as f32 + block_size as f32 / 2.0,
-                );
-                break;
+// The area after a header line's tag: no numbers for the whole map, or x, y, width and height.
+// Anything else is warned about and the line should be skipped
+pub fn parse_area(key: &str, value: &str, parts: &[&str]) -> Option<MapArea> {
+    let mut numbers = Vec::new();
+    for part in parts {
+        match part.parse::<usize>() {
+            Ok(number) => numbers.push(number),
+            Err(_) => {
+                elog!("Warning: Ignoring '{} = {}', '{}' is not a whole number of cells", key, value, part);
+                return None;
             }
         }
     }
+    match numbers[..] {
+        [] => Some(MapArea::Map),
+        [x, y, width, height] => Some(MapArea::Rect(x, y, width, height)),
+        _ => {
+            elog!("Warning: Ignoring '{} = {}', an area needs x, y, width and height", key, value);
+            None
+        }
+    }
+}
+
+// The map and its player start, at the block size its header asks for
+pub fn load_maze_with_player(filename: &str) -> Result<MazeData, String> {
+    Ok(maze_data_from(load_maze(filename)?, map_block_size(&load_map_meta(filename))))
+}
+
+// `block_size = <n>` from a map header, DEFAULT_BLOCK_SIZE when missing or out of range
+pub fn map_block_size(meta: &[(String, String)]) -> usize {
+    let Some((_, value)) = meta.iter().rev().find(|(key, _)| key == "block_size") else {
+        return DEFAULT_BLOCK_SIZE;
+    };
+    match value.parse::<usize>() {
+        Ok(size) if (MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE).contains(&size) => size,
+        _ => {
+            elog!("Warning: Ignoring block_size {}, it must be a whole number from {} to {}", value, MIN_BLOCK_SIZE, MAX_BLOCK_SIZE);
+            DEFAULT_BLOCK_SIZE
+        }
+    }
+}
+
+// Wrap an in-memory maze (e.g. a generated one) and locate its player start
+pub fn maze_data_from(maze: Maze, block_size: usize) -> MazeData {
+    // Find player start position
+    let mut player_start = Vector2::new(block_size as f32 * 1.5, block_size as f32 * 1.5); // Default fallback
+
+    if let Some((col_index, row_index, _)) = maze.cells().find(|&(_, _, cell)| cell == Cell::Spawn) {
+        // Convert maze coordinates to world coordinates
+        player_start = Vector2::new(
+            col_index as f32 * block_size as f32 + block_size as f32 / 2.0,
+            row_index as f32 * block_size as f32 + block_size as f32 / 2.0,
+        );
+    }
 
     MazeData {
         maze,
         player_start,
+        block_size,
     }
 }
 
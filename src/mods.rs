@@ -0,0 +1,94 @@
+// mods.rs
+
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+// Community content without touching the base install. Each folder in mods/ is one mod
+// laid out like the game folder, and its files replace the base files with the same path:
+//   mods/bloody/assets/textures/large_door_rgba.png  replaces  assets/textures/large_door_rgba.png
+//   mods/bloody/maze2.txt                            replaces  maze2.txt
+// Mods apply in name order, so a later mod wins when two replace the same file.
+// Files are read at startup, switching mods on or off takes effect on the next launch.
+
+pub const MODS_DIR: &str = "mods";
+const DISABLED_PATH: &str = "mods/disabled.txt"; // One disabled mod name per line
+
+// Mods switched on when the game started, the only ones files are looked up in
+static ACTIVE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub struct ModEntry {
+    pub name: &'static str, // Folders are scanned once per launch, so names can live as long as the game
+    pub enabled: bool,
+}
+
+pub struct ModList {
+    pub entries: Vec<ModEntry>,
+}
+
+impl ModList {
+    // Every folder in mods/, enabled unless listed in mods/disabled.txt
+    pub fn scan() -> Self {
+        let disabled: Vec<String> = fs::read_to_string(DISABLED_PATH)
+            .map(|contents| contents.lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect())
+            .unwrap_or_default();
+
+        let mut names: Vec<String> = match fs::read_dir(MODS_DIR) {
+            Ok(dir) => dir
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect(),
+            Err(_) => Vec::new(), // No mods folder, plain base game
+        };
+        names.sort();
+
+        let entries = names
+            .into_iter()
+            .map(|name| {
+                let enabled = !disabled.contains(&name);
+                ModEntry { name: Box::leak(name.into_boxed_str()), enabled }
+            })
+            .collect();
+        ModList { entries }
+    }
+
+    // Make the enabled mods the ones files resolve through for this launch
+    pub fn activate(&self) {
+        let enabled: Vec<String> = self.entries.iter().filter(|entry| entry.enabled).map(|entry| entry.name.to_string()).collect();
+        if !enabled.is_empty() {
+            log!("Mods enabled: {}", enabled.join(", "));
+        }
+        *ACTIVE.lock().unwrap() = enabled;
+    }
+
+    pub fn save(&self) {
+        let mut contents = String::new();
+        for entry in self.entries.iter().filter(|entry| !entry.enabled) {
+            contents.push_str(entry.name);
+            contents.push('\n');
+        }
+        if let Err(e) = fs::write(DISABLED_PATH, contents) {
+            elog!("Warning: Could not save {}: {:?}", DISABLED_PATH, e);
+        }
+    }
+
+    // True once the list differs from what was activated at startup
+    pub fn needs_restart(&self) -> bool {
+        let active = ACTIVE.lock().unwrap();
+        !self.entries.iter().filter(|entry| entry.enabled).map(|entry| entry.name).eq(active.iter().map(String::as_str))
+    }
+}
+
+// Where a game file is actually read from: the last enabled mod that has it, or the base path
+pub fn resolve(path: &str) -> String {
+    let active = ACTIVE.lock().unwrap();
+    for name in active.iter().rev() {
+        let candidate = Path::new(MODS_DIR).join(name).join(path);
+        if candidate.is_file() {
+            log!("Using {} from mod {}", path, name);
+            return candidate.to_string_lossy().into_owned();
+        }
+    }
+    path.to_string()
+}
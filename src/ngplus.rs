@@ -0,0 +1,86 @@
+// ngplus.rs
+
+use raylib::prelude::*;
+
+use crate::enemy::{Enemy, MovementPattern};
+use crate::pickups::Pickup;
+use crate::profile::Profile;
+
+// New Game Plus opens once every campaign map has been finished. It plays the campaign again
+// from the hub with tougher enemies, and a map header can give it a second spawn table that
+// replaces the map's usual enemies, coins and lantern flasks:
+//   ngplus_enemy = shield 5 3        (kind and cell: patrol, wander, chase, cloaked, shield or guard)
+//   ngplus_enemy = patrol 3 5 7 5    (a patrol also takes the cell it walks to)
+//   ngplus_item = coin 4 1           (coin or flask, and cell)
+// A map without `ngplus_enemy` lines keeps its usual enemies, and one without `ngplus_item`
+// lines its usual items. The profile keeps New Game Plus progress apart from the first
+// campaign: which maps are finished, opening the hub's portals again one by one, and best grades.
+
+const HEALTH_SCALE: f32 = 1.5; // Enemy health in New Game Plus, times the usual
+const SPEED_SCALE: f32 = 1.25; // Enemy movement speed, times the usual
+const COIN_VALUE: u32 = 1;
+
+pub const ENEMY_KINDS: [&str; 6] = ["patrol", "wander", "chase", "cloaked", "shield", "guard"];
+
+// One `ngplus_enemy` line, in world coordinates at cell centers
+pub struct SpawnEntry {
+    pub kind: &'static str,
+    pub pos: Vector2,
+    pub patrol_end: Option<Vector2>,
+}
+
+#[derive(Default)]
+pub struct SpawnTable {
+    pub enemies: Vec<SpawnEntry>,
+    pub coins: Vec<Vector2>,
+    pub flasks: Vec<Vector2>,
+}
+
+impl SpawnTable {
+    // The `ngplus_enemy` and `ngplus_item` lines of a map's metadata
+    pub fn from_meta(meta: &[(String, String)], block_size: usize) -> Self {
+        let block = block_size as f32;
+        let center = |x: &str, y: &str| {
+            let (x, y) = x.parse::<usize>().ok().zip(y.parse::<usize>().ok())?;
+            Some(Vector2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block))
+        };
+        let mut table = SpawnTable::default();
+        for (key, value) in meta.iter() {
+            let parts: Vec<&str> = value.split_whitespace().collect();
+            let parsed = match (key.as_str(), &parts[..]) {
+                ("ngplus_enemy", &["patrol", x, y, end_x, end_y]) => center(x, y).zip(center(end_x, end_y))
+                    .map(|(pos, end)| table.enemies.push(SpawnEntry { kind: "patrol", pos, patrol_end: Some(end) })),
+                ("ngplus_enemy", &[kind, x, y]) => ENEMY_KINDS.iter().find(|&&known| known == kind && known != "patrol").zip(center(x, y))
+                    .map(|(&kind, pos)| table.enemies.push(SpawnEntry { kind, pos, patrol_end: None })),
+                ("ngplus_item", &["coin", x, y]) => center(x, y).map(|pos| table.coins.push(pos)),
+                ("ngplus_item", &["flask", x, y]) => center(x, y).map(|pos| table.flasks.push(pos)),
+                ("ngplus_enemy" | "ngplus_item", _) => None,
+                _ => Some(()),
+            };
+            if parsed.is_none() {
+                elog!("Warning: Ignoring malformed New Game Plus spawn '{} = {}'", key, value);
+            }
+        }
+        table
+    }
+
+    pub fn coin_pickups(&self) -> Vec<Pickup> {
+        self.coins.iter().map(|&pos| Pickup { pos, value: COIN_VALUE }).collect()
+    }
+}
+
+// Every campaign map finished the first time through
+pub fn unlocked(profile: &Profile, maps: &[&str]) -> bool {
+    maps.iter().all(|map| profile.map_completed(map, false))
+}
+
+// Tougher enemies for New Game Plus: more health, and all but guards quicker on their feet
+pub fn toughen(enemies: &mut [Enemy]) {
+    for enemy in enemies.iter_mut() {
+        enemy.max_health *= HEALTH_SCALE;
+        enemy.health = enemy.max_health;
+        if enemy.movement_pattern != MovementPattern::Stationary {
+            enemy.movement_speed *= SPEED_SCALE;
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// pathfind.rs
+
+use std::collections::VecDeque;
+
+use crate::maze::{Maze, is_walkable};
+
+// Grid pathfinding over walkable cells, four-way, every step costing the same. Searches
+// give up past a step limit so a far or unreachable goal never floods a large map.
+
+// Cells to walk from `from` to `to`, without `from` itself; empty when already there
+pub fn path(maze: &Maze, from: (usize, usize), to: (usize, usize), max_steps: usize) -> Option<Vec<(usize, usize)>> {
+    if from == to {
+        return Some(Vec::new());
+    }
+    if !maze.is_walkable(to.0, to.1) {
+        return None;
+    }
+
+    let width = maze.width();
+    let mut came_from = vec![usize::MAX; width * maze.height()];
+    let mut queue = VecDeque::new();
+    came_from[from.1 * width + from.0] = from.1 * width + from.0;
+    queue.push_back((from, 0));
+
+    while let Some(((x, y), steps)) = queue.pop_front() {
+        if (x, y) == to {
+            // Walk the links back to the start
+            let mut cells = Vec::with_capacity(steps);
+            let mut index = y * width + x;
+            while index != from.1 * width + from.0 {
+                cells.push((index % width, index / width));
+                index = came_from[index];
+            }
+            cells.reverse();
+            return Some(cells);
+        }
+        if steps >= max_steps {
+            continue;
+        }
+
+        let neighbours = [(x as i32 + 1, y as i32), (x as i32 - 1, y as i32), (x as i32, y as i32 + 1), (x as i32, y as i32 - 1)];
+        for (next_x, next_y) in neighbours {
+            if !maze.get_i32(next_x, next_y).is_some_and(is_walkable) {
+                continue;
+            }
+            let index = next_y as usize * width + next_x as usize;
+            if came_from[index] == usize::MAX {
+                came_from[index] = y * width + x;
+                queue.push_back(((next_x as usize, next_y as usize), steps + 1));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,72 @@
+// pickups.rs
+
+use raylib::prelude::*;
+
+use crate::maze::{Cell, Maze, is_open_floor};
+use crate::rng::Rng;
+
+const PICKUP_RADIUS: f32 = 0.4; // How close the player must walk to collect, in cells
+
+pub struct Pickup {
+    pub pos: Vector2,
+    pub value: u32, // Coins granted
+}
+
+// Coins sit on 'c' cells in hand-made maps
+pub fn coins_from_maze(maze: &Maze, block_size: usize) -> Vec<Pickup> {
+    let mut pickups = Vec::new();
+    for (col_index, row_index, cell) in maze.cells() {
+        if cell == Cell::Coin {
+            pickups.push(Pickup {
+                pos: Vector2::new(
+                    col_index as f32 * block_size as f32 + block_size as f32 / 2.0,
+                    row_index as f32 * block_size as f32 + block_size as f32 / 2.0,
+                ),
+                value: 1,
+            });
+        }
+    }
+    pickups
+}
+
+// Scatter coins over open floor cells of a generated maze, deterministic for a given seed
+pub fn scatter_coins(maze: &Maze, block_size: usize, seed: u64, count: usize) -> Vec<Pickup> {
+    let open_cells: Vec<(usize, usize)> = maze
+        .cells()
+        .filter(|&(_, _, cell)| is_open_floor(cell))
+        .map(|(col_index, row_index, _)| (col_index, row_index))
+        .collect();
+
+    let mut pickups = Vec::new();
+    if open_cells.is_empty() {
+        return pickups;
+    }
+
+    let mut rng = Rng::new(seed);
+    for _ in 0..count {
+        let (col, row) = open_cells[rng.range(open_cells.len())];
+        pickups.push(Pickup {
+            pos: Vector2::new(
+                col as f32 * block_size as f32 + block_size as f32 / 2.0,
+                row as f32 * block_size as f32 + block_size as f32 / 2.0,
+            ),
+            value: 1,
+        });
+    }
+    pickups
+}
+
+// Remove every pickup the player is touching and return the coins gained
+pub fn collect_pickups(pickups: &mut Vec<Pickup>, player_pos: Vector2, block_size: usize) -> u32 {
+    let mut gained = 0;
+    pickups.retain(|pickup| {
+        let distance = ((pickup.pos.x - player_pos.x).powi(2) + (pickup.pos.y - player_pos.y).powi(2)).sqrt();
+        if distance <= PICKUP_RADIUS * block_size as f32 {
+            gained += pickup.value;
+            false
+        } else {
+            true
+        }
+    });
+    gained
+}
@@ -0,0 +1,66 @@
+// ping.rs
+
+use raylib::prelude::*;
+
+use crate::maze::{Maze, is_walkable};
+use crate::player::Player;
+
+pub const PING_LIFETIME: f32 = 6.0; // Seconds a marker stays up
+const PING_RANGE: f32 = 12.0; // Furthest a ping can land, in cells
+const PING_WALL_GAP: f32 = 0.2; // Markers sit this far in front of the wall they point at
+
+// Marker colors by player index, so each co-op player can tell whose ping is whose; the
+// companion wisp marks what it finds in its own color
+pub const PING_COLORS: [Color; 3] = [Color::new(80, 220, 255, 255), Color::new(255, 120, 220, 255), Color::new(255, 230, 120, 255)];
+pub const COMPANION_PING_OWNER: usize = 2;
+
+// Temporary world marker placed with the ping action, shown as a beacon and on the minimap
+pub struct Ping {
+    pub pos: Vector2,
+    pub owner: usize, // Index of the player who placed it
+    pub age: f32,
+}
+
+impl Ping {
+    pub fn color(&self) -> Color {
+        PING_COLORS[self.owner % PING_COLORS.len()]
+    }
+
+    // 1.0 when placed, fading to 0.0 as it expires
+    pub fn strength(&self) -> f32 {
+        (1.0 - self.age / PING_LIFETIME).clamp(0.0, 1.0)
+    }
+}
+
+// Floor point the player is looking at: the ray stops just short of the first wall
+pub fn ping_target(player: &Player, maze: &Maze, block_size: usize) -> Vector2 {
+    let step = block_size as f32 / 10.0;
+    let (dir_x, dir_y) = (player.a.cos(), player.a.sin());
+
+    let mut distance = 0.0;
+    while distance < PING_RANGE * block_size as f32 {
+        let next = distance + step;
+        let x = player.pos.x + dir_x * next;
+        let y = player.pos.y + dir_y * next;
+        if !maze.at_world(x, y, block_size).is_some_and(is_walkable) {
+            break;
+        }
+        distance = next;
+    }
+
+    let distance = (distance - PING_WALL_GAP * block_size as f32).max(0.0);
+    Vector2::new(player.pos.x + dir_x * distance, player.pos.y + dir_y * distance)
+}
+
+// Each player keeps one ping: a new one replaces theirs
+pub fn place_ping(pings: &mut Vec<Ping>, pos: Vector2, owner: usize) {
+    pings.retain(|ping| ping.owner != owner);
+    pings.push(Ping { pos, owner, age: 0.0 });
+}
+
+pub fn update_pings(pings: &mut Vec<Ping>, delta_time: f32) {
+    for ping in pings.iter_mut() {
+        ping.age += delta_time;
+    }
+    pings.retain(|ping| ping.age < PING_LIFETIME);
+}
@@ -2,19 +2,54 @@
 
 use raylib::prelude::*;
 use std::f32::consts::PI;
-use crate::maze::Maze;
-use crate::audio::AudioManager;
+use crate::actions::{Action, ActionMap};
+use crate::status::StatusEffects;
+use crate::lantern::Lantern;
+use crate::maze::{Cell, Maze, floor_speed_multiplier};
+use crate::sim::{PlayerInput, Vec2, try_step};
+use crate::sway::WeaponSway;
+
+const FOV_PUNCH: f32 = 0.06; // How much the view narrows at the peak of a swing
+const ATTACK_BUFFER_TIME: f32 = 0.2; // A press this long before the sword is ready still swings
+const COMBO_WINDOW: f32 = 0.35; // Seconds after a swing in which the next one continues the chain
+
+// One hit of the combo chain, scaling the weapon's damage and hit cone
+pub struct ComboHit {
+    pub damage_scale: f32,
+    pub arc_scale: f32,
+}
+
+pub const COMBO_HITS: [ComboHit; 3] = [
+    ComboHit { damage_scale: 1.0, arc_scale: 1.0 }, // Opening slash
+    ComboHit { damage_scale: 1.1, arc_scale: 0.8 }, // Quicker backhand, narrower
+    ComboHit { damage_scale: 1.5, arc_scale: 1.3 }, // Wide finisher
+];
 
 pub struct Player {
     pub pos: Vector2,
     pub a: f32,
     pub fov: f32, // field of view
+    pub base_fov: f32, // fov without the attack punch
     pub mouse_sensitivity: f32,
     pub is_attacking: bool,
     pub attack_timer: f32,
     pub attack_duration: f32,
     pub attack_cooldown: f32,
     pub enemy_hit_this_attack: bool, // Track if we hit an enemy during current attack
+    swing_started: bool, // Set when a swing begins, cleared once its sound has played
+    moved: bool, // Set by movement input, cleared once the footsteps have followed it
+    attack_buffer: f32, // Time left on a press queued while the sword wasn't ready
+    pub combo_step: usize, // Index into COMBO_HITS of the current or last swing
+    combo_timer: f32, // Time left to chain the next swing
+    pub health: f32,
+    pub max_health: f32,
+    pub move_speed_multiplier: f32,
+    pub attack_damage_bonus: f32,
+    pub light_radius: f32, // Distance in cells before walls and sprites start fading into the fog, lantern lit
+    pub lantern: Lantern, // Oil and whether it is lit, see lantern.rs
+    pub light_scale: f32, // Share of the light radius zone modifiers leave, see zones.rs
+    pub status: StatusEffects, // Burning, poisoned or slowed
+    pub sway: WeaponSway, // Where the sword trails the camera, see sway.rs
 }
 
 impl Player {
@@ -23,22 +58,99 @@ impl Player {
             pos,
             a,
             fov,
+            base_fov: fov,
             mouse_sensitivity,
             is_attacking: false,
             attack_timer: 0.0,
             attack_duration: 0.25, // Faster attack duration for more responsive feel
             attack_cooldown: 0.0,
             enemy_hit_this_attack: false,
+            swing_started: false,
+            moved: false,
+            attack_buffer: 0.0,
+            combo_step: 0,
+            combo_timer: 0.0,
+            health: 100.0,
+            max_health: 100.0,
+            move_speed_multiplier: 1.0,
+            attack_damage_bonus: 0.0,
+            light_radius: 2.0,
+            lantern: Lantern::new(),
+            light_scale: 1.0,
+            status: StatusEffects::new(),
+            sway: WeaponSway::default(),
         }
     }
 
+    // Back to base stats, used when a new game or run starts
+    // The view changed shape, e.g. the window was resized, see view_fov
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov;
+        self.base_fov = fov;
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.max_health = 100.0;
+        self.health = self.max_health;
+        self.move_speed_multiplier = 1.0;
+        self.attack_damage_bonus = 0.0;
+        self.light_radius = 2.0;
+        self.lantern = Lantern::new();
+        self.status.clear();
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    pub fn take_damage(&mut self, amount: f32) {
+        self.health = (self.health - amount).max(0.0);
+    }
+
+    // How far the player can see right now, with the lantern out, guttering or burning steadily,
+    // and closed in further by darkness or fog zones
+    pub fn sight_radius(&self) -> f32 {
+        self.lantern.radius(self.light_radius) * self.light_scale
+    }
+
+    pub fn health_ratio(&self) -> f32 {
+        self.health / self.max_health
+    }
+
+    // Swing now if the sword is ready, otherwise queue the press for a moment
     pub fn start_attack(&mut self) {
-        if !self.is_attacking && self.attack_cooldown <= 0.0 {
-            self.is_attacking = true;
-            self.attack_timer = self.attack_duration;
-            self.attack_cooldown = 0.1; // Small cooldown to prevent spam clicking
-            self.enemy_hit_this_attack = false; // Reset hit flag for new attack
+        if self.is_attacking || self.attack_cooldown > 0.0 {
+            self.attack_buffer = ATTACK_BUFFER_TIME;
+            return;
         }
+
+        // Swings in quick succession walk through the combo, a pause starts it over
+        self.combo_step = if self.combo_timer > 0.0 { (self.combo_step + 1) % COMBO_HITS.len() } else { 0 };
+        self.combo_timer = 0.0;
+        self.attack_buffer = 0.0;
+        self.is_attacking = true;
+        self.attack_timer = self.attack_duration;
+        self.attack_cooldown = 0.1; // Small cooldown to prevent spam clicking
+        self.enemy_hit_this_attack = false; // Reset hit flag for new attack
+        self.swing_started = true;
+    }
+
+    pub fn combo_hit(&self) -> &ComboHit {
+        &COMBO_HITS[self.combo_step]
+    }
+
+    // True once per swing, right after it starts
+    pub fn take_swing_started(&mut self) -> bool {
+        let started = self.swing_started;
+        self.swing_started = false;
+        started
+    }
+
+    // True if the player walked since the last call
+    pub fn take_moved(&mut self) -> bool {
+        let moved = self.moved;
+        self.moved = false;
+        moved
     }
 
     pub fn update_attack(&mut self, delta_time: f32) {
@@ -47,8 +159,15 @@ impl Player {
             if self.attack_timer <= 0.0 {
                 self.is_attacking = false;
                 self.attack_timer = 0.0;
+                self.combo_timer = COMBO_WINDOW;
             }
+        } else {
+            self.combo_timer = (self.combo_timer - delta_time).max(0.0);
         }
+
+        // Narrow the view through the swing and back so hits feel heavier
+        let punch = (self.get_attack_progress() * PI).sin();
+        self.fov = self.base_fov * (1.0 - FOV_PUNCH * punch);
         
         if self.attack_cooldown > 0.0 {
             self.attack_cooldown -= delta_time;
@@ -56,6 +175,19 @@ impl Player {
                 self.attack_cooldown = 0.0;
             }
         }
+
+        // Fire a queued press as soon as the sword is ready
+        if self.attack_buffer > 0.0 {
+            self.attack_buffer -= delta_time;
+            if !self.is_attacking && self.attack_cooldown <= 0.0 {
+                self.start_attack();
+            }
+        }
+    }
+
+    // Maze cell under the player, empty floor when outside the map
+    pub fn current_floor(&self, maze: &Maze, block_size: usize) -> Cell {
+        maze.at_world(self.pos.x, self.pos.y, block_size).unwrap_or(Cell::Empty)
     }
 
     pub fn get_attack_progress(&self) -> f32 {
@@ -66,48 +198,32 @@ impl Player {
     }
 }
 
-fn check_collision(maze: &Maze, x: f32, y: f32, block_size: usize) -> bool {
-    if x < 0.0 || y < 0.0 {
-        return true; // Out of bounds
-    }
-    
-    let i = (x as usize) / block_size;
-    let j = (y as usize) / block_size;
-    
-    if j >= maze.len() || i >= maze[0].len() {
-        return true; // Out of bounds
-    }
-    
-    // Treat 'p' (player spawn) as walkable space like ' '
-    let cell = maze[j][i];
-    cell != ' ' && cell != 'p' // Return true if it's a wall
-}
-
 pub fn process_events(
     player: &mut Player, 
     rl: &RaylibHandle, 
+    actions: &ActionMap,
     maze: &Maze, 
     block_size: usize, 
     window_width: i32, 
     window_height: i32,
-    audio_manager: &AudioManager,
-    walking_sound: &Option<Sound>,
     delta_time: f32
 ) {
-    const MOVE_SPEED: f32 = 10.0;
+    let input = read_input(player, rl, actions, window_width, window_height);
+    apply_input(player, &input, maze, block_size, delta_time);
+}
+
+// Everything the keyboard, mouse and controller ask for this frame, through the bindings of the
+// chosen input preset. Only the mouse is touched here: it is put back in the middle of the
+// window once its movement has been read.
+pub fn read_input(player: &Player, rl: &RaylibHandle, actions: &ActionMap, window_width: i32, window_height: i32) -> PlayerInput {
     const ROTATION_SPEED: f32 = PI / 10.0;
-    const CONTROLLER_SENSITIVITY: f32 = 0.03; // Right stick sensitivity for looking
+    const CONTROLLER_SENSITIVITY: f32 = 0.03; // Looking stick sensitivity
     const CONTROLLER_DEADZONE: f32 = 0.15; // Deadzone for analog sticks
 
-    let mut is_moving = false;
-
-    // Update attack state
-    player.update_attack(delta_time);
-
-    // Check if a gamepad is connected (PS5 controller)
-    let gamepad_available = rl.is_gamepad_available(0);
+    // Sprint with Shift or by clicking in the moving stick
+    let mut input = PlayerInput { sprint: actions.down(rl, Action::Sprint), ..PlayerInput::default() };
 
-    // Mouse camera control (only if no gamepad or gamepad right stick not being used)
+    // Mouse camera control (only if the looking stick is not being used)
     let mouse_pos = rl.get_mouse_position();
     let center_x = window_width as f32 / 2.0;
     let center_y = window_height as f32 / 2.0;
@@ -115,217 +231,93 @@ pub fn process_events(
     let mouse_delta_x = mouse_pos.x - center_x;
     
     // Controller camera control takes priority over mouse
-    if gamepad_available {
-        let right_stick_x = rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_RIGHT_X);
-        if right_stick_x.abs() > CONTROLLER_DEADZONE {
-            player.a += right_stick_x * CONTROLLER_SENSITIVITY;
-        } else if mouse_delta_x.abs() > 1.0 {
-            // Fall back to mouse if right stick not being used
-            player.a += mouse_delta_x * player.mouse_sensitivity;
-            // Reset mouse to center to prevent drift
-            unsafe {
-                raylib::ffi::SetMousePosition(center_x as i32, center_y as i32);
-            }
-        }
-    } else {
-        // No gamepad, use mouse
-        if mouse_delta_x.abs() > 1.0 {
-            player.a += mouse_delta_x * player.mouse_sensitivity;
-            // Reset mouse to center to prevent drift
-            unsafe {
-                raylib::ffi::SetMousePosition(center_x as i32, center_y as i32);
-            }
+    let look_x = actions.look_stick(rl);
+    if look_x.abs() > CONTROLLER_DEADZONE {
+        input.turn += look_x * CONTROLLER_SENSITIVITY;
+    } else if mouse_delta_x.abs() > 1.0 {
+        input.turn += mouse_delta_x * player.mouse_sensitivity;
+        // Reset mouse to center to prevent drift
+        unsafe {
+            raylib::ffi::SetMousePosition(center_x as i32, center_y as i32);
         }
     }
 
-    // Movement controls - Controller takes priority
-    if gamepad_available {
-        // Left stick for movement
-        let left_stick_x = rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_LEFT_X);
-        let left_stick_y = rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_LEFT_Y);
-        
-        // Forward/Backward (left stick Y-axis, inverted because up is negative)
-        if left_stick_y.abs() > CONTROLLER_DEADZONE {
-            let move_amount = -left_stick_y * MOVE_SPEED; // Negative because up should be forward
-            let new_x = player.pos.x + move_amount * player.a.cos();
-            let new_y = player.pos.y + move_amount * player.a.sin();
-            if !check_collision(maze, new_x, new_y, block_size) {
-                player.pos.x = new_x;
-                player.pos.y = new_y;
-                is_moving = true;
-            }
-        }
-        
-        // Strafe Left/Right (left stick X-axis)
-        if left_stick_x.abs() > CONTROLLER_DEADZONE {
-            let strafe_angle = player.a + PI / 2.0; // Right direction
-            let move_amount = left_stick_x * MOVE_SPEED;
-            let new_x = player.pos.x + move_amount * strafe_angle.cos();
-            let new_y = player.pos.y + move_amount * strafe_angle.sin();
-            if !check_collision(maze, new_x, new_y, block_size) {
-                player.pos.x = new_x;
-                player.pos.y = new_y;
-                is_moving = true;
-            }
-        }
-        
-        // D-Pad as backup movement controls
-        if rl.is_gamepad_button_down(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP) {
-            // Move forward
-            let new_x = player.pos.x + MOVE_SPEED * player.a.cos();
-            let new_y = player.pos.y + MOVE_SPEED * player.a.sin();
-            if !check_collision(maze, new_x, new_y, block_size) {
-                player.pos.x = new_x;
-                player.pos.y = new_y;
-                is_moving = true;
-            }
-        }
-        if rl.is_gamepad_button_down(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN) {
-            // Move backward
-            let new_x = player.pos.x - MOVE_SPEED * player.a.cos();
-            let new_y = player.pos.y - MOVE_SPEED * player.a.sin();
-            if !check_collision(maze, new_x, new_y, block_size) {
-                player.pos.x = new_x;
-                player.pos.y = new_y;
-                is_moving = true;
-            }
-        }
-        if rl.is_gamepad_button_down(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT) {
-            // Strafe left
-            let strafe_angle = player.a - PI / 2.0;
-            let new_x = player.pos.x + MOVE_SPEED * strafe_angle.cos();
-            let new_y = player.pos.y + MOVE_SPEED * strafe_angle.sin();
-            if !check_collision(maze, new_x, new_y, block_size) {
-                player.pos.x = new_x;
-                player.pos.y = new_y;
-                is_moving = true;
-            }
-        }
-        if rl.is_gamepad_button_down(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT) {
-            // Strafe right
-            let strafe_angle = player.a + PI / 2.0;
-            let new_x = player.pos.x + MOVE_SPEED * strafe_angle.cos();
-            let new_y = player.pos.y + MOVE_SPEED * strafe_angle.sin();
-            if !check_collision(maze, new_x, new_y, block_size) {
-                player.pos.x = new_x;
-                player.pos.y = new_y;
-                is_moving = true;
-            }
-        }
-        
-        // Shoulder buttons for rotation (as backup to right stick)
-        if rl.is_gamepad_button_down(0, GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_1) {
-            player.a -= ROTATION_SPEED;
-        }
-        if rl.is_gamepad_button_down(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_1) {
-            player.a += ROTATION_SPEED;
-        }
-    }
+    // Directions are relative to the view: forward, back, left and right
+    let forward = 0.0;
+    let back = PI;
+    let left = -PI / 2.0;
+    let right = PI / 2.0;
 
-    // WASD movement (keyboard - works alongside or without controller)
-    if rl.is_key_down(KeyboardKey::KEY_W) {
-        // Move forward
-        let new_x = player.pos.x + MOVE_SPEED * player.a.cos();
-        let new_y = player.pos.y + MOVE_SPEED * player.a.sin();
-        if !check_collision(maze, new_x, new_y, block_size) {
-            player.pos.x = new_x;
-            player.pos.y = new_y;
-            is_moving = true;
-        }
-    }
-    if rl.is_key_down(KeyboardKey::KEY_S) {
-        // Move backward
-        let new_x = player.pos.x - MOVE_SPEED * player.a.cos();
-        let new_y = player.pos.y - MOVE_SPEED * player.a.sin();
-        if !check_collision(maze, new_x, new_y, block_size) {
-            player.pos.x = new_x;
-            player.pos.y = new_y;
-            is_moving = true;
-        }
+    // The moving stick: forward/backward on its Y axis (inverted because up is negative), strafing on X
+    let (move_x, move_y) = actions.move_stick(rl);
+    if move_y.abs() > CONTROLLER_DEADZONE {
+        input.steps.push((forward, -move_y));
     }
-    if rl.is_key_down(KeyboardKey::KEY_A) {
-        // Strafe left (perpendicular to current direction)
-        let strafe_angle = player.a - PI / 2.0;
-        let new_x = player.pos.x + MOVE_SPEED * strafe_angle.cos();
-        let new_y = player.pos.y + MOVE_SPEED * strafe_angle.sin();
-        if !check_collision(maze, new_x, new_y, block_size) {
-            player.pos.x = new_x;
-            player.pos.y = new_y;
-            is_moving = true;
-        }
+    if move_x.abs() > CONTROLLER_DEADZONE {
+        input.steps.push((right, move_x));
     }
-    if rl.is_key_down(KeyboardKey::KEY_D) {
-        // Strafe right (perpendicular to current direction)
-        let strafe_angle = player.a + PI / 2.0;
-        let new_x = player.pos.x + MOVE_SPEED * strafe_angle.cos();
-        let new_y = player.pos.y + MOVE_SPEED * strafe_angle.sin();
-        if !check_collision(maze, new_x, new_y, block_size) {
-            player.pos.x = new_x;
-            player.pos.y = new_y;
-            is_moving = true;
+
+    // Movement keys and the D-pad
+    let steps = [
+        (Action::Forward, forward),
+        (Action::Back, back),
+        (Action::StrafeLeft, left),
+        (Action::StrafeRight, right),
+    ];
+    for (action, direction) in steps {
+        if actions.down(rl, action) {
+            input.steps.push((direction, 1.0));
         }
     }
 
-    // Keep arrow key controls for backwards compatibility
-    if rl.is_key_down(KeyboardKey::KEY_LEFT) {
-        player.a -= ROTATION_SPEED;
+    // Turning keys and the shoulder buttons, as backup to the stick and mouse
+    if actions.down(rl, Action::TurnLeft) {
+        input.turn -= ROTATION_SPEED;
     }
-    if rl.is_key_down(KeyboardKey::KEY_RIGHT) {
-        player.a += ROTATION_SPEED;
+    if actions.down(rl, Action::TurnRight) {
+        input.turn += ROTATION_SPEED;
     }
-    if rl.is_key_down(KeyboardKey::KEY_DOWN) {
-        let new_x = player.pos.x - MOVE_SPEED * player.a.cos();
-        let new_y = player.pos.y - MOVE_SPEED * player.a.sin();
-        if !check_collision(maze, new_x, new_y, block_size) {
-            player.pos.x = new_x;
-            player.pos.y = new_y;
-            is_moving = true;
-        }
+
+    // Attack with the bound keys or buttons, or the left mouse button
+    input.attack = actions.pressed(rl, Action::Attack) || rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+    input.pad_attack = actions.pressed_on_gamepad(rl, Action::Attack);
+
+    input
+}
+
+// Turns, moves and swings the player as asked, stopping each step that would end in a wall
+pub fn apply_input(player: &mut Player, input: &PlayerInput, maze: &Maze, block_size: usize, delta_time: f32) {
+    const BASE_MOVE_SPEED: f32 = 0.1; // Cells per frame
+    const SPRINT_MULTIPLIER: f32 = 1.6;
+
+    // Update attack state
+    player.update_attack(delta_time);
+
+    player.a += input.turn;
+
+    // Water and swamp slow the player down
+    let floor = player.current_floor(maze, block_size);
+    let mut move_speed = BASE_MOVE_SPEED * block_size as f32 * player.move_speed_multiplier * player.status.speed_multiplier() * floor_speed_multiplier(floor);
+    if input.sprint {
+        move_speed *= SPRINT_MULTIPLIER;
     }
-    if rl.is_key_down(KeyboardKey::KEY_UP) {
-        let new_x = player.pos.x + MOVE_SPEED * player.a.cos();
-        let new_y = player.pos.y + MOVE_SPEED * player.a.sin();
-        if !check_collision(maze, new_x, new_y, block_size) {
-            player.pos.x = new_x;
-            player.pos.y = new_y;
+
+    let mut is_moving = false;
+    let mut taken = Vec2::default(); // Along and across the view, in full steps
+    for &(direction, amount) in &input.steps {
+        let step = Vec2::from_angle(player.a + direction, amount * move_speed);
+        if let Some(pos) = try_step(maze, player.pos.into(), step, block_size) {
+            player.pos = pos.into();
             is_moving = true;
+            taken = taken + Vec2::from_angle(direction, amount * if input.sprint { SPRINT_MULTIPLIER } else { 1.0 });
         }
     }
+    player.sway.update(input.turn, taken.x, taken.y, delta_time);
 
-    // Attack controls
-    if gamepad_available {
-        // R2 trigger (Right Trigger 2) for attack
-        if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_2) {
-            player.start_attack();
-        }
-        // Alternative: Square button for attack
-        if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT) {
-            player.start_attack();
-        }
-    }
-    
-    // Keyboard attack controls
-    if rl.is_key_pressed(KeyboardKey::KEY_SPACE) || rl.is_key_pressed(KeyboardKey::KEY_E) {
-        player.start_attack();
-    }
-    
-    // Mouse attack control
-    if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+    if input.attack {
         player.start_attack();
     }
 
-    // Handle walking sound based on movement
-    if let Some(sound) = walking_sound {
-        if is_moving {
-            // Start playing sound if not already playing
-            if !sound.is_playing() {
-                audio_manager.play_footstep(sound);
-            }
-        } else {
-            // Stop sound if playing and player stopped moving
-            if sound.is_playing() {
-                sound.stop();
-            }
-        }
-    }
+    // The audio update plays footsteps from this
+    player.moved = is_moving;
 }
@@ -0,0 +1,248 @@
+// postfx.rs
+
+use raylib::prelude::Color;
+
+use crate::color;
+use crate::framebuffer::Framebuffer;
+
+// Full-screen effects run over the framebuffer after the world is drawn and before it is uploaded
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostEffect {
+    Underwater, // Tint and wavy horizontal distortion while standing in water or swamp
+    Darkness,   // Dims the screen inside darkness zones, see zones.rs
+    LowHealth,  // Drains color from the screen as the player gets hurt
+    Berserk,    // Sepia tone with film grain
+    Scanlines,  // Darkened rows like an old CRT monitor
+}
+
+// Game state the effects react to, gathered once per frame
+pub struct PostFxContext {
+    pub time: f32,
+    pub health_ratio: f32, // 0.0 = dead, 1.0 = full health
+    pub underwater: bool,
+    pub water_tint: Color, // Color mixed into the screen while underwater
+    pub dim: f32,          // 0.0 to 1.0, how far the screen is pulled toward black
+}
+
+// What a menu or dialog does to the frame behind it: darkens all of it and blurs the part under
+// its panel, so the game shows through the panel frosted. The panel is in UI pixels.
+pub struct Backdrop {
+    pub panel: (i32, i32, i32, i32), // x, y, width, height
+    pub darken: f32,                 // 0.0 to 1.0, how far the frame is pulled toward black
+}
+
+const BACKDROP_BLUR: f32 = 0.012; // Box blur radius as a share of the frame width
+const BACKDROP_PASSES: usize = 2; // Box blurs in a row, two come close to a Gaussian
+
+pub struct PostProcessor {
+    chain: Vec<(PostEffect, bool)>, // Effects run in this order, each one can be switched off
+    scratch: Vec<u8>,               // Copy of the frame for effects that move pixels around
+}
+
+impl PostProcessor {
+    pub fn new() -> Self {
+        PostProcessor {
+            // Distortion first so the color passes and scanlines stay aligned to the screen
+            chain: vec![
+                (PostEffect::Underwater, true),
+                (PostEffect::Darkness, true),
+                (PostEffect::LowHealth, true),
+                (PostEffect::Berserk, false),
+                (PostEffect::Scanlines, false),
+            ],
+            scratch: Vec::new(),
+        }
+    }
+
+    pub fn is_enabled(&self, effect: PostEffect) -> bool {
+        self.chain.iter().any(|&(e, enabled)| e == effect && enabled)
+    }
+
+    pub fn set_enabled(&mut self, effect: PostEffect, enabled: bool) {
+        for entry in self.chain.iter_mut() {
+            if entry.0 == effect {
+                entry.1 = enabled;
+            }
+        }
+    }
+
+    pub fn toggle(&mut self, effect: PostEffect) {
+        let enabled = self.is_enabled(effect);
+        self.set_enabled(effect, !enabled);
+    }
+
+    pub fn apply(&mut self, framebuffer: &mut Framebuffer, context: &PostFxContext) {
+        let width = framebuffer.width as usize;
+        let height = framebuffer.height as usize;
+        let pixels = framebuffer.pixels_mut();
+        if pixels.is_empty() {
+            return;
+        }
+
+        for &(effect, enabled) in self.chain.iter() {
+            if !enabled {
+                continue;
+            }
+            match effect {
+                PostEffect::Underwater => {
+                    if context.underwater {
+                        apply_underwater(pixels, &mut self.scratch, width, height, context.time);
+                        apply_tint(pixels, context.water_tint, 0.3);
+                    }
+                }
+                PostEffect::Darkness => {
+                    if context.dim > 0.0 {
+                        apply_tint(pixels, Color::BLACK, context.dim);
+                    }
+                }
+                PostEffect::LowHealth => {
+                    // Only kicks in below half health, fully grey near death
+                    let strength = ((0.5 - context.health_ratio) / 0.5).clamp(0.0, 1.0);
+                    if strength > 0.0 {
+                        apply_desaturate(pixels, strength);
+                    }
+                }
+                PostEffect::Berserk => apply_berserk(pixels, context.time),
+                PostEffect::Scanlines => apply_scanlines(pixels, width, height),
+            }
+        }
+    }
+
+    // Blur and darken behind an overlay, for a `ui_width` by `ui_height` UI over the frame
+    pub fn apply_backdrop(&mut self, framebuffer: &mut Framebuffer, backdrop: &Backdrop, ui_width: i32, ui_height: i32) {
+        let width = framebuffer.width as usize;
+        let height = framebuffer.height as usize;
+        let pixels = framebuffer.pixels_mut();
+        if pixels.is_empty() || ui_width <= 0 || ui_height <= 0 {
+            return;
+        }
+
+        // The panel in framebuffer pixels
+        let (x, y, panel_width, panel_height) = backdrop.panel;
+        let to_frame = |value: i32, ui: i32, frame: usize| ((value as f32 / ui as f32 * frame as f32) as i32).clamp(0, frame as i32) as usize;
+        let (x0, x1) = (to_frame(x, ui_width, width), to_frame(x + panel_width, ui_width, width));
+        let (y0, y1) = (to_frame(y, ui_height, height), to_frame(y + panel_height, ui_height, height));
+        let radius = ((width as f32 * BACKDROP_BLUR) as usize).max(1);
+        if x1 > x0 && y1 > y0 {
+            for _ in 0..BACKDROP_PASSES {
+                for row in y0..y1 {
+                    blur_line(pixels, &mut self.scratch, (row * width + x0) * 4, 4, x1 - x0, radius);
+                }
+                for column in x0..x1 {
+                    blur_line(pixels, &mut self.scratch, (y0 * width + column) * 4, width * 4, y1 - y0, radius);
+                }
+            }
+        }
+        apply_tint(pixels, Color::BLACK, backdrop.darken);
+    }
+}
+
+// Box blur `count` pixels starting at byte `start`, `stride` bytes apart, each averaged with the
+// `radius` on either side. Running sums keep it linear in the line's length whatever the radius.
+fn blur_line(pixels: &mut [u8], scratch: &mut Vec<u8>, start: usize, stride: usize, count: usize, radius: usize) {
+    scratch.clear();
+    scratch.extend((0..count).flat_map(|i| {
+        let at = start + i * stride;
+        [pixels[at], pixels[at + 1], pixels[at + 2]]
+    }));
+    let mut sums = [0u32; 3];
+    for i in 0..radius.min(count) {
+        for channel in 0..3 {
+            sums[channel] += scratch[i * 3 + channel] as u32;
+        }
+    }
+    for i in 0..count {
+        // Slide the window: take in the pixel `radius` ahead, drop the one past `radius` behind
+        if i + radius < count {
+            for channel in 0..3 {
+                sums[channel] += scratch[(i + radius) * 3 + channel] as u32;
+            }
+        }
+        if i > radius {
+            for channel in 0..3 {
+                sums[channel] -= scratch[(i - radius - 1) * 3 + channel] as u32;
+            }
+        }
+        let covered = (i + radius).min(count - 1) + 1 - i.saturating_sub(radius);
+        let at = start + i * stride;
+        for channel in 0..3 {
+            pixels[at + channel] = (sums[channel] / covered as u32) as u8;
+        }
+    }
+}
+
+fn luminance(r: u8, g: u8, b: u8) -> f32 {
+    0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32
+}
+
+fn apply_underwater(pixels: &mut [u8], scratch: &mut Vec<u8>, width: usize, height: usize, time: f32) {
+    scratch.clear();
+    scratch.extend_from_slice(pixels);
+
+    for y in 0..height {
+        // Each row slides sideways on a slow sine wave
+        let offset = ((y as f32 * 0.04 + time * 3.0).sin() * 6.0) as i32;
+        let row = y * width * 4;
+
+        for x in 0..width {
+            let source_x = (x as i32 + offset).clamp(0, width as i32 - 1) as usize;
+            let dst = row + x * 4;
+            let src = row + source_x * 4;
+            pixels[dst..dst + 4].copy_from_slice(&scratch[src..src + 4]);
+        }
+    }
+}
+
+fn apply_tint(pixels: &mut [u8], tint: Color, strength: f32) {
+    let target = [tint.r, tint.g, tint.b];
+    for pixel in pixels.chunks_exact_mut(4) {
+        for (channel, &target) in pixel.iter_mut().zip(target.iter()) {
+            *channel = color::mix_gamma_channel(*channel, target, strength);
+        }
+    }
+}
+
+fn apply_desaturate(pixels: &mut [u8], strength: f32) {
+    for pixel in pixels.chunks_exact_mut(4) {
+        let gray = luminance(pixel[0], pixel[1], pixel[2]) as u8;
+        for channel in pixel.iter_mut().take(3) {
+            *channel = color::lerp_channel(*channel, gray, strength);
+        }
+    }
+}
+
+fn apply_berserk(pixels: &mut [u8], time: f32) {
+    // New grain pattern every frame
+    let frame_seed = (time * 60.0) as u32;
+
+    for (i, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+        let r = pixel[0] as f32;
+        let g = pixel[1] as f32;
+        let b = pixel[2] as f32;
+
+        // Cheap integer hash for the grain noise, in the range -18..18
+        let mut hash = (i as u32).wrapping_mul(747796405) ^ frame_seed.wrapping_mul(2891336453);
+        hash ^= hash >> 16;
+        hash = hash.wrapping_mul(2246822519);
+        hash ^= hash >> 13;
+        let grain = (hash % 37) as f32 - 18.0;
+
+        pixel[0] = (r * 0.393 + g * 0.769 + b * 0.189 + grain).clamp(0.0, 255.0) as u8;
+        pixel[1] = (r * 0.349 + g * 0.686 + b * 0.168 + grain).clamp(0.0, 255.0) as u8;
+        pixel[2] = (r * 0.272 + g * 0.534 + b * 0.131 + grain).clamp(0.0, 255.0) as u8;
+    }
+}
+
+fn apply_scanlines(pixels: &mut [u8], width: usize, height: usize) {
+    let row_bytes = width * 4;
+
+    // Darken every other row
+    for y in (1..height).step_by(2) {
+        let row = &mut pixels[y * row_bytes..(y + 1) * row_bytes];
+        for pixel in row.chunks_exact_mut(4) {
+            pixel[0] = (pixel[0] as u16 * 3 / 5) as u8;
+            pixel[1] = (pixel[1] as u16 * 3 / 5) as u8;
+            pixel[2] = (pixel[2] as u16 * 3 / 5) as u8;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+// profile.rs
+
+use std::collections::BTreeMap;
+
+use crate::grading::Grade;
+use crate::savefile::{SaveFormat, load_versioned, save_versioned};
+
+pub const PROFILE_PATH: &str = "profile.txt";
+pub const DEFAULT_PLAYER_NAME: &str = "Player";
+pub const MAX_NAME_LENGTH: usize = 16;
+
+// Bump with a new migration step whenever a key changes meaning or name
+const PROFILE_FORMAT: SaveFormat = SaveFormat { name: "profile", version: 1, migrate: migrate_profile };
+
+// Persistent player data stored as simple "key=value" lines
+pub struct Profile {
+    path: String,
+    values: BTreeMap<String, String>,
+    error: Option<String>, // Why the file on disk could not be used, it is then never saved over
+}
+
+impl Profile {
+    pub fn load(path: &str) -> Self {
+        let mut error = None;
+        let values = match load_versioned(path, &PROFILE_FORMAT) {
+            Ok(Some(values)) => {
+                log!("Loaded profile from {}", path);
+                values
+            }
+            Ok(None) => {
+                log!("No profile found at {}, starting fresh", path);
+                BTreeMap::new()
+            }
+            Err(e) => {
+                let message = format!("Profile {} {}. Progress will not be saved this session.", path, e);
+                elog!("Error: {}", message);
+                error = Some(message);
+                BTreeMap::new()
+            }
+        };
+
+        Profile {
+            path: path.to_string(),
+            values,
+            error,
+        }
+    }
+
+    pub fn save(&self) {
+        if self.error.is_none() {
+            save_versioned(&self.path, "Raycaster Dungeon player profile", &PROFILE_FORMAT, &self.values);
+        }
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|value| value.as_str())
+    }
+
+    pub fn set(&mut self, key: &str, value: String) {
+        self.values.insert(key.to_string(), value);
+    }
+
+    pub fn get_f32(&self, key: &str) -> Option<f32> {
+        self.get(key).and_then(|value| value.parse().ok())
+    }
+
+    // Entered on the on-screen keyboard from Settings
+    pub fn player_name(&self) -> &str {
+        self.get("name").filter(|name| !name.is_empty()).unwrap_or(DEFAULT_PLAYER_NAME)
+    }
+
+    // Rogue-lite meta-currency, kept across runs
+    pub fn meta_currency(&self) -> u32 {
+        self.get("meta_currency").and_then(|value| value.parse().ok()).unwrap_or(0)
+    }
+
+    pub fn add_meta_currency(&mut self, amount: u32) {
+        let total = self.meta_currency() + amount;
+        self.set("meta_currency", total.to_string());
+    }
+
+    pub fn run_best_floor(&self) -> u32 {
+        self.get("run_best_floor").and_then(|value| value.parse().ok()).unwrap_or(0)
+    }
+
+    pub fn record_run_floor(&mut self, floor: u32) {
+        if floor > self.run_best_floor() {
+            self.set("run_best_floor", floor.to_string());
+        }
+    }
+
+    // Campaign maps finished at least once, which opens the next map's portal in the hub.
+    // New Game Plus keeps its own record under "_ngplus" keys, see ngplus.rs.
+    pub fn map_completed(&self, filename: &str, ng_plus: bool) -> bool {
+        self.get(&progress_key("completed", filename, ng_plus)) == Some("true")
+    }
+
+    pub fn record_map_completed(&mut self, filename: &str, ng_plus: bool) {
+        self.set(&progress_key("completed", filename, ng_plus), "true".to_string());
+    }
+
+    // Best grade earned on a map file, shown on its card
+    pub fn best_grade(&self, filename: &str, ng_plus: bool) -> Option<Grade> {
+        self.get(&progress_key("best_grade", filename, ng_plus)).and_then(Grade::parse)
+    }
+
+    // Returns true if the grade beat the stored best for that map
+    pub fn record_grade(&mut self, filename: &str, ng_plus: bool, grade: Grade) -> bool {
+        let is_best = self.best_grade(filename, ng_plus).is_none_or(|best| grade > best);
+        if is_best {
+            self.set(&progress_key("best_grade", filename, ng_plus), grade.letter().to_string());
+        }
+        is_best
+    }
+
+    // Daily Dungeon best times are kept per date, apart from the regular maps
+    pub fn daily_best(&self, date_label: &str) -> Option<f32> {
+        self.get_f32(&format!("daily_best.{}", date_label))
+    }
+
+    // Returns true if the time beat the stored best for that day
+    pub fn record_daily_time(&mut self, date_label: &str, seconds: f32) -> bool {
+        let is_best = self.daily_best(date_label).is_none_or(|best| seconds < best);
+        if is_best {
+            self.set(&format!("daily_best.{}", date_label), format!("{:.2}", seconds));
+        }
+        is_best
+    }
+}
+
+// "completed.maze.txt", or "completed_ngplus.maze.txt" for New Game Plus
+fn progress_key(kind: &str, filename: &str, ng_plus: bool) -> String {
+    format!("{}{}.{}", kind, if ng_plus { "_ngplus" } else { "" }, filename)
+}
+
+// One step per old version. Version 0 is every profile written before versioning,
+// its keys carry over to version 1 unchanged, so there is nothing to convert yet.
+fn migrate_profile(from: u32, _values: &mut BTreeMap<String, String>) {
+    match from {
+        0 => {}
+        _ => elog!("Warning: No profile migration from version {}", from),
+    }
+}
@@ -0,0 +1,68 @@
+// projectiles.rs
+
+use raylib::prelude::*;
+
+use crate::enemy::Enemy;
+use crate::maze::{Maze, is_walkable};
+use crate::status::StatusKind;
+
+// Things that fly in a straight line until they hit an enemy, a wall or run out of range.
+// Only the dart turret fires them for now; they never hurt the player.
+
+const HIT_RADIUS: f32 = 0.3; // Cells from an enemy's center that count as a hit
+
+pub struct Projectile {
+    pub pos: Vector2,
+    pub direction: Vector2,  // Unit vector
+    pub speed: f32,          // Cells per second
+    pub damage: f32,
+    pub inflicts: Option<StatusKind>, // Effect left on the enemy it hits
+    pub range: f32,          // Cells it can still fly
+}
+
+// What a projectile did this frame, for the world to turn into events and drops
+pub struct ProjectileHit {
+    pub pos: Vector2,
+    pub killed: bool,
+    pub blocked: bool, // Glanced off a raised shield
+}
+
+pub fn update_projectiles(projectiles: &mut Vec<Projectile>, enemies: &mut [Enemy], maze: &Maze, block_size: usize,
+                          delta_time: f32) -> Vec<ProjectileHit> {
+    let block = block_size as f32;
+    let mut hits = Vec::new();
+
+    projectiles.retain_mut(|projectile| {
+        let step = projectile.speed * delta_time;
+        projectile.pos.x += projectile.direction.x * step * block;
+        projectile.pos.y += projectile.direction.y * step * block;
+        projectile.range -= step;
+        if projectile.range <= 0.0 || !maze.at_world(projectile.pos.x, projectile.pos.y, block_size).is_some_and(is_walkable) {
+            return false;
+        }
+
+        let target = enemies.iter_mut().find(|enemy| {
+            !enemy.is_dead && ((enemy.pos.x - projectile.pos.x).powi(2) + (enemy.pos.y - projectile.pos.y).powi(2)).sqrt() <= HIT_RADIUS * block
+        });
+        let Some(enemy) = target else {
+            return true;
+        };
+
+        // Shields stop darts flying at their front like they stop swords
+        let source = Vector2::new(projectile.pos.x - projectile.direction.x * block, projectile.pos.y - projectile.direction.y * block);
+        if enemy.blocks(source) {
+            enemy.block();
+            hits.push(ProjectileHit { pos: enemy.pos, killed: false, blocked: true });
+            return false;
+        }
+
+        if let Some(kind) = projectile.inflicts {
+            enemy.status.apply(kind);
+        }
+        enemy.awareness = 1.0;
+        let killed = enemy.take_damage(projectile.damage);
+        hits.push(ProjectileHit { pos: enemy.pos, killed, blocked: false });
+        false
+    });
+    hits
+}
@@ -0,0 +1,358 @@
+// prompts.rs
+
+use raylib::prelude::*;
+
+use crate::actions::InputPreset;
+
+const CELL: i32 = 64; // Size of one glyph in the atlas
+
+// Atlas cells, left to right
+const KEYCAP: i32 = 0;
+const PS_CROSS: i32 = 1;
+const PS_CIRCLE: i32 = 2;
+const PS_SQUARE: i32 = 3;
+const PS_TRIANGLE: i32 = 4;
+const XBOX_FACE: i32 = 5;
+const DPAD: i32 = 6;
+const MENU: i32 = 7;
+const TRIGGER: i32 = 8;
+const ATLAS_CELLS: i32 = 9;
+
+// Whatever the player touched last decides which glyphs are shown
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputDevice {
+    Keyboard,
+    PlayStation,
+    Xbox,
+}
+
+// Actions the menus and HUD prompt for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Prompt {
+    Confirm,
+    Back,
+    Pause,
+    Attack,
+    Sprint,
+    Potion,
+    Navigate, // Up/down through a list
+    Adjust,   // Left/right to change a value
+    Move,
+    Load,     // Open the Load Game screen
+    Interact, // Use a hub portal
+    Command,  // Send the companion after an enemy
+    Switch,   // Pick the next deployable
+    Erase,    // Delete a character on the on-screen keyboard
+    Done,     // Finish typing on the on-screen keyboard
+    Export,   // Write the run summary from an end screen
+    Route,    // Look over the route heatmap from an end screen
+}
+
+// Keys that count as keyboard activity when switching glyphs back from a controller
+const KEYBOARD_KEYS: [KeyboardKey; 21] = [
+    KeyboardKey::KEY_W,
+    KeyboardKey::KEY_A,
+    KeyboardKey::KEY_S,
+    KeyboardKey::KEY_D,
+    KeyboardKey::KEY_I,
+    KeyboardKey::KEY_J,
+    KeyboardKey::KEY_K,
+    KeyboardKey::KEY_L,
+    KeyboardKey::KEY_UP,
+    KeyboardKey::KEY_DOWN,
+    KeyboardKey::KEY_LEFT,
+    KeyboardKey::KEY_RIGHT,
+    KeyboardKey::KEY_ENTER,
+    KeyboardKey::KEY_SPACE,
+    KeyboardKey::KEY_ESCAPE,
+    KeyboardKey::KEY_E,
+    KeyboardKey::KEY_F,
+    KeyboardKey::KEY_C,
+    KeyboardKey::KEY_X,
+    KeyboardKey::KEY_M,
+    KeyboardKey::KEY_BACKSPACE,
+];
+
+pub struct ButtonPrompts {
+    atlas: Option<Texture2D>,
+    pub device: InputDevice,
+    pub preset: InputPreset, // Gameplay prompts follow its bindings, see actions.rs
+    pub key_labels: Vec<(Prompt, &'static str)>, // Keys rebound on the Controls screen, in place of the preset's
+}
+
+impl ButtonPrompts {
+    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
+        let atlas = match rl.load_texture_from_image(thread, &generate_atlas()) {
+            Ok(texture) => Some(texture),
+            Err(e) => {
+                elog!("Warning: Could not create button prompt atlas: {:?}", e);
+                None
+            }
+        };
+
+        ButtonPrompts {
+            atlas,
+            device: InputDevice::Keyboard,
+            preset: InputPreset::Default,
+            key_labels: Vec::new(),
+        }
+    }
+
+    // Switch glyphs to the device that was used most recently
+    pub fn update_device(&mut self, rl: &RaylibHandle) {
+        let pad_used = rl.is_gamepad_available(0) &&
+            (rl.get_gamepad_button_pressed().is_some() ||
+             rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_LEFT_X).abs() > 0.5 ||
+             rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_LEFT_Y).abs() > 0.5);
+
+        if pad_used {
+            let name = rl.get_gamepad_name(0).unwrap_or_default().to_lowercase();
+            let is_playstation = ["playstation", "sony", "dualsense", "dualshock", "ps4", "ps5", "wireless controller"]
+                .iter()
+                .any(|tag| name.contains(tag));
+            self.device = if is_playstation { InputDevice::PlayStation } else { InputDevice::Xbox };
+            return;
+        }
+
+        let mouse_delta = rl.get_mouse_delta();
+        if KEYBOARD_KEYS.iter().any(|&key| rl.is_key_down(key)) || mouse_delta.x != 0.0 || mouse_delta.y != 0.0 {
+            self.device = InputDevice::Keyboard;
+        }
+    }
+
+    // Atlas cell, label drawn on top and its color for a prompt on the active device
+    fn glyph(&self, prompt: Prompt) -> (i32, &'static str, Color) {
+        let key_text = Color::new(30, 30, 35, 255);
+        let left_handed = self.preset == InputPreset::LeftHanded;
+        let southpaw = self.preset == InputPreset::Southpaw;
+        match self.device {
+            InputDevice::Keyboard => {
+                let label = match prompt {
+                    Prompt::Confirm => "ENTER",
+                    Prompt::Back | Prompt::Pause => "ESC",
+                    Prompt::Attack => "SPACE",
+                    Prompt::Sprint if left_handed => "RSHIFT",
+                    Prompt::Sprint => "SHIFT",
+                    Prompt::Potion => "H",
+                    Prompt::Navigate => "W/S",
+                    Prompt::Adjust => "A/D",
+                    Prompt::Move if left_handed => "IJKL",
+                    Prompt::Move => "WASD",
+                    Prompt::Load => "L",
+                    Prompt::Interact if left_handed => ";",
+                    Prompt::Interact => "F",
+                    Prompt::Command if left_handed => ".",
+                    Prompt::Command => "C",
+                    Prompt::Switch if left_handed => ",",
+                    Prompt::Switch => "X",
+                    Prompt::Erase => "BKSP",
+                    Prompt::Done => "ENTER",
+                    Prompt::Export => "E",
+                    Prompt::Route => "M",
+                };
+                let label = self.key_labels.iter().find(|(rebound, _)| *rebound == prompt).map_or(label, |&(_, name)| name);
+                (KEYCAP, label, key_text)
+            }
+            InputDevice::PlayStation => match prompt {
+                Prompt::Confirm | Prompt::Interact => (PS_CROSS, "", Color::WHITE),
+                Prompt::Back | Prompt::Switch => (PS_CIRCLE, "", Color::WHITE),
+                Prompt::Potion | Prompt::Export => (PS_TRIANGLE, "", Color::WHITE),
+                Prompt::Load | Prompt::Erase | Prompt::Route => (PS_SQUARE, "", Color::WHITE),
+                Prompt::Attack => (TRIGGER, "R2", Color::WHITE),
+                Prompt::Sprint => (TRIGGER, if southpaw { "R3" } else { "L3" }, Color::WHITE),
+                Prompt::Command => (TRIGGER, "L2", Color::WHITE),
+                Prompt::Pause | Prompt::Done => (MENU, "", Color::WHITE),
+                Prompt::Navigate | Prompt::Adjust | Prompt::Move => (DPAD, "", Color::WHITE),
+            },
+            InputDevice::Xbox => match prompt {
+                Prompt::Confirm | Prompt::Interact => (XBOX_FACE, "A", Color::new(90, 200, 90, 255)),
+                Prompt::Back | Prompt::Switch => (XBOX_FACE, "B", Color::new(220, 70, 70, 255)),
+                Prompt::Potion | Prompt::Export => (XBOX_FACE, "Y", Color::new(230, 200, 60, 255)),
+                Prompt::Load | Prompt::Erase | Prompt::Route => (XBOX_FACE, "X", Color::new(70, 130, 230, 255)),
+                Prompt::Attack => (TRIGGER, "RT", Color::WHITE),
+                Prompt::Sprint => (TRIGGER, if southpaw { "RS" } else { "LS" }, Color::WHITE),
+                Prompt::Command => (TRIGGER, "LT", Color::WHITE),
+                Prompt::Pause | Prompt::Done => (MENU, "", Color::WHITE),
+                Prompt::Navigate | Prompt::Adjust | Prompt::Move => (DPAD, "", Color::WHITE),
+            },
+        }
+    }
+
+    fn glyph_width(&self, prompt: Prompt, size: i32) -> i32 {
+        let (cell, label, _) = self.glyph(prompt);
+        if cell == KEYCAP {
+            // Keycaps stretch to fit longer key names
+            size.max(label_font(size) * label.len() as i32 / 2 + size / 2)
+        } else {
+            size
+        }
+    }
+
+    // Draws one glyph with its top-left corner at (x, y), returning its width
+    pub fn draw_glyph(&self, d: &mut RaylibDrawHandle, prompt: Prompt, x: i32, y: i32, size: i32) -> i32 {
+        let (cell, label, label_color) = self.glyph(prompt);
+        let width = self.glyph_width(prompt, size);
+
+        match self.atlas {
+            Some(ref atlas) => {
+                let source = Rectangle::new((cell * CELL) as f32, 0.0, CELL as f32, CELL as f32);
+                let dest = Rectangle::new(x as f32, y as f32, width as f32, size as f32);
+                d.draw_texture_pro(atlas, source, dest, Vector2::zero(), 0.0, Color::WHITE);
+            }
+            None => d.draw_rectangle_lines(x, y, width, size, Color::LIGHTGRAY),
+        }
+
+        if !label.is_empty() {
+            let font_size = label_font(size);
+            let label_width = font_size * label.len() as i32 / 2; // Approximate text width
+            d.draw_text(label, x + (width - label_width) / 2, y + (size - font_size) / 2, font_size, label_color);
+        }
+
+        width
+    }
+
+    pub fn row_width(&self, items: &[(Prompt, &str)], size: i32) -> i32 {
+        let font_size = size * 3 / 4;
+        let items_width: i32 = items
+            .iter()
+            .map(|&(prompt, action)| self.glyph_width(prompt, size) + 6 + font_size * action.len() as i32 / 2)
+            .sum();
+        items_width + size / 2 * (items.len() as i32 - 1).max(0)
+    }
+
+    // A row of "glyph action" pairs starting at x, e.g. [ENTER] Select  [ESC] Back
+    pub fn draw_row(&self, d: &mut RaylibDrawHandle, items: &[(Prompt, &str)], x: i32, y: i32, size: i32, color: Color) {
+        let font_size = size * 3 / 4;
+        let mut x = x;
+
+        for &(prompt, action) in items {
+            let glyph_width = self.draw_glyph(d, prompt, x, y, size);
+            d.draw_text(action, x + glyph_width + 6, y + (size - font_size) / 2, font_size, color);
+            x += glyph_width + 6 + font_size * action.len() as i32 / 2 + size / 2;
+        }
+    }
+
+    pub fn draw_row_centered(&self, d: &mut RaylibDrawHandle, items: &[(Prompt, &str)], center_x: i32, y: i32, size: i32, color: Color) {
+        let x = center_x - self.row_width(items, size) / 2;
+        self.draw_row(d, items, x, y, size, color);
+    }
+}
+
+fn label_font(size: i32) -> i32 {
+    (size / 2).max(10)
+}
+
+fn distance_to_segment(px: f32, py: f32, ax: f32, ay: f32, bx: f32, by: f32) -> f32 {
+    let (dx, dy) = (bx - ax, by - ay);
+    let t = (((px - ax) * dx + (py - ay) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+    let (cx, cy) = (ax + dx * t, ay + dy * t);
+    ((px - cx) * (px - cx) + (py - cy) * (py - cy)).sqrt()
+}
+
+// Rounded rectangle coverage test in cell coordinates
+fn in_rounded_rect(x: f32, y: f32, left: f32, top: f32, right: f32, bottom: f32, radius: f32) -> bool {
+    let cx = x.clamp(left + radius, right - radius);
+    let cy = y.clamp(top + radius, bottom - radius);
+    x >= left && x <= right && y >= top && y <= bottom && (x - cx) * (x - cx) + (y - cy) * (y - cy) <= radius * radius
+}
+
+// Every glyph is drawn procedurally into one image so there is no extra asset to ship
+fn generate_atlas() -> Image {
+    let mut image = Image::gen_image_color(CELL * ATLAS_CELLS, CELL, Color::new(0, 0, 0, 0));
+    let base = Color::new(35, 35, 42, 255);
+    let rim = Color::new(90, 90, 100, 255);
+
+    for cell in 0..ATLAS_CELLS {
+        for y in 0..CELL {
+            for x in 0..CELL {
+                let (fx, fy) = (x as f32 + 0.5, y as f32 + 0.5);
+                let radius = ((fx - 32.0) * (fx - 32.0) + (fy - 32.0) * (fy - 32.0)).sqrt();
+                let round_button = radius <= 30.0;
+                let round_rim = round_button && radius > 27.0;
+
+                let color = match cell {
+                    KEYCAP => {
+                        // Light key face with a darker bottom edge
+                        if !in_rounded_rect(fx, fy, 2.0, 2.0, 62.0, 62.0, 10.0) {
+                            None
+                        } else if !in_rounded_rect(fx, fy, 6.0, 5.0, 58.0, 54.0, 8.0) {
+                            Some(Color::new(150, 150, 158, 255))
+                        } else {
+                            Some(Color::new(225, 225, 230, 255))
+                        }
+                    }
+                    PS_CROSS | PS_CIRCLE | PS_SQUARE | PS_TRIANGLE => {
+                        let symbol = match cell {
+                            PS_CROSS => {
+                                let d1 = distance_to_segment(fx, fy, 19.0, 19.0, 45.0, 45.0);
+                                let d2 = distance_to_segment(fx, fy, 45.0, 19.0, 19.0, 45.0);
+                                (d1.min(d2) < 3.5).then_some(Color::new(125, 165, 235, 255))
+                            }
+                            PS_CIRCLE => ((radius - 14.0).abs() < 3.0).then_some(Color::new(235, 95, 95, 255)),
+                            PS_SQUARE => {
+                                let edge = (fx - 32.0).abs().max((fy - 32.0).abs());
+                                (edge > 10.5 && edge < 16.0).then_some(Color::new(225, 135, 205, 255))
+                            }
+                            _ => {
+                                let d1 = distance_to_segment(fx, fy, 32.0, 15.0, 16.0, 44.0);
+                                let d2 = distance_to_segment(fx, fy, 16.0, 44.0, 48.0, 44.0);
+                                let d3 = distance_to_segment(fx, fy, 48.0, 44.0, 32.0, 15.0);
+                                (d1.min(d2).min(d3) < 3.0).then_some(Color::new(95, 205, 175, 255))
+                            }
+                        };
+                        if !round_button {
+                            None
+                        } else if symbol.is_some() {
+                            symbol
+                        } else if round_rim {
+                            Some(rim)
+                        } else {
+                            Some(base)
+                        }
+                    }
+                    XBOX_FACE => {
+                        // The letter is drawn over this in the button color
+                        if round_rim { Some(rim) } else if round_button { Some(base) } else { None }
+                    }
+                    DPAD => {
+                        let horizontal = in_rounded_rect(fx, fy, 4.0, 22.0, 60.0, 42.0, 4.0);
+                        let vertical = in_rounded_rect(fx, fy, 22.0, 4.0, 42.0, 60.0, 4.0);
+                        if radius < 6.0 {
+                            Some(Color::new(20, 20, 25, 255))
+                        } else if horizontal || vertical {
+                            Some(Color::new(70, 70, 80, 255))
+                        } else {
+                            None
+                        }
+                    }
+                    MENU => {
+                        // Small pill with three lines
+                        if !in_rounded_rect(fx, fy, 8.0, 16.0, 56.0, 48.0, 14.0) {
+                            None
+                        } else if (22.0..42.0).contains(&fx) && [24.0, 32.0, 40.0].iter().any(|line| (fy - line).abs() < 1.5) {
+                            Some(Color::WHITE)
+                        } else {
+                            Some(base)
+                        }
+                    }
+                    _ => {
+                        // Trigger: a shoulder button, rounder at the top
+                        if !in_rounded_rect(fx, fy, 6.0, 6.0, 58.0, 58.0, 8.0) || (fy < 26.0 && !in_rounded_rect(fx, fy, 6.0, 6.0, 58.0, 58.0, 22.0)) {
+                            None
+                        } else if !in_rounded_rect(fx, fy, 9.0, 9.0, 55.0, 55.0, 6.0) {
+                            Some(rim)
+                        } else {
+                            Some(base)
+                        }
+                    }
+                };
+
+                if let Some(color) = color {
+                    image.draw_pixel(cell * CELL + x, y, color);
+                }
+            }
+        }
+    }
+
+    image
+}
@@ -0,0 +1,242 @@
+// props.rs
+
+use raylib::prelude::*;
+use std::f32::consts::TAU;
+
+use crate::FloorItem;
+use crate::color;
+use crate::enemy::Enemy;
+use crate::maze::Maze;
+use crate::rng::Rng;
+use crate::sim::{Vec2, line_of_sight, swing_hit};
+use crate::status::StatusKind;
+
+// Breakable decorations placed by a map's header, one per line with the cell it stands in:
+//   prop = crate 3 5
+//   prop = barrel 7 2
+//   prop = keg 12 9      (powder keg, explodes)
+// Sword swings smash them like they hit enemies. Crates break at the first blow and hold coins,
+// barrels take two and may hold lantern oil; a powder keg bursts at once, hurting and burning
+// every enemy in the blast that it can reach and setting off other props caught in it. Broken
+// props fly apart in a short shower of splinters and leave their debris on the floor.
+
+const BREAK_TIME: f32 = 0.7;       // Seconds the splinters fly
+const SHARDS: usize = 6;
+const BLAST_RADIUS: f32 = 1.6;     // Cells
+const BLAST_DAMAGE: f32 = 45.0;    // At the center, falling off to half at the edge
+const BLAST_TIME: f32 = 0.35;      // Seconds the fireball lasts
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PropKind {
+    Crate,
+    Barrel,
+    Keg,
+}
+
+impl PropKind {
+    fn from_name(name: &str) -> Option<PropKind> {
+        match name {
+            "crate" => Some(PropKind::Crate),
+            "barrel" => Some(PropKind::Barrel),
+            "keg" => Some(PropKind::Keg),
+            _ => None,
+        }
+    }
+
+    fn hits(&self) -> u32 {
+        match self {
+            PropKind::Barrel => 2,
+            PropKind::Crate | PropKind::Keg => 1,
+        }
+    }
+
+    // Width and height in wall heights, and the color of the wood
+    fn shape(&self) -> (f32, f32, Color) {
+        match self {
+            PropKind::Crate => (0.4, 0.35, Color::new(150, 105, 60, 255)),
+            PropKind::Barrel => (0.3, 0.45, Color::new(115, 75, 45, 255)),
+            PropKind::Keg => (0.28, 0.38, Color::new(165, 45, 35, 255)),
+        }
+    }
+}
+
+pub struct Prop {
+    pub kind: PropKind,
+    pub pos: Vector2,
+    hits_left: u32,
+    pub broken: Option<f32>, // Seconds since it broke
+}
+
+impl Prop {
+    pub fn is_broken(&self) -> bool {
+        self.broken.is_some()
+    }
+}
+
+// What came out of a broken prop
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Loot {
+    Coins(u32),
+    Flask,
+}
+
+// Everything one swing set off
+#[derive(Default)]
+pub struct Smash {
+    pub broken: usize,
+    pub explosions: usize,
+    pub loot: Vec<(Vector2, Loot)>,
+    pub hits: usize,         // Enemies caught in a blast
+    pub kills: Vec<Vector2>, // Where the blasts killed them
+}
+
+// The `prop` lines of a map's metadata
+pub fn props_from_meta(meta: &[(String, String)], block_size: usize) -> Vec<Prop> {
+    let block = block_size as f32;
+    let mut props = Vec::new();
+    for (key, value) in meta.iter().filter(|(key, _)| key == "prop") {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        let parsed = match parts[..] {
+            [name, x, y] => PropKind::from_name(name).zip(x.parse::<usize>().ok().zip(y.parse::<usize>().ok())),
+            _ => None,
+        };
+        let Some((kind, (x, y))) = parsed else {
+            elog!("Warning: '{} = {}' needs crate, barrel or keg, x and y", key, value);
+            continue;
+        };
+        let pos = Vector2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block);
+        props.push(Prop { kind, pos, hits_left: kind.hits(), broken: None });
+    }
+    if !props.is_empty() {
+        log!("{} breakable props", props.len());
+    }
+    props
+}
+
+// Back to the props a save had broken, already lying in pieces
+pub fn restore_broken(props: &mut [Prop], broken: &[usize]) {
+    for &index in broken {
+        if let Some(prop) = props.get_mut(index) {
+            prop.hits_left = 0;
+            prop.broken = Some(BREAK_TIME);
+        }
+    }
+}
+
+pub fn broken_indices(props: &[Prop]) -> Vec<usize> {
+    props.iter().enumerate().filter(|(_, prop)| prop.is_broken()).map(|(index, _)| index).collect()
+}
+
+pub fn update_props(props: &mut [Prop], delta_time: f32) {
+    for prop in props.iter_mut() {
+        if let Some(time) = prop.broken.as_mut() {
+            *time = (*time + delta_time).min(BREAK_TIME);
+        }
+    }
+}
+
+// A swing at its peak lands on every intact prop in its arc. Returns the ones it broke, for break_props.
+pub fn strike_props(props: &mut [Prop], origin: Vector2, facing: f32, range: f32, arc: f32) -> Vec<usize> {
+    let mut breaking: Vec<usize> = Vec::new();
+    for (index, prop) in props.iter_mut().enumerate() {
+        if prop.is_broken() || swing_hit(origin.into(), facing, prop.pos.into(), range, arc).is_none() {
+            continue;
+        }
+        prop.hits_left = prop.hits_left.saturating_sub(1);
+        if prop.hits_left == 0 {
+            breaking.push(index);
+        }
+    }
+    breaking
+}
+
+// Breaks the props, dropping their loot. Kegs that burst set off whatever is in the blast, so one
+// swing can clear a room.
+pub fn break_props(props: &mut [Prop], mut breaking: Vec<usize>, enemies: &mut [Enemy], maze: &Maze, block_size: usize, rng: &mut Rng) -> Smash {
+    let mut smash = Smash::default();
+    let blast = BLAST_RADIUS * block_size as f32;
+    while let Some(index) = breaking.pop() {
+        let prop = &mut props[index];
+        if prop.is_broken() {
+            continue;
+        }
+        prop.broken = Some(0.0);
+        smash.broken += 1;
+        let (kind, pos) = (prop.kind, prop.pos);
+        match kind {
+            PropKind::Crate => smash.loot.push((pos, Loot::Coins(1 + rng.range(3) as u32))),
+            PropKind::Barrel if rng.next_f32() < 0.5 => smash.loot.push((pos, Loot::Flask)),
+            PropKind::Barrel => smash.loot.push((pos, Loot::Coins(1))),
+            PropKind::Keg => {
+                smash.explosions += 1;
+                log!("Powder keg exploded");
+                let reaches = |target: Vector2| {
+                    let distance = Vec2::from(pos).distance(target.into());
+                    (distance <= blast && line_of_sight(pos.into(), target.into(), maze, block_size)).then_some(distance)
+                };
+                for enemy in enemies.iter_mut().filter(|enemy| !enemy.is_dead) {
+                    let Some(distance) = reaches(enemy.pos) else {
+                        continue;
+                    };
+                    smash.hits += 1;
+                    enemy.awareness = 1.0;
+                    enemy.status.apply(StatusKind::Burning);
+                    if enemy.take_damage(BLAST_DAMAGE * (1.0 - 0.5 * distance / blast)) {
+                        smash.kills.push(enemy.pos);
+                    }
+                }
+                for (other, prop) in props.iter().enumerate() {
+                    if !prop.is_broken() && reaches(prop.pos).is_some() {
+                        breaking.push(other);
+                    }
+                }
+            }
+        }
+    }
+    smash
+}
+
+// Props as floor boxes for render_floor_items: whole ones with their bands, breaking ones as
+// splinters thrown out and falling, a fireball over a keg, and debris once it has settled
+pub fn prop_boxes(props: &[Prop], block_size: usize) -> Vec<FloorItem> {
+    let block = block_size as f32;
+    let mut boxes = Vec::new();
+    for prop in props.iter() {
+        let (width, height, wood) = prop.kind.shape();
+        let band = color::scale(wood, 0.55);
+        let Some(time) = prop.broken else {
+            // Bands first, the body drawn at the same depth only fills in around them
+            match prop.kind {
+                PropKind::Crate => boxes.push((prop.pos, width + 0.01, 0.04, height - 0.04, band)),
+                PropKind::Barrel => {
+                    boxes.push((prop.pos, width + 0.01, 0.03, height * 0.2, band));
+                    boxes.push((prop.pos, width + 0.01, 0.03, height * 0.75, band));
+                }
+                PropKind::Keg => boxes.push((prop.pos, width + 0.01, 0.05, height * 0.45, Color::new(230, 190, 60, 255))),
+            }
+            boxes.push((prop.pos, width, height, 0.0, wood));
+            continue;
+        };
+
+        if time >= BREAK_TIME {
+            boxes.push((prop.pos, width * 1.1, 0.03, 0.0, band));
+            continue;
+        }
+        let t = time / BREAK_TIME;
+        for shard in 0..SHARDS {
+            let angle = shard as f32 * TAU / SHARDS as f32 + prop.pos.x; // Turned per prop so they don't all match
+            let spread = t * 0.6 * block;
+            let pos = Vector2::new(prop.pos.x + angle.cos() * spread, prop.pos.y + angle.sin() * spread);
+            let lift = (height * 0.5 + 1.2 * t - 2.0 * t * t).max(0.0);
+            let size = 0.08 * (1.0 - 0.5 * t);
+            boxes.push((pos, size, size, lift, if shard % 2 == 0 { wood } else { band }));
+        }
+        if prop.kind == PropKind::Keg && time < BLAST_TIME {
+            let grow = time / BLAST_TIME;
+            let size = 0.3 + 0.9 * grow;
+            let fire = color::lerp(Color::new(255, 230, 120, 255), Color::new(220, 70, 20, 255), grow);
+            boxes.push((prop.pos, size, size, 0.1, fire));
+        }
+    }
+    boxes
+}
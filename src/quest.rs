@@ -0,0 +1,130 @@
+// quest.rs
+
+use crate::maze::{Cell, Maze};
+
+// Fetch chains from a map's header: bring an item to a spot, which may hand over the item for
+// the next step, until the last delivery opens the map's quest gates. Steps are done in order
+// and each one's objective is shown on the HUD while it is current; a delivery can leave a note
+// behind as a callout, for whoever or whatever took the item:
+//   quest_item = behelit 3 5                  (item name, the cell it lies on)
+//   quest_step = behelit 12 3 Bring the behelit to the shrine | The shrine hums and a key drops out
+//   quest_step = key 14 9 Unlock the old gate
+//   quest_gate = 15 9                         (a wall cell that opens when the chain is done)
+// An item with no quest_item line is handed over when the step before the one that needs it is
+// delivered. Items are picked up by walking over them and delivered by standing on or next to
+// the step's cell. Saves keep how many steps are done and what is being carried.
+
+pub struct QuestStep {
+    pub item: String,
+    pub target: (usize, usize),
+    pub objective: String,
+    pub note: Option<String>, // Callout shown on delivery
+}
+
+pub struct QuestChain {
+    pub steps: Vec<QuestStep>,
+    pub items: Vec<(String, (usize, usize))>, // Still lying on the map
+    placed: Vec<(String, (usize, usize))>,    // Every quest_item line, to put the chain back on load
+    gates: Vec<(usize, usize)>,
+    pub done: usize,                          // Steps delivered
+    pub carrying: Vec<String>,
+}
+
+// Cell numbers from the start of a header value, and whatever text follows them
+fn cells_and_rest(value: &str, count: usize) -> Option<(Vec<usize>, &str)> {
+    let mut rest = value.trim_start();
+    let mut numbers = Vec::with_capacity(count);
+    for _ in 0..count {
+        let (number, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        numbers.push(number.parse().ok()?);
+        rest = tail.trim_start();
+    }
+    Some((numbers, rest))
+}
+
+impl QuestChain {
+    // None for maps without quest steps
+    pub fn from_meta(meta: &[(String, String)]) -> Option<QuestChain> {
+        let mut chain = QuestChain { steps: Vec::new(), items: Vec::new(), placed: Vec::new(), gates: Vec::new(), done: 0, carrying: Vec::new() };
+        for (key, value) in meta.iter() {
+            match key.as_str() {
+                "quest_item" => match value.split_once(char::is_whitespace).and_then(|(name, rest)| Some((name, cells_and_rest(rest, 2)?))) {
+                    Some((name, (cell, ""))) => chain.placed.push((name.to_string(), (cell[0], cell[1]))),
+                    _ => elog!("Warning: Quest item '{}' needs a name, x and y", value),
+                },
+                "quest_step" => match value.split_once(char::is_whitespace).and_then(|(item, rest)| Some((item, cells_and_rest(rest, 2)?))) {
+                    Some((item, (cell, text))) if !text.is_empty() => {
+                        let (objective, note) = match text.split_once('|') {
+                            Some((objective, note)) => (objective.trim(), Some(note.trim().to_string())),
+                            None => (text, None),
+                        };
+                        chain.steps.push(QuestStep { item: item.to_string(), target: (cell[0], cell[1]), objective: objective.to_string(), note });
+                    }
+                    _ => elog!("Warning: Quest step '{}' needs an item, x, y and an objective", value),
+                },
+                "quest_gate" => match cells_and_rest(value, 2) {
+                    Some((cell, "")) => chain.gates.push((cell[0], cell[1])),
+                    _ => elog!("Warning: Quest gate '{}' needs x and y", value),
+                },
+                _ => {}
+            }
+        }
+        if chain.steps.is_empty() {
+            return None;
+        }
+        log!("Quest chain with {} steps", chain.steps.len());
+        chain.restore(0, &[]);
+        Some(chain)
+    }
+
+    // What the HUD asks for, None once the chain is done
+    pub fn objective(&self) -> Option<&str> {
+        self.steps.get(self.done).map(|step| step.objective.as_str())
+    }
+
+    // Back to `done` steps delivered and `carrying` in hand, as a save left it. Items already
+    // carried or delivered are off the map; gates are opened from the saved map cells.
+    pub fn restore(&mut self, done: usize, carrying: &[String]) {
+        self.done = done.min(self.steps.len());
+        self.carrying = carrying.to_vec();
+        let delivered: Vec<&str> = self.steps[..self.done].iter().map(|step| step.item.as_str()).collect();
+        self.items = self.placed.iter().filter(|(name, _)| !delivered.contains(&name.as_str()) && !carrying.contains(name)).cloned().collect();
+    }
+
+    // Call every frame with the player's cell, returns callouts for what happened
+    pub fn update(&mut self, cell: (usize, usize), maze: &mut Maze) -> Vec<String> {
+        let mut messages = Vec::new();
+        while let Some(index) = self.items.iter().position(|(_, at)| *at == cell) {
+            let (name, _) = self.items.remove(index);
+            messages.push(format!("Picked up the {}", name));
+            self.carrying.push(name);
+        }
+
+        let Some(step) = self.steps.get(self.done) else {
+            return messages;
+        };
+        let (dx, dy) = (cell.0.abs_diff(step.target.0), cell.1.abs_diff(step.target.1));
+        let Some(held) = self.carrying.iter().position(|name| *name == step.item).filter(|_| dx + dy <= 1) else {
+            return messages;
+        };
+
+        self.carrying.remove(held);
+        messages.push(step.note.clone().unwrap_or_else(|| format!("Delivered the {}", step.item)));
+        self.done += 1;
+        log!("Quest step {} of {} done", self.done, self.steps.len());
+
+        // The next step's item comes from here unless it lies somewhere on the map
+        if let Some(next) = self.steps.get(self.done) {
+            if !self.placed.iter().any(|(name, _)| *name == next.item) && !self.carrying.contains(&next.item) {
+                messages.push(format!("Received the {}", next.item));
+                self.carrying.push(next.item.clone());
+            }
+        } else {
+            for &(x, y) in self.gates.iter() {
+                maze.set(x, y, Cell::Empty);
+            }
+            messages.push("Somewhere a gate grinds open".to_string());
+        }
+        messages
+    }
+}
@@ -0,0 +1,141 @@
+// replay.rs
+
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use std::fs;
+
+use crate::player::Player;
+
+pub const DEMO_PATH: &str = "demo.replay"; // Demo run played on the title screen
+const SAMPLE_INTERVAL: f32 = 0.05; // Seconds between recorded frames
+const REPLAY_VERSION: u32 = 1;     // Replays without a version line are version 0, laid out the same
+
+// Player pose at one moment of a recorded run
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayFrame {
+    pub time: f32,
+    pub pos: Vector2,
+    pub angle: f32,
+}
+
+// A recorded run: the map it was played on, its seed and the player's path through it
+pub struct Replay {
+    pub map: String,
+    pub seed: u64, // Seed the map's enemies and loot were rolled from
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new(map: &str, seed: u64) -> Self {
+        Replay {
+            map: map.to_string(),
+            seed,
+            frames: Vec::new(),
+        }
+    }
+
+    // Plain text: "version <n>", "map <file>" and "seed <n>" lines followed by "time x y angle" lines.
+    // Replays from a newer release are refused rather than misread.
+    pub fn load(path: &str) -> Option<Replay> {
+        let contents = fs::read_to_string(path).ok()?;
+        let mut replay = Replay::new("", 0);
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(version) = line.strip_prefix("version ") {
+                match version.trim().parse::<u32>() {
+                    Ok(version) if version <= REPLAY_VERSION => {}
+                    Ok(version) => {
+                        elog!("Error: Replay {} is version {}, but this build only reads up to version {}", path, version, REPLAY_VERSION);
+                        return None;
+                    }
+                    Err(_) => {
+                        elog!("Error: Replay {} has an invalid version: {}", path, line);
+                        return None;
+                    }
+                }
+                continue;
+            }
+            if let Some(map) = line.strip_prefix("map ") {
+                replay.map = map.trim().to_string();
+                continue;
+            }
+            if let Some(seed) = line.strip_prefix("seed ") {
+                match seed.trim().parse() {
+                    Ok(seed) => replay.seed = seed,
+                    Err(_) => elog!("Warning: Ignoring malformed replay seed: {}", line),
+                }
+                continue;
+            }
+
+            let values: Vec<f32> = line.split_whitespace().filter_map(|value| value.parse().ok()).collect();
+            if values.len() != 4 {
+                elog!("Warning: Ignoring malformed replay line: {}", line);
+                continue;
+            }
+            replay.frames.push(ReplayFrame {
+                time: values[0],
+                pos: Vector2::new(values[1], values[2]),
+                angle: values[3],
+            });
+        }
+
+        if replay.map.is_empty() || replay.frames.len() < 2 {
+            elog!("Warning: Replay {} has no map or too few frames", path);
+            return None;
+        }
+        log!("Loaded replay {} ({} frames on {})", path, replay.frames.len(), replay.map);
+        Some(replay)
+    }
+
+    pub fn save(&self, path: &str) {
+        let mut contents = String::from("# Raycaster Dungeon replay\n");
+        contents.push_str(&format!("version {}\n", REPLAY_VERSION));
+        contents.push_str(&format!("map {}\n", self.map));
+        contents.push_str(&format!("seed {}\n", self.seed));
+        for frame in self.frames.iter() {
+            contents.push_str(&format!("{:.2} {:.1} {:.1} {:.3}\n", frame.time, frame.pos.x, frame.pos.y, frame.angle));
+        }
+
+        match fs::write(path, contents) {
+            Ok(_) => log!("Saved replay to {}", path),
+            Err(e) => elog!("Warning: Could not save replay to {}: {:?}", path, e),
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.frames.last().map_or(0.0, |frame| frame.time)
+    }
+
+    // Records the player every SAMPLE_INTERVAL seconds of run time
+    pub fn record(&mut self, time: f32, player: &Player) {
+        if let Some(last) = self.frames.last() && time - last.time < SAMPLE_INTERVAL {
+            return;
+        }
+        self.frames.push(ReplayFrame { time, pos: player.pos, angle: player.a });
+    }
+
+    // Pose at any time, interpolated between the recorded frames
+    pub fn sample(&self, time: f32) -> (Vector2, f32) {
+        let next = self.frames.iter().position(|frame| frame.time > time).unwrap_or(self.frames.len() - 1).max(1);
+        let a = &self.frames[next - 1];
+        let b = &self.frames[next];
+
+        let t = ((time - a.time) / (b.time - a.time).max(0.001)).clamp(0.0, 1.0);
+        let pos = Vector2::new(a.pos.x + (b.pos.x - a.pos.x) * t, a.pos.y + (b.pos.y - a.pos.y) * t);
+
+        // Turn the short way round
+        let mut angle_diff = b.angle - a.angle;
+        while angle_diff > PI {
+            angle_diff -= 2.0 * PI;
+        }
+        while angle_diff < -PI {
+            angle_diff += 2.0 * PI;
+        }
+
+        (pos, a.angle + angle_diff * t)
+    }
+}
@@ -0,0 +1,155 @@
+// report.rs
+
+use crate::create_enemies_for_maze;
+use crate::crash;
+use crate::enemy::{Enemy, MovementPattern};
+use crate::lantern::scatter_flasks;
+use crate::mapstats::walk_distances;
+use crate::maze::{Cell, is_walkable, load_map_meta, load_maze_with_player};
+use crate::ngplus::SpawnTable;
+use crate::pickups::coins_from_maze;
+use crate::props::{PropKind, props_from_meta};
+use crate::rng::Rng;
+use crate::swarm::swarms_from_meta;
+
+// Map balance tool, run as `--report <map> [--ngplus]` without opening a window. It places the
+// enemies, coins, flasks, props and swarms the way starting the map does (with seed 0, which
+// only moves the scattered flasks around, not their number) and prints what the player will
+// meet: enemies by type, how far the goal is, item totals, and the floor that can't be walked
+// to from the spawn. --ngplus reads the map's New Game Plus spawn table instead, if it has one.
+// Walking distances are in cells, four-way, the same way map cards work out par times.
+// The game logs placing everything to stderr here, so the report alone can be piped or diffed.
+
+const KINDS: [&str; 7] = ["patrol", "wander", "chase", "cloaked", "shield", "guard", "arena"];
+const LISTED_CELLS: usize = 20; // Unreachable cells printed one by one, the rest only counted
+
+pub fn run(args: &[String]) -> i32 {
+    let (filename, ng_plus) = match args {
+        [filename] => (filename, false),
+        [filename, flag] if flag == "--ngplus" => (filename, true),
+        _ => {
+            eprintln!("Usage: --report <map> [--ngplus]");
+            return 2;
+        }
+    };
+    let data = match load_maze_with_player(filename) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return 1;
+        }
+    };
+
+    crash::log_to_stderr();
+    let maze = &data.maze;
+    let block_size = data.block_size;
+    let meta = load_map_meta(filename);
+    let mut rng = Rng::new(0);
+    let table = ng_plus.then(|| SpawnTable::from_meta(&meta, block_size));
+    if table.as_ref().is_some_and(|table| table.enemies.is_empty()) {
+        eprintln!("Warning: {} has no New Game Plus enemies, reporting its usual ones", filename);
+    }
+
+    let enemies = create_enemies_for_maze(maze, block_size, table.as_ref(), &mut rng);
+    let coins = match table.as_ref().filter(|table| !table.coins.is_empty()) {
+        Some(table) => table.coins.len(),
+        None => coins_from_maze(maze, block_size).len(),
+    };
+    let flasks = match table.as_ref().filter(|table| !table.flasks.is_empty()) {
+        Some(table) => table.flasks.len(),
+        None => scatter_flasks(maze, block_size, &mut rng.fork()).len(),
+    };
+    let props = props_from_meta(&meta, block_size);
+    let swarms = swarms_from_meta(&meta, block_size, &mut rng.fork());
+
+    let cell_of = |enemy: &Enemy| (enemy.pos.x as usize / block_size, enemy.pos.y as usize / block_size);
+    let index = |(x, y): (usize, usize)| y * maze.width() + x;
+    let goals: Vec<(usize, usize)> = maze.cells().filter(|&(_, _, cell)| cell == Cell::Goal).map(|(x, y, _)| (x, y)).collect();
+    let spawn = maze.cells().find(|&(_, _, cell)| cell == Cell::Spawn).map(|(x, y, _)| (x, y));
+    let to_goal = walk_distances(maze, &goals);
+    let from_spawn = spawn.map(|spawn| walk_distances(maze, &[spawn]));
+
+    println!("Balance report for {}{}", filename, if ng_plus { " (New Game Plus)" } else { "" });
+    println!("  Size: {}x{} cells, {} units per cell", maze.width(), maze.height(), block_size);
+
+    println!("  Enemies: {}", enemies.len());
+    for kind in KINDS {
+        let count = enemies.iter().filter(|enemy| enemy_kind(enemy) == kind).count();
+        if count > 0 {
+            println!("    {:<8} {}", kind, count);
+        }
+    }
+    if !swarms.is_empty() {
+        let critters: usize = swarms.iter().map(|swarm| swarm.critters.len()).sum();
+        println!("  Swarms: {} ({} critters)", swarms.len(), critters);
+    }
+
+    match spawn {
+        None => println!("  Spawn to goal: the map has no spawn"),
+        Some(_) if goals.is_empty() => println!("  Spawn to goal: the map has no goal"),
+        Some(spawn) => match to_goal[index(spawn)] {
+            usize::MAX => println!("  Spawn to goal: unreachable"),
+            steps => println!("  Spawn to goal: {} cells", steps),
+        },
+    }
+    let enemy_distances: Vec<usize> = enemies.iter().map(|enemy| to_goal[index(cell_of(enemy))]).filter(|&steps| steps != usize::MAX).collect();
+    if !enemy_distances.is_empty() {
+        let average = enemy_distances.iter().sum::<usize>() as f32 / enemy_distances.len() as f32;
+        println!("  Enemies to goal: {:.1} cells on average", average);
+    }
+    if let Some(from_spawn) = from_spawn.as_ref() {
+        let distances: Vec<usize> = enemies.iter().map(|enemy| from_spawn[index(cell_of(enemy))]).filter(|&steps| steps != usize::MAX).collect();
+        if !distances.is_empty() {
+            let average = distances.iter().sum::<usize>() as f32 / distances.len() as f32;
+            let nearest = distances.iter().min().copied().unwrap_or(0);
+            println!("  Enemies from spawn: {:.1} cells on average, the nearest {}", average, nearest);
+        }
+        let stranded = enemies.len() - distances.len();
+        if stranded > 0 {
+            println!("  Enemies out of reach: {}", stranded);
+        }
+    }
+
+    let prop_count = |kind: PropKind| props.iter().filter(|prop| prop.kind == kind).count();
+    println!(
+        "  Items: {} coins, {} flasks, {} crates, {} barrels, {} kegs",
+        coins, flasks, prop_count(PropKind::Crate), prop_count(PropKind::Barrel), prop_count(PropKind::Keg)
+    );
+
+    match from_spawn.as_ref() {
+        Some(from_spawn) => {
+            let unreachable: Vec<(usize, usize)> = maze.cells()
+                .filter(|&(x, y, cell)| is_walkable(cell) && from_spawn[index((x, y))] == usize::MAX)
+                .map(|(x, y, _)| (x, y))
+                .collect();
+            println!("  Unreachable cells: {}", unreachable.len());
+            for (x, y) in unreachable.iter().take(LISTED_CELLS) {
+                println!("    ({}, {})", x, y);
+            }
+            if unreachable.len() > LISTED_CELLS {
+                println!("    ...and {} more", unreachable.len() - LISTED_CELLS);
+            }
+        }
+        None => println!("  Unreachable cells: unknown without a spawn"),
+    }
+    0
+}
+
+// The name a map header's spawn lines use for the enemy, arena for ambush spawns
+fn enemy_kind(enemy: &Enemy) -> &'static str {
+    if enemy.arena {
+        return "arena";
+    }
+    if enemy.cloaked {
+        return "cloaked";
+    }
+    if enemy.def.shield.is_some() {
+        return "shield";
+    }
+    match enemy.movement_pattern {
+        MovementPattern::Patrol => "patrol",
+        MovementPattern::Wander => "wander",
+        MovementPattern::Chase => "chase",
+        MovementPattern::Stationary => "guard",
+    }
+}
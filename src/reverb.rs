@@ -0,0 +1,67 @@
+// reverb.rs
+
+// Acoustic zones tagged in a map's metadata. Sound effects played inside one get
+// delayed, quieter echo taps, so the audio reflects the space the player is in.
+// A map header tags rectangles of cells, later lines win where they overlap:
+//   reverb = hall 2 1 8 5    (kind, x, y, width, height in cells)
+//   reverb = cave            (no rectangle: the whole map)
+
+use crate::maze::{MapArea, parse_area};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReverbZone {
+    Dry, // No echo, the default outside tagged areas
+    SmallRoom,
+    LargeHall,
+    Cave,
+}
+
+// Echo taps of a zone: tap n plays `delay * n` seconds late at `decay^n` volume
+#[derive(Clone, Copy, Debug)]
+pub struct ReverbParams {
+    pub taps: u32,
+    pub delay: f32,
+    pub decay: f32,
+    pub pitch: f32, // Caves swallow the highs, a slightly lower pitch stands in for that
+}
+
+impl ReverbZone {
+    pub fn from_name(name: &str) -> Option<ReverbZone> {
+        match name {
+            "dry" | "none" => Some(ReverbZone::Dry),
+            "room" | "small_room" => Some(ReverbZone::SmallRoom),
+            "hall" | "large_hall" => Some(ReverbZone::LargeHall),
+            "cave" => Some(ReverbZone::Cave),
+            _ => None,
+        }
+    }
+
+    pub fn params(self) -> ReverbParams {
+        match self {
+            ReverbZone::Dry => ReverbParams { taps: 0, delay: 0.0, decay: 0.0, pitch: 1.0 },
+            ReverbZone::SmallRoom => ReverbParams { taps: 1, delay: 0.07, decay: 0.3, pitch: 1.0 },
+            ReverbZone::LargeHall => ReverbParams { taps: 2, delay: 0.18, decay: 0.4, pitch: 1.0 },
+            ReverbZone::Cave => ReverbParams { taps: 3, delay: 0.25, decay: 0.45, pitch: 0.94 },
+        }
+    }
+}
+
+// The `reverb` lines of a map's metadata, each zone with the area it covers
+pub fn reverb_areas(meta: &[(String, String)]) -> Vec<(ReverbZone, MapArea)> {
+    let mut areas = Vec::new();
+    for (key, value) in meta.iter().filter(|(key, _)| key == "reverb") {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        let Some(zone) = parts.first().and_then(|name| ReverbZone::from_name(name)) else {
+            elog!("Warning: Unknown reverb zone in '{} = {}', expected room, hall, cave or dry", key, value);
+            continue;
+        };
+        if let Some(area) = parse_area(key, value, &parts[1..]) {
+            areas.push((zone, area));
+        }
+    }
+    areas
+}
+
+pub fn zone_at(areas: &[(ReverbZone, MapArea)], cell: (usize, usize)) -> ReverbZone {
+    areas.iter().rev().find(|(_, area)| area.contains(cell)).map_or(ReverbZone::Dry, |&(zone, _)| zone)
+}
@@ -0,0 +1,103 @@
+// revive.rs
+
+use raylib::prelude::*;
+
+use crate::enemy::Enemy;
+use crate::player::Player;
+use crate::sim::Vec2;
+
+// A player knocked to zero health with a partner at hand goes down instead of dying. Downed,
+// they can only look around while they bleed out; the partner gets them back up by staying at
+// their side long enough, the partner's version of holding interact over them. Enemies drop
+// the downed player and go for the reviver instead, and any of them getting within reach of it
+// starts the revive over. If the bleed-out runs out first the player dies as usual.
+// There is no second player yet, so the companion wisp is the partner: with the wisp along,
+// the player can be revived once every REVIVE_COOLDOWN seconds.
+
+const BLEED_OUT_TIME: f32 = 15.0; // Seconds a downed player has left
+const REVIVE_TIME: f32 = 3.0;     // Seconds the partner has to stay beside them
+const REVIVE_RANGE: f32 = 1.5;    // Cells from the downed player the partner has to be within
+const THREAT_RANGE: f32 = 1.0;    // Cells; an enemy this close to the reviver interrupts it
+const REVIVE_HEALTH: f32 = 0.3;   // Share of max health a revived player gets up with
+const REVIVE_COOLDOWN: f32 = 60.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReviveEvent {
+    Downed,
+    Interrupted,
+    Revived,
+    Died, // Bled out, or went down with nobody to help
+}
+
+struct Downed {
+    bleed_out: f32, // Seconds left
+    progress: f32,  // Seconds the partner has spent reviving, back to 0.0 when interrupted
+}
+
+pub struct Revive {
+    downed: Option<Downed>,
+    cooldown: f32,
+}
+
+impl Revive {
+    pub fn new() -> Self {
+        Revive { downed: None, cooldown: 0.0 }
+    }
+
+    pub fn is_downed(&self) -> bool {
+        self.downed.is_some()
+    }
+
+    // Call every frame once the fighting is done, with where the partner is if there is one
+    pub fn update(&mut self, delta_time: f32, player: &mut Player, reviver: Option<Vector2>, enemies: &[Enemy], block_size: usize) -> Option<ReviveEvent> {
+        let block = block_size as f32;
+        let distance = |a: Vector2, b: Vector2| Vec2::from(a).distance(Vec2::from(b));
+        self.cooldown = (self.cooldown - delta_time).max(0.0);
+
+        let Some(downed) = self.downed.as_mut() else {
+            if !player.is_dead() {
+                return None;
+            }
+            if reviver.is_none() || self.cooldown > 0.0 {
+                return Some(ReviveEvent::Died);
+            }
+            player.status.clear();
+            self.downed = Some(Downed { bleed_out: BLEED_OUT_TIME, progress: 0.0 });
+            log!("Player downed, bleeding out in {:.0}s", BLEED_OUT_TIME);
+            return Some(ReviveEvent::Downed);
+        };
+
+        // Health stays at zero while downed, whatever still lands on the player
+        player.health = 0.0;
+        downed.bleed_out -= delta_time;
+        if downed.bleed_out <= 0.0 {
+            self.downed = None;
+            self.cooldown = REVIVE_COOLDOWN;
+            log!("Player bled out");
+            return Some(ReviveEvent::Died);
+        }
+
+        let reviver = reviver.filter(|&pos| distance(pos, player.pos) <= REVIVE_RANGE * block)?;
+        let threatened = enemies.iter().any(|enemy| !enemy.is_dead && distance(enemy.pos, reviver) <= THREAT_RANGE * block);
+        if threatened {
+            let interrupted = downed.progress > 0.0;
+            downed.progress = 0.0;
+            return interrupted.then_some(ReviveEvent::Interrupted);
+        }
+        downed.progress += delta_time;
+        if downed.progress < REVIVE_TIME {
+            return None;
+        }
+
+        self.downed = None;
+        self.cooldown = REVIVE_COOLDOWN;
+        player.health = player.max_health * REVIVE_HEALTH;
+        log!("Player revived with {:.0} health", player.health);
+        Some(ReviveEvent::Revived)
+    }
+
+    // Share of the revive done and of the bleed-out left, for the HUD
+    pub fn meters(&self) -> Option<(f32, f32)> {
+        self.downed.as_ref().map(|downed| (downed.progress / REVIVE_TIME, downed.bleed_out / BLEED_OUT_TIME))
+    }
+}
@@ -0,0 +1,53 @@
+// rng.rs
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Small xorshift generator so a seed always plays out the same on every machine.
+// Maze generation, coin scattering, enemy wandering and loot all draw from it,
+// so a map's seed is enough to reproduce it in replays and daily runs.
+#[derive(Clone, Copy, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Zero would get xorshift stuck, so mix the seed first
+        Rng { state: seed.wrapping_mul(0x9E3779B97F4A7C15) | 1 }
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // 0..max
+    pub fn range(&mut self, max: usize) -> usize {
+        (self.next() % max.max(1) as u64) as usize
+    }
+
+    // 0.0..1.0
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    // Independent generator for one consumer (an enemy, a loot table), so adding
+    // draws in one place doesn't shift everything else
+    pub fn fork(&mut self) -> Rng {
+        Rng::new(self.next())
+    }
+}
+
+// Fresh seed for runs and hand-made maps
+pub fn seed_from_clock() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(1)
+}
@@ -0,0 +1,114 @@
+// run.rs
+
+use crate::player::Player;
+
+// Permanent boosts offered between floors of a rogue-lite run
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Upgrade {
+    Speed,
+    Damage,
+    MaxHealth,
+    LanternRadius,
+}
+
+pub const ALL_UPGRADES: [Upgrade; 4] = [Upgrade::Speed, Upgrade::Damage, Upgrade::MaxHealth, Upgrade::LanternRadius];
+
+pub const CURRENCY_PER_FLOOR: u32 = 10; // Meta-currency earned for every floor cleared
+
+impl Upgrade {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Upgrade::Speed => "Swift Boots",
+            Upgrade::Damage => "Sharpened Blade",
+            Upgrade::MaxHealth => "Iron Will",
+            Upgrade::LanternRadius => "Brighter Lantern",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Upgrade::Speed => "+15% movement speed",
+            Upgrade::Damage => "+5 sword damage",
+            Upgrade::MaxHealth => "+25 max health",
+            Upgrade::LanternRadius => "See 30% further into the dark",
+        }
+    }
+
+    pub fn apply(&self, player: &mut Player) {
+        match self {
+            Upgrade::Speed => player.move_speed_multiplier += 0.15,
+            Upgrade::Damage => player.attack_damage_bonus += 5.0,
+            Upgrade::MaxHealth => {
+                player.max_health += 25.0;
+                player.health += 25.0;
+            }
+            Upgrade::LanternRadius => player.light_radius *= 1.3,
+        }
+    }
+}
+
+// Progress through one run of generated floors
+pub struct RunState {
+    pub floor: u32, // Current floor, starting at 1
+    base_seed: u64,
+    pub offered: [Upgrade; 3],
+    pub selected: usize,
+    pub taken: Vec<Upgrade>,
+}
+
+impl RunState {
+    pub fn new(base_seed: u64) -> Self {
+        RunState {
+            floor: 1,
+            base_seed,
+            offered: [Upgrade::Speed, Upgrade::Damage, Upgrade::MaxHealth],
+            selected: 0,
+            taken: Vec::new(),
+        }
+    }
+
+    // Picks a saved run back up on the floor it was saved on
+    pub fn resume(base_seed: u64, floor: u32, taken: Vec<Upgrade>) -> Self {
+        RunState { floor, taken, ..RunState::new(base_seed) }
+    }
+
+    pub fn base_seed(&self) -> u64 {
+        self.base_seed
+    }
+
+    pub fn floor_seed(&self) -> u64 {
+        self.base_seed.wrapping_add(self.floor as u64 * 7919)
+    }
+
+    // Floors grow with depth, capped so the deepest floors stay playable
+    pub fn floor_size(&self) -> (usize, usize) {
+        let depth = self.floor as usize;
+        ((4 + depth * 2).min(20), (3 + depth).min(12))
+    }
+
+    // Pick three of the four upgrades, leaving out a different one each floor
+    pub fn offer_upgrades(&mut self) {
+        let skipped = (self.floor_seed() % ALL_UPGRADES.len() as u64) as usize;
+        let mut offered = ALL_UPGRADES.iter().enumerate().filter(|(i, _)| *i != skipped).map(|(_, upgrade)| *upgrade);
+        for slot in self.offered.iter_mut() {
+            *slot = offered.next().unwrap_or(Upgrade::Speed);
+        }
+        self.selected = 0;
+    }
+
+    // Applies the highlighted upgrade and moves on to the next floor
+    pub fn take_selected(&mut self, player: &mut Player) {
+        let upgrade = self.offered[self.selected];
+        upgrade.apply(player);
+        self.taken.push(upgrade);
+        self.floor += 1;
+    }
+
+    pub fn floors_cleared(&self) -> u32 {
+        self.floor - 1
+    }
+
+    pub fn currency_earned(&self) -> u32 {
+        self.floors_cleared() * CURRENCY_PER_FLOOR
+    }
+}
@@ -0,0 +1,101 @@
+// savefile.rs
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+
+// Versioned "key=value" files, shared by the profile and the settings.
+// Each file carries a `version=N` line. Files from an older release are copied to
+// `<file>.v<N>.bak` and migrated one version at a time as they load; files from a
+// newer release are refused, and the caller keeps them untouched instead of saving over them.
+
+// One file format: its current version and the step that upgrades data from `from` to `from + 1`
+pub struct SaveFormat {
+    pub name: &'static str,
+    pub version: u32,
+    pub migrate: fn(from: u32, values: &mut BTreeMap<String, String>),
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Unreadable(String),                    // The file exists but could not be read
+    BadVersion(String),                    // The version line is not a number
+    TooNew { found: u32, supported: u32 }, // Written by a newer release
+    BackupFailed(String),                  // Old file could not be kept before migrating
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Unreadable(e) => write!(f, "could not be read ({})", e),
+            LoadError::BadVersion(version) => write!(f, "has an invalid version '{}'", version),
+            LoadError::TooNew { found, supported } => {
+                write!(f, "is version {}, but this build only reads up to version {}", found, supported)
+            }
+            LoadError::BackupFailed(e) => write!(f, "could not be backed up before upgrading ({})", e),
+        }
+    }
+}
+
+// The version a file was written at and its values
+type Versioned = (u32, BTreeMap<String, String>);
+
+// A file's version and values as written, nothing backed up or migrated. Ok(None) when there is no file yet
+pub fn read_versioned(path: &str, format: &SaveFormat) -> Result<Option<Versioned>, LoadError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(LoadError::Unreadable(e.to_string())),
+    };
+
+    let mut values = BTreeMap::new();
+    let mut version = 0; // Files from before versioning have no version line
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once('=') {
+            Some((key, value)) if key.trim() == "version" => {
+                version = value.trim().parse().map_err(|_| LoadError::BadVersion(value.trim().to_string()))?;
+            }
+            Some((key, value)) => {
+                values.insert(key.trim().to_string(), value.trim().to_string());
+            }
+            None => elog!("Warning: Ignoring malformed {} line: {}", format.name, line),
+        }
+    }
+
+    if version > format.version {
+        return Err(LoadError::TooNew { found: version, supported: format.version });
+    }
+    Ok(Some((version, values)))
+}
+
+// Ok(None) when there is no file yet
+pub fn load_versioned(path: &str, format: &SaveFormat) -> Result<Option<BTreeMap<String, String>>, LoadError> {
+    let Some((version, mut values)) = read_versioned(path, format)? else {
+        return Ok(None);
+    };
+    if version < format.version {
+        let backup = format!("{}.v{}.bak", path, version);
+        fs::copy(path, &backup).map_err(|e| LoadError::BackupFailed(e.to_string()))?;
+        log!("Upgrading {} from version {} to {}, old file kept as {}", path, version, format.version, backup);
+        for from in version..format.version {
+            (format.migrate)(from, &mut values);
+        }
+    }
+    Ok(Some(values))
+}
+
+pub fn save_versioned(path: &str, header: &str, format: &SaveFormat, values: &BTreeMap<String, String>) {
+    let mut contents = format!("# {}\nversion={}\n", header, format.version);
+    for (key, value) in values.iter() {
+        contents.push_str(&format!("{}={}\n", key, value));
+    }
+
+    if let Err(e) = fs::write(path, contents) {
+        elog!("Warning: Could not save {} to {}: {:?}", format.name, path, e);
+    }
+}
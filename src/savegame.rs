@@ -0,0 +1,256 @@
+// savegame.rs
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::automap::{Marker, decode_markers, encode_markers};
+use crate::inventory::{STARTING_CALTROPS, STARTING_TURRETS};
+use crate::lantern::FULL_TANK;
+use crate::mapdelta::MapDelta;
+use crate::mapgen::civil_from_days;
+use crate::run::{ALL_UPGRADES, Upgrade};
+use crate::savefile::{LoadError, SaveFormat, load_versioned, read_versioned, save_versioned};
+
+// Snapshots of a game in progress. The manual slot is written from the pause menu; the
+// game also autosaves on reaching a checkpoint, on finishing a map (a save at the start of
+// the next one) and on descending to a new run floor, rotating through three slots so the
+// oldest autosave is the one replaced. The Daily Dungeon is a one-sitting challenge and is
+// never saved. Enemies are not part of a save and start over when it loads, but the map itself
+// comes back as it was left (see mapdelta.rs): coins picked up stay gone, a cleared arena stays open.
+
+pub const MANUAL_SLOT: &str = "save_manual.txt";
+pub const AUTOSAVE_SLOTS: [&str; 3] = ["autosave_1.txt", "autosave_2.txt", "autosave_3.txt"];
+pub const RUN_MAP_KEY: &str = "run"; // Stands in for a map file in saves of a rogue-lite run
+pub const MAX_LABEL_LENGTH: usize = 24;
+
+const SAVEGAME_FORMAT: SaveFormat = SaveFormat { name: "save game", version: 2, migrate: migrate_savegame };
+
+#[derive(Clone, Debug)]
+pub struct SaveGame {
+    pub map: String,                       // Map file, or RUN_MAP_KEY
+    pub seed: u64,                         // Seed the map was built from
+    pub run_seed: u64,                     // Base seed of the run, unused for map files
+    pub run_floor: u32,
+    pub upgrades: Vec<Upgrade>,            // Taken so far in the run, applied again to base stats on load
+    pub position: Option<(f32, f32, f32)>, // x, y and view angle, None starts at the map's spawn
+    pub health: f32,
+    pub coins: u32,
+    pub potions: u32,
+    pub weapon: usize,                     // Index into WEAPONS
+    pub caltrops: u32,
+    pub turrets: u32,
+    pub run_time: f32,
+    pub splits: Vec<f32>,
+    pub checkpoints: Vec<(usize, usize)>,  // Checkpoint cells already split on
+    pub delta: Option<MapDelta>,           // Changes to the map, None for the map as it starts
+    pub quest_done: usize,                 // Steps of the map's quest chain delivered
+    pub quest_carrying: Vec<String>,       // Quest items in hand
+    pub markers: Vec<Marker>,              // Notes pinned to the full-screen map
+    pub props_broken: Vec<usize>,          // Header order of the props smashed so far
+    pub lantern_fuel: f32,                 // Fraction of a full tank
+    pub new_game_plus: bool,               // Saved during New Game Plus, see ngplus.rs
+    pub reason: String,                    // Shown on the Load Game screen, e.g. "Checkpoint"
+    pub label: String,                     // Name typed for a manual save, empty for autosaves
+    pub saved_at: u64,                     // Unix seconds
+}
+
+impl SaveGame {
+    // The start of a map with base stats and only the starting deployables carried
+    pub fn fresh(map: &str, seed: u64, reason: &str) -> Self {
+        SaveGame {
+            map: map.to_string(),
+            seed,
+            run_seed: 0,
+            run_floor: 1,
+            upgrades: Vec::new(),
+            position: None,
+            health: 100.0,
+            coins: 0,
+            potions: 0,
+            weapon: 0,
+            caltrops: STARTING_CALTROPS,
+            turrets: STARTING_TURRETS,
+            run_time: 0.0,
+            splits: Vec::new(),
+            checkpoints: Vec::new(),
+            delta: None,
+            quest_done: 0,
+            quest_carrying: Vec::new(),
+            markers: Vec::new(),
+            props_broken: Vec::new(),
+            lantern_fuel: FULL_TANK,
+            new_game_plus: false,
+            reason: reason.to_string(),
+            label: String::new(),
+            saved_at: now(),
+        }
+    }
+
+    // Ok(None) for an empty slot
+    pub fn load(path: &str) -> Result<Option<SaveGame>, LoadError> {
+        let Some(values) = load_versioned(path, &SAVEGAME_FORMAT)? else {
+            return Ok(None);
+        };
+        let Some(map) = values.get("map") else {
+            return Err(LoadError::Unreadable("no map".to_string()));
+        };
+
+        let number = |key: &str| values.get(key).and_then(|value| value.parse::<f32>().ok());
+        let count = |key: &str| values.get(key).and_then(|value| value.parse::<u64>().ok());
+        let list = |key: &str| -> Vec<String> {
+            values.get(key).map(|value| value.split(',').filter(|item| !item.is_empty()).map(str::to_string).collect()).unwrap_or_default()
+        };
+
+        let mut save = SaveGame::fresh(map, count("seed").unwrap_or(0), values.get("reason").map_or("", String::as_str));
+        save.run_seed = count("run_seed").unwrap_or(0);
+        save.run_floor = count("run_floor").unwrap_or(1).max(1) as u32;
+        save.upgrades = list("upgrades")
+            .iter()
+            .filter_map(|name| ALL_UPGRADES.iter().find(|upgrade| upgrade.name() == name).copied())
+            .collect();
+        save.position = match (number("x"), number("y"), number("angle")) {
+            (Some(x), Some(y), Some(angle)) => Some((x, y, angle)),
+            _ => None,
+        };
+        save.health = number("health").unwrap_or(save.health);
+        save.coins = count("coins").unwrap_or(0) as u32;
+        save.potions = count("potions").unwrap_or(0) as u32;
+        save.weapon = count("weapon").unwrap_or(0) as usize;
+        save.caltrops = count("caltrops").map_or(save.caltrops, |count| count as u32);
+        save.turrets = count("turrets").map_or(save.turrets, |count| count as u32);
+        save.run_time = number("run_time").unwrap_or(0.0);
+        save.splits = list("splits").iter().filter_map(|time| time.parse().ok()).collect();
+        save.checkpoints = list("checkpoints")
+            .iter()
+            .filter_map(|cell| {
+                let (x, y) = cell.split_once(':')?;
+                Some((x.parse().ok()?, y.parse().ok()?))
+            })
+            .collect();
+        if values.contains_key("arena_cleared") {
+            save.delta = Some(MapDelta::decode(&values));
+        }
+        save.quest_done = count("quest_done").unwrap_or(0) as usize;
+        save.quest_carrying = list("quest_carrying");
+        save.lantern_fuel = number("lantern_fuel").unwrap_or(FULL_TANK).clamp(0.0, FULL_TANK);
+        save.new_game_plus = values.get("new_game_plus").is_some_and(|value| value == "true");
+        save.markers = values.get("markers").map_or(Vec::new(), |value| decode_markers(value));
+        save.props_broken = list("props_broken").iter().filter_map(|index| index.parse().ok()).collect();
+        save.label = values.get("label").cloned().unwrap_or_default();
+        save.saved_at = count("saved_at").unwrap_or(0);
+        Ok(Some(save))
+    }
+
+    pub fn save(&self, path: &str) {
+        let mut values = BTreeMap::new();
+        values.insert("map".to_string(), self.map.clone());
+        values.insert("seed".to_string(), self.seed.to_string());
+        values.insert("run_seed".to_string(), self.run_seed.to_string());
+        values.insert("run_floor".to_string(), self.run_floor.to_string());
+        let upgrades: Vec<&str> = self.upgrades.iter().map(Upgrade::name).collect();
+        values.insert("upgrades".to_string(), upgrades.join(","));
+        if let Some((x, y, angle)) = self.position {
+            values.insert("x".to_string(), format!("{:.2}", x));
+            values.insert("y".to_string(), format!("{:.2}", y));
+            values.insert("angle".to_string(), format!("{:.4}", angle));
+        }
+        values.insert("health".to_string(), format!("{:.1}", self.health));
+        values.insert("coins".to_string(), self.coins.to_string());
+        values.insert("potions".to_string(), self.potions.to_string());
+        values.insert("weapon".to_string(), self.weapon.to_string());
+        values.insert("caltrops".to_string(), self.caltrops.to_string());
+        values.insert("turrets".to_string(), self.turrets.to_string());
+        values.insert("lantern_fuel".to_string(), format!("{:.3}", self.lantern_fuel));
+        values.insert("run_time".to_string(), format!("{:.2}", self.run_time));
+        let splits: Vec<String> = self.splits.iter().map(|time| format!("{:.2}", time)).collect();
+        values.insert("splits".to_string(), splits.join(","));
+        let checkpoints: Vec<String> = self.checkpoints.iter().map(|(x, y)| format!("{}:{}", x, y)).collect();
+        values.insert("checkpoints".to_string(), checkpoints.join(","));
+        if let Some(ref delta) = self.delta {
+            for (key, value) in delta.encode() {
+                values.insert(key.to_string(), value);
+            }
+        }
+        if self.quest_done > 0 || !self.quest_carrying.is_empty() {
+            values.insert("quest_done".to_string(), self.quest_done.to_string());
+            values.insert("quest_carrying".to_string(), self.quest_carrying.join(","));
+        }
+        if !self.markers.is_empty() {
+            values.insert("markers".to_string(), encode_markers(&self.markers));
+        }
+        if !self.props_broken.is_empty() {
+            let broken: Vec<String> = self.props_broken.iter().map(usize::to_string).collect();
+            values.insert("props_broken".to_string(), broken.join(","));
+        }
+        if self.new_game_plus {
+            values.insert("new_game_plus".to_string(), "true".to_string());
+        }
+        values.insert("reason".to_string(), self.reason.clone());
+        if !self.label.is_empty() {
+            values.insert("label".to_string(), self.label.clone());
+        }
+        values.insert("saved_at".to_string(), self.saved_at.to_string());
+        save_versioned(path, "Raycaster Dungeon save game", &SAVEGAME_FORMAT, &values);
+    }
+
+    // "2024-05-17 21:04 UTC"
+    pub fn saved_label(&self) -> String {
+        let (year, month, day) = civil_from_days((self.saved_at / 86_400) as i64);
+        let minutes = self.saved_at % 86_400 / 60;
+        format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, minutes / 60, minutes % 60)
+    }
+}
+
+// The manual slot, refused when it holds a save from a newer release
+pub fn save_manual(save: &SaveGame) -> Result<(), LoadError> {
+    if let Err(e @ LoadError::TooNew { .. }) = read_versioned(MANUAL_SLOT, &SAVEGAME_FORMAT) {
+        elog!("Warning: Not saving over {}, it {}", MANUAL_SLOT, e);
+        return Err(e);
+    }
+    save.save(MANUAL_SLOT);
+    log!("Saved the game to {}", MANUAL_SLOT);
+    Ok(())
+}
+
+// Writes over the oldest autosave, or an empty slot first. Slots from a newer release are left alone.
+// Only each slot's `saved_at` is read, an old slot is not backed up and migrated just to be compared.
+pub fn autosave(save: &SaveGame) {
+    let mut oldest: Option<(&str, u64)> = None;
+    for slot in AUTOSAVE_SLOTS {
+        let saved_at = match read_versioned(slot, &SAVEGAME_FORMAT) {
+            Ok(Some((_, values))) => values.get("saved_at").and_then(|value| value.parse().ok()).unwrap_or(0),
+            Ok(None) | Err(LoadError::Unreadable(_)) | Err(LoadError::BadVersion(_)) => 0,
+            Err(e) => {
+                elog!("Warning: Not autosaving over {}, it {}", slot, e);
+                continue;
+            }
+        };
+        if oldest.is_none_or(|(_, oldest_at)| saved_at < oldest_at) {
+            oldest = Some((slot, saved_at));
+        }
+    }
+
+    match oldest {
+        Some((slot, _)) => {
+            save.save(slot);
+            log!("Autosaved to {} ({})", slot, save.reason);
+        }
+        None => elog!("Warning: No autosave slot could be used"),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
+
+// One step per old version. Saves were first written at version 1.
+fn migrate_savegame(from: u32, values: &mut BTreeMap<String, String>) {
+    match from {
+        // Version 1 kept every coin left in the map as one list, which cannot tell the map's coins
+        // from dropped ones. The map's coins all come back; the player keeps what they carried.
+        1 => {
+            values.remove("pickups");
+        }
+        _ => elog!("Warning: No save game migration from version {}", from),
+    }
+}
@@ -0,0 +1,2198 @@
+// screens.rs
+
+use raylib::prelude::*;
+use std::time::Instant;
+
+use crate::actions::{ACTIONS, Action, ActionMap, BUTTON_NAMES, Binding, INPUT_PRESETS, InputPreset, KEY_NAMES};
+use crate::arena::{Arena, ArenaState};
+use crate::audio::AudioManager;
+use crate::automap::{MapView, RADIAL_OPTIONS, draw_automap, map_layout, place_marker, radial_option};
+use crate::chatter::ChatterTrigger;
+use crate::companion::Companion;
+use crate::debugview::DebugView;
+use crate::deployables::DeployableKind;
+use crate::dialog::ConfirmDialog;
+use crate::director::{DIFFICULTIES, Difficulty, Director};
+use crate::framebuffer::Framebuffer;
+use crate::framediff::{self, PERFORMANCE, QUALITY};
+use crate::gyro::Gyro;
+use crate::hub::{HUB_MAP, Portal, facing_portal, is_unlocked};
+use crate::inventory::{Inventory, SHOP_ITEMS, WEAPONS};
+use crate::lantern::draw_fuel_gauge;
+use crate::mapgen::generate_maze;
+use crate::grading::{Grade, Grading, grade_map};
+use crate::mapstats::MapStats;
+use crate::ngplus;
+use crate::maze::{DEFAULT_BLOCK_SIZE, MazeData, is_floor_zone, load_maze_with_player, maze_data_from};
+use crate::mods::ModList;
+use crate::pickups::Pickup;
+use crate::player::Player;
+use crate::postfx::{Backdrop, PostEffect, PostFxContext, PostProcessor};
+use crate::profile::{MAX_NAME_LENGTH, Profile};
+use crate::props::{broken_indices, restore_broken};
+use crate::prompts::{ButtonPrompts, Prompt};
+use crate::replay::{Replay, DEMO_PATH};
+use crate::rng::seed_from_clock;
+use crate::run::RunState;
+use crate::savefile::LoadError;
+use crate::savegame::{AUTOSAVE_SLOTS, MANUAL_SLOT, MAX_LABEL_LENGTH, RUN_MAP_KEY, SaveGame, autosave, save_manual};
+use crate::settings::{
+    DESKTOP_PRESET, DisplayPreset, HANDHELD_PRESET, MINIMAP_SIZES, MINIMAP_ZOOMS, RENDER_SCALES, SETTINGS_PATH, Settings, UI_SCALES,
+};
+use crate::sim::Vec2;
+use crate::splits::SplitTimer;
+use crate::stealth::draw_gem;
+use crate::stress::FrameProfile;
+use crate::summary::{RunSummary, draw_end_prompts};
+use crate::textures::TextureManager;
+use crate::title::TitleCamera;
+use crate::ui::{self, KeyRepeat, OnScreenKeyboard, TextEvent, TextInput, UiEvent, UiInput, VolumePopup, Widget};
+use crate::timescale::TimeScale;
+use crate::visualizer::MusicVisualizer;
+use crate::world::{FrameInput, World, WorldEvent};
+use crate::{
+    AVAILABLE_MAPS, DAILY_MAP_INDEX, HUB_MAP_INDEX, PAUSE_OPTIONS, RUN_MAP_INDEX, SPLASH_DURATION, STRESS_MAP_INDEX, format_time, generate_daily_maze, goal_prompt,
+    campaign_maps, load_demo, pause_menu_panel, load_map, map_filename, map_index_of, map_name, mods_layout, render_game_over_screen, render_load_menu, render_minimap, render_name_entry, render_pause_menu,
+    render_controls_menu, render_mods_menu, render_settings_menu, render_shop_screen, render_splash_screen, render_start_screen, render_sword,
+    render_upgrade_screen, render_victory_screen, render_world, SettingId, settings_layout, settings_widgets, split_key,
+    step_volume, view_fov, water_tint,
+};
+
+const ATTRACT_IDLE_TIME: f32 = 30.0; // Idle seconds on the start screen before the demo plays
+const BACKDROP_VEIL: u8 = 70;        // Alpha of the dimming drawn over the HUD behind a menu or dialog
+const HUD_MAX_ASPECT: f32 = 16.0 / 9.0; // Widest a pillarboxed HUD gets
+
+// Everything that outlives a single screen: the framebuffer, the world being played,
+// the profile, audio and settings
+pub struct Game<'aud> {
+    pub framebuffer: Framebuffer,
+    pub window_width: i32,
+    pub window_height: i32,
+    pub time: f32,                        // Seconds since launch, for effects and blinking text
+    pub selected_map: usize,
+    pub world: World,
+    pub texture_cache: TextureManager,
+    pub prompts: ButtonPrompts,           // Button glyphs for the last used input device
+    pub run_state: RunState,
+    pub fixed_seed: Option<u64>,          // From --seed, replaces the clock seed of runs and maps
+    pub time_scale: TimeScale,            // Game clock rate, hit-stop and death slow motion
+    pub profile: Profile,
+    pub daily_date: String,               // Daily Dungeon date the game was launched on
+    pub splits: SplitTimer,               // Speedrun splits for the current attempt
+    pub demo: Option<(Replay, MazeData)>, // Recorded run shown when the title screen sits idle
+    pub recording: Replay,                // Current attempt, kept as the new demo if it is quick enough
+    pub run_time: f32,                    // Seconds spent playing the current map
+    pub resumed: bool,                    // The attempt was loaded from a save, so it sets no bests or demo
+    pub campaign: bool,                   // Started from the hub, finished maps lead back to it
+    pub new_game_plus: bool,              // Playing the campaign again with tougher enemies, see ngplus.rs
+    pub audio_manager: AudioManager<'aud>, // Music tracks, footsteps and the mix
+    pub visualizer: MusicVisualizer,      // Start screen effects following the music, see visualizer.rs
+    pub sword_sound: Option<Sound<'aud>>,
+    pub hit_sound: Option<Sound<'aud>>,
+    pub death_sound: Option<Sound<'aud>>,
+    pub gate_sound: Option<Sound<'aud>>,
+    pub critical_sound: Option<Sound<'aud>>, // The hit sound again, pitched up
+    pub show_minimap: bool,
+    pub minimap_zoom: i32,                // Current minimap pixels per cell
+    pub minimap_default_zoom: i32,        // Zoom each map starts at
+    pub minimap_size: i32,
+    pub performance_mode: bool,           // Performance vs quality rendering
+    pub post_processor: PostProcessor,    // Screen effects applied before upload
+    pub settings_error: Option<String>,   // Why settings.txt could not be used, it is then never saved over
+    pub map_error: Option<String>,        // Why the last map could not be started, until one starts
+    pub muted: Option<(bool, f32)>,       // Music switch and SFX volume to save while a --mute launch holds them off
+    pub mods: ModList,                    // Mod folders and their switches, applied at startup
+    pub ui_scale: f32,                    // HUD and menus are laid out for the window divided by this, then drawn enlarged
+    pub render_scale: f32,                // Fraction of the window resolution the 3D view renders at
+    pub companion: bool,                  // Bring the wisp along on every map
+    pub difficulty: Difficulty,           // How hard the director pushes, see director.rs
+    pub shadows: bool,                    // Shadow blobs under sprites
+    pub gyro: Gyro,                       // The controller's motion sensors, read while gyro aim is on
+    pub gyro_aim: bool,
+    pub gyro_sensitivity: f32,
+    pub actions: ActionMap,               // What the keys, buttons and sticks do, from the input preset
+    pub hud_pillarbox: bool,              // Keep the HUD to a HUD_MAX_ASPECT box in the middle of wider windows
+    pub aim_assist: f32,                  // Pull of controller swings toward enemies, off in the Daily Dungeon
+    pub ai_overlay: bool,                 // Enemy AI details on the debug view and the full map, F6
+}
+
+impl<'aud> Game<'aud> {
+    // Fresh seed for a run or map attempt, unless one was fixed on the command line
+    fn seed(&self) -> u64 {
+        self.fixed_seed.unwrap_or_else(seed_from_clock)
+    }
+
+    // A new game starts from base stats, and a run rolls a fresh seed
+    pub fn start_new_game(&mut self, rl: &mut RaylibHandle) -> Result<(), String> {
+        self.world.player.reset_stats();
+        self.world.inventory = Inventory::new();
+        if self.selected_map == RUN_MAP_INDEX {
+            self.run_state = RunState::new(self.seed());
+        }
+        self.campaign = self.selected_map == HUB_MAP_INDEX;
+        self.new_game_plus &= self.campaign;
+        self.start_map(rl)
+    }
+
+    // Load the selected map and begin a fresh attempt on it, music from the top
+    fn start_map(&mut self, rl: &mut RaylibHandle) -> Result<(), String> {
+        let seed = self.seed();
+        self.start_map_seeded(rl, seed)
+    }
+
+    fn start_map_seeded(&mut self, rl: &mut RaylibHandle, seed: u64) -> Result<(), String> {
+        self.world.new_game_plus = self.new_game_plus;
+        load_map(&mut self.world, self.selected_map, &self.run_state, seed)?;
+        self.map_error = None;
+        self.run_time = 0.0;
+        self.resumed = false;
+        self.time_scale.clear();
+        self.minimap_zoom = self.minimap_default_zoom;
+        self.splits = SplitTimer::new(&split_key(self.selected_map, &self.daily_date), &self.profile);
+        self.recording = Replay::new(map_filename(self.selected_map).unwrap_or(""), self.world.seed);
+        self.audio_manager.play_music(self.selected_map);
+        self.audio_manager.play_chatter(ChatterTrigger::MapStart);
+        self.world.companion = self.companion.then(|| Companion::new(self.world.player.pos));
+        self.world.director = Director::new(self.difficulty);
+        self.capture_mouse(rl);
+        Ok(())
+    }
+
+    // A map that could not be loaded sends the player back to the start screen, which says why
+    pub fn map_failed(&mut self, rl: &mut RaylibHandle, error: String) -> Transition {
+        elog!("Error: {}", error);
+        self.map_error = Some(error);
+        self.leave_map(rl);
+        Transition::PopToRoot
+    }
+
+    // Continue from a save: the same map and seed, stats and inventory, and the spot it was saved at
+    pub fn resume(&mut self, rl: &mut RaylibHandle, save: &SaveGame) -> Result<(), String> {
+        let map_index = if save.map == RUN_MAP_KEY {
+            RUN_MAP_INDEX
+        } else {
+            map_index_of(&save.map).ok_or_else(|| format!("The map {} is not available", save.map))?
+        };
+
+        self.selected_map = map_index;
+        self.campaign = map_index == HUB_MAP_INDEX;
+        self.new_game_plus = save.new_game_plus;
+        self.run_state = RunState::resume(save.run_seed, save.run_floor, save.upgrades.clone());
+        self.world.player.reset_stats();
+        for upgrade in save.upgrades.iter() {
+            upgrade.apply(&mut self.world.player);
+        }
+        self.world.inventory = Inventory {
+            coins: save.coins,
+            potions: save.potions,
+            weapon: WEAPONS.get(save.weapon).copied().unwrap_or(WEAPONS[0]),
+            caltrops: save.caltrops,
+            turrets: save.turrets,
+            deployable: DeployableKind::Caltrops,
+        };
+        self.start_map_seeded(rl, save.seed)?;
+
+        let player = &mut self.world.player;
+        player.health = save.health.clamp(1.0, player.max_health);
+        player.lantern.fuel = save.lantern_fuel;
+        if let Some((x, y, angle)) = save.position {
+            player.pos = Vector2::new(x, y);
+            player.a = angle;
+        }
+        if let Some(ref delta) = save.delta {
+            self.world.apply_delta(delta);
+        }
+        if let Some(quest) = self.world.quest.as_mut() {
+            quest.restore(save.quest_done, &save.quest_carrying);
+        }
+        self.world.markers = save.markers.clone();
+        restore_broken(&mut self.world.props, &save.props_broken);
+        self.run_time = save.run_time;
+        self.splits.restore(save.splits.clone(), save.checkpoints.clone());
+        self.resumed = true;
+        log!("Resumed {} from a save ({})", map_name(map_index), save.reason);
+        Ok(())
+    }
+
+    // Snapshot of the current attempt, None for the Daily Dungeon, which is never saved.
+    // `here` keeps the player's spot and what has changed in the map, otherwise it resumes at the map's spawn.
+    pub fn snapshot(&self, reason: &str, here: bool) -> Option<SaveGame> {
+        let map = if self.selected_map == RUN_MAP_INDEX { RUN_MAP_KEY } else { map_filename(self.selected_map)? };
+        let player = &self.world.player;
+        let inventory = &self.world.inventory;
+
+        let mut save = SaveGame::fresh(map, self.world.seed, reason);
+        save.run_seed = self.run_state.base_seed();
+        save.run_floor = self.run_state.floor;
+        save.upgrades = self.run_state.taken.clone();
+        save.health = player.health;
+        save.coins = inventory.coins;
+        save.potions = inventory.potions;
+        save.weapon = WEAPONS.iter().position(|weapon| weapon.name == inventory.weapon.name).unwrap_or(0);
+        save.caltrops = inventory.caltrops;
+        save.turrets = inventory.turrets;
+        save.lantern_fuel = player.lantern.fuel;
+        save.new_game_plus = self.new_game_plus;
+        save.run_time = self.run_time;
+        save.splits = self.splits.splits.clone();
+        if here {
+            save.position = Some((player.pos.x, player.pos.y, player.a));
+            save.checkpoints = self.splits.touched().to_vec();
+            save.delta = Some(self.world.delta());
+            if let Some(ref quest) = self.world.quest {
+                save.quest_done = quest.done;
+                save.quest_carrying = quest.carrying.clone();
+            }
+            save.markers = self.world.markers.clone();
+            save.props_broken = broken_indices(&self.world.props);
+        }
+        Some(save)
+    }
+
+    // Size the HUD and menus lay themselves out in
+    pub fn ui_width(&self) -> i32 {
+        (self.window_width as f32 / self.ui_scale) as i32
+    }
+
+    pub fn ui_height(&self) -> i32 {
+        (self.window_height as f32 / self.ui_scale) as i32
+    }
+
+    // UI units left empty either side of a pillarboxed HUD
+    pub fn hud_inset(&self) -> i32 {
+        let max_width = (self.ui_height() as f32 * HUD_MAX_ASPECT) as i32;
+        if self.hud_pillarbox { ((self.ui_width() - max_width) / 2).max(0) } else { 0 }
+    }
+
+    pub fn hud_width(&self) -> i32 {
+        self.ui_width() - 2 * self.hud_inset()
+    }
+
+    pub fn framebuffer_size(&self) -> (u32, u32) {
+        let width = (self.window_width as f32 * self.render_scale) as u32;
+        let height = (self.window_height as f32 * self.render_scale) as u32;
+        (width.max(1), height.max(1))
+    }
+
+    // The 3D view renders into a smaller framebuffer that is stretched over the window when drawn
+    pub fn resize_framebuffer(&mut self) {
+        let (width, height) = self.framebuffer_size();
+        self.framebuffer = Framebuffer::new(width, height);
+        self.framebuffer.set_background_color(Color::new(50, 50, 100, 255));
+        self.world.player.set_fov(view_fov(width, height));
+    }
+
+    pub fn toggle_ai_overlay(&mut self) {
+        self.ai_overlay = !self.ai_overlay;
+        log!("Enemy AI overlay {}", if self.ai_overlay { "on" } else { "off" });
+    }
+
+    // Step through the zoom levels, positive zooms in
+    fn zoom_minimap(&mut self, steps: i32) {
+        let current = MINIMAP_ZOOMS.iter().position(|zoom| *zoom == self.minimap_zoom).unwrap_or(2) as i32;
+        let level = (current + steps).clamp(0, MINIMAP_ZOOMS.len() as i32 - 1);
+        self.minimap_zoom = MINIMAP_ZOOMS[level as usize];
+    }
+
+    // Mouse look: hide the cursor and center it
+    fn capture_mouse(&self, rl: &mut RaylibHandle) {
+        rl.disable_cursor();
+        rl.set_mouse_position(Vector2::new(self.window_width as f32 / 2.0, self.window_height as f32 / 2.0));
+    }
+
+    // Drop the map and its enemies and stop its music when going back to main menu
+    fn leave_map(&mut self, rl: &mut RaylibHandle) {
+        self.world.unload();
+        rl.enable_cursor();
+        self.audio_manager.stop_music();
+    }
+
+    fn set_music_volume(&mut self, volume: f32) {
+        self.audio_manager.set_music_volume(volume);
+    }
+
+    pub fn set_sfx_volume(&mut self, volume: f32) {
+        self.audio_manager.set_sfx_volume(volume);
+        self.apply_sfx_volumes();
+        if let Some((_, sfx_volume)) = self.muted.as_mut() {
+            *sfx_volume = volume;
+        }
+    }
+
+    // Changed by the player, so it is also what gets saved during a --mute launch
+    pub fn set_music_enabled(&mut self, enabled: bool) {
+        self.audio_manager.set_music_enabled(enabled);
+        if let Some((music_enabled, _)) = self.muted.as_mut() {
+            *music_enabled = enabled;
+        }
+    }
+
+    // --mute: music and sound effects off for this launch only. Settings keep saving the
+    // loaded values, unless the player changes them meanwhile.
+    pub fn mute(&mut self) {
+        let held = (self.audio_manager.is_music_enabled(), self.audio_manager.get_sfx_volume());
+        self.set_music_enabled(false);
+        self.set_sfx_volume(0.0);
+        self.muted = Some(held);
+    }
+
+    // Master scales every group, so the one-shot sounds are re-applied too
+    fn set_master_volume(&mut self, volume: f32) {
+        self.audio_manager.set_master_volume(volume);
+        self.apply_sfx_volumes();
+    }
+
+    fn apply_sfx_volumes(&mut self) {
+        self.audio_manager.setup_combat_sounds(&mut self.sword_sound, &mut self.hit_sound, &mut self.death_sound);
+        self.audio_manager.setup_gate_sound(&mut self.gate_sound);
+        self.audio_manager.setup_critical_sound(&mut self.critical_sound);
+    }
+
+    pub fn settings(&self) -> Settings {
+        Settings {
+            performance_mode: self.performance_mode,
+            render_scale: self.render_scale,
+            ui_scale: self.ui_scale,
+            show_minimap: self.show_minimap,
+            minimap_zoom: self.minimap_default_zoom,
+            minimap_size: self.minimap_size,
+            music_enabled: self.muted.map_or(self.audio_manager.is_music_enabled(), |(music_enabled, _)| music_enabled),
+            master_volume: self.audio_manager.get_master_volume(),
+            music_volume: self.audio_manager.get_music_volume(),
+            sfx_volume: self.muted.map_or(self.audio_manager.get_sfx_volume(), |(_, sfx_volume)| sfx_volume),
+            berserk: self.post_processor.is_enabled(PostEffect::Berserk),
+            scanlines: self.post_processor.is_enabled(PostEffect::Scanlines),
+            feedback_fx: self.post_processor.is_enabled(PostEffect::LowHealth),
+            companion: self.companion,
+            shadows: self.shadows,
+            difficulty: self.difficulty,
+            gyro_aim: self.gyro_aim,
+            gyro_sensitivity: self.gyro_sensitivity,
+            input_preset: self.actions.preset,
+            hud_pillarbox: self.hud_pillarbox,
+            aim_assist: self.aim_assist,
+            rebinds: self.actions.rebinds().to_vec(),
+        }
+    }
+
+    pub fn apply_display_preset(&mut self, preset: &DisplayPreset) {
+        self.performance_mode = preset.performance_mode;
+        self.render_scale = preset.render_scale;
+        self.ui_scale = preset.ui_scale;
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.performance_mode = settings.performance_mode;
+        self.render_scale = settings.render_scale;
+        self.ui_scale = settings.ui_scale;
+        self.show_minimap = settings.show_minimap;
+        self.minimap_zoom = settings.minimap_zoom;
+        self.minimap_default_zoom = settings.minimap_zoom;
+        self.minimap_size = settings.minimap_size;
+        self.audio_manager.set_music_enabled(settings.music_enabled);
+        self.audio_manager.set_music_volume(settings.music_volume);
+        self.audio_manager.set_sfx_volume(settings.sfx_volume);
+        self.set_master_volume(settings.master_volume);
+        self.post_processor.set_enabled(PostEffect::Berserk, settings.berserk);
+        self.post_processor.set_enabled(PostEffect::Scanlines, settings.scanlines);
+        self.post_processor.set_enabled(PostEffect::LowHealth, settings.feedback_fx);
+        self.post_processor.set_enabled(PostEffect::Underwater, settings.feedback_fx);
+        self.companion = settings.companion;
+        self.shadows = settings.shadows;
+        self.difficulty = settings.difficulty;
+        self.gyro_aim = settings.gyro_aim;
+        self.gyro_sensitivity = settings.gyro_sensitivity;
+        self.set_input_preset(settings.input_preset);
+        for &(action, binding) in settings.rebinds.iter() {
+            self.rebind(action, binding);
+        }
+        self.hud_pillarbox = settings.hud_pillarbox;
+        self.aim_assist = settings.aim_assist;
+    }
+
+    // A fresh preset drops any rebinds
+    pub fn set_input_preset(&mut self, preset: InputPreset) {
+        self.actions = ActionMap::new(preset);
+        self.prompts.preset = preset;
+        self.prompts.key_labels.clear();
+    }
+
+    // The HUD's keyboard prompts follow rebound keys
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        self.actions.rebind(action, binding);
+        let prompt = match action {
+            Action::Attack => Prompt::Attack,
+            Action::Sprint => Prompt::Sprint,
+            Action::Potion => Prompt::Potion,
+            Action::Command => Prompt::Command,
+            Action::Switch => Prompt::Switch,
+            Action::Deploy => Prompt::Interact,
+            _ => return,
+        };
+        if let Binding::Key(_) = binding {
+            self.prompts.key_labels.retain(|(labelled, _)| *labelled != prompt);
+            self.prompts.key_labels.push((prompt, binding.name()));
+        }
+    }
+
+    pub fn save_settings(&self) {
+        if self.settings_error.is_none() {
+            self.settings().save(SETTINGS_PATH);
+        }
+    }
+
+    // Profile, settings and map problems, shown on the start screen
+    fn load_errors(&self) -> Vec<&str> {
+        self.profile.error().into_iter().chain(self.settings_error.as_deref()).chain(self.map_error.as_deref()).collect()
+    }
+}
+
+// What the top screen asks of the stack after its update
+pub enum Transition {
+    None,
+    Push(Box<dyn Screen>),    // Open a screen over this one
+    Pop,                      // Close this screen, back to the one below
+    Replace(Box<dyn Screen>), // Swap this screen for another
+    PopToRoot,                // Close everything down to the start screen
+    Quit,
+}
+
+pub trait Screen {
+    // Input and simulation for one frame, only the top screen updates
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, delta_time: f32) -> Transition;
+
+    // Software rendering into the framebuffer, true if the framebuffer should be shown
+    fn render(&mut self, _game: &mut Game, _delta_time: f32) -> bool {
+        false
+    }
+
+    // Raylib drawing on top of the framebuffer
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle);
+
+    // Menus and dialogs over the game: how to blur and darken the frame behind them
+    fn backdrop(&self, _game: &Game) -> Option<Backdrop> {
+        None
+    }
+
+    // HUDs keep to the middle of ultrawide windows when the HUD is pillarboxed, drawn into a
+    // game.hud_width() wide area
+    fn pillarboxed(&self) -> bool {
+        false
+    }
+
+    // Overlays keep the screen below them visible, frozen
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+// Open screens from bottom to top, e.g. Settings over Pause over Playing over the start screen
+pub struct ScreenStack {
+    screens: Vec<Box<dyn Screen>>,
+}
+
+impl ScreenStack {
+    pub fn new(root: Box<dyn Screen>) -> Self {
+        ScreenStack { screens: vec![root] }
+    }
+
+    pub fn push(&mut self, screen: Box<dyn Screen>) {
+        self.screens.push(screen);
+    }
+
+    // Update the top screen and apply its transition, false once the game should quit
+    pub fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, delta_time: f32) -> bool {
+        let transition = match self.screens.last_mut() {
+            Some(screen) => screen.update(game, rl, delta_time),
+            None => return false,
+        };
+
+        match transition {
+            Transition::None => {}
+            Transition::Push(screen) => self.screens.push(screen),
+            Transition::Pop => {
+                if self.screens.len() > 1 {
+                    self.screens.pop();
+                }
+            }
+            Transition::Replace(screen) => {
+                self.screens.pop();
+                self.screens.push(screen);
+            }
+            Transition::PopToRoot => self.screens.truncate(1),
+            Transition::Quit => return false,
+        }
+        true
+    }
+
+    // Render and draw the topmost full screen and every overlay above it
+    pub fn draw(&mut self, game: &mut Game, rl: &mut RaylibHandle, thread: &RaylibThread, delta_time: f32) {
+        let base = self.screens.iter().rposition(|screen| !screen.is_overlay()).unwrap_or(0);
+
+        let mut show_framebuffer = false;
+        for screen in self.screens[base..].iter_mut() {
+            show_framebuffer |= screen.render(game, delta_time);
+        }
+        // Menus and dialogs frost the frame behind them
+        let (ui_width, ui_height) = (game.ui_width(), game.ui_height());
+        if show_framebuffer {
+            let backdrops: Vec<Backdrop> = self.screens[base..].iter().filter_map(|screen| screen.backdrop(game)).collect();
+            for backdrop in backdrops.iter() {
+                game.post_processor.apply_backdrop(&mut game.framebuffer, backdrop, ui_width, ui_height);
+            }
+        }
+        let framebuffer_texture = if show_framebuffer {
+            game.framebuffer.get_texture(rl, thread).ok()
+        } else {
+            None
+        };
+
+        let mut d = rl.begin_drawing(thread);
+        d.clear_background(Color::BLACK);
+        if let Some(ref texture) = framebuffer_texture {
+            let stretch = game.window_width as f32 / game.framebuffer.width as f32;
+            d.draw_texture_ex(texture, Vector2::zero(), 0.0, stretch, Color::WHITE);
+        }
+
+        // Screens draw in UI coordinates, zoomed up to the window
+        for screen in self.screens[base..].iter() {
+            let inset = if screen.pillarboxed() { game.hud_inset() } else { 0 };
+            let offset = Vector2::new(inset as f32 * game.ui_scale, 0.0);
+            let camera = Camera2D { offset, target: Vector2::zero(), rotation: 0.0, zoom: game.ui_scale };
+            let mut d = d.begin_mode2D(camera);
+            // The HUD and menus drawn so far get a light veil, as the frame itself is already
+            // darkened. With no frame to darken, the whole dimming is done here.
+            if let Some(backdrop) = screen.backdrop(game) {
+                let alpha = if framebuffer_texture.is_some() { BACKDROP_VEIL } else { (backdrop.darken * 255.0) as u8 };
+                d.draw_rectangle(0, 0, ui_width, ui_height, Color::new(0, 0, 0, alpha));
+            }
+            screen.draw(game, &mut d);
+        }
+    }
+}
+
+pub struct SplashScreen {
+    timer: f32,
+}
+
+impl SplashScreen {
+    pub fn new() -> Self {
+        SplashScreen { timer: 0.0 }
+    }
+}
+
+impl Screen for SplashScreen {
+    fn update(&mut self, _game: &mut Game, rl: &mut RaylibHandle, delta_time: f32) -> Transition {
+        self.timer += delta_time;
+
+        // Any key, click or controller button skips the intro
+        let skip_pressed = rl.get_key_pressed().is_some() ||
+            rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) ||
+            (rl.is_gamepad_available(0) &&
+                (rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN) ||
+                 rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT)));
+
+        if skip_pressed || self.timer >= SPLASH_DURATION {
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        render_splash_screen(d, self.timer, game.ui_width(), game.ui_height());
+    }
+}
+
+// What the start screen's cards and header show, gathered from the profile each frame
+pub struct StartMenu<'a> {
+    pub selected_map: usize,
+    pub gamepad: Option<String>, // The connected controller's name
+    pub daily_date: &'a str,
+    pub daily_best: Option<f32>,
+    pub run_best_floor: u32,
+    pub meta_currency: u32,
+    pub maps_cleared: usize,
+    pub ng_plus: Option<bool>, // None until New Game Plus is unlocked, then whether the campaign card is set to it
+    pub player_name: &'a str,
+    pub map_stats: &'a [MapStats],
+    pub best_grades: Vec<(Option<Grade>, Option<Grade>)>, // One per hand-made map, the first campaign's and New Game Plus's
+    pub load_errors: Vec<&'a str>,
+}
+
+// Map selection over a fly-through of the selected map, or the attract demo when idle
+pub struct StartScreen {
+    title_mazes: Vec<MazeData>, // Every map, for the animated background
+    map_stats: Vec<MapStats>,   // One per hand-made map, for its card
+    title_camera: TitleCamera,
+    title_map: usize,           // Map the camera is currently flying through
+    attract_idle: f32,          // Seconds without input
+    attract_time: Option<f32>,  // Playback time while the attract demo is showing
+}
+
+impl StartScreen {
+    pub fn new(game: &Game) -> Self {
+        // Preload every map for the animated title screen background. A map that cannot be read
+        // flies through a generated maze instead, and says why once it is picked.
+        let (preview_width, preview_height) = game.run_state.floor_size();
+        let run_preview = || maze_data_from(generate_maze(preview_width, preview_height, game.run_state.floor_seed()), DEFAULT_BLOCK_SIZE);
+        let preload = |filename: &str| {
+            load_maze_with_player(filename).unwrap_or_else(|e| {
+                elog!("Warning: {}", e);
+                run_preview()
+            })
+        };
+        let mut title_mazes: Vec<MazeData> = AVAILABLE_MAPS
+            .iter()
+            .map(|map| preload(map.filename))
+            .collect();
+        let map_stats = AVAILABLE_MAPS
+            .iter()
+            .zip(title_mazes.iter())
+            .map(|(map, data)| MapStats::scan(map.filename, data))
+            .collect();
+        title_mazes.push(generate_daily_maze());
+        title_mazes.push(run_preview());
+        title_mazes.push(preload(HUB_MAP));
+
+        let title_camera = TitleCamera::new(title_maze(&title_mazes, game.selected_map).player_start);
+        StartScreen {
+            title_mazes,
+            map_stats,
+            title_camera,
+            title_map: game.selected_map,
+            attract_idle: 0.0,
+            attract_time: None,
+        }
+    }
+}
+
+// The stress map has no card or preview, the first map stands in for it
+fn title_maze(title_mazes: &[MazeData], map_index: usize) -> &MazeData {
+    title_mazes.get(map_index).unwrap_or(&title_mazes[0])
+}
+
+impl Screen for StartScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, delta_time: f32) -> Transition {
+        // Check for controller connection
+        let gamepad_available = rl.is_gamepad_available(0);
+
+        // Handle start screen input - Controller takes priority
+        let mut input_handled = false;
+
+        // Any input resets the idle timer, and the first press only stops the attract demo
+        let mouse_delta = rl.get_mouse_delta();
+        let any_input = rl.get_key_pressed().is_some() ||
+            rl.get_gamepad_button_pressed().is_some() ||
+            rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) ||
+            mouse_delta.x != 0.0 || mouse_delta.y != 0.0;
+        if any_input {
+            self.attract_idle = 0.0;
+            if self.attract_time.take().is_some() {
+                input_handled = true;
+            }
+        } else {
+            self.attract_idle += delta_time;
+            if self.attract_time.is_none() && self.attract_idle >= ATTRACT_IDLE_TIME && game.demo.is_some() {
+                self.attract_time = Some(0.0);
+            }
+        }
+
+        // Attract mode plays the recorded demo on a loop
+        if let (Some(time), Some((replay, _))) = (self.attract_time.as_mut(), game.demo.as_ref()) {
+            *time += delta_time;
+            if *time > replay.duration() {
+                *time = 0.0;
+            }
+        }
+
+        // Left and right switch the campaign to New Game Plus once the first campaign is finished
+        let switch_mode = rl.is_key_pressed(KeyboardKey::KEY_LEFT) || rl.is_key_pressed(KeyboardKey::KEY_RIGHT) ||
+            (gamepad_available && (rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT) ||
+                                   rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT)));
+        if !input_handled && switch_mode && game.selected_map == HUB_MAP_INDEX && ngplus::unlocked(&game.profile, &campaign_maps()) {
+            game.new_game_plus = !game.new_game_plus;
+            input_handled = true;
+        }
+
+        if gamepad_available && !input_handled {
+            // D-Pad navigation
+            if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP) && game.selected_map > 0 {
+                game.selected_map -= 1;
+                input_handled = true;
+            }
+            if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN) && game.selected_map < HUB_MAP_INDEX {
+                game.selected_map += 1;
+                input_handled = true;
+            }
+
+            // X button (Cross) or A button to confirm
+            if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN) ||
+               rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT) {
+                if let Err(e) = game.start_new_game(rl) {
+                    return game.map_failed(rl, e);
+                }
+                return Transition::Push(Box::new(PlayingScreen::new()));
+            }
+
+            // Square or X lists the saves
+            if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT) {
+                return Transition::Push(Box::new(LoadGameScreen::new()));
+            }
+        }
+
+        // Keyboard fallback if no controller input
+        if !input_handled {
+            if rl.is_key_pressed(KeyboardKey::KEY_UP) && game.selected_map > 0 {
+                game.selected_map -= 1;
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_DOWN) && game.selected_map < HUB_MAP_INDEX {
+                game.selected_map += 1;
+            }
+
+            if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+                if let Err(e) = game.start_new_game(rl) {
+                    return game.map_failed(rl, e);
+                }
+                return Transition::Push(Box::new(PlayingScreen::new()));
+            }
+
+            if rl.is_key_pressed(KeyboardKey::KEY_L) {
+                return Transition::Push(Box::new(LoadGameScreen::new()));
+            }
+
+            if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                return Transition::Quit; // Exit game from start screen
+            }
+        }
+
+        // Restart the fly-through when the selection changes
+        if self.title_map != game.selected_map {
+            self.title_map = game.selected_map;
+            self.title_camera.reset(title_maze(&self.title_mazes, game.selected_map).player_start);
+        }
+
+        // The selected map's track plays behind the menu, and the title moves with it
+        if game.audio_manager.current_track() != Some(game.selected_map) {
+            game.audio_manager.play_music(game.selected_map);
+        }
+        game.visualizer.update(delta_time, game.audio_manager.current_track(), game.audio_manager.music_time());
+        Transition::None
+    }
+
+    fn render(&mut self, game: &mut Game, delta_time: f32) -> bool {
+        game.framebuffer.clear();
+        match (self.attract_time, game.demo.as_ref()) {
+            (Some(time), Some((replay, demo_maze))) => {
+                let (pos, angle) = replay.sample(time);
+                let demo_camera = Player::new(pos, angle, view_fov(game.framebuffer.width, game.framebuffer.height), 0.0);
+                render_world(&mut game.framebuffer, &demo_maze.maze, demo_maze.block_size, &demo_camera, &game.texture_cache, game.performance_mode, None);
+            }
+            _ => {
+                // Fly the scripted camera through the selected map
+                let preview = title_maze(&self.title_mazes, game.selected_map);
+                self.title_camera.camera.set_fov(view_fov(game.framebuffer.width, game.framebuffer.height));
+                self.title_camera.update(&mut game.framebuffer, &preview.maze, preview.block_size, delta_time);
+                render_world(&mut game.framebuffer, &preview.maze, preview.block_size, &self.title_camera.camera, &game.texture_cache, game.performance_mode, None);
+            }
+        }
+        true
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        let gamepad = d.is_gamepad_available(0).then(|| d.get_gamepad_name(0).unwrap_or("Controller".to_string()));
+
+        let menu = StartMenu {
+            selected_map: game.selected_map,
+            gamepad,
+            daily_date: &game.daily_date,
+            daily_best: game.profile.daily_best(&game.daily_date),
+            run_best_floor: game.profile.run_best_floor(),
+            meta_currency: game.profile.meta_currency(),
+            maps_cleared: AVAILABLE_MAPS.iter().filter(|map| game.profile.map_completed(map.filename, game.new_game_plus)).count(),
+            ng_plus: ngplus::unlocked(&game.profile, &campaign_maps()).then_some(game.new_game_plus),
+            player_name: game.profile.player_name(),
+            map_stats: &self.map_stats,
+            best_grades: AVAILABLE_MAPS.iter()
+                .map(|map| (game.profile.best_grade(map.filename, false), game.profile.best_grade(map.filename, true)))
+                .collect(),
+            load_errors: game.load_errors(),
+        };
+        render_start_screen(d, &menu, &game.prompts, &game.visualizer, game.ui_width(), game.ui_height());
+        if self.attract_time.is_some() {
+            let blink = (game.time * 2.0) as i32 % 2 == 0;
+            d.draw_text("DEMO", 30, 30, 40, Color::RED);
+            if blink {
+                d.draw_text("Press any button", 30, 75, 20, Color::WHITE);
+            }
+        }
+    }
+}
+
+// The game itself: world simulation, HUD and quick setting keys
+pub struct PlayingScreen {
+    objective_prompt: Option<String>, // Objective hint for the HUD
+    volume_popup: VolumePopup,        // Bar shown briefly when a volume changes
+    music_volume_keys: KeyRepeat,
+    sfx_volume_keys: KeyRepeat,
+    debug_view: DebugView,            // F4 overhead view of the rays and enemy AI
+    portal: Option<(Portal, bool)>,   // Hub portal in front of the player and whether it is open
+    frame_profile: FrameProfile,      // Timings logged on the stress map
+}
+
+impl PlayingScreen {
+    pub fn new() -> Self {
+        PlayingScreen {
+            objective_prompt: None,
+            volume_popup: VolumePopup::new(),
+            music_volume_keys: KeyRepeat::new(),
+            sfx_volume_keys: KeyRepeat::new(),
+            debug_view: DebugView::new(),
+            portal: None,
+            frame_profile: FrameProfile::new(),
+        }
+    }
+
+    // Autosplit on the goal, then an upgrade pick for runs or the victory screen
+    fn finish_map(game: &mut Game, rl: &mut RaylibHandle) -> Transition {
+        // Keep the splits if they beat the personal best
+        game.splits.split(game.run_time);
+        if !game.resumed && game.splits.commit_if_best(&mut game.profile) {
+            log!("New personal best splits for {}", map_name(game.selected_map));
+            game.profile.save();
+        }
+        game.splits.export(map_name(game.selected_map));
+
+        // A quicker finish on a map file replaces the title screen demo
+        if map_filename(game.selected_map).is_some() && !game.resumed &&
+           game.demo.as_ref().is_none_or(|(replay, _)| game.recording.duration() < replay.duration()) {
+            game.recording.save(DEMO_PATH);
+            game.demo = load_demo();
+        }
+
+        // Daily Dungeon times are tracked per day in the profile
+        if game.selected_map == DAILY_MAP_INDEX {
+            if game.profile.record_daily_time(&game.daily_date, game.run_time) {
+                log!("New Daily Dungeon best for {}: {}", game.daily_date, format_time(game.run_time));
+            }
+            game.profile.save();
+        }
+
+        // Finished map files open the next portal in the hub, and are graded against the map's thresholds
+        let mut grading = None;
+        if let Some(map) = AVAILABLE_MAPS.get(game.selected_map) {
+            game.profile.record_map_completed(map.filename, game.new_game_plus);
+            match load_maze_with_player(map.filename) {
+                Ok(data) => {
+                    let stats = MapStats::scan(map.filename, &data);
+                    let result = grade_map(map.filename, &stats, game.run_time, &game.world.tally, game.world.coins_found());
+                    let best = game.profile.record_grade(map.filename, game.new_game_plus, result.grade);
+                    grading = Some((result, best));
+                }
+                Err(e) => elog!("Warning: Not grading {}: {}", map.filename, e),
+            }
+            game.profile.save();
+        }
+
+        // Finishing a map file autosaves the start of the next one, or the hub in campaign mode
+        let next = if game.campaign && map_filename(game.selected_map).is_some() {
+            Some(SaveGame::fresh(HUB_MAP, game.seed(), "Back to hub"))
+        } else {
+            AVAILABLE_MAPS.get(game.selected_map + 1).map(|next| SaveGame::fresh(next.filename, game.seed(), "Next map"))
+        };
+        if let Some(mut save) = next {
+            save.new_game_plus = game.new_game_plus;
+            autosave(&save);
+        }
+
+        rl.enable_cursor();
+
+        // Runs continue to an upgrade pick and the next floor instead of ending
+        if game.selected_map == RUN_MAP_INDEX {
+            game.run_state.offer_upgrades();
+            Transition::Replace(Box::new(UpgradeScreen))
+        } else {
+            Transition::Replace(Box::new(VictoryScreen::new(grading)))
+        }
+    }
+
+    // A run ends for good on death, banking what it earned
+    fn player_died(game: &mut Game, rl: &mut RaylibHandle) -> Transition {
+        if game.selected_map == RUN_MAP_INDEX {
+            game.profile.add_meta_currency(game.run_state.currency_earned());
+            game.profile.record_run_floor(game.run_state.floor);
+            game.profile.save();
+        }
+        rl.enable_cursor();
+        game.audio_manager.stop_music();
+        Transition::Replace(Box::new(GameOverScreen::new()))
+    }
+}
+
+impl Screen for PlayingScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, delta_time: f32) -> Transition {
+        let delta_time = game.time_scale.advance(delta_time);
+        game.run_time += delta_time;
+        game.recording.record(game.run_time, &game.world.player);
+
+        // ESC key to pause OR controller Options button
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) ||
+           (rl.is_gamepad_available(0) && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT)) {
+            rl.enable_cursor();
+            // Pause music when game is paused
+            game.audio_manager.pause_music();
+            return Transition::Push(Box::new(PauseScreen::new()));
+        }
+
+        // Tab or the controller's Share button opens the full-screen map
+        let map_pressed = rl.is_key_pressed(KeyboardKey::KEY_TAB) ||
+            (rl.is_gamepad_available(0) && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT));
+        if map_pressed && let Some(cell) = game.world.player_cell() {
+            rl.enable_cursor();
+            return Transition::Push(Box::new(MapScreen::new(cell)));
+        }
+
+        // Gyro aim turns the view on top of the stick and mouse
+        let gyro_turn = if game.gyro_aim {
+            game.gyro.update(delta_time);
+            game.gyro.turn(delta_time, game.gyro_sensitivity)
+        } else {
+            0.0
+        };
+
+        // Step the game and react to what happened in it
+        let started = Instant::now();
+        let events = game.world.update(delta_time, &mut FrameInput {
+            rl,
+            window_width: game.window_width,
+            window_height: game.window_height,
+            gyro_turn,
+            actions: &game.actions,
+            // Daily Dungeon times are compared between players, so nobody gets help there
+            aim_assist: if game.selected_map == DAILY_MAP_INDEX { 0.0 } else { game.aim_assist },
+        });
+        if game.selected_map == STRESS_MAP_INDEX {
+            self.frame_profile.add_update(started.elapsed());
+        }
+        let mut transition = Transition::None;
+        for event in events {
+            match event {
+                // The whoosh plays as the swing starts, hits add their impact on top
+                WorldEvent::SwingStarted => {
+                    if let Some(ref sound) = game.sword_sound {
+                        game.audio_manager.play_sword_swing(sound);
+                    }
+                }
+                // Smashed props thud like a hit, a powder keg booms like a critical and a shield rings like one
+                WorldEvent::EnemyHit | WorldEvent::PropBroken => {
+                    if let Some(ref sound) = game.hit_sound {
+                        game.audio_manager.play_enemy_hit(sound);
+                    }
+                }
+                WorldEvent::CriticalHit | WorldEvent::Explosion | WorldEvent::Blocked => {
+                    if let Some(ref sound) = game.critical_sound {
+                        game.audio_manager.play_critical_hit(sound);
+                    }
+                }
+                // Killing blows land with a moment of hit-stop
+                WorldEvent::EnemyKilled => {
+                    if let Some(ref sound) = game.death_sound {
+                        game.audio_manager.play_enemy_death(sound);
+                    }
+                    game.time_scale.hit_stop();
+                }
+                // Checkpoint cells split the timer and autosave the first time they are crossed
+                WorldEvent::Checkpoint(cell) => {
+                    if game.splits.touch_checkpoint(cell, game.run_time) && let Some(save) = game.snapshot("Checkpoint", true) {
+                        autosave(&save);
+                    }
+                }
+                // Arena doors slam shut on entry and grind open once it is cleared, as do quest gates.
+                // The radio has a word for the arena's fights.
+                WorldEvent::ArenaLocked | WorldEvent::ArenaCleared | WorldEvent::QuestGatesOpened => {
+                    if let Some(ref sound) = game.gate_sound {
+                        game.audio_manager.play_gate(sound);
+                    }
+                    match event {
+                        WorldEvent::ArenaLocked => game.audio_manager.play_chatter(ChatterTrigger::BossStart),
+                        WorldEvent::ArenaCleared => game.audio_manager.play_chatter(ChatterTrigger::ArenaCleared),
+                        _ => {}
+                    }
+                }
+                WorldEvent::ZoneEntered => game.audio_manager.play_chatter(ChatterTrigger::ZoneEntered),
+                WorldEvent::LowHealth => game.audio_manager.play_chatter(ChatterTrigger::LowHealth),
+                WorldEvent::PlayerDowned => game.audio_manager.play_chatter(ChatterTrigger::Downed),
+                WorldEvent::GoalReached if matches!(transition, Transition::None) => {
+                    transition = PlayingScreen::finish_map(game, rl);
+                }
+                // Death plays out in slow motion first
+                WorldEvent::PlayerDied => {
+                    game.world.dying = true;
+                    game.time_scale.death_slow_motion();
+                }
+                _ => {}
+            }
+        }
+        if game.world.dying && !game.time_scale.in_slow_motion() && matches!(transition, Transition::None) {
+            transition = PlayingScreen::player_died(game, rl);
+        }
+
+        // Toggle minimap with M key
+        if rl.is_key_pressed(KeyboardKey::KEY_M) {
+            game.show_minimap = !game.show_minimap;
+        }
+
+        // Toggle performance mode with P key
+        if rl.is_key_pressed(KeyboardKey::KEY_P) {
+            game.performance_mode = !game.performance_mode;
+        }
+
+        // Toggle music with N key
+        if rl.is_key_pressed(KeyboardKey::KEY_N) {
+            let enabled = !game.audio_manager.is_music_enabled();
+            game.set_music_enabled(enabled);
+        }
+
+        // Toggle the overhead debug view with F4, and the enemy AI overlay on it with F6
+        if rl.is_key_pressed(KeyboardKey::KEY_F4) {
+            self.debug_view.toggle();
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_F6) {
+            game.toggle_ai_overlay();
+        }
+
+        // F9 saves this frame in quality and performance mode with a heatmap of what differs
+        if rl.is_key_pressed(KeyboardKey::KEY_F9) {
+            let diff = framediff::capture(&mut game.framebuffer, &game.world, &game.texture_cache, game.shadows, QUALITY, PERFORMANCE);
+            log!("Frame diff: {} of {} pixels differ ({:.1}%), largest difference {}, saved {}", diff.changed, diff.total,
+                 diff.changed as f32 * 100.0 / diff.total.max(1) as f32, diff.max_difference, diff.path);
+        }
+
+        // Step through an open hub portal with F or the controller's bottom face button
+        if let Some((portal, true)) = self.portal.filter(|_| matches!(transition, Transition::None))
+            && game.actions.pressed(rl, Action::Deploy)
+        {
+            log!("Entering {} from the hub", map_name(portal.map_index));
+            game.selected_map = portal.map_index;
+            if let Err(e) = game.start_map(rl) {
+                return game.map_failed(rl, e);
+            }
+            self.portal = None;
+            return Transition::None;
+        }
+
+        // Volume controls: +/- for music, [/] for sound effects, in 5% steps with hold-to-repeat.
+        // With the minimap up, +/- and the mouse wheel zoom it instead.
+        let music_step = if game.show_minimap {
+            let mut zoom = rl.get_mouse_wheel_move().signum() as i32;
+            if rl.is_key_pressed(KeyboardKey::KEY_EQUAL) || rl.is_key_pressed(KeyboardKey::KEY_KP_ADD) {
+                zoom += 1;
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_MINUS) || rl.is_key_pressed(KeyboardKey::KEY_KP_SUBTRACT) {
+                zoom -= 1;
+            }
+            game.zoom_minimap(zoom);
+            0
+        } else {
+            self.music_volume_keys.step(rl, &[KeyboardKey::KEY_EQUAL, KeyboardKey::KEY_KP_ADD],
+                                        &[KeyboardKey::KEY_MINUS, KeyboardKey::KEY_KP_SUBTRACT], delta_time)
+        };
+        if music_step != 0 {
+            game.set_music_volume(step_volume(game.audio_manager.get_music_volume(), music_step));
+            self.volume_popup.show("Music", game.audio_manager.get_music_volume());
+        }
+        let sfx_step = self.sfx_volume_keys.step(rl, &[KeyboardKey::KEY_RIGHT_BRACKET], &[KeyboardKey::KEY_LEFT_BRACKET], delta_time);
+        if sfx_step != 0 {
+            game.set_sfx_volume(step_volume(game.audio_manager.get_sfx_volume(), sfx_step));
+            self.volume_popup.show("Sound Effects", game.audio_manager.get_sfx_volume());
+        }
+        self.volume_popup.update(delta_time);
+
+        transition
+    }
+
+    fn render(&mut self, game: &mut Game, delta_time: f32) -> bool {
+        game.framebuffer.clear();
+
+        // Portal the player is facing, only the hub has any
+        self.portal = match game.world.maze_data {
+            Some(ref data) if !game.world.portals.is_empty() => {
+                facing_portal(&mut game.framebuffer, &data.maze, &game.world.player, &game.world.portals, game.world.block_size)
+                    .map(|portal| (portal, is_unlocked(&game.profile, &campaign_maps(), portal.map_index, game.new_game_plus)))
+            }
+            _ => None,
+        };
+        // Outlined in the prompt's color
+        let highlight = self.portal.map(|(portal, unlocked)| (portal.cell, if unlocked { Color::SKYBLUE } else { Color::GRAY }));
+
+        let started = Instant::now();
+        game.world.render(&mut game.framebuffer, &game.texture_cache, game.performance_mode, game.shadows, highlight);
+        if game.selected_map == STRESS_MAP_INDEX {
+            let alive = game.world.enemies.iter().filter(|enemy| !enemy.is_dead).count();
+            self.frame_profile.add_render(started.elapsed(), delta_time, alive);
+        }
+
+        // Objective hint for the HUD, the map's quest comes before finding the exit
+        let quest_objective = game.world.quest.as_ref().and_then(|quest| quest.objective());
+        self.objective_prompt = match game.world.maze_data {
+            Some(_) if quest_objective.is_some() => quest_objective.map(|objective| format!("Objective: {}", objective)),
+            Some(ref data) => goal_prompt(&mut game.framebuffer, &game.world.player, &data.maze, game.world.block_size),
+            None => None,
+        };
+        self.debug_view.trace(&mut game.framebuffer, &game.world);
+
+        // Screen effects go on top of the finished 3D view
+        let floor = game.world.floor();
+        game.post_processor.apply(&mut game.framebuffer, &PostFxContext {
+            time: game.time,
+            health_ratio: game.world.player.health_ratio(),
+            underwater: is_floor_zone(floor),
+            water_tint: water_tint(floor),
+            dim: game.world.zones.dim(),
+        });
+        true
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        let (window_width, window_height) = (game.hud_width(), game.ui_height());
+        let world = &game.world;
+
+        // Render sword (always visible, with attack animation when attacking)
+        render_sword(d, &world.player, &game.texture_cache, &world.inventory.weapon, window_width, window_height);
+
+        // Draw UI elements
+        let alive_enemies = world.enemies.iter().filter(|e| !e.is_dead).count();
+
+        let fps = d.get_fps();
+        d.draw_text(&format!("FPS: {}", fps), 10, 10, 20, Color::WHITE);
+        d.draw_text(&format!("Enemies: {}", alive_enemies), 10, 35, 18, Color::YELLOW);
+
+        // Controller status
+        if d.is_gamepad_available(0) {
+            let gamepad_name = d.get_gamepad_name(0).unwrap_or("Controller".to_string());
+            d.draw_text(&format!("Controller: {}", gamepad_name), 10, 55, 16, Color::GREEN);
+        } else {
+            d.draw_text("Controller: Not Connected", 10, 55, 16, Color::GRAY);
+        }
+
+        let prompts = &game.prompts;
+        prompts.draw_row(d, &[(Prompt::Move, "Move"), (Prompt::Pause, "Pause")], 10, 74, 20, Color::WHITE);
+        prompts.draw_row(d, &[(Prompt::Attack, "Attack"), (Prompt::Sprint, "Sprint"), (Prompt::Potion, "Potion")],
+                         10, 96, 20, Color::YELLOW);
+        if let Some(ref companion) = world.companion {
+            let label = if companion.cooldown > 0.0 { format!("Wisp resting {:.0}s", companion.cooldown.ceil()) } else { "Wisp: Distract".to_string() };
+            prompts.draw_row(d, &[(Prompt::Command, &label)], 10, 116, 16, Color::SKYBLUE);
+        }
+        d.draw_text("M: Toggle minimap | Tab: Map", 10, 135, 16, Color::WHITE);
+        d.draw_text("P: Toggle performance mode", 10, 155, 16, Color::WHITE);
+        d.draw_text("N: Toggle music | G: Ping | L: Lantern", 10, 175, 16, Color::WHITE);
+        let plus_minus = if game.show_minimap { "+/-, wheel: Minimap zoom" } else { "+/-: Music volume" };
+        d.draw_text(&format!("{} | [/]: SFX volume", plus_minus), 10, 195, 16, Color::WHITE);
+        d.draw_text("F11: Toggle fullscreen | F4: Debug view | F9: Frame diff", 10, 215, 16, Color::WHITE);
+        d.draw_text(&format!("Minimap: {}", if game.show_minimap { "ON" } else { "OFF" }), 10, 235, 16, Color::WHITE);
+        d.draw_text(&format!("Performance: {}", if game.performance_mode { "HIGH" } else { "QUALITY" }), 10, 255, 16, Color::WHITE);
+        d.draw_text(&format!("Music: {} (Vol: {:.0}%)", if game.audio_manager.is_music_enabled() { "ON" } else { "OFF" }, game.audio_manager.get_music_volume() * 100.0), 10, 275, 16, Color::WHITE);
+
+        // Objective prompt near the top of the screen
+        if let Some(ref prompt) = self.objective_prompt {
+            let prompt_width = 22 * prompt.len() as i32 / 2; // Approximate text width
+            let prompt_x = (window_width - prompt_width) / 2;
+            d.draw_text(prompt, prompt_x + 2, 62, 22, Color::new(0, 0, 0, 180));
+            d.draw_text(prompt, prompt_x, 60, 22, Color::GOLD);
+        }
+
+        // Player health bar
+        let health_bar_width = 300;
+        let health_fill = (health_bar_width as f32 * world.player.health_ratio()) as i32;
+        d.draw_rectangle(20, window_height - 50, health_bar_width, 20, Color::new(40, 0, 0, 200));
+        d.draw_rectangle(20, window_height - 50, health_fill, 20, Color::new(200, 30, 30, 255));
+        d.draw_rectangle_lines(20, window_height - 50, health_bar_width, 20, Color::WHITE);
+        d.draw_text(&format!("HP {:.0}/{:.0}", world.player.health, world.player.max_health), 28, window_height - 48, 16, Color::WHITE);
+
+        // Subtitle of the radio line being spoken, above the health bar
+        if let Some(subtitle) = game.audio_manager.chatter_subtitle() {
+            let subtitle_width = subtitle.len() as i32 * 20 / 2; // Approximate text width
+            let subtitle_x = (window_width - subtitle_width) / 2;
+            d.draw_rectangle(subtitle_x - 12, window_height - 118, subtitle_width + 24, 32, Color::new(0, 0, 0, 170));
+            d.draw_text(subtitle, subtitle_x, window_height - 112, 20, Color::new(230, 230, 200, 255));
+        }
+
+        // Downed: the wisp's revive filling up over the bleed-out running down
+        if let Some((revived, bleed_out)) = world.revive.meters() {
+            let (bar_width, bar_x, bar_y) = (360, (window_width - 360) / 2, window_height / 2 + 60);
+            d.draw_text("DOWNED", (window_width - 90) / 2, bar_y - 34, 28, Color::RED);
+            d.draw_rectangle(bar_x, bar_y, bar_width, 14, Color::new(0, 0, 0, 180));
+            d.draw_rectangle(bar_x, bar_y, (bar_width as f32 * revived) as i32, 14, Color::SKYBLUE);
+            d.draw_rectangle(bar_x, bar_y + 18, (bar_width as f32 * bleed_out) as i32, 6, Color::new(200, 30, 30, 255));
+            d.draw_rectangle_lines(bar_x, bar_y, bar_width, 14, Color::WHITE);
+        }
+
+        d.draw_text(&format!("Coins: {} | Potions: {} | {}", world.inventory.coins, world.inventory.potions, world.inventory.weapon.name),
+                    340, window_height - 48, 16, Color::GOLD);
+
+        // Selected deployable under the coin counter, the hub uses the interact key for portals
+        if world.portals.is_empty() {
+            let kind = world.inventory.deployable;
+            let label = format!("{} x{}", kind.name(), world.inventory.deployables_left(kind));
+            prompts.draw_row(d, &[(Prompt::Interact, &label), (Prompt::Switch, "Switch")], 340, window_height - 28, 16, Color::LIGHTGRAY);
+        }
+
+        if game.selected_map == RUN_MAP_INDEX {
+            d.draw_text(&format!("Floor {}", game.run_state.floor), 20, window_height - 80, 22, Color::GOLD);
+        }
+
+        // Visibility gem: how easily enemies spot the player
+        draw_gem(d, world.visibility, window_width - 40, window_height - 40);
+        draw_fuel_gauge(d, &world.player.lantern, window_width - 80, window_height - 22, game.run_time);
+
+        // Status effect icons above the coin counter: initial and seconds left
+        for (i, effect) in world.player.status.iter().enumerate() {
+            let icon_x = 340 + i as i32 * 64;
+            let icon_y = window_height - 84;
+            let color = effect.kind.color();
+            d.draw_rectangle(icon_x, icon_y, 28, 28, Color::new(color.r / 3, color.g / 3, color.b / 3, 220));
+            d.draw_rectangle_lines(icon_x, icon_y, 28, 28, color);
+            d.draw_text(&effect.kind.name()[..1], icon_x + 8, icon_y + 4, 22, color);
+            d.draw_text(&format!("{:.0}s", effect.remaining.ceil()), icon_x + 32, icon_y + 8, 16, Color::WHITE);
+        }
+
+        // Sealed arena callout until its enemies are dead
+        if world.arena.as_ref().is_some_and(|arena| arena.state == ArenaState::Locked) {
+            let text = format!("The doors are sealed! Enemies left: {}", Arena::remaining(&world.enemies));
+            let text_width = 22 * text.len() as i32 / 2; // Approximate text width
+            d.draw_text(&text, (window_width - text_width) / 2, 95, 22, Color::RED);
+        }
+
+        // Hub portal prompt, or what still has to be finished to open it
+        match self.portal {
+            Some((portal, true)) => {
+                prompts.draw_row_centered(d, &[(Prompt::Interact, &format!("Enter {}", map_name(portal.map_index)))],
+                                          window_width / 2, window_height / 2 + 60, 22, Color::SKYBLUE);
+            }
+            Some((portal, false)) => {
+                let text = format!("Locked: finish {} first", map_name(portal.map_index - 1));
+                let text_width = 22 * text.len() as i32 / 2; // Approximate text width
+                d.draw_text(&text, (window_width - text_width) / 2, window_height / 2 + 60, 22, Color::GRAY);
+            }
+            None => {}
+        }
+
+        world.hints.draw(d, window_width, window_height);
+        self.volume_popup.draw(d, window_width);
+        game.splits.draw(d, game.run_time, window_width);
+
+        // Render minimap if enabled
+        if game.show_minimap {
+            render_minimap(d, world, game.minimap_size, game.minimap_zoom, window_width, window_height);
+        }
+        self.debug_view.draw(d, world, game.ai_overlay, window_width, window_height);
+    }
+
+    fn pillarboxed(&self) -> bool {
+        true
+    }
+}
+
+// Pause menu over the frozen game
+pub struct PauseScreen {
+    selected_option: usize,         // Index into PAUSE_OPTIONS
+    message: Option<&'static str>, // Outcome of Save Game
+    naming: Option<(OnScreenKeyboard, SaveGame)>, // Save Game asks for a label before writing the save
+}
+
+impl PauseScreen {
+    pub fn new() -> Self {
+        PauseScreen { selected_option: 0, message: None, naming: None }
+    }
+}
+
+impl Screen for PauseScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        // The keyboard takes all input while it is open
+        if let Some((keyboard, save)) = &mut self.naming {
+            let input = TextInput::gather(rl, game.ui_scale);
+            match keyboard.handle(&input, game.ui_width(), game.ui_height()) {
+                Some(TextEvent::Done(label)) => {
+                    save.label = label;
+                    self.message = Some(match save_manual(save) {
+                        Ok(()) => "Game saved",
+                        Err(_) => "The save slot is from a newer version",
+                    });
+                    self.naming = None;
+                }
+                Some(TextEvent::Cancelled) => self.naming = None,
+                None => {}
+            }
+            return Transition::None;
+        }
+
+        // Check for controller connection
+        let gamepad_available = rl.is_gamepad_available(0);
+
+        // Handle pause menu input - Controller takes priority
+        let mut input_handled = false;
+        let mut option_chosen = false;
+        let mut resume_requested = false;
+
+        if gamepad_available {
+            // D-Pad navigation
+            if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP) {
+                self.selected_option = (self.selected_option + PAUSE_OPTIONS.len() - 1) % PAUSE_OPTIONS.len();
+                input_handled = true;
+            }
+            if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN) {
+                self.selected_option = (self.selected_option + 1) % PAUSE_OPTIONS.len();
+                input_handled = true;
+            }
+
+            // X button (Cross) or A button to confirm
+            if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN) ||
+               rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT) {
+                option_chosen = true;
+                input_handled = true;
+            }
+
+            // Options button to resume (alternative)
+            if rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT) {
+                resume_requested = true;
+                input_handled = true;
+            }
+        }
+
+        // Keyboard fallback if no controller input
+        if !input_handled {
+            if rl.is_key_pressed(KeyboardKey::KEY_UP) || rl.is_key_pressed(KeyboardKey::KEY_W) {
+                self.selected_option = (self.selected_option + PAUSE_OPTIONS.len() - 1) % PAUSE_OPTIONS.len();
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_DOWN) || rl.is_key_pressed(KeyboardKey::KEY_S) {
+                self.selected_option = (self.selected_option + 1) % PAUSE_OPTIONS.len();
+            }
+
+            if rl.is_key_pressed(KeyboardKey::KEY_ENTER) || rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+                option_chosen = true;
+            }
+
+            if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                resume_requested = true;
+            }
+        }
+
+        if option_chosen {
+            match self.selected_option {
+                0 => resume_requested = true,
+                1 => {
+                    // Restart the current map from scratch
+                    if let Err(e) = game.start_map(rl) {
+                        return game.map_failed(rl, e);
+                    }
+                    return Transition::Pop;
+                }
+                2 => {
+                    // The manual slot is separate from the rotating autosaves, and is named before it is written
+                    match game.snapshot("Manual save", true) {
+                        Some(save) => {
+                            let label = SaveGame::load(MANUAL_SLOT).ok().flatten().map_or(String::new(), |previous| previous.label);
+                            let label = if label.is_empty() { map_name(game.selected_map).to_string() } else { label };
+                            self.naming = Some((OnScreenKeyboard::new("NAME THIS SAVE", &label, MAX_LABEL_LENGTH), save));
+                            self.message = None;
+                        }
+                        None => self.message = Some("The Daily Dungeon cannot be saved"),
+                    }
+                }
+                3 => return Transition::Push(Box::new(SettingsScreen::new())),
+                4 => return Transition::Push(Box::new(ControlsScreen::new())),
+                5 => {
+                    // Ask before throwing away the current run
+                    return Transition::Push(Box::new(ConfirmScreen::new(
+                        ConfirmDialog::new("Quit to menu?", "Unsaved progress will be lost"),
+                        ConfirmAction::ReturnToMenu,
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        if resume_requested {
+            game.capture_mouse(rl);
+            // Resume music when game resumes
+            game.audio_manager.resume_music();
+            return Transition::Pop;
+        }
+        Transition::None
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        render_pause_menu(d, self.selected_option, (map_name(game.selected_map), game.run_time), self.message, &game.prompts,
+                          game.ui_width(), game.ui_height());
+        if let Some((keyboard, _)) = &self.naming {
+            render_name_entry(d, keyboard, &game.prompts, game.ui_width(), game.ui_height());
+        }
+    }
+
+    fn backdrop(&self, game: &Game) -> Option<Backdrop> {
+        Some(Backdrop { panel: pause_menu_panel(game.ui_width(), game.ui_height()), darken: 0.5 })
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// The full-screen map over the frozen game, for placing and removing markers, or over an end
+// screen to look back at the route heatmap
+pub struct MapScreen {
+    cursor: (usize, usize),  // Cell being marked
+    radial: Option<usize>,   // Highlighted option while the radial menu is open
+    message: Option<&'static str>,
+    heatmap: bool,           // Cells tinted by how often they were visited
+    review: bool,            // Opened from an end screen, markers stay as they are
+}
+
+impl MapScreen {
+    pub fn new(cursor: (usize, usize)) -> Self {
+        MapScreen { cursor, radial: None, message: None, heatmap: false, review: false }
+    }
+
+    pub fn review(cursor: (usize, usize)) -> Self {
+        MapScreen { cursor, radial: None, message: None, heatmap: true, review: true }
+    }
+}
+
+impl Screen for MapScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        let Some((width, height)) = game.world.maze().map(|maze| (maze.width(), maze.height())) else {
+            return Transition::Pop;
+        };
+        let Some((origin_x, origin_y, cell_size)) = map_layout(&game.world, game.ui_width(), game.ui_height()) else {
+            return Transition::Pop;
+        };
+        if rl.is_key_pressed(KeyboardKey::KEY_F6) {
+            game.toggle_ai_overlay();
+        }
+        let gamepad = rl.is_gamepad_available(0);
+        let pressed = |rl: &RaylibHandle, keys: &[KeyboardKey], button: GamepadButton| {
+            keys.iter().any(|&key| rl.is_key_pressed(key)) || (gamepad && rl.is_gamepad_button_pressed(0, button))
+        };
+        let up = pressed(rl, &[KeyboardKey::KEY_UP, KeyboardKey::KEY_W], GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP);
+        let right = pressed(rl, &[KeyboardKey::KEY_RIGHT, KeyboardKey::KEY_D], GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT);
+        let down = pressed(rl, &[KeyboardKey::KEY_DOWN, KeyboardKey::KEY_S], GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN);
+        let left = pressed(rl, &[KeyboardKey::KEY_LEFT, KeyboardKey::KEY_A], GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT);
+        let confirm = pressed(rl, &[KeyboardKey::KEY_ENTER, KeyboardKey::KEY_SPACE], GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN) ||
+            rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+        let back = pressed(rl, &[KeyboardKey::KEY_ESCAPE], GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT);
+        let mouse = rl.get_mouse_position() / game.ui_scale;
+        let mouse_delta = rl.get_mouse_delta();
+        let mouse_moved = mouse_delta.x != 0.0 || mouse_delta.y != 0.0;
+
+        if self.radial.is_none() && pressed(rl, &[KeyboardKey::KEY_H], GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT) {
+            self.heatmap = !self.heatmap;
+        }
+        // Looking back from an end screen, where the mouse stays free
+        if self.review {
+            return if back || rl.is_key_pressed(KeyboardKey::KEY_M) {
+                Transition::Pop
+            } else {
+                Transition::None
+            };
+        }
+
+        if let Some(selected) = self.radial {
+            // Each direction picks the option on that side, the mouse and the stick point at one
+            let center = Vec2::new((origin_x + self.cursor.0 as i32 * cell_size) as f32 + cell_size as f32 / 2.0,
+                                      (origin_y + self.cursor.1 as i32 * cell_size) as f32 + cell_size as f32 / 2.0);
+            let stick = if gamepad {
+                Vec2::new(rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_LEFT_X),
+                          rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_LEFT_Y))
+            } else {
+                Vec2::default()
+            };
+            let mut selected = selected;
+            for (index, chosen) in [up, right, down, left].into_iter().enumerate() {
+                if chosen {
+                    selected = index;
+                }
+            }
+            let pointing = Vec2::from(mouse) - center;
+            if mouse_moved && pointing.length() > 12.0 {
+                selected = radial_option(pointing);
+            }
+            if stick.length() > 0.5 {
+                selected = radial_option(stick);
+            }
+            self.radial = Some(selected);
+
+            if confirm {
+                let markers = &mut game.world.markers;
+                self.message = match RADIAL_OPTIONS[selected] {
+                    Some(kind) if place_marker(markers, self.cursor, kind) => None,
+                    Some(_) => Some("Every marker is in use, remove one first"),
+                    None => {
+                        markers.retain(|marker| marker.cell != self.cursor);
+                        None
+                    }
+                };
+                self.radial = None;
+            } else if back {
+                self.radial = None;
+            }
+            return Transition::None;
+        }
+
+        let (mut x, mut y) = (self.cursor.0 as i32, self.cursor.1 as i32);
+        x += right as i32 - left as i32;
+        y += down as i32 - up as i32;
+        if mouse_moved {
+            let (mouse_x, mouse_y) = ((mouse.x as i32 - origin_x).div_euclid(cell_size), (mouse.y as i32 - origin_y).div_euclid(cell_size));
+            if (0..width as i32).contains(&mouse_x) && (0..height as i32).contains(&mouse_y) {
+                (x, y) = (mouse_x, mouse_y);
+            }
+        }
+        self.cursor = (x.clamp(0, width as i32 - 1) as usize, y.clamp(0, height as i32 - 1) as usize);
+
+        if confirm {
+            // Start on the kind already there, so a second look doesn't change it by accident
+            let current = game.world.markers.iter().find(|marker| marker.cell == self.cursor)
+                .and_then(|marker| RADIAL_OPTIONS.iter().position(|option| *option == Some(marker.kind)));
+            self.radial = Some(current.unwrap_or(0));
+            self.message = None;
+        }
+        if back || pressed(rl, &[KeyboardKey::KEY_TAB], GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT) {
+            game.capture_mouse(rl);
+            return Transition::Pop;
+        }
+        Transition::None
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        let view = MapView {
+            cursor: self.cursor,
+            radial: self.radial,
+            message: self.message,
+            heatmap: self.heatmap,
+            review: self.review,
+            ai_overlay: game.ai_overlay,
+        };
+        draw_automap(d, &game.world, &view, game.ui_width(), game.ui_height());
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+pub struct SettingsScreen {
+    selected: usize,              // Focused settings widget
+    open_dropdown: Option<SettingId>, // Dropdown whose list is showing
+    keyboard: Option<OnScreenKeyboard>, // Open while the profile name is being typed
+}
+
+impl SettingsScreen {
+    pub fn new() -> Self {
+        SettingsScreen { selected: 0, open_dropdown: None, keyboard: None }
+    }
+
+    // The widgets in menu order, and the setting each one controls
+    fn widgets(&self, game: &Game) -> (Vec<SettingId>, Vec<Widget>) {
+        settings_widgets(&game.settings(), self.open_dropdown).into_iter().unzip()
+    }
+}
+
+impl Screen for SettingsScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        if let Some(keyboard) = &mut self.keyboard {
+            let input = TextInput::gather(rl, game.ui_scale);
+            match keyboard.handle(&input, game.ui_width(), game.ui_height()) {
+                Some(TextEvent::Done(name)) => {
+                    game.profile.set("name", name);
+                    game.profile.save();
+                    log!("Profile name set to {}", game.profile.player_name());
+                    self.keyboard = None;
+                }
+                Some(TextEvent::Cancelled) => self.keyboard = None,
+                None => {}
+            }
+            return Transition::None;
+        }
+
+        let input = UiInput::gather(rl, game.ui_scale);
+        let (ids, mut widgets) = self.widgets(game);
+        let layout = settings_layout(game.ui_width(), game.ui_height());
+
+        let mut transition = Transition::None;
+        match ui::handle(&mut widgets, &mut self.selected, &input, &layout) {
+            Some(UiEvent::Changed(index)) => match (ids[index], &widgets[index]) {
+                (SettingId::DisplayPreset, Widget::Dropdown { selected, .. }) => match selected {
+                    1 => game.apply_display_preset(&DESKTOP_PRESET),
+                    2 => game.apply_display_preset(&HANDHELD_PRESET),
+                    _ => {} // Custom is whatever the other settings say
+                },
+                (SettingId::PerformanceMode, Widget::Dropdown { selected, .. }) => game.performance_mode = *selected == 1,
+                (SettingId::RenderScale, Widget::Dropdown { selected, .. }) => game.render_scale = RENDER_SCALES[*selected],
+                (SettingId::UiScale, Widget::Dropdown { selected, .. }) => game.ui_scale = UI_SCALES[*selected],
+                (SettingId::MinimapSize, Widget::Dropdown { selected, .. }) => game.minimap_size = MINIMAP_SIZES[*selected],
+                (SettingId::MinimapZoom, Widget::Dropdown { selected, .. }) => {
+                    game.minimap_default_zoom = MINIMAP_ZOOMS[*selected];
+                    game.minimap_zoom = game.minimap_default_zoom;
+                }
+                (SettingId::Difficulty, Widget::Dropdown { selected, .. }) => game.difficulty = DIFFICULTIES[*selected],
+                (SettingId::InputPreset, Widget::Dropdown { selected, .. }) => game.set_input_preset(INPUT_PRESETS[*selected]),
+                (SettingId::Minimap, Widget::Toggle { value, .. }) => game.show_minimap = *value,
+                (SettingId::Music, Widget::Toggle { value, .. }) => game.set_music_enabled(*value),
+                (SettingId::Berserk, Widget::Toggle { value, .. }) => game.post_processor.set_enabled(PostEffect::Berserk, *value),
+                (SettingId::Scanlines, Widget::Toggle { value, .. }) => game.post_processor.set_enabled(PostEffect::Scanlines, *value),
+                (SettingId::FeedbackFx, Widget::Toggle { value, .. }) => {
+                    // Gameplay feedback effects share one switch
+                    game.post_processor.set_enabled(PostEffect::LowHealth, *value);
+                    game.post_processor.set_enabled(PostEffect::Underwater, *value);
+                }
+                (SettingId::Companion, Widget::Toggle { value, .. }) => game.companion = *value,
+                (SettingId::Shadows, Widget::Toggle { value, .. }) => game.shadows = *value,
+                (SettingId::GyroAim, Widget::Toggle { value, .. }) => {
+                    game.gyro_aim = *value;
+                    game.gyro.release();
+                }
+                (SettingId::HudPillarbox, Widget::Toggle { value, .. }) => game.hud_pillarbox = *value,
+                (SettingId::MasterVolume, Widget::Slider { value, .. }) => game.set_master_volume(*value),
+                (SettingId::MusicVolume, Widget::Slider { value, .. }) => game.set_music_volume(*value),
+                (SettingId::SfxVolume, Widget::Slider { value, .. }) => game.set_sfx_volume(*value),
+                (SettingId::GyroSensitivity, Widget::Slider { value, .. }) => game.gyro_sensitivity = *value,
+                (SettingId::AimAssist, Widget::Slider { value, .. }) => game.aim_assist = *value,
+                (SettingId::Controls, _) => transition = Transition::Push(Box::new(ControlsScreen::new())),
+                (SettingId::ProfileName, _) => {
+                    self.keyboard = Some(OnScreenKeyboard::new("PROFILE NAME", game.profile.player_name(), MAX_NAME_LENGTH));
+                }
+                (SettingId::Mods, _) => transition = Transition::Push(Box::new(ModsScreen { selected: 0 })),
+                (SettingId::Back, _) => transition = Transition::Pop,
+                (id, _) => elog!("Warning: Settings widget {:?} is not the kind of widget its setting expects", id),
+            },
+            Some(UiEvent::Back) => transition = Transition::Pop,
+            None => {}
+        }
+        if matches!(transition, Transition::Pop) {
+            game.save_settings();
+        }
+
+        // Dropdowns are the only widgets with state beyond the settings themselves
+        self.open_dropdown = widgets.iter().position(|widget| matches!(widget, Widget::Dropdown { open: true, .. })).map(|index| ids[index]);
+        transition
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        let (_, widgets) = self.widgets(game);
+        render_settings_menu(d, self.selected, &widgets, &game.prompts, game.ui_width(), game.ui_height());
+        if let Some(keyboard) = &self.keyboard {
+            render_name_entry(d, keyboard, &game.prompts, game.ui_width(), game.ui_height());
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// Switch mods on and off, saved right away and used from the next launch
+pub struct ModsScreen {
+    selected: usize,
+}
+
+impl ModsScreen {
+    fn widgets(game: &Game) -> Vec<Widget> {
+        let mut widgets: Vec<Widget> = game.mods.entries.iter()
+            .map(|entry| Widget::Toggle { label: entry.name, value: entry.enabled })
+            .collect();
+        widgets.push(Widget::Button { label: "Back" });
+        widgets
+    }
+}
+
+impl Screen for ModsScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        let input = UiInput::gather(rl, game.ui_scale);
+        let mut widgets = Self::widgets(game);
+        let layout = mods_layout(widgets.len(), game.ui_width(), game.ui_height());
+
+        match ui::handle(&mut widgets, &mut self.selected, &input, &layout) {
+            Some(UiEvent::Changed(index)) => match &widgets[index] {
+                Widget::Toggle { value, .. } => {
+                    game.mods.entries[index].enabled = *value;
+                    game.mods.save();
+                    Transition::None
+                }
+                _ => Transition::Pop, // Back button
+            },
+            Some(UiEvent::Back) => Transition::Pop,
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        let widgets = Self::widgets(game);
+        render_mods_menu(d, self.selected, &widgets, game.mods.needs_restart(), &game.prompts,
+                         game.ui_width(), game.ui_height());
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// Every action with its keys and buttons, read from the action map each frame. Confirm on one
+// waits for the next key or button and puts it in place of the action's first one; Escape or
+// the controller's Options button cancels, as neither can be bound. Bindings that another action
+// or a fixed hotkey shares are shown in red, and the selected action's clashes are spelled out.
+pub struct ControlsScreen {
+    selected: usize, // ACTIONS, then Reset and Back
+    column: usize,   // 0 for the keyboard, 1 for the controller
+    waiting: bool,   // Listening for the new key or button
+}
+
+// One action's line on the controls screen
+pub struct ControlsRow {
+    pub label: &'static str,
+    pub keys: String,    // Key names joined with " / ", "-" when there are none
+    pub buttons: String, // Button names, the same way
+    pub key_clash: bool, // Some key is shared with another action or a fixed hotkey
+    pub button_clash: bool,
+    pub note: Option<String>, // What the bindings clash with, e.g. "E also: Lantern", shown for the selected row
+}
+
+const CONTROLS_RESET: usize = ACTIONS.len();
+const CONTROLS_BACK: usize = ACTIONS.len() + 1;
+
+impl ControlsScreen {
+    pub fn new() -> Self {
+        ControlsScreen { selected: 0, column: 0, waiting: false }
+    }
+
+    fn rows(game: &Game) -> Vec<ControlsRow> {
+        ACTIONS.iter().map(|&action| {
+            let bindings = game.actions.bindings(action);
+            let names = |keys: bool| {
+                let names: Vec<&str> = bindings.iter().filter(|binding| matches!(binding, Binding::Key(_)) == keys).map(Binding::name).collect();
+                if names.is_empty() { "-".to_string() } else { names.join(" / ") }
+            };
+            let clashes = |keys: bool| bindings.iter()
+                .filter(|binding| matches!(binding, Binding::Key(_)) == keys)
+                .any(|&binding| !game.actions.conflicts(action, binding).is_empty());
+            let notes: Vec<String> = bindings.iter()
+                .filter_map(|&binding| {
+                    let others = game.actions.conflicts(action, binding);
+                    (!others.is_empty()).then(|| format!("{} also: {}", binding.name(), others.join(", ")))
+                })
+                .collect();
+            ControlsRow {
+                label: action.label(),
+                keys: names(true),
+                buttons: names(false),
+                key_clash: clashes(true),
+                button_clash: clashes(false),
+                note: (!notes.is_empty()).then(|| notes.join("; ")),
+            }
+        }).collect()
+    }
+}
+
+impl Screen for ControlsScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        let pad = rl.is_gamepad_available(0);
+        if self.waiting {
+            if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) || (pad && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT)) {
+                self.waiting = false;
+                return Transition::None;
+            }
+            let binding = if self.column == 0 {
+                KEY_NAMES.iter().find(|&&(key, _)| rl.is_key_pressed(key)).map(|&(key, _)| Binding::Key(key))
+            } else {
+                BUTTON_NAMES.iter().find(|&&(button, _)| pad && rl.is_gamepad_button_pressed(0, button)).map(|&(button, _)| Binding::Button(button))
+            };
+            if let Some(binding) = binding {
+                let action = ACTIONS[self.selected];
+                game.rebind(action, binding);
+                log!("Bound {} to {}", action.label(), binding.name());
+                self.waiting = false;
+            }
+            return Transition::None;
+        }
+
+        let input = UiInput::gather(rl, game.ui_scale);
+        let rows = ACTIONS.len() + 2;
+        if input.up {
+            self.selected = (self.selected + rows - 1) % rows;
+        }
+        if input.down {
+            self.selected = (self.selected + 1) % rows;
+        }
+        if input.left || input.right {
+            self.column = 1 - self.column;
+        }
+        if input.back {
+            game.save_settings();
+            return Transition::Pop;
+        }
+        if input.confirm {
+            match self.selected {
+                CONTROLS_RESET => {
+                    let preset = game.actions.preset;
+                    game.set_input_preset(preset);
+                }
+                CONTROLS_BACK => {
+                    game.save_settings();
+                    return Transition::Pop;
+                }
+                _ => self.waiting = true,
+            }
+        }
+        Transition::None
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        render_controls_menu(d, &Self::rows(game), (self.selected, self.column), self.waiting, &game.prompts, game.ui_width(), game.ui_height());
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+const SLOT_TITLES: [&str; 4] = ["Manual Save", "Autosave 1", "Autosave 2", "Autosave 3"];
+
+// The manual save and the three autosaves, read when the screen opens
+pub struct LoadGameScreen {
+    selected: usize,
+    slots: Vec<Result<Option<SaveGame>, LoadError>>, // Same order as SLOT_TITLES
+    error: Option<String>,                           // Why the last pick could not be loaded
+}
+
+impl LoadGameScreen {
+    pub fn new() -> Self {
+        let slots = std::iter::once(MANUAL_SLOT).chain(AUTOSAVE_SLOTS).map(SaveGame::load).collect();
+        LoadGameScreen { selected: 0, slots, error: None }
+    }
+
+    // Title, details and whether the slot can be loaded, for each slot
+    fn rows(&self) -> Vec<(String, String, bool)> {
+        let newest = self.slots[1..]
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| slot.as_ref().ok()?.as_ref().map(|save| (i + 1, save.saved_at)))
+            .max_by_key(|&(_, saved_at)| saved_at)
+            .map(|(i, _)| i);
+
+        self.slots
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| {
+                let title = match slot {
+                    Ok(Some(save)) if !save.label.is_empty() => format!("{}: {}", SLOT_TITLES[i], save.label),
+                    _ => SLOT_TITLES[i].to_string(),
+                };
+                let title = if Some(i) == newest { format!("{} (newest)", title) } else { title };
+                match slot {
+                    Ok(Some(save)) => {
+                        let map = if save.map == RUN_MAP_KEY {
+                            format!("Rogue-lite Run, floor {}", save.run_floor)
+                        } else {
+                            AVAILABLE_MAPS.iter().find(|map| map.filename == save.map).map_or(save.map.clone(), |map| map.name.to_string())
+                        };
+                        let detail = format!("{} | {} | {} | {}", map, save.reason, format_time(save.run_time), save.saved_label());
+                        (title, detail, true)
+                    }
+                    Ok(None) => (title, "Empty".to_string(), false),
+                    Err(e) => (title, format!("This save {}", e), false),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Screen for LoadGameScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        let input = UiInput::gather(rl, game.ui_scale);
+        if input.up {
+            self.selected = (self.selected + self.slots.len() - 1) % self.slots.len();
+        }
+        if input.down {
+            self.selected = (self.selected + 1) % self.slots.len();
+        }
+        if input.back {
+            return Transition::Pop;
+        }
+
+        if input.confirm && let Ok(Some(save)) = &self.slots[self.selected] {
+            match game.resume(rl, save) {
+                Ok(()) => return Transition::Replace(Box::new(PlayingScreen::new())),
+                Err(e) => {
+                    elog!("Warning: Could not load {}: {}", SLOT_TITLES[self.selected], e);
+                    self.error = Some(e);
+                }
+            }
+        }
+        Transition::None
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        render_load_menu(d, self.selected, &self.rows(), self.error.as_deref(), &game.prompts, game.ui_width(), game.ui_height());
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// Actions that must be confirmed through a dialog before they run
+#[derive(PartialEq)]
+pub enum ConfirmAction {
+    ReturnToMenu,
+    QuitGame,
+}
+
+pub struct ConfirmScreen {
+    dialog: ConfirmDialog,
+    action: ConfirmAction,
+}
+
+impl ConfirmScreen {
+    pub fn new(dialog: ConfirmDialog, action: ConfirmAction) -> Self {
+        ConfirmScreen { dialog, action }
+    }
+}
+
+impl Screen for ConfirmScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        match self.dialog.handle_input(rl) {
+            Some(true) => match self.action {
+                ConfirmAction::ReturnToMenu => {
+                    game.leave_map(rl);
+                    Transition::PopToRoot
+                }
+                ConfirmAction::QuitGame => Transition::Quit,
+            },
+            Some(false) => Transition::Pop,
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        self.dialog.draw(d, game.ui_width(), game.ui_height());
+    }
+
+    fn backdrop(&self, game: &Game) -> Option<Backdrop> {
+        Some(Backdrop { panel: ConfirmDialog::panel(game.ui_width(), game.ui_height()), darken: 0.3 })
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// Pick one of the offered upgrades between run floors
+pub struct UpgradeScreen;
+
+impl Screen for UpgradeScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        let gamepad_available = rl.is_gamepad_available(0);
+        let run_state = &mut game.run_state;
+        let option_count = run_state.offered.len();
+
+        if rl.is_key_pressed(KeyboardKey::KEY_LEFT) || rl.is_key_pressed(KeyboardKey::KEY_A) ||
+           rl.is_key_pressed(KeyboardKey::KEY_UP) || rl.is_key_pressed(KeyboardKey::KEY_W) ||
+           (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT)) {
+            run_state.selected = (run_state.selected + option_count - 1) % option_count;
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_RIGHT) || rl.is_key_pressed(KeyboardKey::KEY_D) ||
+           rl.is_key_pressed(KeyboardKey::KEY_DOWN) || rl.is_key_pressed(KeyboardKey::KEY_S) ||
+           (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT)) {
+            run_state.selected = (run_state.selected + 1) % option_count;
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) || rl.is_key_pressed(KeyboardKey::KEY_SPACE) ||
+           (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN)) {
+            // Take the upgrade, then visit the shop before descending
+            run_state.take_selected(&mut game.world.player);
+            return Transition::Replace(Box::new(ShopScreen::new()));
+        }
+        Transition::None
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        render_upgrade_screen(d, &game.run_state, &game.prompts, game.ui_width(), game.ui_height());
+    }
+}
+
+pub struct ShopScreen {
+    selected_item: usize, // Index into SHOP_ITEMS, SHOP_ITEMS.len() is "Descend"
+}
+
+impl ShopScreen {
+    pub fn new() -> Self {
+        ShopScreen { selected_item: 0 }
+    }
+}
+
+impl Screen for ShopScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        let gamepad_available = rl.is_gamepad_available(0);
+        let option_count = SHOP_ITEMS.len() + 1; // Items plus "Descend"
+
+        if rl.is_key_pressed(KeyboardKey::KEY_UP) || rl.is_key_pressed(KeyboardKey::KEY_W) ||
+           (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP)) {
+            self.selected_item = (self.selected_item + option_count - 1) % option_count;
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_DOWN) || rl.is_key_pressed(KeyboardKey::KEY_S) ||
+           (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN)) {
+            self.selected_item = (self.selected_item + 1) % option_count;
+        }
+
+        let confirm = rl.is_key_pressed(KeyboardKey::KEY_ENTER) || rl.is_key_pressed(KeyboardKey::KEY_SPACE) ||
+            (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN));
+        let leave = rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) ||
+            (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT));
+
+        if confirm && self.selected_item < SHOP_ITEMS.len() {
+            let item = SHOP_ITEMS[self.selected_item];
+            if item.buy(&mut game.world.inventory, game.run_state.floor) {
+                log!("Bought {} for {} coins", item.name(), item.price(game.run_state.floor));
+            }
+        } else if confirm || leave {
+            // Descend to the next floor, autosaving its start
+            if let Err(e) = load_map(&mut game.world, RUN_MAP_INDEX, &game.run_state, game.run_state.floor_seed()) {
+                return game.map_failed(rl, e);
+            }
+            if let Some(save) = game.snapshot("Floor start", false) {
+                autosave(&save);
+            }
+            game.capture_mouse(rl);
+            return Transition::Replace(Box::new(PlayingScreen::new()));
+        }
+        Transition::None
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        render_shop_screen(d, self.selected_item, &game.world.inventory, game.run_state.floor, &game.prompts,
+                           game.ui_width(), game.ui_height());
+    }
+}
+
+// M or Square on an end screen looks back over the route heatmap
+fn review_route(game: &Game, rl: &RaylibHandle) -> Option<Transition> {
+    let pressed = rl.is_key_pressed(KeyboardKey::KEY_M) ||
+        (rl.is_gamepad_available(0) && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT));
+    let cell = game.world.player_cell().filter(|_| pressed)?;
+    Some(Transition::Push(Box::new(MapScreen::review(cell))))
+}
+
+// E or Triangle on an end screen writes the summary of the map that just ended, once
+fn export_summary(game: &Game, rl: &RaylibHandle, outcome: &str, exported: &mut Option<Result<String, String>>) {
+    let pressed = rl.is_key_pressed(KeyboardKey::KEY_E) ||
+        (rl.is_gamepad_available(0) && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP));
+    if !pressed || exported.is_some() {
+        return;
+    }
+    let world = &game.world;
+    let summary = RunSummary {
+        outcome,
+        map: map_name(game.selected_map),
+        seed: world.seed,
+        time: game.run_time,
+        tally: world.tally,
+        health: world.player.health,
+        coins: world.inventory.coins,
+        size: world.maze().map_or((0, 0), |maze| (maze.width(), maze.height())),
+        visited: &world.visited,
+        settings: game.settings().values(),
+    };
+    *exported = Some(summary.export());
+}
+
+pub struct GameOverScreen {
+    exported: Option<Result<String, String>>, // Where the run summary went, once written
+}
+
+impl GameOverScreen {
+    pub fn new() -> Self {
+        GameOverScreen { exported: None }
+    }
+}
+
+impl Screen for GameOverScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        export_summary(game, rl, "death", &mut self.exported);
+        if let Some(transition) = review_route(game, rl) {
+            return transition;
+        }
+        let gamepad_available = rl.is_gamepad_available(0);
+        let retry = rl.is_key_pressed(KeyboardKey::KEY_ENTER) || rl.is_key_pressed(KeyboardKey::KEY_SPACE) ||
+            (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN));
+        let to_menu = rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) ||
+            (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT));
+
+        if retry && game.selected_map != RUN_MAP_INDEX {
+            // Regular maps can be retried straight away
+            match game.start_new_game(rl) {
+                Ok(()) => Transition::Replace(Box::new(PlayingScreen::new())),
+                Err(e) => game.map_failed(rl, e),
+            }
+        } else if retry || to_menu {
+            // Runs always go back to the menu
+            game.leave_map(rl);
+            Transition::PopToRoot
+        } else {
+            Transition::None
+        }
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        let run_summary = if game.selected_map == RUN_MAP_INDEX { Some(&game.run_state) } else { None };
+        render_game_over_screen(d, run_summary, game.profile.meta_currency(), &game.prompts, game.ui_width(), game.ui_height());
+        draw_end_prompts(d, self.exported.as_ref(), &game.prompts, game.ui_width(), game.ui_height());
+    }
+}
+
+pub struct VictoryScreen {
+    exported: Option<Result<String, String>>,
+    grading: Option<(Grading, bool)>, // Grade of a finished map file, and whether it is a new best
+}
+
+impl VictoryScreen {
+    pub fn new(grading: Option<(Grading, bool)>) -> Self {
+        VictoryScreen { exported: None, grading }
+    }
+}
+
+impl Screen for VictoryScreen {
+    fn update(&mut self, game: &mut Game, rl: &mut RaylibHandle, _delta_time: f32) -> Transition {
+        export_summary(game, rl, "victory", &mut self.exported);
+        if let Some(transition) = review_route(game, rl) {
+            return transition;
+        }
+        // Handle victory screen input
+        let gamepad_available = rl.is_gamepad_available(0);
+        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) || rl.is_key_pressed(KeyboardKey::KEY_SPACE) ||
+           (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN)) {
+            // Campaign maps lead back to the hub, anything else to the start screen
+            if game.campaign {
+                game.selected_map = HUB_MAP_INDEX;
+                if let Err(e) = game.start_map(rl) {
+                    return game.map_failed(rl, e);
+                }
+                return Transition::Replace(Box::new(PlayingScreen::new()));
+            }
+            game.leave_map(rl);
+            return Transition::PopToRoot;
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) ||
+           (gamepad_available && rl.is_gamepad_button_pressed(0, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT)) {
+            return Transition::Push(Box::new(ConfirmScreen::new(
+                ConfirmDialog::new("Quit the game?", "You will return to the desktop"),
+                ConfirmAction::QuitGame,
+            )));
+        }
+        Transition::None
+    }
+
+    fn draw(&self, game: &Game, d: &mut RaylibDrawHandle) {
+        render_victory_screen(d, game.campaign, self.grading.as_ref(), &game.prompts, game.ui_width(), game.ui_height());
+        draw_end_prompts(d, self.exported.as_ref(), &game.prompts, game.ui_width(), game.ui_height());
+    }
+}
@@ -0,0 +1,191 @@
+// settings.rs
+
+use std::collections::BTreeMap;
+
+use crate::actions::{ACTIONS, Action, Binding, INPUT_PRESETS, InputPreset};
+use crate::director::{DIFFICULTIES, Difficulty};
+use crate::savefile::{LoadError, SaveFormat, load_versioned, save_versioned};
+
+pub const SETTINGS_PATH: &str = "settings.txt";
+
+// Bump with a new migration step whenever a key changes meaning or name
+const SETTINGS_FORMAT: SaveFormat = SaveFormat { name: "settings", version: 1, migrate: migrate_settings };
+
+pub const RENDER_SCALES: [f32; 3] = [0.5, 0.75, 1.0];
+pub const UI_SCALES: [f32; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
+pub const MINIMAP_ZOOMS: [i32; 5] = [4, 6, 8, 12, 16]; // Minimap pixels per maze cell
+pub const MINIMAP_SIZES: [i32; 3] = [160, 200, 280];   // Minimap width and height in pixels
+pub const GYRO_SENSITIVITY: (f32, f32, f32) = (0.25, 3.0, 0.25); // Slider minimum, maximum and step
+pub const AIM_ASSIST: (f32, f32, f32) = (0.0, 1.0, 0.1);
+
+// Display settings picked together: performance mode, render scale and UI scale
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayPreset {
+    pub performance_mode: bool,
+    pub render_scale: f32,
+    pub ui_scale: f32,
+}
+
+pub const DESKTOP_PRESET: DisplayPreset = DisplayPreset { performance_mode: false, render_scale: 1.0, ui_scale: 1.0 };
+
+// Small screens like the Steam Deck: half resolution 3D view and the HUD and menus a size up
+pub const HANDHELD_PRESET: DisplayPreset = DisplayPreset { performance_mode: true, render_scale: 0.5, ui_scale: 1.25 };
+
+// Everything on the settings screen, kept between launches
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub performance_mode: bool,
+    pub render_scale: f32,
+    pub ui_scale: f32,
+    pub show_minimap: bool,
+    pub minimap_zoom: i32, // Zoom each map starts at, +/- and the mouse wheel change it while playing
+    pub minimap_size: i32,
+    pub music_enabled: bool,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub berserk: bool,
+    pub scanlines: bool,
+    pub feedback_fx: bool, // Damage and water screen effects
+    pub companion: bool,   // The wisp that follows the player, see companion.rs
+    pub shadows: bool,     // Blobs under enemies and coins, they cost fill rate
+    pub difficulty: Difficulty, // Pacing of the enemy director, see director.rs
+    pub gyro_aim: bool,         // Turn with the controller's gyro, see gyro.rs
+    pub gyro_sensitivity: f32,  // Multiplies the controller's own turn rate
+    pub input_preset: InputPreset, // Key and stick layout, see actions.rs
+    pub hud_pillarbox: bool,       // Keep the HUD to a 16:9 box on ultrawide windows
+    pub aim_assist: f32,           // Pull of controller swings toward enemies, 0.0 turns it off
+    pub rebinds: Vec<(Action, Binding)>, // Controls screen changes on top of the input preset, see actions.rs
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            performance_mode: false,
+            render_scale: 1.0,
+            ui_scale: 1.0,
+            show_minimap: false,
+            minimap_zoom: 8,
+            minimap_size: 200,
+            music_enabled: true,
+            master_volume: 1.0,
+            music_volume: 0.5,
+            sfx_volume: 0.7,
+            berserk: false,
+            scanlines: false,
+            feedback_fx: true,
+            companion: false,
+            shadows: true,
+            difficulty: Difficulty::Normal,
+            gyro_aim: false,
+            gyro_sensitivity: 1.0,
+            input_preset: InputPreset::Default,
+            hud_pillarbox: false,
+            aim_assist: 0.5,
+            rebinds: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    // Defaults when there is no file yet, and for any key that is missing or unreadable
+    pub fn load(path: &str) -> Result<Settings, LoadError> {
+        let mut settings = Settings::default();
+        let Some(values) = load_versioned(path, &SETTINGS_FORMAT)? else {
+            return Ok(settings);
+        };
+
+        let flag = |key: &str, default: bool| values.get(key).and_then(|value| value.parse().ok()).unwrap_or(default);
+        let number = |key: &str, default: f32| values.get(key).and_then(|value| value.parse().ok()).unwrap_or(default);
+        let volume = |key: &str, default: f32| number(key, default).clamp(0.0, 1.0);
+        settings.performance_mode = flag("performance_mode", settings.performance_mode);
+        settings.render_scale = nearest(number("render_scale", settings.render_scale), &RENDER_SCALES);
+        settings.ui_scale = nearest(number("ui_scale", settings.ui_scale), &UI_SCALES);
+        settings.show_minimap = flag("show_minimap", settings.show_minimap);
+        settings.minimap_zoom = nearest_size(number("minimap_zoom", settings.minimap_zoom as f32) as i32, &MINIMAP_ZOOMS);
+        settings.minimap_size = nearest_size(number("minimap_size", settings.minimap_size as f32) as i32, &MINIMAP_SIZES);
+        settings.music_enabled = flag("music_enabled", settings.music_enabled);
+        settings.master_volume = volume("master_volume", settings.master_volume);
+        settings.music_volume = volume("music_volume", settings.music_volume);
+        settings.sfx_volume = volume("sfx_volume", settings.sfx_volume);
+        settings.berserk = flag("berserk", settings.berserk);
+        settings.scanlines = flag("scanlines", settings.scanlines);
+        settings.feedback_fx = flag("feedback_fx", settings.feedback_fx);
+        settings.companion = flag("companion", settings.companion);
+        settings.shadows = flag("shadows", settings.shadows);
+        if let Some(value) = values.get("difficulty") {
+            settings.difficulty = DIFFICULTIES.into_iter().find(|difficulty| difficulty.name() == value).unwrap_or(settings.difficulty);
+        }
+        settings.gyro_aim = flag("gyro_aim", settings.gyro_aim);
+        settings.gyro_sensitivity = number("gyro_sensitivity", settings.gyro_sensitivity).clamp(GYRO_SENSITIVITY.0, GYRO_SENSITIVITY.1);
+        if let Some(value) = values.get("input_preset") {
+            settings.input_preset = INPUT_PRESETS.into_iter().find(|preset| preset.name() == value).unwrap_or(settings.input_preset);
+        }
+        settings.hud_pillarbox = flag("hud_pillarbox", settings.hud_pillarbox);
+        settings.aim_assist = number("aim_assist", settings.aim_assist).clamp(AIM_ASSIST.0, AIM_ASSIST.1);
+        for action in ACTIONS {
+            let key = values.get(&format!("bind_{}", action.id())).map(|name| (name, Binding::key_named(name)));
+            let button = values.get(&format!("bind_pad_{}", action.id())).map(|name| (name, Binding::button_named(name)));
+            for (name, binding) in key.into_iter().chain(button) {
+                match binding {
+                    Some(binding) => settings.rebinds.push((action, binding)),
+                    None => elog!("Warning: Ignoring unknown binding {} for {}", name, action.label()),
+                }
+            }
+        }
+        log!("Loaded settings from {}", path);
+        Ok(settings)
+    }
+
+    // Every setting as it is written to the file
+    pub fn values(&self) -> BTreeMap<String, String> {
+        let mut values = BTreeMap::new();
+        values.insert("performance_mode".to_string(), self.performance_mode.to_string());
+        values.insert("render_scale".to_string(), format!("{:.2}", self.render_scale));
+        values.insert("ui_scale".to_string(), format!("{:.2}", self.ui_scale));
+        values.insert("show_minimap".to_string(), self.show_minimap.to_string());
+        values.insert("minimap_zoom".to_string(), self.minimap_zoom.to_string());
+        values.insert("minimap_size".to_string(), self.minimap_size.to_string());
+        values.insert("music_enabled".to_string(), self.music_enabled.to_string());
+        values.insert("master_volume".to_string(), format!("{:.2}", self.master_volume));
+        values.insert("music_volume".to_string(), format!("{:.2}", self.music_volume));
+        values.insert("sfx_volume".to_string(), format!("{:.2}", self.sfx_volume));
+        values.insert("berserk".to_string(), self.berserk.to_string());
+        values.insert("scanlines".to_string(), self.scanlines.to_string());
+        values.insert("feedback_fx".to_string(), self.feedback_fx.to_string());
+        values.insert("companion".to_string(), self.companion.to_string());
+        values.insert("shadows".to_string(), self.shadows.to_string());
+        values.insert("difficulty".to_string(), self.difficulty.name().to_string());
+        values.insert("gyro_aim".to_string(), self.gyro_aim.to_string());
+        values.insert("gyro_sensitivity".to_string(), format!("{:.2}", self.gyro_sensitivity));
+        values.insert("input_preset".to_string(), self.input_preset.name().to_string());
+        values.insert("hud_pillarbox".to_string(), self.hud_pillarbox.to_string());
+        values.insert("aim_assist".to_string(), format!("{:.2}", self.aim_assist));
+        for (action, binding) in self.rebinds.iter() {
+            let key = match binding {
+                Binding::Key(_) => format!("bind_{}", action.id()),
+                Binding::Button(_) => format!("bind_pad_{}", action.id()),
+            };
+            values.insert(key, binding.name().to_string());
+        }
+        values
+    }
+
+    pub fn save(&self, path: &str) {
+        save_versioned(path, "Raycaster Dungeon settings", &SETTINGS_FORMAT, &self.values());
+    }
+}
+
+// Closest of the offered choices, so a hand-edited file cannot pick an odd scale
+pub fn nearest(value: f32, choices: &[f32]) -> f32 {
+    choices.iter().copied().min_by(|a, b| (a - value).abs().total_cmp(&(b - value).abs())).unwrap_or(value)
+}
+
+pub fn nearest_size(value: i32, choices: &[i32]) -> i32 {
+    choices.iter().copied().min_by_key(|choice| (choice - value).abs()).unwrap_or(value)
+}
+
+// Settings were first saved at version 1, so there is no older layout to convert
+fn migrate_settings(from: u32, _values: &mut BTreeMap<String, String>) {
+    elog!("Warning: No settings migration from version {}", from);
+}
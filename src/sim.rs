@@ -0,0 +1,341 @@
+// sim.rs
+
+use std::f32::consts::PI;
+
+use crate::maze::{Cell, Maze, is_open_floor, is_partial_wall, is_see_through_wall, is_walkable, touches_partial_wall};
+
+// Game rules with no raylib in them: collision, movement steps, line of sight, goal detection
+// and sword hit resolution. Everything is in world units on plain floats and Vec2, so it
+// runs under `cargo test` without a window, a GPU or an audio device. The game converts
+// raylib's Vector2 at the call sites; input is read from the devices into PlayerInput
+// first, and player.rs applies that to the player through these functions.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vec2 {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Vec2 { x, y }
+    }
+
+    // `length` long, pointing along `angle`
+    pub fn from_angle(angle: f32, length: f32) -> Self {
+        Vec2::new(angle.cos() * length, angle.sin() * length)
+    }
+
+    pub fn length(self) -> f32 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    pub fn distance(self, other: Vec2) -> f32 {
+        (other - self).length()
+    }
+}
+
+impl std::ops::Add for Vec2 {
+    type Output = Vec2;
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl std::ops::Sub for Vec2 {
+    type Output = Vec2;
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl std::ops::Mul<f32> for Vec2 {
+    type Output = Vec2;
+    fn mul(self, factor: f32) -> Vec2 {
+        Vec2::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl From<raylib::prelude::Vector2> for Vec2 {
+    fn from(v: raylib::prelude::Vector2) -> Vec2 {
+        Vec2::new(v.x, v.y)
+    }
+}
+
+impl From<Vec2> for raylib::prelude::Vector2 {
+    fn from(v: Vec2) -> raylib::prelude::Vector2 {
+        raylib::prelude::Vector2::new(v.x, v.y)
+    }
+}
+
+// What the player asked for this frame, whatever device it came from
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerInput {
+    pub turn: f32,             // Radians added to the view angle
+    pub steps: Vec<(f32, f32)>, // (direction from the view angle, fraction of a full step), taken in order
+    pub sprint: bool,
+    pub attack: bool,
+    pub pad_attack: bool,      // The attack came from the controller, the only one aim assist helps
+}
+
+const GOAL_RADIUS: f32 = 0.7;   // Cells from the goal's center that count as reaching it
+const ENEMY_MARGIN: f32 = 0.2;  // Cells, half the side of an enemy's collision box
+
+// True if the player can't stand at `pos`: walls, the outside of the map and thin walls near their segment
+pub fn player_blocked(maze: &Maze, pos: Vec2, block_size: usize) -> bool {
+    let Some(cell) = maze.at_world(pos.x, pos.y, block_size) else {
+        return true;
+    };
+    if is_partial_wall(cell) {
+        return touches_partial_wall(cell, pos.x, pos.y, block_size);
+    }
+    !is_walkable(cell)
+}
+
+// Where one step of the player ends, None if it would end inside a wall
+pub fn try_step(maze: &Maze, pos: Vec2, step: Vec2, block_size: usize) -> Option<Vec2> {
+    let next = pos + step;
+    (!player_blocked(maze, next, block_size)).then_some(next)
+}
+
+// Enemies are a box, every corner of which must be on open floor
+pub fn enemy_blocked(maze: &Maze, pos: Vec2, block_size: usize) -> bool {
+    let margin = block_size as f32 * ENEMY_MARGIN;
+    let corners = [(-margin, -margin), (margin, -margin), (-margin, margin), (margin, margin)];
+    corners.iter().any(|&(dx, dy)| !maze.at_world(pos.x + dx, pos.y + dy, block_size).is_some_and(is_open_floor))
+}
+
+// `distance` along the line from `pos` to `target`, without overshooting it
+pub fn step_toward(pos: Vec2, target: Vec2, distance: f32) -> Vec2 {
+    let gap = pos.distance(target);
+    if gap <= distance || gap == 0.0 {
+        return target;
+    }
+    pos + (target - pos) * (distance / gap)
+}
+
+// Sampled every quarter cell; fences, grates and windows can be seen through
+pub fn line_of_sight(from: Vec2, to: Vec2, maze: &Maze, block_size: usize) -> bool {
+    let steps = (from.distance(to) / (block_size as f32 * 0.25)) as i32;
+    (0..=steps).all(|i| {
+        let t = if steps == 0 { 0.0 } else { i as f32 / steps as f32 };
+        let point = from + (to - from) * t;
+        match maze.at_world(point.x, point.y, block_size) {
+            Some(cell) => is_open_floor(cell) || is_see_through_wall(cell),
+            None => true,
+        }
+    })
+}
+
+// Close enough to the center of a goal cell in or next to the player's cell
+pub fn goal_reached(maze: &Maze, pos: Vec2, block_size: usize) -> bool {
+    let block = block_size as f32;
+    let (cell_x, cell_y) = ((pos.x / block) as i32, (pos.y / block) as i32);
+    for y in cell_y - 1..=cell_y + 1 {
+        for x in cell_x - 1..=cell_x + 1 {
+            if maze.get_i32(x, y) == Some(Cell::Goal) {
+                let center = Vec2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block);
+                if pos.distance(center) <= block * GOAL_RADIUS {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+// Into -PI..=PI
+pub fn normalize_angle(angle: f32) -> f32 {
+    let mut angle = angle;
+    while angle > PI {
+        angle -= 2.0 * PI;
+    }
+    while angle < -PI {
+        angle += 2.0 * PI;
+    }
+    angle
+}
+
+// Angle off the middle of a swing for a target inside its range and cone, None if it misses
+pub fn swing_hit(origin: Vec2, facing: f32, target: Vec2, range: f32, arc: f32) -> Option<f32> {
+    if origin.distance(target) > range {
+        return None;
+    }
+    let offset = target - origin;
+    let angle_diff = normalize_angle(offset.y.atan2(offset.x) - facing);
+    (angle_diff.abs() <= arc).then_some(angle_diff)
+}
+
+// Radians to turn toward the target nearest the middle of the view, inside `window` either side
+// and `range`, scaled by `strength` so the view is pulled rather than snapped. 0.0 without one.
+pub fn aim_assist(origin: Vec2, facing: f32, targets: impl Iterator<Item = Vec2>, range: f32, window: f32, strength: f32) -> f32 {
+    targets
+        .filter_map(|target| swing_hit(origin, facing, target, range, window))
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .map_or(0.0, |angle_diff| angle_diff * strength)
+}
+
+// True if `attacker` is within `arc` radians either side of straight behind a target facing `heading`
+pub fn from_behind(target: Vec2, heading: f32, attacker: Vec2, arc: f32) -> bool {
+    let offset = attacker - target;
+    normalize_angle(offset.y.atan2(offset.x) - heading - PI).abs() <= arc
+}
+
+// True if `attacker` is within `arc` radians either side of straight ahead of a target facing `heading`
+pub fn from_front(target: Vec2, heading: f32, attacker: Vec2, arc: f32) -> bool {
+    let offset = attacker - target;
+    normalize_angle(offset.y.atan2(offset.x) - heading).abs() <= arc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: usize = 100;
+
+    fn maze(rows: &[&str]) -> Maze {
+        Maze::from_rows(rows.iter().map(|row| row.chars().map(Cell::from_char).collect()).collect())
+    }
+
+    fn center(x: usize, y: usize) -> Vec2 {
+        Vec2::new((x as f32 + 0.5) * BLOCK as f32, (y as f32 + 0.5) * BLOCK as f32)
+    }
+
+    fn corridor() -> Maze {
+        maze(&[
+            "+----+",
+            "|p  g|",
+            "+----+",
+        ])
+    }
+
+    #[test]
+    fn walls_and_outside_block_the_player() {
+        let maze = corridor();
+        assert!(!player_blocked(&maze, center(1, 1), BLOCK));
+        assert!(!player_blocked(&maze, center(2, 1), BLOCK));
+        assert!(player_blocked(&maze, center(0, 1), BLOCK));
+        assert!(player_blocked(&maze, center(2, 0), BLOCK));
+        assert!(player_blocked(&maze, Vec2::new(-10.0, 150.0), BLOCK));
+        assert!(player_blocked(&maze, center(9, 9), BLOCK));
+    }
+
+    #[test]
+    fn steps_into_walls_are_refused() {
+        let maze = corridor();
+        let start = center(1, 1);
+        assert_eq!(try_step(&maze, start, Vec2::new(40.0, 0.0), BLOCK), Some(Vec2::new(190.0, 150.0)));
+        assert_eq!(try_step(&maze, start, Vec2::new(0.0, -80.0), BLOCK), None);
+        assert_eq!(try_step(&maze, start, Vec2::new(-80.0, 0.0), BLOCK), None);
+    }
+
+    #[test]
+    fn water_is_walkable_and_enemies_keep_off_walls() {
+        let maze = maze(&[
+            "+-----+",
+            "|p~   |",
+            "|     |",
+            "|     |",
+            "+-----+",
+        ]);
+        assert!(!player_blocked(&maze, center(2, 1), BLOCK));
+        assert!(!enemy_blocked(&maze, center(3, 2), BLOCK));
+        assert!(enemy_blocked(&maze, Vec2::new(350.0, 115.0), BLOCK)); // Box pokes into the top wall
+    }
+
+    #[test]
+    fn fences_only_block_near_their_segment() {
+        let maze = maze(&[
+            "+---+",
+            "| = |",
+            "+---+",
+        ]);
+        assert!(player_blocked(&maze, center(2, 1), BLOCK));
+        assert!(!player_blocked(&maze, Vec2::new(250.0, 105.0), BLOCK));
+    }
+
+    #[test]
+    fn walls_block_sight_and_windows_do_not() {
+        let maze = maze(&[
+            "+-----+",
+            "|  |  |",
+            "|  w  |",
+            "+-----+",
+        ]);
+        assert!(line_of_sight(center(1, 1), center(2, 2), &maze, BLOCK));
+        assert!(!line_of_sight(center(1, 1), center(5, 1), &maze, BLOCK));
+        assert!(line_of_sight(center(1, 2), center(5, 2), &maze, BLOCK));
+    }
+
+    #[test]
+    fn goal_is_reached_near_its_center() {
+        let maze = corridor();
+        assert!(goal_reached(&maze, center(4, 1), BLOCK));
+        assert!(goal_reached(&maze, Vec2::new(390.0, 150.0), BLOCK)); // 0.6 cells short, next cell over
+        assert!(!goal_reached(&maze, center(3, 1) - Vec2::new(30.0, 0.0), BLOCK));
+        assert!(!goal_reached(&maze, center(1, 1), BLOCK));
+    }
+
+    #[test]
+    fn goal_needs_a_goal_cell() {
+        let maze = maze(&[
+            "+--+",
+            "|p |",
+            "+--+",
+        ]);
+        assert!(!goal_reached(&maze, center(2, 1), BLOCK));
+    }
+
+    #[test]
+    fn steps_toward_stop_at_the_target() {
+        let target = Vec2::new(10.0, 0.0);
+        assert_eq!(step_toward(Vec2::default(), target, 4.0), Vec2::new(4.0, 0.0));
+        assert_eq!(step_toward(Vec2::default(), target, 20.0), target);
+        assert_eq!(step_toward(target, target, 1.0), target);
+    }
+
+    #[test]
+    fn swings_hit_inside_range_and_cone() {
+        let origin = Vec2::default();
+        assert_eq!(swing_hit(origin, 0.0, Vec2::new(100.0, 0.0), 150.0, PI / 6.0), Some(0.0));
+        assert!(swing_hit(origin, 0.0, Vec2::new(200.0, 0.0), 150.0, PI / 6.0).is_none());
+        assert!(swing_hit(origin, 0.0, Vec2::new(0.0, 100.0), 150.0, PI / 6.0).is_none());
+        // Facing just under PI still hits a target just past -PI
+        assert!(swing_hit(origin, PI - 0.05, Vec2::from_angle(-PI + 0.05, 100.0), 150.0, PI / 6.0).is_some());
+    }
+
+    #[test]
+    fn aim_assist_pulls_toward_the_nearest_target_in_the_window() {
+        let origin = Vec2::default();
+        let near_middle = Vec2::from_angle(0.1, 100.0);
+        let off_middle = Vec2::from_angle(-0.3, 100.0);
+        let turn = aim_assist(origin, 0.0, [off_middle, near_middle].into_iter(), 300.0, 0.35, 0.5);
+        assert!((turn - 0.05).abs() < 1e-4);
+        // Outside the window or the range, nothing moves
+        assert_eq!(aim_assist(origin, 0.0, [Vec2::from_angle(0.5, 100.0)].into_iter(), 300.0, 0.35, 0.5), 0.0);
+        assert_eq!(aim_assist(origin, 0.0, [Vec2::from_angle(0.1, 400.0)].into_iter(), 300.0, 0.35, 0.5), 0.0);
+    }
+
+    #[test]
+    fn backstabs_need_to_come_from_behind() {
+        let target = Vec2::new(100.0, 100.0);
+        assert!(from_behind(target, 0.0, Vec2::new(20.0, 100.0), PI / 3.0));
+        assert!(from_behind(target, 0.0, Vec2::new(20.0, 60.0), PI / 3.0)); // About 27 degrees off
+        assert!(!from_behind(target, 0.0, Vec2::new(180.0, 100.0), PI / 3.0));
+        assert!(!from_behind(target, 0.0, Vec2::new(100.0, 20.0), PI / 3.0)); // Beside it
+        // Facing down the screen, its back is up
+        assert!(from_behind(target, PI / 2.0, Vec2::new(100.0, 20.0), PI / 3.0));
+    }
+
+    #[test]
+    fn shields_only_cover_the_front() {
+        let target = Vec2::new(100.0, 100.0);
+        assert!(from_front(target, 0.0, Vec2::new(180.0, 100.0), PI / 3.0));
+        assert!(from_front(target, 0.0, Vec2::new(180.0, 140.0), PI / 3.0)); // About 27 degrees off
+        assert!(!from_front(target, 0.0, Vec2::new(100.0, 180.0), PI / 3.0)); // Flanked
+        assert!(!from_front(target, 0.0, Vec2::new(20.0, 100.0), PI / 3.0));
+        // Facing up the screen, around the wrap from -PI to PI
+        assert!(from_front(target, -PI / 2.0, Vec2::new(100.0, 20.0), PI / 3.0));
+        assert!(from_front(target, PI, Vec2::new(20.0, 105.0), PI / 3.0));
+    }
+}
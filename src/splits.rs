@@ -0,0 +1,127 @@
+// splits.rs
+
+use raylib::prelude::*;
+use std::fs;
+
+use crate::profile::Profile;
+
+// Speedrun timer: a split is recorded at every checkpoint cell and on touching the goal
+pub struct SplitTimer {
+    key: String,                   // Profile key suffix, one personal best per map
+    pub splits: Vec<f32>,          // Run time at each split of the current attempt
+    best: Vec<f32>,                // Personal best splits loaded from the profile
+    touched: Vec<(usize, usize)>,  // Checkpoint cells already split on
+}
+
+impl SplitTimer {
+    pub fn new(key: &str, profile: &Profile) -> Self {
+        let best = profile
+            .get(&format!("splits.{}", key))
+            .map(|value| value.split(',').filter_map(|split| split.trim().parse().ok()).collect())
+            .unwrap_or_default();
+
+        SplitTimer {
+            key: key.to_string(),
+            splits: Vec::new(),
+            best,
+            touched: Vec::new(),
+        }
+    }
+
+    pub fn split(&mut self, time: f32) {
+        self.splits.push(time);
+    }
+
+    // Splits once per checkpoint cell, returns true on the first touch
+    pub fn touch_checkpoint(&mut self, cell: (usize, usize), time: f32) -> bool {
+        if self.touched.contains(&cell) {
+            return false;
+        }
+        self.touched.push(cell);
+        self.split(time);
+        true
+    }
+
+    pub fn touched(&self) -> &[(usize, usize)] {
+        &self.touched
+    }
+
+    // Picks an attempt back up from a save: its splits so far and the checkpoints they came from
+    pub fn restore(&mut self, splits: Vec<f32>, touched: Vec<(usize, usize)>) {
+        self.splits = splits;
+        self.touched = touched;
+    }
+
+    // Time against the personal best at the same split, negative is ahead
+    pub fn delta(&self, index: usize) -> Option<f32> {
+        match (self.splits.get(index), self.best.get(index)) {
+            (Some(time), Some(best)) => Some(time - best),
+            _ => None,
+        }
+    }
+
+    // Reaching more splits, or the same number faster, is a new personal best
+    fn beats_best(&self) -> bool {
+        if self.splits.len() != self.best.len() {
+            return self.splits.len() > self.best.len();
+        }
+        match (self.splits.last(), self.best.last()) {
+            (Some(time), Some(best)) => time < best,
+            _ => false,
+        }
+    }
+
+    // Stores the current splits as the personal best if they beat it
+    pub fn commit_if_best(&mut self, profile: &mut Profile) -> bool {
+        if !self.beats_best() {
+            return false;
+        }
+        let value: Vec<String> = self.splits.iter().map(|time| format!("{:.2}", time)).collect();
+        profile.set(&format!("splits.{}", self.key), value.join(","));
+        self.best = self.splits.clone();
+        true
+    }
+
+    // Writes the attempt to splits_<key>.txt for speedrunners to keep or share
+    pub fn export(&self, title: &str) {
+        let path = format!("splits_{}.txt", self.key);
+        let mut contents = format!("# {} splits\n", title);
+        let mut previous = 0.0;
+        for (i, time) in self.splits.iter().enumerate() {
+            let delta = match self.delta(i) {
+                Some(delta) => format!("{:+.2}", delta),
+                None => "-".to_string(),
+            };
+            contents.push_str(&format!("{}\t{:.2}\t{:.2}\t{}\n", i + 1, time, time - previous, delta));
+            previous = *time;
+        }
+
+        match fs::write(&path, contents) {
+            Ok(_) => log!("Exported splits to {}", path),
+            Err(e) => elog!("Warning: Could not export splits to {}: {:?}", path, e),
+        }
+    }
+
+    // Timer in the top-right corner with the latest splits and their deltas below
+    pub fn draw(&self, d: &mut RaylibDrawHandle, time: f32, screen_width: i32) {
+        let x = screen_width - 220;
+        d.draw_rectangle(x - 10, 10, 220, 40 + 22 * self.splits.len().min(5) as i32, Color::new(0, 0, 0, 150));
+        d.draw_text(&format_split(time), x, 16, 28, Color::WHITE);
+
+        let first = self.splits.len().saturating_sub(5);
+        for (row, i) in (first..self.splits.len()).enumerate() {
+            let y = 50 + row as i32 * 22;
+            d.draw_text(&format!("{}. {}", i + 1, format_split(self.splits[i])), x, y, 18, Color::LIGHTGRAY);
+            if let Some(delta) = self.delta(i) {
+                // Green when ahead of the personal best, red when behind
+                let color = if delta <= 0.0 { Color::GREEN } else { Color::RED };
+                d.draw_text(&format!("{:+.1}", delta), x + 140, y, 18, color);
+            }
+        }
+    }
+}
+
+fn format_split(seconds: f32) -> String {
+    let minutes = (seconds / 60.0) as u32;
+    format!("{:02}:{:05.2}", minutes, seconds - minutes as f32 * 60.0)
+}
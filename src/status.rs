@@ -0,0 +1,137 @@
+// status.rs
+
+use raylib::prelude::*;
+
+use crate::maze::Cell;
+
+// Lingering effects on the player or an enemy: burning and poison hurt on a fixed tick,
+// slowed cuts movement speed. They come from hazard floors, enemies whose definition
+// inflicts one with their hits, and weapons that set enemies alight. Applying an effect
+// that is already running only tops up its time, so standing in fire never stacks damage.
+
+const TICK_INTERVAL: f32 = 0.5;    // Seconds between damage ticks
+const SLOWED_MULTIPLIER: f32 = 0.6; // Movement speed while slowed
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatusKind {
+    Burning,
+    Poisoned,
+    Slowed,
+}
+
+impl StatusKind {
+    // Name used in enemy definitions and on the HUD
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "burning" => Some(StatusKind::Burning),
+            "poisoned" => Some(StatusKind::Poisoned),
+            "slowed" => Some(StatusKind::Slowed),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusKind::Burning => "Burning",
+            StatusKind::Poisoned => "Poisoned",
+            StatusKind::Slowed => "Slowed",
+        }
+    }
+
+    // Seconds an application lasts
+    pub fn duration(self) -> f32 {
+        match self {
+            StatusKind::Burning => 3.0,
+            StatusKind::Poisoned => 6.0,
+            StatusKind::Slowed => 2.5,
+        }
+    }
+
+    fn tick_damage(self) -> f32 {
+        match self {
+            StatusKind::Burning => 3.0,
+            StatusKind::Poisoned => 1.5,
+            StatusKind::Slowed => 0.0,
+        }
+    }
+
+    // HUD icon color, also blended into the sprites of affected enemies
+    pub fn color(self) -> Color {
+        match self {
+            StatusKind::Burning => Color::new(255, 120, 20, 255),
+            StatusKind::Poisoned => Color::new(90, 220, 60, 255),
+            StatusKind::Slowed => Color::new(90, 160, 255, 255),
+        }
+    }
+}
+
+// Hazard floors keep applying their effect while something stands on them
+pub fn floor_hazard(cell: Cell) -> Option<StatusKind> {
+    match cell {
+        Cell::FireTrap => Some(StatusKind::Burning),
+        Cell::Swamp => Some(StatusKind::Poisoned),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub remaining: f32, // Seconds left
+    tick_timer: f32,    // Seconds until the next damage tick
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    pub fn new() -> Self {
+        StatusEffects { effects: Vec::new() }
+    }
+
+    // Start an effect, or top up the time of one already running
+    pub fn apply(&mut self, kind: StatusKind) {
+        match self.effects.iter_mut().find(|effect| effect.kind == kind) {
+            Some(effect) => effect.remaining = effect.remaining.max(kind.duration()),
+            None => self.effects.push(StatusEffect { kind, remaining: kind.duration(), tick_timer: TICK_INTERVAL }),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+        self.effects.iter()
+    }
+
+    // Age the effects and return the damage from ticks that came due this frame
+    pub fn update(&mut self, delta_time: f32) -> f32 {
+        let mut damage = 0.0;
+        for effect in self.effects.iter_mut() {
+            effect.remaining -= delta_time;
+            effect.tick_timer -= delta_time;
+            while effect.tick_timer <= 0.0 {
+                effect.tick_timer += TICK_INTERVAL;
+                damage += effect.kind.tick_damage();
+            }
+        }
+        self.effects.retain(|effect| effect.remaining > 0.0);
+        damage
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.has(StatusKind::Slowed) { SLOWED_MULTIPLIER } else { 1.0 }
+    }
+
+    // Color to blend into a sprite, the most recent effect wins
+    pub fn tint(&self) -> Option<Color> {
+        self.effects.last().map(|effect| effect.kind.color())
+    }
+}
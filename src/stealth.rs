@@ -0,0 +1,85 @@
+// stealth.rs
+
+use raylib::prelude::*;
+
+use crate::color;
+use crate::has_line_of_sight;
+use crate::maze::{Cell, Maze};
+use crate::ping::Ping;
+use crate::player::Player;
+use crate::status::StatusKind;
+
+// How well lit the player is, from 0.0 (pitch dark) to 1.0 (fully lit). There is no light
+// map, so the light comes from the things that already glow: the goal beacon, fire traps,
+// ping pillars, the player's own lantern and being on fire. Lights behind a wall don't count.
+// Enemies notice a well lit player quickly and can be crept past in dark corridors.
+
+const AMBIENT_LIGHT: f32 = 0.15;  // Light everywhere, so nobody is ever fully invisible
+const LANTERN_LIGHT: f32 = 0.05;  // Per cell of lantern radius, so a bigger lantern gives you away
+const GLOW_RADIUS: f32 = 3.0;     // Cells a glowing cell or ping lights up around it
+const GOAL_LIGHT: f32 = 0.7;
+const FIRE_LIGHT: f32 = 0.5;
+const PING_LIGHT: f32 = 0.5;      // At full strength, fading with the ping
+const BURNING_LIGHT: f32 = 0.5;
+
+fn glow(cell: Cell) -> f32 {
+    match cell {
+        Cell::Goal => GOAL_LIGHT,
+        Cell::FireTrap => FIRE_LIGHT,
+        _ => 0.0,
+    }
+}
+
+pub fn illumination(player: &Player, maze: &Maze, pings: &[Ping], block_size: usize) -> f32 {
+    let block = block_size as f32;
+    let falloff = |pos: Vector2| {
+        let distance = ((pos.x - player.pos.x).powi(2) + (pos.y - player.pos.y).powi(2)).sqrt() / block;
+        (1.0 - distance / GLOW_RADIUS).max(0.0)
+    };
+
+    let mut light = AMBIENT_LIGHT + LANTERN_LIGHT * player.sight_radius();
+    if player.status.has(StatusKind::Burning) {
+        light += BURNING_LIGHT;
+    }
+
+    // Glowing cells around the player
+    let reach = GLOW_RADIUS.ceil() as i32;
+    let (cell_x, cell_y) = ((player.pos.x / block) as i32, (player.pos.y / block) as i32);
+    for y in cell_y - reach..=cell_y + reach {
+        for x in cell_x - reach..=cell_x + reach {
+            let Some(cell) = maze.get_i32(x, y).filter(|&cell| glow(cell) > 0.0) else {
+                continue;
+            };
+            let center = Vector2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block);
+            if falloff(center) > 0.0 && has_line_of_sight(center, player.pos, maze, block_size) {
+                light += glow(cell) * falloff(center);
+            }
+        }
+    }
+
+    for ping in pings.iter() {
+        if has_line_of_sight(ping.pos, player.pos, maze, block_size) {
+            light += PING_LIGHT * ping.strength() * falloff(ping.pos);
+        }
+    }
+    light.min(1.0)
+}
+
+// Visibility gem at the bottom of the HUD, dull when hidden and glowing when lit
+pub fn draw_gem(d: &mut RaylibDrawHandle, visibility: f32, center_x: i32, center_y: i32) {
+    let color = color::lerp(Color::new(25, 30, 45, 255), Color::new(255, 235, 150, 255), visibility);
+    let (x, y) = (center_x as f32, center_y as f32);
+    let (half_width, half_height) = (14.0, 20.0);
+    let top = Vector2::new(x, y - half_height);
+    let bottom = Vector2::new(x, y + half_height);
+    let left = Vector2::new(x - half_width, y);
+    let right = Vector2::new(x + half_width, y);
+
+    // raylib wants counter-clockwise triangles
+    d.draw_triangle(top, left, right, color);
+    d.draw_triangle(left, bottom, right, color::scale(color, 0.8));
+    d.draw_line_ex(top, left, 2.0, Color::WHITE);
+    d.draw_line_ex(left, bottom, 2.0, Color::WHITE);
+    d.draw_line_ex(bottom, right, 2.0, Color::WHITE);
+    d.draw_line_ex(right, top, 2.0, Color::WHITE);
+}
@@ -0,0 +1,160 @@
+// stress.rs
+
+use raylib::prelude::*;
+use std::time::Duration;
+
+use crate::enemy::{ENEMY_DEFS_PATH, Enemy, EnemyDef, load_enemy_defs};
+use crate::mapgen::generate_maze;
+use crate::maze::{Cell, Maze};
+use crate::rng::Rng;
+use crate::sim::Vec2;
+use crate::swarm::{Swarm, SwarmKind};
+
+// Benchmark map for --stress: a generated maze far bigger than any hand-made one, with straight
+// corridors cut clean across it so rays travel the whole map and rows of enemies are in view
+// at every distance, filled with as many enemies as asked for. While it is being played the
+// time spent simulating and rendering is logged every few seconds.
+
+pub const MAX_STRESS_SIZE: usize = 1000;   // In maze.py cells, per side
+pub const DEFAULT_STRESS_ENEMIES: usize = 200;
+const CORRIDOR_SPACING: usize = 6;         // Maze cells between the straight corridors
+const SAFE_DISTANCE: usize = 8;            // Grid cells around the spawn left without enemies
+const REPORT_INTERVAL: f32 = 5.0;          // Seconds between timing reports
+const ENEMIES_PER_SWARM: usize = 10;       // One swarm of rats or bats for this many enemies asked for
+const SWARM_SIZE: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StressMap {
+    pub width: usize,   // In maze.py cells, like the Daily Dungeon's size
+    pub height: usize,
+    pub enemies: usize,
+}
+
+impl StressMap {
+    // "<width>x<height>", e.g. 200x120
+    pub fn parse_size(text: &str) -> Result<(usize, usize), String> {
+        let size = text.split_once('x').and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+        match size {
+            Some((width, height)) if (2..=MAX_STRESS_SIZE).contains(&width) && (2..=MAX_STRESS_SIZE).contains(&height) => Ok((width, height)),
+            Some(_) => Err(format!("--stress sizes go from 2 to {} cells per side, got {}", MAX_STRESS_SIZE, text)),
+            None => Err(format!("--stress expects a size like 200x120, got {}", text)),
+        }
+    }
+
+    pub fn build(&self, seed: u64) -> Maze {
+        let mut maze = generate_maze(self.width, self.height, seed);
+
+        // Long sightlines: every few rows and columns, knock out the walls along a straight line
+        for row in (CORRIDOR_SPACING..self.height).step_by(CORRIDOR_SPACING) {
+            let y = row * 2 + 1;
+            for x in 1..maze.width() - 1 {
+                knock_out(&mut maze, x, y);
+            }
+        }
+        for column in (CORRIDOR_SPACING..self.width).step_by(CORRIDOR_SPACING) {
+            let x = column * 3 + 1;
+            for y in 1..maze.height() - 1 {
+                knock_out(&mut maze, x, y);
+            }
+        }
+        maze
+    }
+
+    // Enemies on random open cells away from the spawn, cycling through the four behaviours
+    pub fn spawn_enemies(&self, maze: &Maze, block_size: usize, rng: &mut Rng) -> Vec<Enemy> {
+        let def = load_enemy_defs(ENEMY_DEFS_PATH).get(&'a').copied().unwrap_or(EnemyDef::default_for('a'));
+        let block = block_size as f32;
+        let center = |x: usize, y: usize| Vector2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block);
+        let open: Vec<(usize, usize)> = maze
+            .cells()
+            .filter(|&(x, y, cell)| cell == Cell::Empty && x + y > SAFE_DISTANCE)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        if open.is_empty() {
+            return Vec::new();
+        }
+
+        let mut enemies = Vec::with_capacity(self.enemies);
+        for i in 0..self.enemies {
+            let (x, y) = open[rng.range(open.len())];
+            let pos = center(x, y);
+            let mut enemy = match i % 4 {
+                0 => Enemy::new_chase(pos.x, pos.y, def),
+                1 => Enemy::new_wander(pos.x, pos.y, def, block),
+                2 => {
+                    // Back and forth along the row when there is room, otherwise on the spot
+                    let end = if (1..=2).all(|step| maze.get(x + step, y) == Some(Cell::Empty)) { center(x + 2, y) } else { pos };
+                    Enemy::new_patrol(pos.x, pos.y, def, end.x, end.y)
+                }
+                _ => Enemy::new(pos.x, pos.y, def),
+            };
+            enemy.rng = rng.fork();
+            enemies.push(enemy);
+        }
+        log!("Stress map: {}x{} grid cells, {} enemies", maze.width(), maze.height(), enemies.len());
+        enemies
+    }
+
+    // Swarms alongside the enemies, enough of them that their update runs in parallel
+    pub fn spawn_swarms(&self, maze: &Maze, block_size: usize, rng: &mut Rng) -> Vec<Swarm> {
+        let block = block_size as f32;
+        let open: Vec<(usize, usize)> = maze
+            .cells()
+            .filter(|&(x, y, cell)| cell == Cell::Empty && x + y > SAFE_DISTANCE)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        if open.is_empty() {
+            return Vec::new();
+        }
+
+        let swarms: Vec<Swarm> = (0..self.enemies / ENEMIES_PER_SWARM)
+            .map(|i| {
+                let (x, y) = open[rng.range(open.len())];
+                let kind = if i % 2 == 0 { SwarmKind::Rats } else { SwarmKind::Bats };
+                Swarm::new(kind, Vec2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block), SWARM_SIZE, block_size, rng.fork())
+            })
+            .collect();
+        log!("Stress map: {} swarms of {}", swarms.len(), SWARM_SIZE);
+        swarms
+    }
+}
+
+// Walls only, so the spawn and goal stay where they are
+fn knock_out(maze: &mut Maze, x: usize, y: usize) {
+    if matches!(maze.get(x, y), Some(Cell::Wall { .. })) {
+        maze.set(x, y, Cell::Empty);
+    }
+}
+
+// Running totals of the frame's phases, logged and reset every REPORT_INTERVAL
+pub struct FrameProfile {
+    update: Duration,
+    render: Duration,
+    frames: u32,
+    elapsed: f32,
+}
+
+impl FrameProfile {
+    pub fn new() -> Self {
+        FrameProfile { update: Duration::ZERO, render: Duration::ZERO, frames: 0, elapsed: 0.0 }
+    }
+
+    pub fn add_update(&mut self, time: Duration) {
+        self.update += time;
+    }
+
+    // Rendering ends the frame, so this also counts it and reports when due
+    pub fn add_render(&mut self, time: Duration, delta_time: f32, enemies: usize) {
+        self.render += time;
+        self.frames += 1;
+        self.elapsed += delta_time;
+        if self.elapsed < REPORT_INTERVAL {
+            return;
+        }
+
+        let average = |total: Duration| total.as_secs_f64() * 1000.0 / self.frames as f64;
+        log!("Stress: {:.0} fps, {} enemies alive, update {:.2} ms, render {:.2} ms per frame",
+             self.frames as f32 / self.elapsed, enemies, average(self.update), average(self.render));
+        *self = FrameProfile::new();
+    }
+}
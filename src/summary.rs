@@ -0,0 +1,124 @@
+// summary.rs
+
+use raylib::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::convert::json_string;
+use crate::heatmap::Visits;
+use crate::prompts::{ButtonPrompts, Prompt};
+use crate::world::WorldEvent;
+
+// When a map ends, in victory or death, the end screen offers to write a summary of the attempt
+// to run_summary_<unix seconds>.json for sharing or for an outside stats viewer: the outcome and
+// stats, a heatmap of the visits to and seconds spent in each cell on the route, and the
+// settings it was played with, as they are written to settings.txt. The file looks like:
+//   {
+//     "outcome": "victory", "map": "Maze 1", "seed": 42, "time": 63.20, ...
+//     "stats": { "kills": 4, "hits": 9, ... },
+//     "heatmap": { "width": 13, "height": 9, "cells": [[1, 1, 2, 2.35], [2, 1, 1, 0.50], ...] },  (x, y, visits, seconds)
+//     "settings": { "difficulty": "Normal", ... }
+//   }
+
+// What happened during the attempt, counted from the world's events
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Tally {
+    pub kills: u32,
+    pub hits: u32,      // Blows landed, criticals included
+    pub criticals: u32,
+    pub damage_taken: f32,
+    pub props_broken: u32,
+}
+
+impl Tally {
+    // Once a frame with that frame's events and the health the player lost in it
+    pub fn record(&mut self, events: &[WorldEvent], damage: f32) {
+        for event in events {
+            match event {
+                WorldEvent::EnemyKilled => self.kills += 1,
+                WorldEvent::EnemyHit => self.hits += 1,
+                WorldEvent::CriticalHit => {
+                    self.hits += 1;
+                    self.criticals += 1;
+                }
+                _ => {}
+            }
+        }
+        self.damage_taken += damage;
+    }
+}
+
+pub struct RunSummary<'a> {
+    pub outcome: &'a str, // "victory" or "death"
+    pub map: &'a str,
+    pub seed: u64,
+    pub time: f32,        // Seconds on the map
+    pub tally: Tally,
+    pub health: f32,      // Left at the end
+    pub coins: u32,
+    pub size: (usize, usize), // Of the map, in cells
+    pub visited: &'a HashMap<(usize, usize), Visits>,
+    pub settings: BTreeMap<String, String>,
+}
+
+impl RunSummary<'_> {
+    pub fn to_json(&self) -> String {
+        let tally = &self.tally;
+        let mut cells: Vec<(&(usize, usize), &Visits)> = self.visited.iter().collect();
+        cells.sort_by_key(|((x, y), _)| (*y, *x));
+        let cells: Vec<String> = cells.iter().map(|((x, y), visits)| format!("[{}, {}, {}, {:.2}]", x, y, visits.count, visits.seconds)).collect();
+        let settings: Vec<String> = self.settings.iter().map(|(key, value)| format!("    {}: {}", json_string(key), json_string(value))).collect();
+
+        let mut json = String::from("{\n");
+        json.push_str(&format!("  \"outcome\": {},\n", json_string(self.outcome)));
+        json.push_str(&format!("  \"map\": {},\n", json_string(self.map)));
+        json.push_str(&format!("  \"seed\": {},\n", self.seed));
+        json.push_str(&format!("  \"time\": {:.2},\n", self.time));
+        json.push_str("  \"stats\": {\n");
+        json.push_str(&format!("    \"kills\": {},\n    \"hits\": {},\n    \"criticals\": {},\n", tally.kills, tally.hits, tally.criticals));
+        json.push_str(&format!("    \"damage_taken\": {:.1},\n    \"health_left\": {:.1},\n", tally.damage_taken, self.health.max(0.0)));
+        json.push_str(&format!("    \"coins\": {},\n    \"props_broken\": {},\n    \"cells_visited\": {}\n", self.coins, tally.props_broken, self.visited.len()));
+        json.push_str("  },\n");
+        json.push_str(&format!("  \"heatmap\": {{\n    \"width\": {},\n    \"height\": {},\n", self.size.0, self.size.1));
+        json.push_str(&format!("    \"cells\": [{}]\n  }},\n", cells.join(", ")));
+        json.push_str(&format!("  \"settings\": {{\n{}\n  }}\n", settings.join(",\n")));
+        json.push_str("}\n");
+        json
+    }
+
+    // Write the summary next to the game, returns the file it went to
+    pub fn export(&self) -> Result<String, String> {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+        let path = format!("run_summary_{}.json", stamp);
+        match fs::write(&path, self.to_json()) {
+            Ok(_) => {
+                log!("Exported run summary to {}", path);
+                Ok(path)
+            }
+            Err(e) => {
+                elog!("Warning: Could not export the run summary to {}: {:?}", path, e);
+                Err(format!("Could not write {}", path))
+            }
+        }
+    }
+}
+
+// Under the end screen's own prompts: the route heatmap, the export prompt until the summary is
+// written, then where it went
+pub fn draw_end_prompts(d: &mut RaylibDrawHandle, exported: Option<&Result<String, String>>, prompts: &ButtonPrompts,
+                        screen_width: i32, screen_height: i32) {
+    let y = screen_height - 100;
+    if exported.is_none() {
+        prompts.draw_row_centered(d, &[(Prompt::Route, "Route heatmap"), (Prompt::Export, "Export summary")], screen_width / 2, y, 22, Color::GRAY);
+        return;
+    }
+    prompts.draw_row_centered(d, &[(Prompt::Route, "Route heatmap")], screen_width / 2, y, 22, Color::GRAY);
+    if let Some(result) = exported {
+        let (text, color) = match result {
+            Ok(path) => (format!("Summary saved to {}", path), Color::LIGHTGRAY),
+            Err(message) => (message.clone(), Color::ORANGE),
+        };
+        d.draw_text(&text, (screen_width - 18 * text.len() as i32 / 2) / 2, y + 34, 18, color);
+    }
+}
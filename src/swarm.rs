@@ -0,0 +1,262 @@
+// swarm.rs
+
+use raylib::prelude::*;
+use std::thread;
+
+use crate::FloorItem;
+use crate::maze::Maze;
+use crate::rng::Rng;
+use crate::sim::{Vec2, enemy_blocked, line_of_sight, swing_hit};
+
+// Swarms of rats or bats, placed by a map's header with the cell they nest in and how many:
+//   swarm = rats 4 7 12
+//   swarm = bats 10 2        (the default 12)
+// They are far cheaper than enemies: no sprite sheet, animation or status effects, and each
+// swarm decides once a frame for all its critters whether to go for the player or circle its
+// nest. Each critter then only steers, boid-style, toward that goal while keeping to the
+// group and out of its neighbours' way. Critters nip the player on contact for a little
+// damage each and die to any blow, a single swing can clear a good part of a swarm.
+// Swarms don't touch each other, so once there are enough critters, as on the stress map, the
+// swarms are split across threads and updated in parallel.
+
+const DEFAULT_COUNT: usize = 12;
+const MIN_COUNT: usize = 10;
+const MAX_COUNT: usize = 40;
+const NEST_SPREAD: f32 = 0.4;         // Cells around the nest critters start in
+const ALERT_RANGE: f32 = 5.0;         // Cells from the swarm's center it notices the player at
+const LEASH: f32 = 9.0;               // Cells from the nest it gives up the chase at
+const CONTACT_RANGE: f32 = 0.3;       // Cells from the player a critter bites at
+const BITE_DAMAGE: f32 = 1.5;
+const BITE_COOLDOWN: f32 = 0.9;       // Seconds between one critter's bites
+const SEPARATION: f32 = 0.18;         // Cells critters keep between each other
+const CORPSE_TIME: f32 = 1.5;         // Seconds a dead critter lies on the floor
+const PARALLEL_CRITTERS: usize = 200; // Living critters over all swarms before the update is threaded
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SwarmKind {
+    Rats,
+    Bats,
+}
+
+impl SwarmKind {
+    fn from_name(name: &str) -> Option<SwarmKind> {
+        match name {
+            "rats" => Some(SwarmKind::Rats),
+            "bats" => Some(SwarmKind::Bats),
+            _ => None,
+        }
+    }
+
+    // Top speed in cells per second
+    fn speed(&self) -> f32 {
+        match self {
+            SwarmKind::Rats => 1.6,
+            SwarmKind::Bats => 2.2,
+        }
+    }
+}
+
+pub struct Critter {
+    pub pos: Vec2,
+    velocity: Vec2,                 // World units per second
+    bite_cooldown: f32,
+    pub dead: Option<f32>,          // Seconds since it died
+    phase: f32,                     // Scurry or wingbeat cycle, offset per critter
+}
+
+pub struct Swarm {
+    pub kind: SwarmKind,
+    pub critters: Vec<Critter>,
+    nest: Vec2,
+    pub alert: bool,
+    rng: Rng,
+}
+
+impl Swarm {
+    pub fn new(kind: SwarmKind, nest: Vec2, count: usize, block_size: usize, rng: Rng) -> Self {
+        let mut rng = rng;
+        let spread = NEST_SPREAD * block_size as f32;
+        let critters = (0..count)
+            .map(|_| Critter {
+                pos: nest + Vec2::new(rng.range_f32(-spread, spread), rng.range_f32(-spread, spread)),
+                velocity: Vec2::new(0.0, 0.0),
+                bite_cooldown: 0.0,
+                dead: None,
+                phase: rng.range_f32(0.0, std::f32::consts::TAU),
+            })
+            .collect();
+        Swarm { kind, critters, nest, alert: false, rng }
+    }
+
+    pub fn alive(&self) -> usize {
+        self.critters.iter().filter(|critter| critter.dead.is_none()).count()
+    }
+
+    // The group decision, once a frame for every critter: the player, or somewhere around the nest
+    fn plan(&mut self, player_pos: Vec2, maze: &Maze, block_size: usize) -> Vec2 {
+        let block = block_size as f32;
+        let living: Vec<Vec2> = self.critters.iter().filter(|critter| critter.dead.is_none()).map(|critter| critter.pos).collect();
+        let center = living.iter().fold(Vec2::new(0.0, 0.0), |sum, &pos| sum + pos) * (1.0 / living.len().max(1) as f32);
+        let near = center.distance(player_pos) < ALERT_RANGE * block && line_of_sight(center, player_pos, maze, block_size);
+        let leashed = self.nest.distance(player_pos) > LEASH * block;
+        if near && !self.alert {
+            log!("A swarm of {} noticed the player", living.len());
+        }
+        self.alert = (self.alert || near) && !leashed;
+        if self.alert {
+            return player_pos;
+        }
+        let drift = NEST_SPREAD * block;
+        self.nest + Vec2::new(self.rng.range_f32(-drift, drift), self.rng.range_f32(-drift, drift))
+    }
+
+    // Move every critter one frame, returns the damage their bites dealt the player
+    pub fn update(&mut self, delta_time: f32, player_pos: Vec2, maze: &Maze, block_size: usize) -> f32 {
+        for critter in self.critters.iter_mut() {
+            if let Some(time) = critter.dead.as_mut() {
+                *time += delta_time;
+            }
+        }
+        self.critters.retain(|critter| critter.dead.is_none_or(|time| time < CORPSE_TIME));
+        if self.alive() == 0 {
+            return 0.0;
+        }
+
+        let block = block_size as f32;
+        let goal = self.plan(player_pos, maze, block_size);
+        let speed = self.kind.speed() * block;
+        let living: Vec<(Vec2, Vec2)> = self.critters.iter().filter(|critter| critter.dead.is_none()).map(|critter| (critter.pos, critter.velocity)).collect();
+        let count = living.len() as f32;
+        let (center, heading) = living.iter().fold((Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0)), |(c, h), &(pos, velocity)| (c + pos, h + velocity));
+        let (center, heading) = (center * (1.0 / count), heading * (1.0 / count));
+
+        let mut damage = 0.0;
+        for critter in self.critters.iter_mut().filter(|critter| critter.dead.is_none()) {
+            // Seek the goal, stay with the group and match its heading, and keep off the others
+            let unit = |v: Vec2| if v.length() > 0.001 { v * (1.0 / v.length()) } else { Vec2::new(0.0, 0.0) };
+            let mut steer = unit(goal - critter.pos) * 1.0 + unit(center - critter.pos) * 0.3 + unit(heading) * 0.2;
+            for &(other, _) in living.iter() {
+                let distance = critter.pos.distance(other);
+                if distance > 0.001 && distance < SEPARATION * block {
+                    steer = steer + unit(critter.pos - other) * (1.0 - distance / (SEPARATION * block)) * 1.5;
+                }
+            }
+            let velocity = critter.velocity + unit(steer) * (speed * 6.0 * delta_time);
+            critter.velocity = if velocity.length() > speed { unit(velocity) * speed } else { velocity };
+
+            // Slide along walls one axis at a time
+            let step = critter.velocity * delta_time;
+            if enemy_blocked(maze, critter.pos + Vec2::new(step.x, 0.0), block_size) {
+                critter.velocity.x = 0.0;
+            } else {
+                critter.pos.x += step.x;
+            }
+            if enemy_blocked(maze, critter.pos + Vec2::new(0.0, step.y), block_size) {
+                critter.velocity.y = 0.0;
+            } else {
+                critter.pos.y += step.y;
+            }
+            critter.phase += delta_time * 14.0;
+
+            critter.bite_cooldown = (critter.bite_cooldown - delta_time).max(0.0);
+            if critter.bite_cooldown <= 0.0 && critter.pos.distance(player_pos) < CONTACT_RANGE * block {
+                critter.bite_cooldown = BITE_COOLDOWN;
+                damage += BITE_DAMAGE;
+            }
+        }
+        damage
+    }
+}
+
+// The `swarm` lines of a map's metadata, each swarm with its own stream of the map's generator
+pub fn swarms_from_meta(meta: &[(String, String)], block_size: usize, rng: &mut Rng) -> Vec<Swarm> {
+    let block = block_size as f32;
+    let mut swarms = Vec::new();
+    for (key, value) in meta.iter().filter(|(key, _)| key == "swarm") {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        let cell = |x: &str, y: &str| x.parse::<usize>().ok().zip(y.parse::<usize>().ok());
+        let parsed = match parts[..] {
+            [name, x, y] => SwarmKind::from_name(name).zip(cell(x, y)).map(|(kind, cell)| (kind, cell, DEFAULT_COUNT)),
+            [name, x, y, count] => SwarmKind::from_name(name).zip(cell(x, y)).zip(count.parse::<usize>().ok())
+                .map(|((kind, cell), count)| (kind, cell, count)),
+            _ => None,
+        };
+        let Some((kind, (x, y), count)) = parsed else {
+            elog!("Warning: '{} = {}' needs rats or bats, x, y and optionally a count", key, value);
+            continue;
+        };
+        let nest = Vec2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block);
+        swarms.push(Swarm::new(kind, nest, count.clamp(MIN_COUNT, MAX_COUNT), block_size, rng.fork()));
+    }
+    if !swarms.is_empty() {
+        log!("{} swarms, {} critters", swarms.len(), swarms.iter().map(|swarm| swarm.critters.len()).sum::<usize>());
+    }
+    swarms
+}
+
+// Every swarm for one frame, returns the damage bites dealt the player. Swarms only read the
+// maze and the player, so with many critters alive they are split into one chunk per core.
+// Scoped threads are spawned each frame: tens of microseconds, small next to steering that many
+// critters, and they borrow the maze and swarms with no long-lived workers to hand them to.
+// A panic in a worker is passed on, the same as one in the single-threaded path.
+pub fn update_swarms(swarms: &mut [Swarm], delta_time: f32, player_pos: Vector2, maze: &Maze, block_size: usize) -> f32 {
+    let player_pos = Vec2::from(player_pos);
+    let living: usize = swarms.iter().map(|swarm| swarm.alive()).sum();
+    let threads = thread::available_parallelism().map_or(1, |cores| cores.get()).min(swarms.len());
+    if living < PARALLEL_CRITTERS || threads < 2 {
+        return swarms.iter_mut().map(|swarm| swarm.update(delta_time, player_pos, maze, block_size)).sum();
+    }
+
+    let chunk = swarms.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let workers: Vec<_> = swarms
+            .chunks_mut(chunk)
+            .map(|group| scope.spawn(move || group.iter_mut().map(|swarm| swarm.update(delta_time, player_pos, maze, block_size)).sum::<f32>()))
+            .collect();
+        workers.into_iter().map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).sum()
+    })
+}
+
+// A swing at its peak kills every critter in its arc, returns how many
+pub fn strike_swarms(swarms: &mut [Swarm], origin: Vector2, facing: f32, range: f32, arc: f32) -> usize {
+    let mut kills = 0;
+    for swarm in swarms.iter_mut() {
+        let before = kills;
+        for critter in swarm.critters.iter_mut().filter(|critter| critter.dead.is_none()) {
+            if swing_hit(origin.into(), facing, critter.pos, range, arc).is_some() {
+                critter.dead = Some(0.0);
+                kills += 1;
+            }
+        }
+        // Losses stir up the rest
+        if kills > before {
+            swarm.alert = true;
+        }
+    }
+    kills
+}
+
+// Critters as floor boxes for render_floor_items: rats scurrying low, bats flapping at head
+// height, and the fallen lying flat until they are cleared away
+pub fn swarm_boxes(swarms: &[Swarm]) -> Vec<FloorItem> {
+    let mut boxes = Vec::new();
+    for swarm in swarms.iter() {
+        for critter in swarm.critters.iter() {
+            let pos: Vector2 = critter.pos.into();
+            let wave = critter.phase.sin();
+            match (swarm.kind, critter.dead) {
+                (SwarmKind::Rats, Some(_)) => boxes.push((pos, 0.12, 0.025, 0.0, Color::new(70, 40, 35, 255))),
+                (SwarmKind::Rats, None) => boxes.push((pos, 0.11, 0.06 + 0.01 * wave, 0.0, Color::new(95, 80, 70, 255))),
+                (SwarmKind::Bats, Some(time)) => {
+                    // Falls to the floor, then lies there
+                    let lift = (0.55 - time * 1.5).max(0.0);
+                    boxes.push((pos, 0.1, 0.03, lift, Color::new(50, 40, 50, 255)));
+                }
+                (SwarmKind::Bats, None) => {
+                    boxes.push((pos, 0.05 + 0.1 * wave.abs(), 0.03, 0.55 + 0.04 * wave, Color::new(45, 35, 45, 255)));
+                }
+            }
+        }
+    }
+    boxes
+}
@@ -0,0 +1,47 @@
+// sway.rs
+
+use crate::sim::Vec2;
+
+// The first-person sword hangs off the camera on a spring. Turning pulls it the other way and
+// walking pushes it back and away from the strafe direction, so it trails the view a little and
+// eases back to rest, overshooting slightly, once the player stops. Offsets are in screen pixels
+// for render_sword, positive x to the right and positive y down.
+
+const STIFFNESS: f32 = 90.0;        // Pull back toward the target, per pixel of offset
+const DAMPING: f32 = 13.0;          // Just under critical, for a small overshoot when settling
+const TURN_SWAY: f32 = 45.0;        // Pixels per radian per second of turning
+const MAX_TURN_SWAY: f32 = 70.0;
+const FORWARD_SWAY: f32 = 18.0;     // Pixels for a full step forward, lowered while walking ahead
+const STRAFE_SWAY: f32 = 30.0;      // Pixels for a full step sideways, dragging behind the strafe
+const MAX_STEP: f32 = 0.05;         // Seconds, longer frames are simulated in pieces so the spring stays stable
+pub const SWAY_TILT: f32 = 0.12;    // Degrees of rotation per pixel of sideways offset
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WeaponSway {
+    pub offset: Vec2,
+    velocity: Vec2,
+}
+
+impl WeaponSway {
+    // `turn` is the radians turned this frame, `forward` and `side` the movement taken in
+    // fractions of a full step along and across the view, side positive to the right
+    pub fn update(&mut self, turn: f32, forward: f32, side: f32, delta_time: f32) {
+        if delta_time <= 0.0 {
+            return;
+        }
+        let turn_rate = turn / delta_time;
+        let target = Vec2::new(
+            (-turn_rate * TURN_SWAY).clamp(-MAX_TURN_SWAY, MAX_TURN_SWAY) - side * STRAFE_SWAY,
+            forward.max(0.0) * FORWARD_SWAY,
+        );
+
+        let mut remaining = delta_time;
+        while remaining > 0.0 {
+            let step = remaining.min(MAX_STEP);
+            let acceleration = (target - self.offset) * STIFFNESS - self.velocity * DAMPING;
+            self.velocity = self.velocity + acceleration * step;
+            self.offset = self.offset + self.velocity * step;
+            remaining -= step;
+        }
+    }
+}
@@ -4,10 +4,26 @@ use raylib::prelude::*;
 use std::collections::HashMap;
 use std::slice;
 
+use crate::mods;
+
+const TRANSPARENT_COLOR: Color = Color::new(152, 0, 136, 255);
+pub const WISP_SHEET: char = 'W'; // Generated sheet for the companion wisp
+pub const WISP_FRAMES: usize = 4;
+const WISP_FRAME_SIZE: i32 = 64;
+pub const FLOOR_TEXTURE: char = '_';   // Texture keys of the cast floor and ceiling, see render_world
+pub const CEILING_TEXTURE: char = '*';
+pub const SHIELD_SHEET: char = 'b'; // The enemy sheet with a fourth row carrying a shield, see enemy.rs
+
+// Index into the texture arrays, resolved once from a map char so hot loops never hash
+pub type TextureId = usize;
+
 pub struct TextureManager {
-    images: HashMap<char, Image>,       // Store images for pixel access
-    textures: HashMap<char, Texture2D>, // Store GPU textures for rendering
-    sprite_sheets: HashMap<char, SpriteSheet>, // Store sprite sheet data
+    images: Vec<Image>,                     // Store images for pixel access, indexed by TextureId
+    half_images: Vec<Image>,                // Half-resolution copies for distant walls, same order as images
+    textures: Vec<Texture2D>,               // Store GPU textures for rendering, same order as images
+    texture_ids: [Option<TextureId>; 128],  // Map char to its TextureId
+    sprite_sheets: Vec<SpriteSheet>,        // Store sprite sheet data
+    sprite_sheet_ids: [Option<usize>; 128], // Map char to its sprite sheet
     sword_image: Option<Image>,         // Store sword image for UI rendering
     sword_texture: Option<Texture2D>,   // Store sword texture for GPU rendering
 }
@@ -19,6 +35,143 @@ pub struct SpriteSheet {
     pub frame_height: u32,
     pub columns: u32,
     pub rows: u32,
+    opaque_spans: Vec<Option<(u32, u32)>>, // First and last opaque row of every frame column
+}
+
+impl SpriteSheet {
+    pub fn new(image: Image, columns: u32, rows: u32) -> Self {
+        let frame_width = image.width as u32 / columns;
+        let frame_height = image.height as u32 / rows;
+
+        // Scan every frame once at load time so drawing can skip transparent texels
+        let mut opaque_spans = Vec::with_capacity((columns * rows * frame_width) as usize);
+        for frame_y in 0..rows {
+            for frame_x in 0..columns {
+                for tx in 0..frame_width {
+                    let mut span: Option<(u32, u32)> = None;
+                    for ty in 0..frame_height {
+                        let x = (frame_x * frame_width + tx) as i32;
+                        let y = (frame_y * frame_height + ty) as i32;
+                        if !is_transparent_color(get_pixel_color(&image, x, y)) {
+                            span = Some((span.map_or(ty, |(top, _)| top), ty));
+                        }
+                    }
+                    opaque_spans.push(span);
+                }
+            }
+        }
+
+        SpriteSheet {
+            image,
+            frame_width,
+            frame_height,
+            columns,
+            rows,
+            opaque_spans,
+        }
+    }
+
+    // Pixels and opaque spans of one frame, looked up once per sprite draw
+    pub fn frame(&self, frame_x: usize, frame_y: usize) -> SpriteFrame<'_> {
+        let frame_x = (frame_x as u32).min(self.columns - 1);
+        let frame_y = (frame_y as u32).min(self.rows - 1);
+        let first_span = ((frame_y * self.columns + frame_x) * self.frame_width) as usize;
+
+        SpriteFrame {
+            pixels: TexturePixels::new(&self.image),
+            left: frame_x * self.frame_width,
+            top: frame_y * self.frame_height,
+            width: self.frame_width,
+            height: self.frame_height,
+            spans: self.opaque_spans.get(first_span..first_span + self.frame_width as usize).unwrap_or(&[]),
+        }
+    }
+}
+
+// Borrowed RGBA bytes of an image, so per-pixel reads are plain slice indexing
+#[derive(Clone, Copy)]
+pub struct TexturePixels<'a> {
+    data: &'a [u8],
+    pub width: u32,
+    pub height: u32,
+}
+
+impl<'a> TexturePixels<'a> {
+    fn new(image: &'a Image) -> Self {
+        let width = image.width.max(0) as u32;
+        let height = image.height.max(0) as u32;
+        let data = if image.data.is_null() {
+            &[][..]
+        } else {
+            unsafe { slice::from_raw_parts(image.data as *const u8, (width * height * 4) as usize) }
+        };
+
+        TexturePixels { data, width, height }
+    }
+
+    // Coordinates are clamped to the image, missing data reads as white
+    #[inline]
+    pub fn color(&self, x: u32, y: u32) -> Color {
+        let x = x.min(self.width.saturating_sub(1));
+        let y = y.min(self.height.saturating_sub(1));
+        let index = ((y * self.width + x) * 4) as usize; // RGBA = 4 bytes per pixel
+        match self.data.get(index..index + 4) {
+            Some(pixel) => Color::new(pixel[0], pixel[1], pixel[2], pixel[3]),
+            None => Color::WHITE,
+        }
+    }
+
+    // A single texture column, for walls where tx is fixed down the whole screen column
+    pub fn column(&self, x: u32) -> TextureColumn<'a> {
+        TextureColumn {
+            data: self.data,
+            offset: (x.min(self.width.saturating_sub(1)) * 4) as usize,
+            stride: (self.width * 4) as usize,
+            height: self.height,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TextureColumn<'a> {
+    data: &'a [u8],
+    offset: usize,
+    stride: usize,
+    pub height: u32,
+}
+
+impl<'a> TextureColumn<'a> {
+    #[inline]
+    pub fn color(&self, y: u32) -> Color {
+        let index = y.min(self.height.saturating_sub(1)) as usize * self.stride + self.offset;
+        match self.data.get(index..index + 4) {
+            Some(pixel) => Color::new(pixel[0], pixel[1], pixel[2], pixel[3]),
+            None => Color::WHITE,
+        }
+    }
+}
+
+// One frame of a sprite sheet
+#[derive(Clone, Copy)]
+pub struct SpriteFrame<'a> {
+    pixels: TexturePixels<'a>,
+    left: u32,
+    top: u32,
+    pub width: u32,
+    pub height: u32,
+    spans: &'a [Option<(u32, u32)>],
+}
+
+impl<'a> SpriteFrame<'a> {
+    #[inline]
+    pub fn color(&self, tx: u32, ty: u32) -> Color {
+        self.pixels.color(self.left + tx.min(self.width - 1), self.top + ty.min(self.height - 1))
+    }
+
+    // Opaque rows of one column, None when the column is fully transparent
+    pub fn column_span(&self, tx: u32) -> Option<(u32, u32)> {
+        self.spans.get(tx as usize).copied().flatten()
+    }
 }
 
 impl TextureManager {
@@ -34,21 +187,28 @@ impl TextureManager {
             ('|', "assets/textures/elements/Elements_06-128x128_rgba.png"), // Weathered stone verticals
             ('g', "assets/textures/elements/Elements_10-128x128_rgba.png"),                   // Large imposing door
             ('#', "assets/elements/Elements_02-128x128_rgba.png"),               // Horror metal for variety
+            ('/', "assets/textures/elements/Elements_06-128x128_rgba.png"),     // Diagonal wall, rising
+            ('\\', "assets/textures/elements/Elements_06-128x128_rgba.png"),    // Diagonal wall, falling
+            ('d', "assets/textures/large_door_rgba.png"),                   // Sealed arena door
+            ('o', "assets/textures/elements/Elements_10-128x128_rgba.png"),  // Hub portal, the goal door in blue
             ('e', "assets/sprite1_rgba.png"),                               // Enemy sprite
+            (FLOOR_TEXTURE, "assets/textures/metals/Metal_07-128x128_rgba.png"),     // Iron tread plate floor
+            (CEILING_TEXTURE, "assets/textures/elements/Elements_04-128x128_rgba.png"), // Smouldering red ceiling
         ];
 
         for (ch, path) in texture_files {
-            println!("Attempting to load texture: {}", path);
+            let path = &mods::resolve(path);
+            log!("Attempting to load texture: {}", path);
             match Image::load_image(path) {
                 Ok(image) => {
                     match rl.load_texture(thread, path) {
                         Ok(texture) => {
-                            println!("Successfully loaded texture: {} ({}x{})", path, image.width, image.height);
+                            log!("Successfully loaded texture: {} ({}x{})", path, image.width, image.height);
                             images.insert(ch, image);
                             textures.insert(ch, texture);
                         }
                         Err(e) => {
-                            eprintln!("Failed to load texture {}: {:?}", path, e);
+                            elog!("Failed to load texture {}: {:?}", path, e);
                             // Fallback to a solid color texture
                             let fallback_image = Image::gen_image_color(64, 64, Color::GRAY);
                             let fallback_texture = rl.load_texture_from_image(thread, &fallback_image).expect("Failed to create fallback texture");
@@ -58,7 +218,7 @@ impl TextureManager {
                     }
                 }
                 Err(e) => {
-                    eprintln!("Failed to load image {}: {:?}", path, e);
+                    elog!("Failed to load image {}: {:?}", path, e);
                     // Fallback to a solid color texture
                     let fallback_image = Image::gen_image_color(64, 64, Color::RED);
                     let fallback_texture = rl.load_texture_from_image(thread, &fallback_image).expect("Failed to create fallback texture");
@@ -68,147 +228,324 @@ impl TextureManager {
             }
         }
 
+        // Procedural iron grate for thin fence walls, transparent between the bars
+        let grate_image = generate_grate_image();
+        for ch in ['=', '!'] {
+            match rl.load_texture_from_image(thread, &grate_image) {
+                Ok(texture) => {
+                    images.insert(ch, grate_image.clone());
+                    textures.insert(ch, texture);
+                }
+                Err(e) => elog!("Failed to create grate texture: {:?}", e),
+            }
+        }
+
+        // Barred window block: stone frame taken from the corner texture with see-through panes
+        if let Some(frame) = images.get(&'+') {
+            let window_image = generate_window_image(frame);
+            match rl.load_texture_from_image(thread, &window_image) {
+                Ok(texture) => {
+                    images.insert('w', window_image);
+                    textures.insert('w', texture);
+                }
+                Err(e) => elog!("Failed to create window texture: {:?}", e),
+            }
+        }
+
         // Initialize sprite sheets
         let mut sprite_sheets = HashMap::new();
         
         // Load sprite sheet for animated enemies (assuming 4x3 grid: 4 columns, 3 rows)
         // Save your sprite sheet as "assets/sprite_sheet.png" 
-        println!("Attempting to load sprite sheet: assets/sprite_sheet_rgba.png");
-        if let Ok(sprite_image) = Image::load_image("assets/sprite_sheet_rgba.png") {
-            println!("Successfully loaded sprite_sheet_rgba.png ({}x{})", sprite_image.width, sprite_image.height);
-            let sprite_sheet = SpriteSheet {
-                frame_width: sprite_image.width as u32 / 4, // 4 columns
-                frame_height: sprite_image.height as u32 / 3, // 3 rows  
-                columns: 4,
-                rows: 3,
-                image: sprite_image,
-            };
-            println!("Created sprite sheet with frame size: {}x{}", sprite_sheet.frame_width, sprite_sheet.frame_height);
+        log!("Attempting to load sprite sheet: assets/sprite_sheet_rgba.png");
+        if let Ok(sprite_image) = Image::load_image(&mods::resolve("assets/sprite_sheet_rgba.png")) {
+            log!("Successfully loaded sprite_sheet_rgba.png ({}x{})", sprite_image.width, sprite_image.height);
+            sprite_sheets.insert(SHIELD_SHEET, SpriteSheet::new(generate_shield_sheet(&sprite_image, 4, 3), 4, 4));
+            let sprite_sheet = SpriteSheet::new(sprite_image, 4, 3); // 4 columns, 3 rows
+            log!("Created sprite sheet with frame size: {}x{}", sprite_sheet.frame_width, sprite_sheet.frame_height);
             sprite_sheets.insert('a', sprite_sheet); // 'a' for animated sprite
         } else {
-            println!("Warning: Could not load sprite_sheet_rgba.png - using fallback for animations");
+            log!("Warning: Could not load sprite_sheet_rgba.png - using fallback for animations");
             // Create a simple fallback sprite sheet
             let fallback_sprite = Image::gen_image_color(128, 96, Color::BLUE); // 4x3 * 32x32 frames
-            let sprite_sheet = SpriteSheet {
-                frame_width: 32,
-                frame_height: 32,
-                columns: 4,
-                rows: 3,
-                image: fallback_sprite,
-            };
+            sprite_sheets.insert(SHIELD_SHEET, SpriteSheet::new(generate_shield_sheet(&fallback_sprite, 4, 3), 4, 4));
+            let sprite_sheet = SpriteSheet::new(fallback_sprite, 4, 3);
             sprite_sheets.insert('a', sprite_sheet);
         }
 
+        sprite_sheets.insert(WISP_SHEET, SpriteSheet::new(generate_wisp_sheet(), WISP_FRAMES as u32, 1));
+
         // Load sword texture for attack animation
-        let (sword_image, sword_texture) = match Image::load_image("assets/sword2.png") {
+        let (sword_image, sword_texture) = match Image::load_image(&mods::resolve("assets/sword2.png")) {
             Ok(image) => {
                 match rl.load_texture_from_image(thread, &image) {
                     Ok(texture) => {
-                        println!("Successfully loaded sword texture: assets/sword2.png ({}x{})", image.width, image.height);
+                        log!("Successfully loaded sword texture: assets/sword2.png ({}x{})", image.width, image.height);
                         (Some(image), Some(texture))
                     }
                     Err(e) => {
-                        eprintln!("Failed to create sword texture: {:?}", e);
+                        elog!("Failed to create sword texture: {:?}", e);
                         (None, None)
                     }
                 }
             }
             Err(e) => {
-                eprintln!("Failed to load sword image: {:?}", e);
+                elog!("Failed to load sword image: {:?}", e);
                 (None, None)
             }
         };
 
+        // Flatten the char maps into arrays indexed by id
+        let mut texture_ids = [None; 128];
+        let mut image_list = Vec::new();
+        let mut half_image_list = Vec::new();
+        let mut texture_list = Vec::new();
+        for (ch, image) in images {
+            if let (Some(texture), Some(slot)) = (textures.remove(&ch), texture_ids.get_mut(ch as usize)) {
+                *slot = Some(image_list.len());
+                half_image_list.push(half_resolution(&image));
+                image_list.push(image);
+                texture_list.push(texture);
+            }
+        }
+
+        let mut sprite_sheet_ids = [None; 128];
+        let mut sprite_sheet_list = Vec::new();
+        for (ch, sprite_sheet) in sprite_sheets {
+            if let Some(slot) = sprite_sheet_ids.get_mut(ch as usize) {
+                *slot = Some(sprite_sheet_list.len());
+                sprite_sheet_list.push(sprite_sheet);
+            }
+        }
+
         TextureManager { 
-            images, 
-            textures, 
-            sprite_sheets,
+            images: image_list, 
+            half_images: half_image_list,
+            textures: texture_list, 
+            texture_ids,
+            sprite_sheets: sprite_sheet_list,
+            sprite_sheet_ids,
             sword_image,
             sword_texture,
         }
     }
 
+    pub fn texture_id(&self, ch: char) -> Option<TextureId> {
+        self.texture_ids.get(ch as usize).copied().flatten()
+    }
+
+    // Resolve once per column or sprite, then read pixels without any lookups
+    pub fn pixels(&self, id: TextureId) -> TexturePixels<'_> {
+        TexturePixels::new(&self.images[id])
+    }
+
+    // The half-resolution copy, for walls far enough away that the full texture would shimmer
+    pub fn half_pixels(&self, id: TextureId) -> TexturePixels<'_> {
+        TexturePixels::new(&self.half_images[id])
+    }
+
     pub fn get_pixel_color(&self, ch: char, tx: u32, ty: u32) -> Color {
-        if let Some(image) = self.images.get(&ch) {
-            let x = tx.min(image.width as u32 - 1) as i32;
-            let y = ty.min(image.height as u32 - 1) as i32;
-            
-            get_pixel_color(image, x, y)
+        if let Some(id) = self.texture_id(ch) {
+            self.pixels(id).color(tx, ty)
         } else {
-            println!("Warning: No texture found for character '{}'", ch);
+            log!("Warning: No texture found for character '{}'", ch);
             Color::WHITE
         }
     }
 
     pub fn get_texture(&self, ch: char) -> Option<&Texture2D> {
-        self.textures.get(&ch)
+        self.texture_id(ch).map(|id| &self.textures[id])
     }
 
-    pub fn get_sprite_frame_color(&self, ch: char, frame_x: usize, frame_y: usize, tx: u32, ty: u32) -> Color {
-        if let Some(sprite_sheet) = self.sprite_sheets.get(&ch) {
-            // Calculate the pixel position within the sprite sheet
-            let pixel_x = (frame_x as u32 * sprite_sheet.frame_width + tx).min(sprite_sheet.image.width as u32 - 1);
-            let pixel_y = (frame_y as u32 * sprite_sheet.frame_height + ty).min(sprite_sheet.image.height as u32 - 1);
-            
-            get_pixel_color(&sprite_sheet.image, pixel_x as i32, pixel_y as i32)
-        } else {
-            // Fallback to regular texture if no sprite sheet found
-            self.get_pixel_color(ch, tx, ty)
-        }
+    pub fn sprite_sheet(&self, ch: char) -> Option<&SpriteSheet> {
+        self.sprite_sheet_ids.get(ch as usize).copied().flatten().map(|id| &self.sprite_sheets[id])
     }
 
-    pub fn has_sprite_sheet(&self, ch: char) -> bool {
-        self.sprite_sheets.contains_key(&ch)
+    pub fn get_sword_texture(&self) -> Option<&Texture2D> {
+        self.sword_texture.as_ref()
     }
+}
 
-    pub fn get_sprite_frame_size(&self, ch: char) -> Option<(u32, u32)> {
-        self.sprite_sheets.get(&ch).map(|sheet| (sheet.frame_width, sheet.frame_height))
+// Function to check if a color should be treated as transparent
+pub fn is_transparent_color(color: Color) -> bool {
+    // Check for exact transparent color match
+    if color == TRANSPARENT_COLOR {
+        return true;
+    }
+    
+    // Check for alpha transparency, partly see-through texels are blended by the renderer
+    if color.a == 0 {
+        return true;
+    }
+    
+    // Specific check for your sprite sheet's background color
+    // Looking at your sprite, the background appears to be a dark gray around RGB(64, 64, 64)
+    // Let's check for colors in that range
+    
+    // Dark gray background (around 50-85 range for all components)
+    if color.r >= 50 && color.r <= 85 &&
+       color.g >= 50 && color.g <= 85 &&
+       color.b >= 50 && color.b <= 85 {
+        return true;
     }
+    
+    // Also check for slightly lighter grays (75-115 range)
+    if color.r >= 75 && color.r <= 115 &&
+       color.g >= 75 && color.g <= 115 &&
+       color.b >= 75 && color.b <= 115 {
+        return true;
+    }
+    
+    // Check for very dark colors (near black)
+    if color.r < 25 && color.g < 25 && color.b < 25 {
+        return true;
+    }
+    
+    // Check for very light colors (near white)
+    if color.r > 230 && color.g > 230 && color.b > 230 {
+        return true;
+    }
+    
+    false
+}
 
-    pub fn get_sword_texture(&self) -> Option<&Texture2D> {
-        self.sword_texture.as_ref()
+fn generate_grate_image() -> Image {
+    let mut image = Image::gen_image_color(128, 128, Color::new(0, 0, 0, 0));
+
+    for y in 0..128 {
+        for x in 0..128 {
+            let vertical_bar = x % 32 < 8;
+            let cross_bar = (8..16).contains(&y) || (112..120).contains(&y);
+            if vertical_bar || cross_bar {
+                // Simple shading so bars look rounded
+                let shade = if vertical_bar { 40 + (x % 32) * 6 } else { 50 + (y % 8) * 5 };
+                image.draw_pixel(x, y, Color::new(shade as u8, (shade * 9 / 10) as u8, (shade * 8 / 10) as u8, 255));
+            }
+        }
     }
+
+    image
 }
 
-fn get_pixel_color(image: &Image, x: i32, y: i32) -> Color {
-    let width = image.width as usize;
-    let height = image.height as usize;
+fn generate_window_image(frame: &Image) -> Image {
+    let mut image = Image::gen_image_color(128, 128, Color::new(0, 0, 0, 0));
+    let frame_width = frame.width.max(1);
+    let frame_height = frame.height.max(1);
 
-    if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
-        return Color::WHITE;
+    for y in 0..128 {
+        for x in 0..128 {
+            let is_frame = !(20..108).contains(&x) || !(20..108).contains(&y);
+            let is_bar = !is_frame && ((x - 20) % 22 < 4 || (60..66).contains(&y));
+
+            if is_frame {
+                let color = get_pixel_color(frame, x * frame_width / 128, y * frame_height / 128);
+                image.draw_pixel(x, y, Color::new(color.r, color.g, color.b, 255));
+            } else if is_bar {
+                image.draw_pixel(x, y, Color::new(70, 60, 55, 255));
+            }
+        }
     }
 
-    let x = (x as usize).min(width - 1);
-    let y = (y as usize).min(height - 1);
+    image
+}
 
-    // Much safer bounds checking
-    let pixel_index = y * width + x;
-    let byte_index = pixel_index * 4; // RGBA = 4 bytes per pixel
-    let total_bytes = width * height * 4;
+// Every texel the average of a 2x2 block, weighted by alpha so the gaps in a grate don't darken
+// its bars; a block that is partly gap comes out partly see-through
+fn half_resolution(image: &Image) -> Image {
+    let pixels = TexturePixels::new(image);
+    let width = (pixels.width / 2).max(1);
+    let height = (pixels.height / 2).max(1);
+    let mut half = Image::gen_image_color(width as i32, height as i32, Color::new(0, 0, 0, 0));
 
-    unsafe {
-        // Null pointer check
-        if image.data.is_null() {
-            return Color::WHITE;
+    for y in 0..height {
+        for x in 0..width {
+            let (mut r, mut g, mut b, mut alpha) = (0u32, 0u32, 0u32, 0u32);
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let color = pixels.color(x * 2 + dx, y * 2 + dy);
+                r += color.r as u32 * color.a as u32;
+                g += color.g as u32 * color.a as u32;
+                b += color.b as u32 * color.a as u32;
+                alpha += color.a as u32;
+            }
+            // Fully transparent blocks stay transparent
+            if let (Some(r), Some(g), Some(b)) = (r.checked_div(alpha), g.checked_div(alpha), b.checked_div(alpha)) {
+                half.draw_pixel(x as i32, y as i32, Color::new(r as u8, g as u8, b as u8, (alpha / 4) as u8));
+            }
         }
-        
-        // Bounds check before creating slice
-        if byte_index + 3 >= total_bytes {
-            return Color::WHITE;
+    }
+
+    half
+}
+
+fn get_pixel_color(image: &Image, x: i32, y: i32) -> Color {
+    if x < 0 || y < 0 || x >= image.width || y >= image.height {
+        return Color::WHITE;
+    }
+
+    TexturePixels::new(image).color(x as u32, y as u32)
+}
+
+// Companion wisp frames: a bright core in a soft halo, each frame a little bigger or smaller
+// so looping them flickers like a flame. Outside the halo is the transparent key color.
+fn generate_wisp_sheet() -> Image {
+    let size = WISP_FRAME_SIZE;
+    let mut image = Image::gen_image_color(size * WISP_FRAMES as i32, size, TRANSPARENT_COLOR);
+    let center = size as f32 / 2.0;
+    for frame in 0..WISP_FRAMES as i32 {
+        let radius = center * [0.8, 0.9, 0.85, 0.75][frame as usize];
+        for y in 0..size {
+            for x in 0..size {
+                let distance = ((x as f32 + 0.5 - center).powi(2) + (y as f32 + 0.5 - center).powi(2)).sqrt() / radius;
+                if distance > 1.0 {
+                    continue;
+                }
+                let glow = (1.0 - distance).powf(0.6);
+                let color = Color::new((150.0 + 105.0 * glow) as u8, (200.0 + 55.0 * glow) as u8, 255, 255);
+                image.draw_pixel(frame * size + x, y, color);
+            }
         }
-        
-        let data = slice::from_raw_parts(image.data as *const u8, total_bytes);
-        
-        // Final safety check
-        if byte_index + 3 < data.len() {
-            Color::new(
-                data[byte_index],     // R
-                data[byte_index + 1], // G
-                data[byte_index + 2], // B
-                data[byte_index + 3], // A
-            )
-        } else {
-            Color::WHITE
+    }
+    image
+}
+
+// The enemy sheet again with one more row: the walk frames with a kite shield held in front,
+// steel with a wooden rim and a brass boss. Shield bearers play it while their shield is up.
+fn generate_shield_sheet(sheet: &Image, columns: i32, rows: i32) -> Image {
+    let (frame_width, frame_height) = (sheet.width / columns, sheet.height / rows);
+    let mut image = Image::gen_image_color(sheet.width, frame_height * (rows + 1), TRANSPARENT_COLOR);
+    for y in 0..sheet.height {
+        for x in 0..sheet.width {
+            image.draw_pixel(x, y, get_pixel_color(sheet, x, y));
+        }
+    }
+
+    let walk_row = 1;
+    let (half_width, top, bottom) = (frame_width as f32 * 0.24, frame_height as f32 * 0.42, frame_height as f32 * 0.92);
+    let rim = (frame_width as f32 * 0.04).max(1.0);
+    for column in 0..columns {
+        let (left, source_top, target_top) = (column * frame_width, walk_row * frame_height, rows * frame_height);
+        for y in 0..frame_height {
+            for x in 0..frame_width {
+                let mut color = get_pixel_color(sheet, left + x, source_top + y);
+                // Straight sides down to the middle, then tapering to a point
+                let (fx, fy) = (x as f32 + 0.5 - frame_width as f32 / 2.0, y as f32 + 0.5);
+                let t = (fy - top) / (bottom - top);
+                let width = half_width * if t < 0.5 { 1.0 } else { 1.0 - (t - 0.5) * 1.8 };
+                if (0.0..=1.0).contains(&t) && fx.abs() <= width {
+                    let edge = (width - fx.abs()).min((fy - top) * 0.7);
+                    let boss = (fx * fx + (fy - (top + bottom) / 2.2).powi(2)).sqrt() < half_width * 0.22;
+                    color = if edge < rim {
+                        Color::new(105, 75, 45, 255)
+                    } else if boss {
+                        Color::new(205, 175, 80, 255)
+                    } else {
+                        let shade = (150.0 + 50.0 * (1.0 - fx.abs() / half_width)) as u8; // Lit down the middle
+                        Color::new(shade, shade.saturating_add(8), shade.saturating_add(20), 255)
+                    };
+                }
+                image.draw_pixel(left + x, target_top + y, color);
+            }
         }
     }
+    image
 }
@@ -0,0 +1,54 @@
+// timescale.rs
+
+// How fast the game clock runs against the real one while playing. A killing blow freezes the
+// action for a moment at a fraction of normal speed (hit-stop), so it lands with some weight,
+// and the player's death plays out in slow motion before the game over screen. The effects are
+// timed in real seconds. The base rate is normally 1.0; --timescale changes it for testing,
+// and the effects scale on top of it.
+
+const HIT_STOP: (f32, f32) = (0.06, 0.2);     // Real seconds, and the rate meanwhile
+const DEATH_SLOW_MO: (f32, f32) = (1.5, 0.25);
+
+pub struct TimeScale {
+    pub base: f32,
+    hit_stop: f32,    // Real seconds left
+    slow_motion: f32, // Real seconds left
+}
+
+impl TimeScale {
+    pub fn new(base: f32) -> Self {
+        TimeScale { base, hit_stop: 0.0, slow_motion: 0.0 }
+    }
+
+    pub fn hit_stop(&mut self) {
+        self.hit_stop = HIT_STOP.0;
+    }
+
+    pub fn death_slow_motion(&mut self) {
+        self.slow_motion = DEATH_SLOW_MO.0;
+    }
+
+    pub fn in_slow_motion(&self) -> bool {
+        self.slow_motion > 0.0
+    }
+
+    // Drop any effect still running, when a map starts
+    pub fn clear(&mut self) {
+        self.hit_stop = 0.0;
+        self.slow_motion = 0.0;
+    }
+
+    // Game seconds for `real_time` real ones, counting the effects down
+    pub fn advance(&mut self, real_time: f32) -> f32 {
+        let mut rate = self.base;
+        if self.slow_motion > 0.0 {
+            rate *= DEATH_SLOW_MO.1;
+            self.slow_motion = (self.slow_motion - real_time).max(0.0);
+        }
+        if self.hit_stop > 0.0 {
+            rate *= HIT_STOP.1;
+            self.hit_stop = (self.hit_stop - real_time).max(0.0);
+        }
+        real_time * rate
+    }
+}
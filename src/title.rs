@@ -0,0 +1,94 @@
+// title.rs
+
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+use crate::caster::cast_ray;
+use crate::framebuffer::Framebuffer;
+use crate::maze::Maze;
+use crate::player::Player;
+
+// Scripted camera that wanders through a maze for the title screen background
+pub struct TitleCamera {
+    pub camera: Player,
+    target_angle: f32,
+    move_speed: f32,
+    turn_speed: f32,
+}
+
+impl TitleCamera {
+    pub fn new(start: Vector2) -> Self {
+        TitleCamera {
+            camera: Player::new(start, 0.0, PI / 3.0, 0.0),
+            target_angle: 0.0,
+            move_speed: 0.9,   // Cells per second, slower than the player for a calm fly-through
+            turn_speed: 1.2,   // radians per second
+        }
+    }
+
+    pub fn reset(&mut self, start: Vector2) {
+        self.camera.pos = start;
+        self.camera.a = 0.0;
+        self.target_angle = 0.0;
+    }
+
+    pub fn update(&mut self, framebuffer: &mut Framebuffer, maze: &Maze, block_size: usize, delta_time: f32) {
+        // Turn smoothly toward the target heading
+        let mut angle_diff = self.target_angle - self.camera.a;
+        while angle_diff > PI {
+            angle_diff -= 2.0 * PI;
+        }
+        while angle_diff < -PI {
+            angle_diff += 2.0 * PI;
+        }
+
+        let max_turn = self.turn_speed * delta_time;
+        if angle_diff.abs() <= max_turn {
+            self.camera.a = self.target_angle;
+        } else {
+            self.camera.a += max_turn * angle_diff.signum();
+        }
+
+        // Only move forward once we are roughly facing where we want to go
+        if angle_diff.abs() > PI / 8.0 {
+            return;
+        }
+
+        let ahead = cast_ray(framebuffer, maze, &self.camera, self.camera.a, block_size, false);
+        let keep_distance = block_size as f32 * 0.6;
+
+        if ahead.distance > keep_distance {
+            let step = (self.move_speed * block_size as f32 * delta_time).min(ahead.distance - keep_distance);
+            self.camera.pos.x += step * self.camera.a.cos();
+            self.camera.pos.y += step * self.camera.a.sin();
+        } else {
+            self.target_angle = self.pick_open_direction(framebuffer, maze, block_size);
+        }
+    }
+
+    // Probe eight directions and head down the longest corridor, avoiding a full U-turn when possible
+    fn pick_open_direction(&self, framebuffer: &mut Framebuffer, maze: &Maze, block_size: usize) -> f32 {
+        let mut best_angle = self.camera.a + PI;
+        let mut best_distance = 0.0;
+
+        for step in 1..8 {
+            let angle = self.camera.a + step as f32 * PI / 4.0;
+            if step == 4 {
+                continue; // Skip straight back, only used as a last resort
+            }
+
+            let probe = cast_ray(framebuffer, maze, &self.camera, angle, block_size, false);
+            if probe.distance > best_distance {
+                best_distance = probe.distance;
+                best_angle = angle;
+            }
+        }
+
+        if best_distance < block_size as f32 * 0.8 {
+            // Dead end, turn around
+            best_angle = self.camera.a + PI;
+        }
+
+        best_angle
+    }
+}
@@ -0,0 +1,597 @@
+// ui.rs
+
+use raylib::prelude::*;
+
+// Reusable menu widgets laid out as a vertical list, driven by keyboard, gamepad or mouse
+pub enum Widget {
+    Button { label: &'static str },
+    Toggle { label: &'static str, value: bool },
+    Slider { label: &'static str, value: f32, min: f32, max: f32, step: f32 },
+    Dropdown { label: &'static str, options: &'static [&'static str], selected: usize, open: bool },
+}
+
+// What happened this frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UiEvent {
+    Changed(usize), // The widget at this index changed value, or a button was pressed
+    Back,           // Back was pressed with nothing left to close
+}
+
+// One frame of menu input from every device
+pub struct UiInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub back: bool,
+    pub mouse: Vector2,
+    pub mouse_moved: bool,
+    pub mouse_pressed: bool,
+    pub mouse_down: bool,
+}
+
+impl UiInput {
+    // `ui_scale` maps the mouse into the scaled menu layout
+    pub fn gather(rl: &RaylibHandle, ui_scale: f32) -> Self {
+        let pad = rl.is_gamepad_available(0);
+        let pad_pressed = |button: GamepadButton| pad && rl.is_gamepad_button_pressed(0, button);
+        let mouse_delta = rl.get_mouse_delta();
+
+        UiInput {
+            up: rl.is_key_pressed(KeyboardKey::KEY_UP) || rl.is_key_pressed(KeyboardKey::KEY_W) ||
+                pad_pressed(GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP),
+            down: rl.is_key_pressed(KeyboardKey::KEY_DOWN) || rl.is_key_pressed(KeyboardKey::KEY_S) ||
+                pad_pressed(GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN),
+            left: rl.is_key_pressed(KeyboardKey::KEY_LEFT) || rl.is_key_pressed(KeyboardKey::KEY_A) ||
+                pad_pressed(GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT),
+            right: rl.is_key_pressed(KeyboardKey::KEY_RIGHT) || rl.is_key_pressed(KeyboardKey::KEY_D) ||
+                pad_pressed(GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT),
+            confirm: rl.is_key_pressed(KeyboardKey::KEY_ENTER) || rl.is_key_pressed(KeyboardKey::KEY_SPACE) ||
+                pad_pressed(GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN),
+            back: rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) ||
+                pad_pressed(GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT),
+            mouse: rl.get_mouse_position() / ui_scale,
+            mouse_moved: mouse_delta.x != 0.0 || mouse_delta.y != 0.0,
+            mouse_pressed: rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT),
+            mouse_down: rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT),
+        }
+    }
+}
+
+// Where a widget list sits on screen
+pub struct Layout {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub row_height: i32,
+}
+
+impl Layout {
+    fn row_y(&self, index: usize) -> i32 {
+        self.y + index as i32 * self.row_height
+    }
+
+    fn contains_row(&self, index: usize, point: Vector2) -> bool {
+        let y = self.row_y(index) as f32;
+        point.x >= self.x as f32 && point.x < (self.x + self.width) as f32 && point.y >= y && point.y < y + self.row_height as f32
+    }
+
+    // Value controls take the right part of each row
+    fn control_x(&self) -> i32 {
+        self.x + self.width / 2 + 20
+    }
+
+    fn control_width(&self) -> i32 {
+        self.width / 2 - 40
+    }
+}
+
+fn open_dropdown(widgets: &[Widget]) -> Option<usize> {
+    widgets.iter().position(|widget| matches!(widget, Widget::Dropdown { open: true, .. }))
+}
+
+// Moves focus and applies input to the focused widget
+pub fn handle(widgets: &mut [Widget], focused: &mut usize, input: &UiInput, layout: &Layout) -> Option<UiEvent> {
+    if widgets.is_empty() {
+        return None;
+    }
+    *focused = (*focused).min(widgets.len() - 1);
+
+    // An open dropdown captures all input until it closes
+    if let Some(index) = open_dropdown(widgets) {
+        if let Widget::Dropdown { options, selected, open, .. } = &mut widgets[index] {
+            let previous = *selected;
+            if input.up {
+                *selected = (*selected + options.len() - 1) % options.len();
+            }
+            if input.down {
+                *selected = (*selected + 1) % options.len();
+            }
+
+            // Mouse picks from the list drawn below the row
+            if input.mouse_pressed {
+                let list_y = layout.row_y(index) + layout.row_height;
+                let row = ((input.mouse.y as i32 - list_y) / 28) as usize;
+                let inside_x = input.mouse.x as i32 >= layout.control_x() && (input.mouse.x as i32) < layout.control_x() + layout.control_width();
+                if inside_x && input.mouse.y as i32 >= list_y && row < options.len() {
+                    *selected = row;
+                }
+                *open = false;
+            }
+            if input.confirm || input.back {
+                *open = false;
+            }
+            if *selected != previous {
+                return Some(UiEvent::Changed(index));
+            }
+        }
+        return None;
+    }
+
+    if input.up {
+        *focused = (*focused + widgets.len() - 1) % widgets.len();
+    }
+    if input.down {
+        *focused = (*focused + 1) % widgets.len();
+    }
+
+    // Hovering focuses a row, clicking activates it
+    let hovered = (0..widgets.len()).find(|&i| layout.contains_row(i, input.mouse));
+    if let Some(index) = hovered && (input.mouse_moved || input.mouse_pressed) {
+        *focused = index;
+    }
+    let clicked = input.mouse_pressed && hovered == Some(*focused);
+    let dragging = input.mouse_down && hovered == Some(*focused);
+
+    let index = *focused;
+    let changed = match &mut widgets[index] {
+        Widget::Button { .. } => input.confirm || clicked,
+        Widget::Toggle { value, .. } => {
+            let flip = input.confirm || input.left || input.right || clicked;
+            if flip {
+                *value = !*value;
+            }
+            flip
+        }
+        Widget::Slider { value, min, max, step, .. } => {
+            let previous = *value;
+            if input.right {
+                *value += *step;
+            }
+            if input.left {
+                *value -= *step;
+            }
+            if dragging {
+                let t = (input.mouse.x - layout.control_x() as f32) / layout.control_width() as f32;
+                if (0.0..=1.0).contains(&t) {
+                    // Snap to whole steps so mouse and keys land on the same values
+                    *value = *min + ((t * (*max - *min)) / *step).round() * *step;
+                }
+            }
+            *value = value.clamp(*min, *max);
+            (*value - previous).abs() > f32::EPSILON
+        }
+        Widget::Dropdown { options, selected, open, .. } => {
+            if input.confirm || clicked {
+                *open = true;
+            }
+            let previous = *selected;
+            if input.right {
+                *selected = (*selected + 1) % options.len();
+            }
+            if input.left {
+                *selected = (*selected + options.len() - 1) % options.len();
+            }
+            *selected != previous
+        }
+    };
+
+    if changed {
+        Some(UiEvent::Changed(index))
+    } else if input.back {
+        Some(UiEvent::Back)
+    } else {
+        None
+    }
+}
+
+pub fn draw(d: &mut RaylibDrawHandle, widgets: &[Widget], focused: usize, layout: &Layout) {
+    for (i, widget) in widgets.iter().enumerate() {
+        let y = layout.row_y(i);
+        let is_focused = i == focused;
+        let color = if is_focused { Color::YELLOW } else { Color::WHITE };
+        let text_y = y + (layout.row_height - 20) / 2;
+
+        if is_focused {
+            d.draw_rectangle(layout.x, y, layout.width, layout.row_height - 4, Color::new(255, 255, 255, 25));
+        }
+
+        match widget {
+            Widget::Button { label } => {
+                let text_width = 20 * label.len() as i32 / 2; // Approximate text width
+                d.draw_text(label, layout.x + (layout.width - text_width) / 2, text_y, 20, color);
+            }
+            Widget::Toggle { label, value } => {
+                d.draw_text(label, layout.x + 20, text_y, 20, color);
+                // Pill switch with a knob on the active side
+                let switch_x = layout.control_x();
+                let switch_y = y + (layout.row_height - 22) / 2;
+                let track = if *value { Color::new(60, 160, 80, 255) } else { Color::new(80, 80, 80, 255) };
+                d.draw_rectangle_rounded(Rectangle::new(switch_x as f32, switch_y as f32, 44.0, 22.0), 1.0, 8, track);
+                let knob_x = if *value { switch_x + 33 } else { switch_x + 11 };
+                d.draw_circle(knob_x, switch_y + 11, 9.0, Color::WHITE);
+                d.draw_text(if *value { "ON" } else { "OFF" }, switch_x + 56, text_y, 20, color);
+            }
+            Widget::Slider { label, value, min, max, .. } => {
+                d.draw_text(label, layout.x + 20, text_y, 20, color);
+                let track_x = layout.control_x();
+                let track_width = layout.control_width() - 60;
+                let track_y = y + layout.row_height / 2 - 3;
+                let t = ((*value - *min) / (*max - *min)).clamp(0.0, 1.0);
+                d.draw_rectangle(track_x, track_y, track_width, 6, Color::new(80, 80, 80, 255));
+                d.draw_rectangle(track_x, track_y, (track_width as f32 * t) as i32, 6, if is_focused { Color::YELLOW } else { Color::LIGHTGRAY });
+                d.draw_circle(track_x + (track_width as f32 * t) as i32, track_y + 3, 8.0, Color::WHITE);
+                d.draw_text(&format!("{:.0}%", t * 100.0), track_x + track_width + 12, text_y, 20, color);
+            }
+            Widget::Dropdown { label, options, selected, .. } => {
+                d.draw_text(label, layout.x + 20, text_y, 20, color);
+                let box_x = layout.control_x();
+                d.draw_rectangle_lines(box_x, y + 4, layout.control_width(), layout.row_height - 12, color);
+                d.draw_text(&format!("< {} >", options[*selected]), box_x + 10, text_y, 20, color);
+            }
+        }
+    }
+
+    // The open list goes on top of the rows below it
+    if let Some(index) = open_dropdown(widgets) && let Widget::Dropdown { options, selected, .. } = &widgets[index] {
+        let list_x = layout.control_x();
+        let list_y = layout.row_y(index) + layout.row_height;
+        let list_width = layout.control_width();
+        d.draw_rectangle(list_x, list_y, list_width, options.len() as i32 * 28, Color::new(25, 25, 30, 250));
+        d.draw_rectangle_lines(list_x, list_y, list_width, options.len() as i32 * 28, Color::YELLOW);
+        for (i, option) in options.iter().enumerate() {
+            let color = if i == *selected { Color::YELLOW } else { Color::WHITE };
+            d.draw_text(option, list_x + 10, list_y + 4 + i as i32 * 28, 20, color);
+        }
+    }
+}
+
+const REPEAT_DELAY: f32 = 0.4; // Seconds a key is held before it starts repeating
+const REPEAT_INTERVAL: f32 = 0.08;
+
+// Turns a pair of increase/decrease keys into single steps with hold-to-repeat
+pub struct KeyRepeat {
+    held: f32,
+    next_repeat: f32,
+}
+
+impl KeyRepeat {
+    pub fn new() -> Self {
+        KeyRepeat { held: 0.0, next_repeat: REPEAT_DELAY }
+    }
+
+    // +1, -1 or 0 steps this frame
+    pub fn step(&mut self, rl: &RaylibHandle, increase: &[KeyboardKey], decrease: &[KeyboardKey], delta_time: f32) -> i32 {
+        let direction = if increase.iter().any(|&key| rl.is_key_down(key)) {
+            1
+        } else if decrease.iter().any(|&key| rl.is_key_down(key)) {
+            -1
+        } else {
+            self.held = 0.0;
+            return 0;
+        };
+
+        if increase.iter().chain(decrease.iter()).any(|&key| rl.is_key_pressed(key)) {
+            self.held = 0.0;
+            self.next_repeat = REPEAT_DELAY;
+            return direction;
+        }
+
+        self.held += delta_time;
+        if self.held >= self.next_repeat {
+            self.next_repeat += REPEAT_INTERVAL;
+            return direction;
+        }
+        0
+    }
+}
+
+const POPUP_DURATION: f32 = 1.5; // Seconds the volume bar stays up after the last change
+
+// Bar that pops up at the top of the screen while a volume changes
+pub struct VolumePopup {
+    label: &'static str,
+    value: f32,
+    timer: f32,
+}
+
+impl VolumePopup {
+    pub fn new() -> Self {
+        VolumePopup { label: "", value: 0.0, timer: 0.0 }
+    }
+
+    pub fn show(&mut self, label: &'static str, value: f32) {
+        self.label = label;
+        self.value = value;
+        self.timer = POPUP_DURATION;
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.timer = (self.timer - delta_time).max(0.0);
+    }
+
+    pub fn draw(&self, d: &mut RaylibDrawHandle, screen_width: i32) {
+        if self.timer <= 0.0 {
+            return;
+        }
+
+        // Fade out over the last third of a second
+        let alpha = ((self.timer / 0.3).min(1.0) * 255.0) as u8;
+        let width = 320;
+        let x = (screen_width - width) / 2;
+        let y = 110;
+
+        d.draw_rectangle(x, y, width, 56, Color::new(0, 0, 0, alpha / 4 * 3));
+        d.draw_text(&format!("{} {:.0}%", self.label, self.value * 100.0), x + 14, y + 8, 18, Color::new(255, 255, 255, alpha));
+
+        // One notch per 5% step
+        let bar_y = y + 34;
+        let notch_width = (width - 28) / 20;
+        let filled = (self.value * 20.0).round() as i32;
+        for i in 0..20 {
+            let color = if i < filled { Color::new(255, 200, 60, alpha) } else { Color::new(80, 80, 80, alpha) };
+            d.draw_rectangle(x + 14 + i * notch_width, bar_y, notch_width - 2, 12, color);
+        }
+    }
+}
+
+// On-screen keyboard for names: a grid of keys picked with the D-pad or mouse, or typed
+// straight in on a keyboard. Only letters, digits, spaces and - _ . ' are accepted, so
+// names are safe to keep in the key=value save files.
+const KEY_ROWS: [&str; 4] = ["ABCDEFGHIJ", "KLMNOPQRST", "UVWXYZ-_.'", "0123456789"];
+const KEY_COLUMNS: usize = 10;
+const KEY_SIZE: i32 = 48;
+const KEY_GAP: i32 = 6;
+const KEYBOARD_WIDTH: i32 = 600;
+pub const KEYBOARD_HEIGHT: i32 = 470;
+const GRID_TOP: i32 = 124; // Below the title and the text field
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Key {
+    Char(char),
+    Shift, // Switch the letters between upper and lower case
+    Space,
+    Erase,
+    Done,
+}
+
+// The row under the grid, each key spanning this many grid columns
+const ACTION_KEYS: [(Key, &str, usize); 4] = [(Key::Shift, "Shift", 2), (Key::Space, "Space", 4), (Key::Erase, "Del", 2), (Key::Done, "Done", 2)];
+
+// How the keyboard was closed
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextEvent {
+    Done(String),
+    Cancelled,
+}
+
+// One frame of input for the on-screen keyboard. Letter keys type instead of moving the
+// focus, so only the arrows and D-pad navigate here.
+pub struct TextInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub press: bool,     // Press the focused key
+    pub erase: bool,
+    pub shift: bool,
+    pub done: bool,
+    pub cancel: bool,
+    pub typed: Vec<char>, // Characters typed on a keyboard this frame
+    pub mouse: Vector2,
+    pub mouse_moved: bool,
+    pub mouse_pressed: bool,
+}
+
+impl TextInput {
+    pub fn gather(rl: &mut RaylibHandle, ui_scale: f32) -> Self {
+        let pad = rl.is_gamepad_available(0);
+        let pad_pressed = |rl: &RaylibHandle, button: GamepadButton| pad && rl.is_gamepad_button_pressed(0, button);
+        let mouse_delta = rl.get_mouse_delta();
+
+        let mut input = TextInput {
+            up: rl.is_key_pressed(KeyboardKey::KEY_UP) || pad_pressed(rl, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP),
+            down: rl.is_key_pressed(KeyboardKey::KEY_DOWN) || pad_pressed(rl, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN),
+            left: rl.is_key_pressed(KeyboardKey::KEY_LEFT) || pad_pressed(rl, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT),
+            right: rl.is_key_pressed(KeyboardKey::KEY_RIGHT) || pad_pressed(rl, GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT),
+            press: pad_pressed(rl, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN),
+            erase: rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) || pad_pressed(rl, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT),
+            shift: pad_pressed(rl, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP),
+            done: rl.is_key_pressed(KeyboardKey::KEY_ENTER) || pad_pressed(rl, GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT),
+            cancel: rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) || pad_pressed(rl, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT),
+            typed: Vec::new(),
+            mouse: rl.get_mouse_position() / ui_scale,
+            mouse_moved: mouse_delta.x != 0.0 || mouse_delta.y != 0.0,
+            mouse_pressed: rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT),
+        };
+        while let Some(ch) = rl.get_char_pressed() {
+            input.typed.push(ch);
+        }
+        input
+    }
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || " -_.'".contains(ch)
+}
+
+pub struct OnScreenKeyboard {
+    pub title: &'static str,
+    pub text: String,
+    max_len: usize,
+    row: usize,    // Focused key, the row past KEY_ROWS is the action row
+    column: usize, // Grid column, on the action row the first column of the key
+    lowercase: bool,
+}
+
+impl OnScreenKeyboard {
+    // Starts out holding `text`, less anything a name can't contain
+    pub fn new(title: &'static str, text: &str, max_len: usize) -> Self {
+        let text = text.chars().filter(|&ch| is_name_char(ch)).take(max_len).collect();
+        OnScreenKeyboard { title, text, max_len, row: 0, column: 0, lowercase: false }
+    }
+
+    // Action key covering `column` and the first column it spans
+    fn action_at(column: usize) -> (usize, Key, &'static str, usize) {
+        let mut start = 0;
+        for (key, label, span) in ACTION_KEYS {
+            if column < start + span {
+                return (start, key, label, span);
+            }
+            start += span;
+        }
+        let (key, label, span) = ACTION_KEYS[ACTION_KEYS.len() - 1];
+        (KEY_COLUMNS - span, key, label, span)
+    }
+
+    fn key_at(&self, row: usize, column: usize) -> Key {
+        match KEY_ROWS.get(row).and_then(|keys| keys.chars().nth(column)) {
+            Some(ch) if self.lowercase => Key::Char(ch.to_ascii_lowercase()),
+            Some(ch) => Key::Char(ch),
+            None => Self::action_at(column).1,
+        }
+    }
+
+    fn origin(screen_width: i32, screen_height: i32) -> (i32, i32) {
+        ((screen_width - KEYBOARD_WIDTH) / 2, (screen_height - KEYBOARD_HEIGHT) / 2)
+    }
+
+    // Top-left corner and width of a key, action keys given by their first column
+    fn key_rect(row: usize, column: usize, screen_width: i32, screen_height: i32) -> (i32, i32, i32) {
+        let (x, y) = Self::origin(screen_width, screen_height);
+        let pitch = KEY_SIZE + KEY_GAP;
+        let grid_x = x + (KEYBOARD_WIDTH - KEY_COLUMNS as i32 * pitch + KEY_GAP) / 2;
+        let span = if row < KEY_ROWS.len() { 1 } else { Self::action_at(column).3 as i32 };
+        (grid_x + column as i32 * pitch, y + GRID_TOP + row as i32 * pitch, span * pitch - KEY_GAP)
+    }
+
+    // The key under a point as (row, column), action keys by their first column
+    fn key_under(point: Vector2, screen_width: i32, screen_height: i32) -> Option<(usize, usize)> {
+        (0..=KEY_ROWS.len())
+            .flat_map(|row| (0..KEY_COLUMNS).map(move |column| (row, column)))
+            .filter(|&(row, column)| row < KEY_ROWS.len() || Self::action_at(column).0 == column)
+            .find(|&(row, column)| {
+                let (x, y, width) = Self::key_rect(row, column, screen_width, screen_height);
+                point.x >= x as f32 && point.x < (x + width) as f32 && point.y >= y as f32 && point.y < (y + KEY_SIZE) as f32
+            })
+    }
+
+    fn type_char(&mut self, ch: char) {
+        if is_name_char(ch) && self.text.chars().count() < self.max_len {
+            self.text.push(ch);
+        }
+    }
+
+    fn press(&mut self, key: Key) -> Option<TextEvent> {
+        match key {
+            Key::Char(ch) => self.type_char(ch),
+            Key::Shift => self.lowercase = !self.lowercase,
+            Key::Space => self.type_char(' '),
+            Key::Erase => {
+                self.text.pop();
+            }
+            Key::Done => return Some(TextEvent::Done(self.text.trim().to_string())),
+        }
+        None
+    }
+
+    pub fn handle(&mut self, input: &TextInput, screen_width: i32, screen_height: i32) -> Option<TextEvent> {
+        if input.cancel {
+            return Some(TextEvent::Cancelled);
+        }
+        for &ch in &input.typed {
+            self.type_char(ch);
+        }
+
+        // Action keys are wider, so left and right step over a whole one
+        let (start, span) = if self.row == KEY_ROWS.len() {
+            let (start, _, _, span) = Self::action_at(self.column);
+            (start, span)
+        } else {
+            (self.column, 1)
+        };
+        if input.up {
+            self.row = (self.row + KEY_ROWS.len()) % (KEY_ROWS.len() + 1);
+        }
+        if input.down {
+            self.row = (self.row + 1) % (KEY_ROWS.len() + 1);
+        }
+        if input.left {
+            self.column = (start + KEY_COLUMNS - 1) % KEY_COLUMNS;
+        }
+        if input.right {
+            self.column = (start + span) % KEY_COLUMNS;
+        }
+        if self.row == KEY_ROWS.len() {
+            self.column = Self::action_at(self.column).0;
+        }
+
+        let hovered = Self::key_under(input.mouse, screen_width, screen_height);
+        if let Some((row, column)) = hovered && (input.mouse_moved || input.mouse_pressed) {
+            self.row = row;
+            self.column = column;
+        }
+
+        if input.erase {
+            self.press(Key::Erase);
+        }
+        if input.shift {
+            self.press(Key::Shift);
+        }
+        if (input.press || (input.mouse_pressed && hovered.is_some())) && let Some(event) = self.press(self.key_at(self.row, self.column)) {
+            return Some(event);
+        }
+        if input.done {
+            return self.press(Key::Done);
+        }
+        None
+    }
+
+    // Panel, text field and keys; the caller adds the button prompts along the bottom
+    pub fn draw(&self, d: &mut RaylibDrawHandle, screen_width: i32, screen_height: i32) {
+        let (x, y) = Self::origin(screen_width, screen_height);
+        d.draw_rectangle(x, y, KEYBOARD_WIDTH, KEYBOARD_HEIGHT, Color::new(40, 40, 40, 245));
+        d.draw_rectangle_lines(x, y, KEYBOARD_WIDTH, KEYBOARD_HEIGHT, Color::WHITE);
+
+        let title_width = 24 * self.title.len() as i32 / 2; // Approximate text width
+        d.draw_text(self.title, x + (KEYBOARD_WIDTH - title_width) / 2, y + 20, 24, Color::WHITE);
+
+        // Text field with a caret after the last character
+        let field_x = x + 30;
+        d.draw_rectangle(field_x, y + 60, KEYBOARD_WIDTH - 60, 44, Color::new(20, 20, 25, 255));
+        d.draw_rectangle_lines(field_x, y + 60, KEYBOARD_WIDTH - 60, 44, Color::LIGHTGRAY);
+        d.draw_text(&self.text, field_x + 12, y + 71, 24, Color::WHITE);
+        let caret_x = field_x + 14 + 24 * self.text.len() as i32 / 2; // Approximate text width
+        d.draw_rectangle(caret_x, y + 70, 2, 26, Color::YELLOW);
+        let count = format!("{}/{}", self.text.chars().count(), self.max_len);
+        d.draw_text(&count, x + KEYBOARD_WIDTH - 30 - 8 * count.len() as i32 - 8, y + 110, 14, Color::GRAY);
+
+        for row in 0..=KEY_ROWS.len() {
+            for column in 0..KEY_COLUMNS {
+                let label = match self.key_at(row, column) {
+                    Key::Char(ch) => ch.to_string(),
+                    _ if Self::action_at(column).0 != column => continue,
+                    _ => Self::action_at(column).2.to_string(),
+                };
+                let (key_x, key_y, width) = Self::key_rect(row, column, screen_width, screen_height);
+                let focused = row == self.row && column == self.column;
+                let fill = if focused { Color::new(200, 170, 40, 255) } else { Color::new(70, 70, 80, 255) };
+                d.draw_rectangle(key_x, key_y, width, KEY_SIZE, fill);
+                let text_color = if focused { Color::BLACK } else { Color::WHITE };
+                let label_width = 20 * label.len() as i32 / 2; // Approximate text width
+                d.draw_text(&label, key_x + (width - label_width) / 2, key_y + (KEY_SIZE - 20) / 2, 20, text_color);
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+// visualizer.rs
+
+use raylib::prelude::*;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::rng::Rng;
+use crate::sim::Vec2;
+
+// The start screen moves with its music: the title swells with the track's loudness and sparks
+// burst from it on the beats. A track's amplitude envelope, how loud it is 20 times a second, is
+// worked out once by decoding the whole file on a background thread, then cached beside it as
+// "<track>.envelope" so later launches only read the numbers back. Beats are picked out of the
+// envelope where it jumps well above the second before. The menu keeps still until a track's
+// envelope is ready, and while music is switched off.
+
+const RATE: f32 = 20.0;         // Envelope levels per second of music
+const BEAT_RISE: f32 = 1.35;    // A level this far above the last second's average is a beat
+const BEAT_FLOOR: f32 = 0.3;    // Quieter levels are never beats
+const BEAT_GAP: usize = 5;      // Levels at least between beats, a quarter of a second
+const SMOOTHING: f32 = 12.0;    // Per second, how fast the shown level follows the envelope
+const SPARKS_PER_BEAT: usize = 14;
+const MAX_SPARKS: usize = 120;
+const SPARK_LIFE: f32 = 1.2;    // Seconds
+
+// Loudness of a track over time, 0.0 to 1.0 against its loudest moment
+pub struct Envelope {
+    levels: Vec<f32>,
+    beats: Vec<bool>, // One per level
+}
+
+impl Envelope {
+    fn new(levels: Vec<f32>) -> Self {
+        let mut beats = vec![false; levels.len()];
+        let window = RATE as usize;
+        let mut last_beat: Option<usize> = None;
+        for i in 1..levels.len() {
+            let before = &levels[i.saturating_sub(window)..i];
+            let average = before.iter().sum::<f32>() / before.len() as f32;
+            let spaced = last_beat.is_none_or(|last| i - last >= BEAT_GAP);
+            if spaced && levels[i] >= BEAT_FLOOR && levels[i] > average * BEAT_RISE {
+                beats[i] = true;
+                last_beat = Some(i);
+            }
+        }
+        Envelope { levels, beats }
+    }
+
+    // RMS of each 1/RATE second of interleaved samples, scaled so the loudest is 1.0
+    fn from_samples(samples: &[f32], channels: usize, sample_rate: usize) -> Self {
+        let window = (sample_rate as f32 / RATE) as usize * channels;
+        let levels: Vec<f32> = samples
+            .chunks(window.max(1))
+            .map(|chunk| (chunk.iter().map(|sample| sample * sample).sum::<f32>() / chunk.len() as f32).sqrt())
+            .collect();
+        let loudest = levels.iter().copied().fold(0.0, f32::max);
+        Envelope::new(levels.into_iter().map(|level| if loudest > 0.0 { level / loudest } else { 0.0 }).collect())
+    }
+
+    // Cache files hold one level per line as a whole percent, after a comment line
+    fn parse(text: &str) -> Option<Self> {
+        let levels: Option<Vec<f32>> = text
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+            .map(|line| line.trim().parse::<u8>().ok().map(|percent| percent.min(100) as f32 / 100.0))
+            .collect();
+        levels.filter(|levels| !levels.is_empty()).map(Envelope::new)
+    }
+
+    fn encode(&self, track: &str) -> String {
+        let mut text = format!("# Amplitude envelope of {}, {} levels per second in percent\n", track, RATE);
+        for level in self.levels.iter() {
+            text.push_str(&format!("{}\n", (level * 100.0).round() as u8));
+        }
+        text
+    }
+
+    fn index(&self, time: f32) -> Option<usize> {
+        let index = (time * RATE) as usize;
+        (index < self.levels.len()).then_some(index)
+    }
+}
+
+// The envelope of `track`, from its cache file or by decoding it, None if neither works
+fn load_envelope(track: &str) -> Option<Envelope> {
+    let cache = format!("{}.envelope", track);
+    if let Some(envelope) = fs::read_to_string(&cache).ok().and_then(|text| Envelope::parse(&text)) {
+        return Some(envelope);
+    }
+
+    let name = CString::new(track).ok()?;
+    let wave = unsafe { raylib::ffi::LoadWave(name.as_ptr()) };
+    if wave.frameCount == 0 || wave.channels == 0 {
+        elog!("Warning: Could not decode {} for the menu visualizer", track);
+        return None;
+    }
+    let samples = unsafe { raylib::ffi::LoadWaveSamples(wave) };
+    let envelope = (!samples.is_null()).then(|| {
+        let count = wave.frameCount as usize * wave.channels as usize;
+        let samples = unsafe { std::slice::from_raw_parts(samples, count) };
+        Envelope::from_samples(samples, wave.channels as usize, wave.sampleRate as usize)
+    });
+    unsafe {
+        if !samples.is_null() {
+            raylib::ffi::UnloadWaveSamples(samples);
+        }
+        raylib::ffi::UnloadWave(wave);
+    }
+
+    let envelope = envelope?;
+    match fs::write(&cache, envelope.encode(track)) {
+        Ok(()) => log!("Analyzed {} for the menu visualizer, cached in {}", track, cache),
+        Err(e) => elog!("Warning: Could not cache the envelope of {}: {}", track, e),
+    }
+    Some(envelope)
+}
+
+struct Spark {
+    offset: Vec2,   // From the title's center
+    velocity: Vec2, // Pixels per second
+    age: f32,
+}
+
+pub struct MusicVisualizer {
+    tracks: Vec<Option<String>>, // Music file of each track, indexed like the map list
+    envelopes: Arc<Mutex<HashMap<String, Envelope>>>, // Filled in by the analysis thread
+    last_index: Option<usize>,   // Envelope level shown last frame, so each beat fires once
+    pub level: f32,              // Loudness right now, smoothed, 0.0 while nothing is playing
+    sparks: Vec<Spark>,
+    rng: Rng,
+}
+
+impl MusicVisualizer {
+    // Starts analyzing every distinct track in the background
+    pub fn new(tracks: Vec<Option<String>>) -> Self {
+        let envelopes = Arc::new(Mutex::new(HashMap::new()));
+        let mut pending: Vec<String> = tracks.iter().flatten().cloned().collect();
+        pending.sort();
+        pending.dedup();
+        let shared = Arc::clone(&envelopes);
+        thread::spawn(move || {
+            for track in pending {
+                if let Some(envelope) = load_envelope(&track) {
+                    shared.lock().unwrap().insert(track, envelope);
+                }
+            }
+        });
+        MusicVisualizer { tracks, envelopes, last_index: None, level: 0.0, sparks: Vec::new(), rng: Rng::new(0x5eed) }
+    }
+
+    // Follow `track`, `time` seconds in, or nothing while music is stopped or switched off
+    pub fn update(&mut self, delta_time: f32, track: Option<usize>, time: Option<f32>) {
+        let path = track.and_then(|track| self.tracks.get(track)).and_then(|path| path.as_ref());
+        let (target, beat) = match (path, time) {
+            (Some(path), Some(time)) => {
+                let envelopes = self.envelopes.lock().unwrap();
+                match envelopes.get(path).and_then(|envelope| envelope.index(time).map(|index| (envelope, index))) {
+                    Some((envelope, index)) => {
+                        let beat = self.last_index != Some(index) && envelope.beats[index];
+                        self.last_index = Some(index);
+                        (envelope.levels[index], beat)
+                    }
+                    None => (0.0, false),
+                }
+            }
+            _ => (0.0, false),
+        };
+        self.level += (target - self.level) * (SMOOTHING * delta_time).min(1.0);
+
+        for spark in self.sparks.iter_mut() {
+            spark.age += delta_time;
+            spark.offset = spark.offset + spark.velocity * delta_time;
+            spark.velocity.y += 60.0 * delta_time; // A little drop as they fade
+        }
+        self.sparks.retain(|spark| spark.age < SPARK_LIFE);
+        if beat {
+            for _ in 0..SPARKS_PER_BEAT.min(MAX_SPARKS - self.sparks.len().min(MAX_SPARKS)) {
+                let angle = self.rng.range_f32(0.0, std::f32::consts::TAU);
+                let speed = self.rng.range_f32(80.0, 220.0) * (0.6 + target);
+                let offset = Vec2::new(self.rng.range_f32(-200.0, 200.0), self.rng.range_f32(-10.0, 10.0));
+                self.sparks.push(Spark { offset, velocity: Vec2::new(angle.cos() * speed, angle.sin() * speed * 0.5), age: 0.0 });
+            }
+        }
+    }
+
+    // Title scale for this frame, 1.0 in silence
+    pub fn pulse(&self) -> f32 {
+        1.0 + 0.12 * self.level
+    }
+
+    // Beat sparks around the title centered on `center`
+    pub fn draw_sparks(&self, d: &mut RaylibDrawHandle, center: Vector2) {
+        for spark in self.sparks.iter() {
+            let fade = 1.0 - spark.age / SPARK_LIFE;
+            let pos = Vec2::from(center) + spark.offset;
+            d.draw_circle(pos.x as i32, pos.y as i32, 1.5 + 2.0 * fade, Color::new(255, 220, 140, (230.0 * fade) as u8));
+        }
+    }
+}
@@ -0,0 +1,195 @@
+// weather.rs
+
+use raylib::prelude::*;
+
+use crate::maze::{MapArea, Maze, is_walkable, parse_area};
+use crate::rng::Rng;
+
+// Environmental effects from a map's header: ash drifting down, embers rising, water dripping
+// from the ceiling, or banks of fog hanging in a corridor. Each line tags a rectangle of cells,
+// or the whole map without one, and later lines win where they overlap:
+//   weather = embers                (the whole map)
+//   weather = fog 3 0 1 8           (kind, x, y, width, height in cells)
+// Particles live in world space around the player, so they stay put as the view turns, and are
+// drawn depth tested into the framebuffer after the sprites. Performance mode draws fewer.
+
+const MAX_PARTICLES: usize = 240;
+const SPAWN_RADIUS: f32 = 6.0;   // Cells around the player particles are kept in
+const SPAWN_ATTEMPTS: usize = 24; // Per frame, most land outside every area or in a wall
+const FADE_TIME: f32 = 0.6;      // Seconds to fade in and out
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeatherKind {
+    Ash,
+    Embers,
+    Drips,
+    Fog,
+}
+
+impl WeatherKind {
+    fn from_name(name: &str) -> Option<WeatherKind> {
+        match name {
+            "ash" => Some(WeatherKind::Ash),
+            "embers" => Some(WeatherKind::Embers),
+            "drips" => Some(WeatherKind::Drips),
+            "fog" => Some(WeatherKind::Fog),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            WeatherKind::Ash => Color::new(175, 170, 165, 255),
+            WeatherKind::Embers => Color::new(255, 140, 40, 255),
+            WeatherKind::Drips => Color::new(150, 185, 215, 255),
+            WeatherKind::Fog => Color::new(190, 195, 205, 255),
+        }
+    }
+
+    // How strongly one particle blends over what is behind it, at full strength
+    pub fn opacity(&self) -> f32 {
+        match self {
+            WeatherKind::Ash => 0.7,
+            WeatherKind::Embers => 0.9,
+            WeatherKind::Drips => 0.6,
+            WeatherKind::Fog => 0.12,
+        }
+    }
+
+    // Width and height on screen, in wall heights
+    pub fn size(&self) -> (f32, f32) {
+        match self {
+            WeatherKind::Ash => (0.012, 0.012),
+            WeatherKind::Embers => (0.01, 0.01),
+            WeatherKind::Drips => (0.005, 0.05),
+            WeatherKind::Fog => (0.7, 0.35),
+        }
+    }
+
+    // Share of the particle budget one area of this kind may use, fog puffs are big and few
+    fn share(&self) -> usize {
+        match self {
+            WeatherKind::Fog => MAX_PARTICLES / 6,
+            _ => MAX_PARTICLES,
+        }
+    }
+}
+
+// The `weather` lines of a map's metadata, each kind with the area it covers
+pub fn weather_areas(meta: &[(String, String)]) -> Vec<(WeatherKind, MapArea)> {
+    let mut areas = Vec::new();
+    for (key, value) in meta.iter().filter(|(key, _)| key == "weather") {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        let Some(kind) = parts.first().and_then(|name| WeatherKind::from_name(name)) else {
+            elog!("Warning: '{} = {}' needs ash, embers, drips or fog", key, value);
+            continue;
+        };
+        if let Some(area) = parse_area(key, value, &parts[1..]) {
+            areas.push((kind, area));
+        }
+    }
+    areas
+}
+
+pub struct Particle {
+    pub kind: WeatherKind,
+    pub pos: Vector2,   // World position on the floor plan
+    pub height: f32,    // Above the floor, in wall heights
+    velocity: Vector2,  // World units per second
+    rise: f32,          // Wall heights per second, negative falls
+    pub age: f32,
+    life: f32,          // Seconds it lasts
+}
+
+impl Particle {
+    // 0.0 to 1.0, fading in when born and out before it dies
+    pub fn strength(&self) -> f32 {
+        if self.kind == WeatherKind::Drips {
+            return 1.0; // Gone too fast to fade
+        }
+        (self.age / FADE_TIME).min((self.life - self.age) / FADE_TIME).clamp(0.0, 1.0)
+    }
+}
+
+pub struct Weather {
+    areas: Vec<(WeatherKind, MapArea)>,
+    pub particles: Vec<Particle>,
+    wind: Vector2, // Cells per second, the same for the whole map
+    rng: Rng,
+}
+
+impl Weather {
+    pub fn new(areas: Vec<(WeatherKind, MapArea)>, rng: Rng) -> Self {
+        let mut rng = rng;
+        let angle = rng.range_f32(0.0, std::f32::consts::TAU);
+        let wind = Vector2::new(angle.cos() * 0.15, angle.sin() * 0.15);
+        if !areas.is_empty() {
+            log!("Weather: {} areas", areas.len());
+        }
+        Weather { areas, particles: Vec::new(), wind, rng }
+    }
+
+    pub fn none() -> Self {
+        Weather::new(Vec::new(), Rng::new(0))
+    }
+
+    fn kind_at(&self, cell: (usize, usize)) -> Option<WeatherKind> {
+        self.areas.iter().rev().find(|(_, area)| area.contains(cell)).map(|&(kind, _)| kind)
+    }
+
+    // Move the particles, drop the spent and far away ones and spawn new ones near the player
+    pub fn update(&mut self, delta_time: f32, player_pos: Vector2, maze: &Maze, block_size: usize) {
+        if self.areas.is_empty() {
+            return;
+        }
+        let block = block_size as f32;
+        let reach = SPAWN_RADIUS * block;
+        for particle in self.particles.iter_mut() {
+            // Ash and embers sway across the wind, fog barely moves with it, drips fall straight
+            let (wind, sway) = (self.wind, (particle.age * 1.3 + particle.life * 7.0).sin());
+            let drift = match particle.kind {
+                WeatherKind::Ash => Vector2::new(wind.x + sway * 0.1, wind.y - sway * 0.1),
+                WeatherKind::Embers => Vector2::new(wind.x * 1.5 + sway * 0.15, wind.y * 1.5),
+                WeatherKind::Drips => Vector2::zero(),
+                WeatherKind::Fog => Vector2::new(wind.x * 0.3, wind.y * 0.3),
+            };
+            particle.pos.x += (particle.velocity.x + drift.x * block) * delta_time;
+            particle.pos.y += (particle.velocity.y + drift.y * block) * delta_time;
+            particle.height += particle.rise * delta_time;
+            particle.age += delta_time;
+        }
+        self.particles.retain(|particle| {
+            let distance = ((particle.pos.x - player_pos.x).powi(2) + (particle.pos.y - player_pos.y).powi(2)).sqrt();
+            let in_wall = !maze.at_world(particle.pos.x, particle.pos.y, block_size).is_some_and(is_walkable);
+            particle.age < particle.life && (0.0..=1.0).contains(&particle.height) && distance < reach && !in_wall
+        });
+
+        for _ in 0..SPAWN_ATTEMPTS {
+            if self.particles.len() >= MAX_PARTICLES {
+                break;
+            }
+            let pos = Vector2::new(
+                player_pos.x + self.rng.range_f32(-reach, reach),
+                player_pos.y + self.rng.range_f32(-reach, reach),
+            );
+            if pos.x < 0.0 || pos.y < 0.0 || !maze.at_world(pos.x, pos.y, block_size).is_some_and(is_walkable) {
+                continue;
+            }
+            let Some(kind) = self.kind_at(((pos.x / block) as usize, (pos.y / block) as usize)) else {
+                continue;
+            };
+            if self.particles.iter().filter(|particle| particle.kind == kind).count() >= kind.share() {
+                continue;
+            }
+            let rng = &mut self.rng;
+            let (height, rise, life) = match kind {
+                WeatherKind::Ash => (rng.range_f32(0.2, 1.0), -rng.range_f32(0.05, 0.1), rng.range_f32(6.0, 10.0)),
+                WeatherKind::Embers => (rng.range_f32(0.0, 0.3), rng.range_f32(0.1, 0.2), rng.range_f32(3.0, 5.0)),
+                WeatherKind::Drips => (1.0, -rng.range_f32(1.2, 1.6), 1.0),
+                WeatherKind::Fog => (rng.range_f32(0.05, 0.35), 0.0, rng.range_f32(8.0, 14.0)),
+            };
+            let velocity = Vector2::new(rng.range_f32(-0.05, 0.05) * block, rng.range_f32(-0.05, 0.05) * block);
+            self.particles.push(Particle { kind, pos, height, velocity, rise, age: 0.0, life });
+        }
+    }
+}
@@ -0,0 +1,725 @@
+// world.rs
+
+use raylib::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+use std::f32::consts::PI;
+
+use crate::actions::{Action, ActionMap};
+use crate::arena::{Arena, ArenaState};
+use crate::automap::Marker;
+use crate::companion::Companion;
+use crate::deployables::{Deployable, deploy, update_deployables};
+use crate::director::{Difficulty, Director, spawn_reinforcement};
+use crate::enemy::{AnimationState, Enemy};
+use crate::factions::{despawn, grudge_targets, provoke, update_grudges};
+use crate::footprints::{Footprint, FootprintKind, step, update_footprints};
+use crate::framebuffer::Framebuffer;
+use crate::heatmap::{Visits, record_visit};
+use crate::hints::HintSystem;
+use crate::hub::Portal;
+use crate::inventory::{Inventory, Weapon};
+use crate::lantern::{FLASK_FUEL, collect_flasks};
+use crate::mapdelta::MapDelta;
+use crate::maze::{Cell, MapArea, Maze, MazeData, is_checkpoint};
+use crate::pickups::{Pickup, collect_pickups};
+use crate::ping::{COMPANION_PING_OWNER, Ping, ping_target, place_ping, update_pings};
+use crate::player::{Player, apply_input, read_input};
+use crate::projectiles::{Projectile, update_projectiles};
+use crate::props::{Loot, Prop, break_props, prop_boxes, strike_props, update_props};
+use crate::quest::QuestChain;
+use crate::revive::{Revive, ReviveEvent};
+use crate::reverb::{ReverbZone, zone_at};
+use crate::rng::Rng;
+use crate::sim::{PlayerInput, Vec2, aim_assist, from_behind, swing_hit};
+use crate::stealth::illumination;
+use crate::swarm::{Swarm, strike_swarms, swarm_boxes, update_swarms};
+use crate::status::floor_hazard;
+use crate::stress::StressMap;
+use crate::summary::Tally;
+use crate::textures::TextureManager;
+use crate::weather::Weather;
+use crate::zones::{ZoneModifier, Zones};
+use crate::{
+    FloorItem, check_goal_reached, has_line_of_sight, deployable_boxes, render_companion, render_enemies, render_floor_items, render_pickups, render_pings, render_shadows, render_weather, render_world,
+};
+
+const SWORD_CENTER_BONUS: f32 = 6.0; // Extra damage for hits in the middle of the swing
+const CRITICAL_MULTIPLIER: f32 = 2.0; // Damage against enemies that haven't noticed the player, or struck from behind
+pub const BACKSTAB_ARC: f32 = PI / 3.0;  // Either side of straight behind an enemy
+pub const ENEMY_ATTACK_RANGE: f32 = 0.9; // In cells, like every distance here
+const ENEMY_ATTACK_DAMAGE: f32 = 8.0;
+const ENEMY_ATTACK_COOLDOWN: f32 = 1.2; // Seconds between hits from the same enemy
+const STRAY_HIT_ARC: f32 = PI / 4.0;   // Either side of an enemy's swing at the player, other factions in it get hit too
+const ENEMY_ATTACK_ANIMATION_RANGE: f32 = 1.5; // Enemies this close play their attack animation
+const LOW_HEALTH: f32 = 0.25; // Share of max health that counts as badly hurt
+const AIM_ASSIST_WINDOW: f32 = 0.35; // Radians either side of the view an enemy can pull it from
+const AIM_ASSIST_RANGE: f32 = 3.0;   // Cells
+
+// Things that happened during an update, in order, for the game states to react to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorldEvent {
+    SwingStarted,
+    EnemyHit,
+    CriticalHit, // Replaces EnemyHit for a sneak attack or backstab
+    EnemyKilled,
+    Checkpoint((usize, usize)), // First touch of a checkpoint cell
+    GoalReached,
+    PlayerDied,
+    ArenaLocked,
+    ArenaCleared,
+    QuestGatesOpened, // The last step of the map's quest chain was delivered
+    PropBroken,
+    Explosion,        // A powder keg burst
+    Blocked,          // A raised shield turned a blow or dart aside
+    ZoneEntered,      // Stepped into a different set of zone modifiers
+    LowHealth,        // Health just dropped under LOW_HEALTH
+    PlayerDowned,     // Down and bleeding out, waiting on the wisp
+}
+
+// Input and the services the simulation needs for one frame
+pub struct FrameInput<'a> {
+    pub rl: &'a RaylibHandle,
+    pub window_width: i32,
+    pub window_height: i32,
+    pub gyro_turn: f32, // Radians from gyro aim this frame, added to the stick and mouse
+    pub actions: &'a ActionMap, // Bindings of the chosen input preset
+    pub aim_assist: f32,        // Share of the way a controller swing turns toward an enemy, 0.0 for none
+}
+
+// The map being played and everything living in it
+pub struct World {
+    pub maze_data: Option<MazeData>, // None while in the menus
+    pub player: Player,
+    pub enemies: Vec<Enemy>,
+    pub pickups: Vec<Pickup>,        // Coins lying in the current map
+    pub hints: HintSystem,           // Trigger-cell callouts for the current map
+    pub inventory: Inventory,        // Coins, potions and the equipped weapon
+    pub arena: Option<Arena>,        // Lockable room, if the map has one
+    pub pings: Vec<Ping>,            // Markers placed with the ping action
+    pub reverb_areas: Vec<(ReverbZone, MapArea)>, // Acoustic zones tagged in the map's metadata
+    pub portals: Vec<Portal>,        // Ways into the campaign maps, only the hub has any
+    pub visited: HashMap<(usize, usize), Visits>, // Where the player has been on this map, for the debug view, heatmap and run summary
+    pub footprints: Vec<Footprint>,  // Fading prints on the floor, shown on the minimap
+    pub visibility: f32,             // How well lit the player is, for the HUD gem and enemy detection
+    pub companion: Option<Companion>, // The wisp, when switched on in Settings
+    pub revive: Revive,              // Downed and waiting on the wisp, see revive.rs
+    pub dying: bool,                 // Dead, the last moments playing out in slow motion before the game over screen
+    pub stress: Option<StressMap>,   // Size and enemy count of the --stress benchmark maze
+    pub new_game_plus: bool,         // Campaign maps load from their New Game Plus spawn tables, see ngplus.rs
+    pub deployables: Vec<Deployable>, // Caltrops and turrets the player has set down
+    pub projectiles: Vec<Projectile>, // Darts in flight
+    pub quest: Option<QuestChain>,   // Key-item chain from the map's header
+    pub markers: Vec<Marker>,        // Notes the player pinned to the full-screen map
+    pub flasks: Vec<Vector2>,        // Lantern oil lying in the current map
+    pub director: Director,          // Pacing of the current map, see director.rs
+    pub weather: Weather,            // Ash, embers, drips and fog from the map's header
+    pub props: Vec<Prop>,            // Crates, barrels and powder kegs to smash
+    pub swarms: Vec<Swarm>,          // Rats and bats, see swarm.rs
+    pub zones: Zones,                // Darkness, fog and other modifiers from the map's header
+    pub tally: Tally,                // Kills, hits and damage on this map, for the run summary
+    footprint_mark: Vector2,         // Where the player last left a print
+    last_cell: Option<(usize, usize)>, // The player's cell last frame, to count steps into each cell
+    original_maze: Option<Maze>,     // The map as loaded, to tell what play has changed
+    original_coins: BTreeSet<(usize, usize)>, // Cells the map's own coins started on
+    pub seed: u64,                   // Seed the current map was built from, saved with replays
+    pub rng: Rng,                    // Loot rolls for the current map
+    pub block_size: usize,
+}
+
+impl World {
+    pub fn new(player: Player, block_size: usize) -> Self {
+        World {
+            maze_data: None,
+            player,
+            enemies: Vec::new(),
+            pickups: Vec::new(),
+            hints: HintSystem::new(),
+            inventory: Inventory::new(),
+            arena: None,
+            pings: Vec::new(),
+            reverb_areas: Vec::new(),
+            portals: Vec::new(),
+            visited: HashMap::new(),
+            footprints: Vec::new(),
+            visibility: 0.0,
+            companion: None,
+            revive: Revive::new(),
+            dying: false,
+            stress: None,
+            new_game_plus: false,
+            deployables: Vec::new(),
+            projectiles: Vec::new(),
+            quest: None,
+            markers: Vec::new(),
+            flasks: Vec::new(),
+            director: Director::new(Difficulty::Normal),
+            weather: Weather::none(),
+            props: Vec::new(),
+            swarms: Vec::new(),
+            zones: Zones::none(),
+            tally: Tally::default(),
+            footprint_mark: Vector2::zero(),
+            last_cell: None,
+            original_maze: None,
+            original_coins: BTreeSet::new(),
+            seed: 0,
+            rng: Rng::new(0),
+            block_size,
+        }
+    }
+
+    // Back to the menus: drop the map and its enemies
+    pub fn unload(&mut self) {
+        self.maze_data = None;
+        self.enemies.clear();
+        self.arena = None;
+        self.pings.clear();
+        self.reverb_areas.clear();
+        self.portals.clear();
+        self.clear_visits();
+        self.footprints.clear();
+        self.companion = None;
+        self.revive = Revive::new();
+        self.dying = false;
+        self.deployables.clear();
+        self.projectiles.clear();
+        self.quest = None;
+        self.markers.clear();
+        self.flasks.clear();
+        self.weather = Weather::none();
+        self.props.clear();
+        self.swarms.clear();
+        self.zones = Zones::none();
+        self.original_maze = None;
+    }
+
+    // Forget the route walked so far, for a new map
+    pub fn clear_visits(&mut self) {
+        self.visited.clear();
+        self.last_cell = None;
+    }
+
+    // Call with the freshly loaded map once its pickups are placed
+    pub fn remember_original(&mut self, maze: &Maze) {
+        let block_size = self.block_size as f32;
+        self.original_maze = Some(maze.clone());
+        self.original_coins = self.pickups.iter().map(|pickup| ((pickup.pos.x / block_size) as usize, (pickup.pos.y / block_size) as usize)).collect();
+    }
+
+    // What play has changed in the current map since it was loaded
+    pub fn delta(&self) -> MapDelta {
+        let mut delta = MapDelta::default();
+        let (Some(data), Some(original)) = (self.maze_data.as_ref(), self.original_maze.as_ref()) else {
+            return delta;
+        };
+
+        // Arena doors belong to the arena, see arena_cleared
+        for (x, y, cell) in original.cells() {
+            match data.maze.get(x, y) {
+                Some(now) if now != cell && !matches!(cell, Cell::Door { .. }) => {
+                    delta.cells.insert((x, y), now);
+                }
+                _ => {}
+            }
+        }
+
+        // The map's own coins sit in the middle of their cell, anything else was dropped
+        let block_size = self.block_size as f32;
+        let mut remaining = BTreeSet::new();
+        for pickup in self.pickups.iter() {
+            let cell = ((pickup.pos.x / block_size) as usize, (pickup.pos.y / block_size) as usize);
+            let center = Vector2::new((cell.0 as f32 + 0.5) * block_size, (cell.1 as f32 + 0.5) * block_size);
+            if self.original_coins.contains(&cell) && pickup.pos == center {
+                remaining.insert(cell);
+            } else {
+                delta.drops.push((pickup.pos.x, pickup.pos.y, pickup.value));
+            }
+        }
+        delta.collected = self.original_coins.difference(&remaining).copied().collect();
+        delta.arena_cleared = self.arena.as_ref().is_some_and(|arena| arena.state == ArenaState::Cleared);
+        delta
+    }
+
+    // How many of the map's own coins have been picked up
+    pub fn coins_found(&self) -> usize {
+        self.delta().collected.len()
+    }
+
+    // Put a freshly loaded map back the way a delta describes it
+    pub fn apply_delta(&mut self, delta: &MapDelta) {
+        let Some(data) = self.maze_data.as_mut() else {
+            return;
+        };
+        for (&(x, y), &cell) in delta.cells.iter() {
+            data.maze.set(x, y, cell);
+        }
+
+        let block_size = self.block_size as f32;
+        self.pickups.retain(|pickup| !delta.collected.contains(&((pickup.pos.x / block_size) as usize, (pickup.pos.y / block_size) as usize)));
+        self.pickups.extend(delta.drops.iter().map(|&(x, y, value)| Pickup { pos: Vector2::new(x, y), value }));
+
+        if delta.arena_cleared && let Some(arena) = self.arena.as_mut() {
+            arena.clear(&mut data.maze);
+            self.enemies.retain(|enemy| !enemy.arena);
+        }
+    }
+
+    pub fn maze(&self) -> Option<&Maze> {
+        self.maze_data.as_ref().map(|data| &data.maze)
+    }
+
+    // Grid cell the player stands in, None when no map is loaded
+    pub fn player_cell(&self) -> Option<(usize, usize)> {
+        self.maze().map(|_| (self.player.pos.x as usize / self.block_size, self.player.pos.y as usize / self.block_size))
+    }
+
+    // Cell under the player, empty floor when no map is loaded
+    pub fn floor(&self) -> Cell {
+        match self.maze() {
+            Some(maze) => self.player.current_floor(maze, self.block_size),
+            None => Cell::Empty,
+        }
+    }
+
+    // Acoustic zone around the player, dry outside tagged areas
+    pub fn reverb_zone(&self) -> ReverbZone {
+        let cell = (self.player.pos.x as usize / self.block_size, self.player.pos.y as usize / self.block_size);
+        zone_at(&self.reverb_areas, cell)
+    }
+
+    // One simulation step: player input, pickups, enemies and combat
+    pub fn update(&mut self, delta_time: f32, input: &mut FrameInput) -> Vec<WorldEvent> {
+        let mut events = Vec::new();
+        let block_size = self.block_size;
+        let Some(data) = self.maze_data.as_ref() else {
+            return events;
+        };
+        let maze = &data.maze;
+        let rl = input.rl;
+        let health_before = self.player.health;
+
+        let mut player_input = read_input(&self.player, rl, input.actions, input.window_width, input.window_height);
+        player_input.turn += input.gyro_turn;
+        let downed = self.revive.is_downed();
+        if downed {
+            // A downed player can only look around
+            player_input = PlayerInput { turn: player_input.turn, ..PlayerInput::default() };
+        } else if self.dying {
+            player_input = PlayerInput::default();
+        }
+        // A controller swing pulls the view a little toward the enemy nearest the middle of it
+        if player_input.pad_attack && input.aim_assist > 0.0 {
+            let origin = self.player.pos;
+            let targets = self.enemies.iter()
+                .filter(|enemy| !enemy.is_dead && has_line_of_sight(origin, enemy.pos, maze, block_size))
+                .map(|enemy| enemy.pos.into());
+            let range = AIM_ASSIST_RANGE * block_size as f32;
+            player_input.turn += aim_assist(origin.into(), self.player.a, targets, range, AIM_ASSIST_WINDOW, input.aim_assist);
+        }
+        apply_input(&mut self.player, &player_input, maze, block_size, delta_time);
+        if self.player.take_swing_started() {
+            events.push(WorldEvent::SwingStarted);
+        }
+
+        let floor = self.player.current_floor(maze, block_size);
+        self.hints.update(floor, delta_time);
+        self.weather.update(delta_time, self.player.pos, maze, block_size);
+
+        // Hazard floors, then damage from whatever the player is suffering
+        if let Some(kind) = floor_hazard(floor) {
+            self.player.status.apply(kind);
+        }
+        let status_damage = self.player.status.update(delta_time);
+        if status_damage > 0.0 {
+            self.player.take_damage(status_damage);
+        }
+        self.inventory.coins += collect_pickups(&mut self.pickups, self.player.pos, block_size);
+        let flasks = collect_flasks(&mut self.flasks, self.player.pos, block_size);
+        if flasks > 0 {
+            self.player.lantern.refuel(FLASK_FUEL * flasks as f32);
+        }
+
+        // L (by default) lights the lantern or puts it out; it burns no oil in the hub, a safe room
+        let actions = input.actions;
+        if actions.pressed(rl, Action::Lantern) {
+            self.player.lantern.toggle();
+        }
+        if self.portals.is_empty() {
+            self.player.lantern.update(delta_time);
+        }
+
+        let cell = (self.player.pos.x as usize / block_size, self.player.pos.y as usize / block_size);
+        record_visit(&mut self.visited, cell, self.last_cell, delta_time);
+        self.last_cell = Some(cell);
+
+        // Zone modifiers close in the light, hold out the lantern or hurry the enemies
+        if let Some(active) = self.zones.update(delta_time, cell) && !active.is_empty() {
+            self.hints.show(active.iter().map(|modifier| modifier.name()).collect::<Vec<_>>().join(", "));
+            events.push(WorldEvent::ZoneEntered);
+        }
+        self.player.light_scale = self.zones.light_scale();
+        self.player.lantern.smothered = self.zones.has(ZoneModifier::NoLantern);
+        if is_checkpoint(floor) {
+            events.push(WorldEvent::Checkpoint(cell));
+        }
+
+        if check_goal_reached(&self.player, maze, block_size) {
+            events.push(WorldEvent::GoalReached);
+        }
+
+        // Drink a potion with H or Triangle/Y
+        if actions.pressed(rl, Action::Potion) && self.player.health < self.player.max_health && !downed
+            && let Some(heal) = self.inventory.use_potion()
+        {
+            self.player.health = (self.player.health + heal).min(self.player.max_health);
+        }
+
+        // Ping whatever the player is looking at with G or the looking stick's button
+        update_pings(&mut self.pings, delta_time);
+        if actions.pressed(rl, Action::Ping) {
+            place_ping(&mut self.pings, ping_target(&self.player, maze, block_size), 0);
+        }
+
+        // The companion follows and looks for coins; C or L2/LT sends it after an enemy
+        if let Some(companion) = self.companion.as_mut() {
+            if let Some(coin) = companion.update(delta_time, self.player.pos, &self.enemies, &self.pickups, maze, block_size) {
+                place_ping(&mut self.pings, coin, COMPANION_PING_OWNER);
+            }
+            if actions.pressed(rl, Action::Command) && !downed && companion.distract(self.player.pos, &mut self.enemies, maze, block_size) {
+                log!("Companion is distracting an enemy");
+            }
+        }
+
+        // F or Cross sets the selected deployable down, X or Circle picks the next one. Not in the hub,
+        // where the same button enters portals.
+        if self.portals.is_empty() {
+            if actions.pressed(rl, Action::Switch) {
+                self.inventory.next_deployable();
+            }
+            if actions.pressed(rl, Action::Deploy) {
+                let kind = self.inventory.deployable;
+                if self.inventory.deployables_left(kind) > 0 && deploy(&mut self.deployables, kind, self.player.pos, block_size) {
+                    self.inventory.take_deployable();
+                    log!("Deployed {}", kind.name());
+                }
+            }
+        }
+
+        // Footprints: blood once the player is badly hurt
+        update_footprints(&mut self.footprints, delta_time);
+        let kind = FootprintKind::for_player(self.player.health_ratio());
+        step(&mut self.footprints, &mut self.footprint_mark, self.player.pos, kind, block_size);
+
+        // Remove enemies that should despawn, then move and animate the rest
+        self.visibility = illumination(&self.player, maze, &self.pings, block_size);
+        despawn(&mut self.enemies);
+        update_grudges(&mut self.enemies, block_size);
+        let opponents = grudge_targets(&self.enemies);
+        let companion_pos = self.companion.as_ref().map(|companion| companion.pos);
+        let reviver = companion_pos.filter(|_| downed);
+        let aggression = self.director.aggression();
+        let enemy_time = delta_time * self.zones.enemy_speed();
+        for (enemy, opponent) in self.enemies.iter_mut().zip(opponents) {
+            enemy.aggression = aggression;
+            // An enemy fighting another faction goes after its opponent, a distracted one after the glowing
+            // wisp, and all of them after the wisp while it revives the player
+            match (opponent, companion_pos.filter(|_| enemy.distracted > 0.0 || downed)) {
+                (Some(pos), _) | (None, Some(pos)) => enemy.update(enemy_time, pos, 1.0, maze, block_size),
+                (None, None) => enemy.update(enemy_time, self.player.pos, self.visibility, maze, block_size),
+            }
+            let lit = self.player.lantern.lights(self.player.pos, self.player.a, enemy.pos, self.player.light_radius, block_size);
+            enemy.update_cloak(delta_time, lit);
+            if enemy.is_dead {
+                continue;
+            }
+            step(&mut self.footprints, &mut enemy.footprint_mark, enemy.pos, FootprintKind::Enemy, block_size);
+
+            // Enemies burn and choke on hazards too, and can die of it
+            if let Some(kind) = maze.at_world(enemy.pos.x, enemy.pos.y, block_size).and_then(floor_hazard) {
+                enemy.status.apply(kind);
+            }
+            let status_damage = enemy.status.update(delta_time);
+            if status_damage > 0.0 && enemy.take_damage(status_damage) {
+                events.push(WorldEvent::EnemyKilled);
+                self.pickups.push(Pickup { pos: enemy.pos, value: 1 + self.rng.range(3) as u32 });
+                continue;
+            }
+
+            // Close enough to swing: attack animation overrides the movement animation
+            let target = opponent.or(reviver).unwrap_or(self.player.pos);
+            let distance_to_target = ((target.x - enemy.pos.x).powi(2) + (target.y - enemy.pos.y).powi(2)).sqrt();
+            if distance_to_target < ENEMY_ATTACK_ANIMATION_RANGE * block_size as f32 && (opponent.is_some() || reviver.is_some() || enemy.distracted <= 0.0) {
+                enemy.set_animation(AnimationState::Attack);
+            }
+        }
+
+        // Traps and turrets, then the darts they fired
+        update_deployables(&mut self.deployables, &mut self.enemies, &mut self.projectiles, maze, block_size, delta_time);
+        for hit in update_projectiles(&mut self.projectiles, &mut self.enemies, maze, block_size, delta_time) {
+            events.push(if hit.blocked { WorldEvent::Blocked } else { WorldEvent::EnemyHit });
+            if hit.killed {
+                events.push(WorldEvent::EnemyKilled);
+                self.pickups.push(Pickup { pos: hit.pos, value: 1 + self.rng.range(3) as u32 });
+            }
+        }
+
+        // Swarms share one decision per group, so even hundreds of critters stay cheap
+        let bites = update_swarms(&mut self.swarms, delta_time, self.player.pos, maze, block_size);
+        if bites > 0.0 {
+            self.player.take_damage(bites);
+        }
+
+        // The frame a swing checks for enemy hits it also lands on the props and critters in its arc
+        let swing_peaking = self.player.is_attacking && !self.player.enemy_hit_this_attack;
+        check_attack_collision(&mut self.player, &mut self.enemies, &self.inventory.weapon, &mut self.pickups, &mut self.rng, block_size, &mut events);
+        update_props(&mut self.props, delta_time);
+        if swing_peaking && self.player.enemy_hit_this_attack {
+            let weapon = &self.inventory.weapon;
+            let combo_hit = self.player.combo_hit();
+            let (range, arc) = (weapon.range * block_size as f32, weapon.arc * combo_hit.arc_scale);
+            let critters = strike_swarms(&mut self.swarms, self.player.pos, self.player.a, range, arc);
+            events.extend(std::iter::repeat_n(WorldEvent::EnemyKilled, critters));
+            let struck = strike_props(&mut self.props, self.player.pos, self.player.a, range, arc);
+            let smash = break_props(&mut self.props, struck, &mut self.enemies, maze, block_size, &mut self.rng);
+            self.tally.props_broken += smash.broken as u32;
+            events.extend(std::iter::repeat_n(WorldEvent::PropBroken, smash.broken.min(1)));
+            events.extend(std::iter::repeat_n(WorldEvent::Explosion, smash.explosions.min(1)));
+            events.extend(std::iter::repeat_n(WorldEvent::EnemyHit, smash.hits));
+            for pos in smash.kills {
+                events.push(WorldEvent::EnemyKilled);
+                self.pickups.push(Pickup { pos, value: 1 + self.rng.range(3) as u32 });
+            }
+            for (pos, loot) in smash.loot {
+                match loot {
+                    Loot::Coins(value) => self.pickups.push(Pickup { pos, value }),
+                    Loot::Flask => self.flasks.push(pos),
+                }
+            }
+        }
+        for pos in apply_enemy_attacks(&mut self.player, &mut self.enemies, maze, block_size) {
+            events.push(WorldEvent::EnemyKilled);
+            self.pickups.push(Pickup { pos, value: 1 + self.rng.range(3) as u32 });
+        }
+
+        // Arena doors shut behind the player and open again once its enemies are dead
+        if let (Some(arena), Some(data)) = (self.arena.as_mut(), self.maze_data.as_mut()) {
+            match arena.update(&mut data.maze, floor, &self.enemies) {
+                Some(ArenaState::Locked) => events.push(WorldEvent::ArenaLocked),
+                Some(ArenaState::Cleared) => events.push(WorldEvent::ArenaCleared),
+                _ => {}
+            }
+        }
+
+        // Quest items are picked up and handed in by walking to them
+        if let (Some(quest), Some(data)) = (self.quest.as_mut(), self.maze_data.as_mut()) {
+            let was_open = quest.objective().is_none();
+            let messages = quest.update(cell, &mut data.maze);
+            if !messages.is_empty() {
+                self.hints.show(messages.join(". "));
+            }
+            if !was_open && quest.objective().is_none() {
+                events.push(WorldEvent::QuestGatesOpened);
+            }
+        }
+
+        // The director watches the fighting and sends reinforcements into long quiet stretches.
+        // Maps without enemies, like the hub, and the stress benchmark are left alone.
+        if !self.enemies.is_empty() && self.stress.is_none() {
+            let damage = (health_before - self.player.health).max(0.0) / self.player.max_health;
+            let hits = events.iter().filter(|event| matches!(event, WorldEvent::EnemyHit | WorldEvent::CriticalHit)).count();
+            if self.director.update(delta_time, self.player.health_ratio(), damage, hits)
+                && let Some(data) = self.maze_data.as_ref()
+                && let Some(enemy) = spawn_reinforcement(&self.enemies, self.player.pos, &data.maze, block_size, &mut self.rng)
+            {
+                self.enemies.push(enemy);
+            }
+        }
+
+        // With the wisp along, a player at zero health goes down before dying. Once dead the
+        // world only plays on until the game over screen.
+        if self.dying {
+            self.tally.record(&events, 0.0);
+            return events;
+        }
+        match self.revive.update(delta_time, &mut self.player, companion_pos, &self.enemies, block_size) {
+            Some(ReviveEvent::Downed) => {
+                // Whatever the wisp was distracting is let go so it can come back
+                for enemy in self.enemies.iter_mut() {
+                    enemy.distracted = 0.0;
+                }
+                self.hints.show("You're down! Hold on while the wisp revives you".to_string());
+                events.push(WorldEvent::PlayerDowned);
+            }
+            Some(ReviveEvent::Interrupted) => self.hints.show("The wisp was driven off, the revive starts over".to_string()),
+            Some(ReviveEvent::Revived) => self.hints.show("Back on your feet".to_string()),
+            Some(ReviveEvent::Died) => events.push(WorldEvent::PlayerDied),
+            None => {}
+        }
+        let low = self.player.max_health * LOW_HEALTH;
+        if health_before >= low && self.player.health < low && !self.player.is_dead() {
+            events.push(WorldEvent::LowHealth);
+        }
+        self.tally.record(&events, (health_before - self.player.health).max(0.0));
+        events
+    }
+
+    // Walls, enemies and pickups into the framebuffer. `highlight` outlines the wall of a cell the player can use.
+    pub fn render(&self, framebuffer: &mut Framebuffer, texture_cache: &TextureManager, performance_mode: bool, shadows: bool,
+                  highlight: Option<((usize, usize), Color)>) {
+        if let Some(ref data) = self.maze_data {
+            render_world(framebuffer, &data.maze, self.block_size, &self.player, texture_cache, performance_mode, highlight);
+            if shadows {
+                render_shadows(framebuffer, &self.player, &self.enemies, &self.pickups, self.block_size);
+            }
+            render_enemies(framebuffer, &self.player, &self.enemies, texture_cache, &data.maze, self.block_size, performance_mode);
+            render_pickups(framebuffer, &self.player, &self.pickups, &data.maze, self.block_size, performance_mode);
+            // Quest items float as violet gems, oil flasks stand on the floor
+            let block = self.block_size as f32;
+            let mut items: Vec<FloorItem> = self.quest.iter().flat_map(|quest| quest.items.iter())
+                .map(|(_, (x, y))| (Vector2::new((*x as f32 + 0.5) * block, (*y as f32 + 0.5) * block), 0.14, 0.18, 0.2, Color::new(190, 110, 255, 255)))
+                .collect();
+            items.extend(self.flasks.iter().map(|&pos| (pos, 0.1, 0.16, 0.0, Color::new(230, 130, 40, 255))));
+            items.extend(prop_boxes(&self.props, self.block_size));
+            items.extend(swarm_boxes(&self.swarms));
+            items.extend(deployable_boxes(&self.player, &self.deployables, &self.projectiles, self.block_size));
+            render_floor_items(framebuffer, &self.player, &items, &data.maze, self.block_size, performance_mode);
+            render_pings(framebuffer, &self.player, &self.pings, self.block_size);
+            render_weather(framebuffer, &self.player, &self.weather, self.block_size, performance_mode);
+            if let Some(ref companion) = self.companion {
+                render_companion(framebuffer, &self.player, companion, texture_cache, &data.maze, self.block_size, performance_mode);
+            }
+        }
+    }
+}
+
+// Function to check if player's attack hits enemies
+pub fn check_attack_collision(
+    player: &mut Player,
+    enemies: &mut Vec<Enemy>,
+    weapon: &Weapon,
+    pickups: &mut Vec<Pickup>,
+    rng: &mut Rng,
+    block_size: usize,
+    events: &mut Vec<WorldEvent>,
+) {
+    if !player.is_attacking {
+        return;
+    }
+
+    let combo_hit = player.combo_hit();
+    let attack_range = weapon.range * block_size as f32; // Range in which attacks can hit
+    let attack_angle = weapon.arc * combo_hit.arc_scale; // Cone in front of player
+    let base_damage = (weapon.damage + player.attack_damage_bonus) * combo_hit.damage_scale;
+
+    // Only process attack collision during the peak of the attack (middle third)
+    let attack_progress = player.get_attack_progress();
+    if attack_progress < 0.2 || attack_progress > 0.8 {
+        return;
+    }
+
+    // Each swing checks for hits once, as soon as it reaches its peak
+    if !player.enemy_hit_this_attack {
+        for enemy in enemies.iter_mut() {
+            if enemy.is_dead {
+                continue;
+            }
+
+            // Check if enemy is within attack range and cone
+            if let Some(angle_diff) = swing_hit(player.pos.into(), player.a, enemy.pos.into(), attack_range, attack_angle) {
+                let distance = Vec2::from(player.pos).distance(enemy.pos.into());
+                player.enemy_hit_this_attack = true;
+
+                // A raised shield turns the blow aside, shield bearers have to be flanked
+                if enemy.blocks(player.pos) {
+                    enemy.block();
+                    events.push(WorldEvent::Blocked);
+                    log!("Blocked by a shield! Distance: {:.1}", distance);
+                    continue;
+                }
+
+                // Hit the enemy, critically if it never saw the player coming or has its back turned
+                let critical = enemy.awareness < 1.0 || from_behind(enemy.pos.into(), enemy.heading, player.pos.into(), BACKSTAB_ARC);
+                events.push(if critical { WorldEvent::CriticalHit } else { WorldEvent::EnemyHit });
+
+                // Hits closer to the center of the swing deal more damage
+                let mut damage = base_damage + SWORD_CENTER_BONUS * (1.0 - angle_diff.abs() / attack_angle);
+                if critical {
+                    damage *= CRITICAL_MULTIPLIER;
+                }
+                if let Some(kind) = weapon.inflicts {
+                    enemy.status.apply(kind);
+                }
+                enemy.awareness = 1.0; // Getting hit gives the player away
+                if enemy.take_hit(damage, critical) {
+                    events.push(WorldEvent::EnemyKilled);
+                    // Fallen enemies drop one to three coins
+                    pickups.push(Pickup { pos: enemy.pos, value: 1 + rng.range(3) as u32 });
+                }
+
+                log!("{} Distance: {:.1}, Angle: {:.1}°", if critical { "Critical hit!" } else { "Enemy hit!" }, distance, angle_diff.to_degrees());
+            }
+        }
+
+        player.enemy_hit_this_attack = true; // A miss still uses up the swing
+    }
+}
+
+// Enemies close enough hit the player on a cooldown, walls block their swings. A swing at the
+// player also catches other-faction enemies beside the attacker, and enemies fighting another
+// faction trade blows with their opponent instead. Returns where the fighting killed enemies.
+fn apply_enemy_attacks(player: &mut Player, enemies: &mut [Enemy], maze: &Maze, block_size: usize) -> Vec<Vector2> {
+    let reach = ENEMY_ATTACK_RANGE * block_size as f32;
+    let mut fallen = Vec::new();
+    for attacker in 0..enemies.len() {
+        let enemy = &enemies[attacker];
+        if enemy.is_dead || enemy.attack_cooldown > 0.0 {
+            continue;
+        }
+        let origin = enemy.pos;
+        let in_reach = |target: Vector2| Vec2::from(origin).distance(target.into()) <= reach && has_line_of_sight(origin, target, maze, block_size);
+
+        if let Some(opponent) = enemy.grudge {
+            let target = enemies[opponent].pos;
+            if in_reach(target) {
+                enemies[attacker].attack_cooldown = ENEMY_ATTACK_COOLDOWN;
+                if hit_enemy(enemies, opponent, attacker) {
+                    fallen.push(target);
+                }
+            }
+            continue;
+        }
+        if enemy.distracted > 0.0 || !in_reach(player.pos) {
+            continue;
+        }
+
+        player.take_damage(ENEMY_ATTACK_DAMAGE);
+        if let Some(kind) = enemy.def.inflicts {
+            player.status.apply(kind);
+        }
+        enemies[attacker].attack_cooldown = ENEMY_ATTACK_COOLDOWN;
+
+        // Other factions caught in the swing
+        let facing = (player.pos.y - origin.y).atan2(player.pos.x - origin.x);
+        for other in 0..enemies.len() {
+            let bystander = &enemies[other];
+            if other == attacker || bystander.is_dead || bystander.faction == enemies[attacker].faction {
+                continue;
+            }
+            let pos = bystander.pos;
+            if swing_hit(origin.into(), facing, pos.into(), reach, STRAY_HIT_ARC).is_some() && hit_enemy(enemies, other, attacker) {
+                fallen.push(pos);
+            }
+        }
+    }
+    fallen
+}
+
+// One enemy's swing landing on another, which turns on it. True if it killed it.
+fn hit_enemy(enemies: &mut [Enemy], victim: usize, attacker: usize) -> bool {
+    if let Some(kind) = enemies[attacker].def.inflicts {
+        enemies[victim].status.apply(kind);
+    }
+    let killed = enemies[victim].take_damage(ENEMY_ATTACK_DAMAGE);
+    provoke(enemies, victim, attacker);
+    killed
+}
@@ -0,0 +1,183 @@
+// zones.rs
+
+use std::collections::{BTreeSet, VecDeque};
+
+use crate::maze::{Cell, MapArea, Maze, is_walkable, parse_area};
+
+// Zone modifiers change the rules while the player stands in a tagged part of a map, a boss
+// arena being the usual place for them. A map header lists the modifiers, then where they hold:
+//   zone = darkness double_speed arena   (the arena room, see below)
+//   zone = fog_in 1 9 13 1               (x, y, width, height in cells)
+//   zone = no_lantern                    (the whole map)
+// The arena room is every floor cell reachable from its `A` trigger cells without passing a
+// `d` door. Modifiers of overlapping zones add up:
+//   darkness      The light closes in at once and the screen dims
+//   fog_in        The light slowly closes in the longer the player stays
+//   no_lantern    The lantern gives no light, it can't be relit until the player leaves
+//   double_speed  Enemies move, attack and animate twice as fast
+
+const DARK_LIGHT: f32 = 0.45;    // Share of the light radius left in full darkness
+const DARK_FADE: f32 = 2.0;      // Darkness gained or lost per second
+const DARK_DIM: f32 = 0.5;       // How far full darkness pulls the screen toward black
+const FOG_LIGHT: f32 = 0.35;     // Share of the light radius left once the fog has closed in
+const FOG_IN_TIME: f32 = 8.0;    // Seconds for the fog to close in, it lifts twice as fast
+const FAST_ENEMIES: f32 = 2.0;   // Time scale of enemies under double_speed
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ZoneModifier {
+    Darkness,
+    FogIn,
+    NoLantern,
+    DoubleSpeed,
+}
+
+impl ZoneModifier {
+    fn from_name(name: &str) -> Option<ZoneModifier> {
+        match name {
+            "darkness" => Some(ZoneModifier::Darkness),
+            "fog_in" => Some(ZoneModifier::FogIn),
+            "no_lantern" => Some(ZoneModifier::NoLantern),
+            "double_speed" => Some(ZoneModifier::DoubleSpeed),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ZoneModifier::Darkness => "Darkness",
+            ZoneModifier::FogIn => "Creeping fog",
+            ZoneModifier::NoLantern => "No lantern",
+            ZoneModifier::DoubleSpeed => "Double-speed enemies",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ZoneArea {
+    Tagged(MapArea),                 // The whole map or a rectangle from the header
+    Cells(BTreeSet<(usize, usize)>), // The arena room, worked out from the maze
+}
+
+#[derive(Clone, Debug)]
+pub struct Zone {
+    pub modifiers: Vec<ZoneModifier>,
+    pub area: ZoneArea,
+}
+
+impl Zone {
+    fn contains(&self, cell: (usize, usize)) -> bool {
+        match &self.area {
+            ZoneArea::Tagged(area) => area.contains(cell),
+            ZoneArea::Cells(cells) => cells.contains(&cell),
+        }
+    }
+}
+
+// The arena's floor: flood out from the trigger cells, stopping at walls and doors
+fn arena_cells(maze: &Maze) -> BTreeSet<(usize, usize)> {
+    let mut cells = BTreeSet::new();
+    let mut queue: VecDeque<(usize, usize)> = maze.cells().filter(|&(_, _, cell)| cell == Cell::ArenaTrigger).map(|(x, y, _)| (x, y)).collect();
+    while let Some((x, y)) = queue.pop_front() {
+        if !cells.insert((x, y)) {
+            continue;
+        }
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+            let open = maze.get_i32(nx, ny).is_some_and(|cell| is_walkable(cell) && !matches!(cell, Cell::Door { .. }));
+            if open && !cells.contains(&(nx as usize, ny as usize)) {
+                queue.push_back((nx as usize, ny as usize));
+            }
+        }
+    }
+    cells
+}
+
+// The `zone` lines of a map's metadata
+pub fn zones_from_meta(meta: &[(String, String)], maze: &Maze) -> Vec<Zone> {
+    let mut zones = Vec::new();
+    for (key, value) in meta.iter().filter(|(key, _)| key == "zone") {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        let modifiers: Vec<ZoneModifier> = parts.iter().map_while(|part| ZoneModifier::from_name(part)).collect();
+        let rest = &parts[modifiers.len()..];
+        if modifiers.is_empty() {
+            elog!("Warning: '{} = {}' needs darkness, fog_in, no_lantern or double_speed, then arena, a rectangle or nothing", key, value);
+            continue;
+        }
+        let area = match rest {
+            ["arena"] => ZoneArea::Cells(arena_cells(maze)),
+            _ => match parse_area(key, value, rest) {
+                Some(area) => ZoneArea::Tagged(area),
+                None => continue,
+            },
+        };
+        if matches!(&area, ZoneArea::Cells(cells) if cells.is_empty()) {
+            elog!("Warning: '{} = {}' names the arena, but the map has no arena trigger", key, value);
+            continue;
+        }
+        zones.push(Zone { modifiers, area });
+    }
+    zones
+}
+
+// The map's zones and how far their gradual effects have set in around the player
+pub struct Zones {
+    zones: Vec<Zone>,
+    active: Vec<ZoneModifier>, // Modifiers of every zone the player stands in
+    pub darkness: f32,         // 0.0 to 1.0, eases toward whether darkness is active
+    fog: f32,                  // 0.0 to 1.0, how far the fog has closed in
+}
+
+impl Zones {
+    pub fn new(zones: Vec<Zone>) -> Self {
+        if !zones.is_empty() {
+            log!("Zones: {}", zones.len());
+        }
+        Zones { zones, active: Vec::new(), darkness: 0.0, fog: 0.0 }
+    }
+
+    pub fn none() -> Self {
+        Zones::new(Vec::new())
+    }
+
+    pub fn has(&self, modifier: ZoneModifier) -> bool {
+        self.active.contains(&modifier)
+    }
+
+    // Call every frame with the player's cell, returns the modifiers when the player steps into
+    // a different set of them
+    pub fn update(&mut self, delta_time: f32, cell: (usize, usize)) -> Option<&[ZoneModifier]> {
+        let mut active: Vec<ZoneModifier> = Vec::new();
+        for modifier in self.zones.iter().filter(|zone| zone.contains(cell)).flat_map(|zone| zone.modifiers.iter()) {
+            if !active.contains(modifier) {
+                active.push(*modifier);
+            }
+        }
+
+        let ease = |value: f32, on: bool, rate: f32| if on { (value + rate).min(1.0) } else { (value - rate).max(0.0) };
+        self.darkness = ease(self.darkness, active.contains(&ZoneModifier::Darkness), DARK_FADE * delta_time);
+        let fog_in = active.contains(&ZoneModifier::FogIn);
+        self.fog = ease(self.fog, fog_in, delta_time / FOG_IN_TIME * if fog_in { 1.0 } else { 2.0 });
+
+        if active == self.active {
+            return None;
+        }
+        log!("Zone modifiers: {:?}", active);
+        self.active = active;
+        Some(&self.active)
+    }
+
+    // Share of the player's light radius left by darkness and fog
+    pub fn light_scale(&self) -> f32 {
+        (1.0 - (1.0 - DARK_LIGHT) * self.darkness) * (1.0 - (1.0 - FOG_LIGHT) * self.fog)
+    }
+
+    // How far the screen is pulled toward black
+    pub fn dim(&self) -> f32 {
+        DARK_DIM * self.darkness
+    }
+
+    // Time scale for the enemies' updates
+    pub fn enemy_speed(&self) -> f32 {
+        if self.has(ZoneModifier::DoubleSpeed) { FAST_ENEMIES } else { 1.0 }
+    }
+}